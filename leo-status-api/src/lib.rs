@@ -0,0 +1,480 @@
+//! The payloads of the leo-status HTTP API, shared by the daemon which serializes them and the Rust clients which
+//! deserialize them. Timestamps are RFC 3339 strings, in the time zone the daemon was started with.
+
+#[cfg(feature = "driver")]
+use leo_status_driver::{
+    state::DeviceState as DriverDeviceState, FirmwareVersion, GpsdoConfig, GpsdoDeviceInfo,
+    GpsdoStatus,
+};
+use serde::{Deserialize, Serialize};
+
+use std::fmt::Display;
+
+/// The lock status of the GPSDO, served at `/status`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct LockStatusResponse {
+    /// The number of times that the GPS lock has been lost since reboot
+    pub loss_count: u8,
+
+    /// Whether the GPSDO has a lock on a GPS signal
+    pub sat_lock: bool,
+
+    /// Whether the PLL is locked to the configured frequencies
+    pub pll_lock: bool,
+
+    /// Whether the system is locked overall
+    pub locked: bool,
+
+    /// The state of the device in its lifecycle, which once the status is stale is the state the device was left in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<DeviceState>,
+
+    /// Whether the device has stopped responding since this status was read, so it may be out of date
+    #[serde(default)]
+    pub stale: bool,
+
+    /// When the device stopped responding, in RFC 3339 format, if the status is stale
+    #[serde(default)]
+    pub stale_since: Option<String>,
+
+    /// The number of seconds since the device stopped responding, if the status is stale
+    #[serde(default)]
+    pub stale_for_secs: Option<f64>,
+
+    /// The number of the poll cycle the status was read in, counting from 1 when the daemon starts. Responses with the
+    /// same number came from the same reading of the GPSDO.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub poll_seq: Option<u64>,
+}
+
+#[cfg(feature = "driver")]
+impl From<&GpsdoStatus> for LockStatusResponse {
+    fn from(value: &GpsdoStatus) -> Self {
+        LockStatusResponse {
+            loss_count: value.loss_count(),
+            sat_lock: value.sat_locked(),
+            pll_lock: value.pll_locked(),
+            locked: value.locked(),
+            state: Some(DriverDeviceState::from_status(value).into()),
+            stale: false,
+            stale_since: None,
+            stale_for_secs: None,
+            poll_seq: None,
+        }
+    }
+}
+
+/// The state of the GPSDO in its lifecycle, as the driver's `state::DeviceState`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceState {
+    /// No device is open
+    Disconnected,
+
+    /// The device is open, but hasn't been read yet
+    Connected,
+
+    /// The PLL isn't locked, as while the GPSDO searches for satellites
+    Acquiring,
+
+    /// Both satellite and PLL lock are held
+    Locked,
+
+    /// The PLL is locked but satellite lock is lost
+    Holdover,
+
+    /// Talking to the open device failed
+    Fault,
+}
+
+#[cfg(feature = "driver")]
+impl From<DriverDeviceState> for DeviceState {
+    fn from(value: DriverDeviceState) -> Self {
+        match value {
+            DriverDeviceState::Disconnected => DeviceState::Disconnected,
+            DriverDeviceState::Connected => DeviceState::Connected,
+            DriverDeviceState::Acquiring => DeviceState::Acquiring,
+            DriverDeviceState::Locked => DeviceState::Locked,
+            DriverDeviceState::Holdover => DeviceState::Holdover,
+            DriverDeviceState::Fault => DeviceState::Fault,
+        }
+    }
+}
+
+/// The parameters of the PLL of the GPSDO, part of `ConfigResponse`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PllParamsResponse {
+    /// The frequency produced by the GPSDO TCXO
+    pub fin: u32,
+
+    /// The divisor of fin before it enters the PLL
+    pub n3: u32,
+    /// The first divisor on the feedback loop
+    pub n2_hs: u8,
+
+    /// The second divisor on the feedback loop
+    pub n2_ls: u32,
+
+    /// The shared divisor on the output from the PLL
+    pub n1_hs: u8,
+
+    /// The divisor after n1_hs, heading to port one
+    pub nc1_ls: u32,
+
+    /// The divisor after n1_hs, heading to port two, omitted on models with a single output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nc2_ls: Option<u32>,
+
+    /// The skew between port one and port two, 0 - 255
+    pub skew: u8,
+
+    /// The PLL bandwidth mode
+    pub bw: u8,
+
+    /// The frequency of fin after division by n3, null if the configuration is invalid
+    pub f3: Option<u32>,
+
+    /// The frequency of the output of the PLL, null if the configuration is invalid
+    pub fosc: Option<u64>,
+}
+
+#[cfg(feature = "driver")]
+impl From<&GpsdoConfig> for PllParamsResponse {
+    fn from(value: &GpsdoConfig) -> Self {
+        PllParamsResponse {
+            fin: value.fin(),
+            n3: value.n3(),
+            n2_hs: value.n2_hs(),
+            n2_ls: value.n2_ls(),
+            n1_hs: value.n1_hs(),
+            nc1_ls: value.nc1_ls(),
+            nc2_ls: value.nc2_ls(),
+            skew: value.skew(),
+            bw: value.bw(),
+            f3: value.checked_f3().ok(),
+            fosc: value.checked_fosc().ok(),
+        }
+    }
+}
+
+/// The configuration of the GPSDO, served at `/config`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ConfigResponse {
+    /// Whether the output1 port of the GPSDO is active
+    pub output1: bool,
+
+    /// Whether the output2 port of the GPSDO is active, omitted on models with a single output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output2: Option<bool>,
+
+    /// The drive level of the signal in milliamps
+    pub level: u8,
+
+    /// The parameters of the PLL which synthesizes the outputs
+    pub pll_params: PllParamsResponse,
+
+    /// The frequency output on output1, null if the configuration is invalid
+    pub fout1: Option<u64>,
+
+    /// The frequency output on output2, omitted on models with a single output or if the configuration is invalid
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fout2: Option<u64>,
+
+    /// Why the output frequencies could not be calculated, omitted unless the configuration is invalid
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_error: Option<String>,
+
+    /// Whether the device has stopped responding since this config was read, so it may be out of date
+    #[serde(default)]
+    pub stale: bool,
+
+    /// When the device stopped responding, in RFC 3339 format, if the config is stale
+    #[serde(default)]
+    pub stale_since: Option<String>,
+
+    /// The number of seconds since the device stopped responding, if the config is stale
+    #[serde(default)]
+    pub stale_for_secs: Option<f64>,
+
+    /// The number of the poll cycle the config was read in, counting from 1 when the daemon starts. Responses with the
+    /// same number came from the same reading of the GPSDO.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub poll_seq: Option<u64>,
+}
+
+#[cfg(feature = "driver")]
+impl From<&GpsdoConfig> for ConfigResponse {
+    fn from(value: &GpsdoConfig) -> Self {
+        ConfigResponse {
+            output1: value.output1(),
+            output2: value.output2(),
+            level: value.level_ma().milliamps().unwrap_or(0),
+            fout1: value.checked_fout1().ok(),
+            fout2: value.checked_fout2().ok().flatten(),
+            config_error: value
+                .checked_fout1()
+                .err()
+                .map(|error| format!("invalid configuration: {}", error)),
+            pll_params: value.into(),
+            stale: false,
+            stale_since: None,
+            stale_for_secs: None,
+            poll_seq: None,
+        }
+    }
+}
+
+/// The GPSDO in use and where it is attached, served at `/device`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DeviceResponse {
+    /// The USB vendor identifier of the GPSDO
+    pub vendor_id: u16,
+
+    /// The USB product identifier of the GPSDO
+    pub product_id: u16,
+
+    /// The serial number of the GPSDO, if it has one
+    pub serial_number: Option<String>,
+
+    /// The manufacturer string of the USB device descriptor of the GPSDO, where the backend can read it
+    #[serde(default)]
+    pub manufacturer: Option<String>,
+
+    /// The product string of the USB device descriptor of the GPSDO, where the backend can read it
+    #[serde(default)]
+    pub product: Option<String>,
+
+    /// The platform specific path used to open the GPSDO
+    pub path: String,
+
+    /// The USB bus and port path the GPSDO is attached to, where known
+    pub port_path: Option<String>,
+
+    /// The USB interface number of the HID interface
+    pub interface_number: i32,
+
+    /// The firmware version of the GPSDO, such as `1.05`, where known
+    pub firmware_version: Option<String>,
+
+    /// An estimate of the number of seconds since the GPSDO booted, from resets of its loss count and how it behaves
+    /// when re-opened. Until a reboot has been seen this is how long it has been read for, so it may be too low.
+    #[serde(default)]
+    pub uptime_secs_estimate: Option<f64>,
+}
+
+#[cfg(feature = "driver")]
+impl From<&GpsdoDeviceInfo> for DeviceResponse {
+    fn from(value: &GpsdoDeviceInfo) -> Self {
+        DeviceResponse {
+            vendor_id: value.vendor_id(),
+            product_id: value.product_id(),
+            serial_number: value.serial_number().map(str::to_owned),
+            manufacturer: value.manufacturer().map(str::to_owned),
+            product: value.product().map(str::to_owned),
+            path: value.path().to_owned(),
+            port_path: value.port_path().map(str::to_owned),
+            interface_number: value.interface_number(),
+            firmware_version: None,
+            uptime_secs_estimate: None,
+        }
+    }
+}
+
+#[cfg(feature = "driver")]
+impl DeviceResponse {
+    pub fn with_firmware_version(mut self, firmware_version: Option<FirmwareVersion>) -> Self {
+        self.firmware_version = firmware_version.map(|version| version.to_string());
+        self
+    }
+}
+
+/// The version of the running daemon, and of the latest release where a release feed is checked, served at `/version`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct VersionResponse {
+    /// The version of the running daemon, such as `2.0.0`
+    pub version: String,
+
+    /// The latest version in the release feed, `None` unless a feed is configured and has been read
+    pub latest_version: Option<String>,
+
+    /// Whether the latest version is newer than the running one, `None` unless the release feed has been read
+    pub update_available: Option<bool>,
+
+    /// When the release feed was last read, `None` unless it has been
+    pub checked_at: Option<String>,
+}
+
+/// A reading of the GPSDO from a single poll cycle, served at `/snapshot`, delivered by the daemon's webhook sink and
+/// accepted at `/ingest`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SnapshotResponse {
+    /// When the GPSDO was read, in RFC 3339 format
+    pub timestamp: String,
+
+    /// The lock status read from the GPSDO
+    pub status: LockStatusResponse,
+
+    /// The configuration read from the GPSDO, with the frequencies derived from it
+    pub config: ConfigResponse,
+
+    /// The GPSDO the reading came from, omitted where not known, such as by the webhook sink
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<DeviceResponse>,
+
+    /// Every way the configuration is outside the legal ranges of the GPSDO, omitted if it is valid
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+
+    /// Whether the device has stopped responding since this reading, so it may be out of date
+    #[serde(default)]
+    pub stale: bool,
+
+    /// When the device stopped responding, in RFC 3339 format, if the reading is stale
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stale_since: Option<String>,
+
+    /// The number of seconds since the device stopped responding, if the reading is stale
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stale_for_secs: Option<f64>,
+}
+
+/// What happened to the lock of the GPSDO
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum LockEventKind {
+    /// The current lock state, sent when a client first connects
+    Snapshot,
+    LockAcquired,
+    LockLost,
+    SatLockAcquired,
+    SatLockLost,
+    PllLockAcquired,
+    PllLockLost,
+}
+
+impl LockEventKind {
+    /// The name of the event, as serialized
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LockEventKind::Snapshot => "snapshot",
+            LockEventKind::LockAcquired => "lock_acquired",
+            LockEventKind::LockLost => "lock_lost",
+            LockEventKind::SatLockAcquired => "sat_lock_acquired",
+            LockEventKind::SatLockLost => "sat_lock_lost",
+            LockEventKind::PllLockAcquired => "pll_lock_acquired",
+            LockEventKind::PllLockLost => "pll_lock_lost",
+        }
+    }
+}
+
+/// A change in the lock state of the GPSDO, along with the complete lock state after the change, streamed at
+/// `/events`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct LockEvent {
+    /// When the change was seen, in RFC 3339 format
+    pub timestamp: String,
+    pub event: LockEventKind,
+    pub locked: bool,
+    pub sat_lock: bool,
+    pub pll_lock: bool,
+    pub loss_count: u8,
+
+    /// The state of the device after the change, as in `LockStatusResponse::state`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<DeviceState>,
+
+    /// The number of the poll cycle the change was seen in, as in `LockStatusResponse::poll_seq`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub poll_seq: Option<u64>,
+}
+
+#[cfg(feature = "driver")]
+impl LockEvent {
+    pub fn new(event: LockEventKind, status: &GpsdoStatus, timestamp: String) -> Self {
+        LockEvent {
+            timestamp,
+            event,
+            locked: status.locked(),
+            sat_lock: status.sat_locked(),
+            pll_lock: status.pll_locked(),
+            loss_count: status.loss_count(),
+            state: Some(DriverDeviceState::from_status(status).into()),
+            poll_seq: None,
+        }
+    }
+}
+
+impl Display for LockEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} locked={} sat_lock={} pll_lock={} loss_count={}",
+            self.timestamp,
+            self.event.as_str(),
+            self.locked,
+            self.sat_lock,
+            self.pll_lock,
+            self.loss_count
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ConfigResponse, LockEvent, LockEventKind, LockStatusResponse};
+
+    #[test]
+    fn config_response_round_trips_as_served() {
+        let served = r#"{"output1":true,"level":8,"pll_params":{"fin":1,"n3":1,"n2_hs":10,"n2_ls":2,"n1_hs":5,"nc1_ls":4,"skew":0,"bw":15,"f3":1,"fosc":20},"fout1":null,"config_error":"invalid configuration: divider nc1_ls is 0","stale":true,"stale_since":"2024-01-01T12:00:00Z","stale_for_secs":12.5}"#;
+
+        let config: ConfigResponse = serde_json::from_str(served).expect("failed to deserialize");
+        assert_eq!(config.output2, None);
+        assert_eq!(config.pll_params.nc2_ls, None);
+        assert_eq!(config.stale_for_secs, Some(12.5));
+
+        assert_eq!(serde_json::to_string(&config).unwrap(), served);
+    }
+
+    #[test]
+    fn lock_status_response_accepts_readings_without_staleness() {
+        let status: LockStatusResponse = serde_json::from_str(
+            r#"{"loss_count":2,"sat_lock":true,"pll_lock":true,"locked":true}"#,
+        )
+        .expect("failed to deserialize");
+
+        assert!(!status.stale);
+        assert_eq!(status.stale_since, None);
+    }
+
+    #[test]
+    fn lock_event_displays_as_printed_by_the_events_command() {
+        let event = LockEvent {
+            timestamp: "2024-01-01T12:00:00Z".to_owned(),
+            event: LockEventKind::SatLockLost,
+            locked: false,
+            sat_lock: false,
+            pll_lock: true,
+            loss_count: 3,
+            state: None,
+            poll_seq: Some(7),
+        };
+
+        assert_eq!(
+            event.to_string(),
+            "2024-01-01T12:00:00Z sat_lock_lost locked=false sat_lock=false pll_lock=true loss_count=3"
+        );
+        assert_eq!(
+            serde_json::to_value(event.event).unwrap(),
+            event.event.as_str()
+        );
+    }
+}
@@ -0,0 +1,211 @@
+use leo_status_api::{ConfigResponse, DeviceResponse, LockEvent, LockStatusResponse};
+use serde::de::DeserializeOwned;
+
+use std::future::Future;
+
+use crate::{endpoint, parse_event_line, ClientError};
+
+/// The HTTP client an `AsyncClient` makes its requests through, so it can be used from any async runtime. Responses
+/// other than 200 OK should be returned as errors.
+pub trait AsyncTransport {
+    type TransportError;
+    type Lines: AsyncLines<TransportError = Self::TransportError>;
+
+    /// GET a URL, returning the body of the response
+    fn get(&self, url: &str) -> impl Future<Output = Result<Vec<u8>, Self::TransportError>> + Send;
+
+    /// GET a URL which streams its response, returning the lines of the body as they arrive, without a timeout
+    fn get_lines(
+        &self,
+        url: &str,
+    ) -> impl Future<Output = Result<Self::Lines, Self::TransportError>> + Send;
+}
+
+/// The lines of a streamed response body
+pub trait AsyncLines {
+    type TransportError;
+
+    /// The next line of the body, without its line ending, or `None` once the body ends
+    fn next_line(
+        &mut self,
+    ) -> impl Future<Output = Result<Option<String>, Self::TransportError>> + Send;
+}
+
+/// The async counterpart of `Client`, reaching the daemon through an `AsyncTransport`. Only available with the `async`
+/// feature.
+pub struct AsyncClient<Transport: AsyncTransport> {
+    transport: Transport,
+    url: String,
+}
+
+impl<Transport: AsyncTransport> AsyncClient<Transport> {
+    /// Reach the daemon serving HTTP at `url`, such as `http://localhost:8000`, through the given transport
+    pub fn new(transport: Transport, url: &str) -> Self {
+        AsyncClient {
+            transport,
+            url: url.to_owned(),
+        }
+    }
+
+    /// GET an endpoint of the daemon, parsing its JSON body
+    async fn get<T: DeserializeOwned>(
+        &self,
+        path: &str,
+    ) -> Result<T, ClientError<Transport::TransportError>> {
+        let body = self
+            .transport
+            .get(&endpoint(&self.url, path))
+            .await
+            .map_err(ClientError::Transport)?;
+
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    /// The latest lock status read by the daemon, from `/status`
+    pub async fn get_status(
+        &self,
+    ) -> Result<LockStatusResponse, ClientError<Transport::TransportError>> {
+        self.get("/status").await
+    }
+
+    /// The latest configuration read by the daemon, from `/config`
+    pub async fn get_config(
+        &self,
+    ) -> Result<ConfigResponse, ClientError<Transport::TransportError>> {
+        self.get("/config").await
+    }
+
+    /// The GPSDO the daemon is reading, from `/device`
+    pub async fn get_device(
+        &self,
+    ) -> Result<DeviceResponse, ClientError<Transport::TransportError>> {
+        self.get("/device").await
+    }
+
+    /// Follow the lock events of the daemon, from `/events`. The first event is the current lock state, and the stream
+    /// then waits for each change.
+    pub async fn watch_events(
+        &self,
+    ) -> Result<AsyncEvents<Transport::Lines>, ClientError<Transport::TransportError>> {
+        let lines = self
+            .transport
+            .get_lines(&endpoint(&self.url, "/events"))
+            .await
+            .map_err(ClientError::Transport)?;
+
+        Ok(AsyncEvents { lines })
+    }
+}
+
+/// The lock events streamed by the daemon, ending when the daemon closes the stream
+pub struct AsyncEvents<Lines: AsyncLines> {
+    lines: Lines,
+}
+
+impl<Lines: AsyncLines> AsyncEvents<Lines> {
+    /// Wait for the next event, `None` once the daemon closes the stream
+    pub async fn next(&mut self) -> Option<Result<LockEvent, ClientError<Lines::TransportError>>> {
+        loop {
+            let line = match self.lines.next_line().await {
+                Ok(Some(line)) => line,
+                Ok(None) => return None,
+                Err(error) => return Some(Err(ClientError::Transport(error))),
+            };
+
+            if let Some(event) = parse_event_line(&line) {
+                return Some(event.map_err(ClientError::from));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use leo_status_api::LockEventKind;
+
+    use std::{
+        collections::VecDeque,
+        future::{ready, Future},
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use super::{AsyncClient, AsyncLines, AsyncTransport, ClientError};
+
+    /// Answers every request at once, `/status` and `/events` with canned responses and anything else with a 503
+    struct MockTransport;
+
+    struct MockLines(VecDeque<&'static str>);
+
+    impl AsyncTransport for MockTransport {
+        type TransportError = u16;
+        type Lines = MockLines;
+
+        fn get(&self, url: &str) -> impl Future<Output = Result<Vec<u8>, u16>> + Send {
+            ready(match url {
+                "http://daemon/status" => Ok(br#"{"loss_count":1,"sat_lock":true,"pll_lock":false,"locked":false,"stale":true,"stale_since":"2024-01-01T00:00:00Z","stale_for_secs":12.5}"#.to_vec()),
+                _ => Err(503),
+            })
+        }
+
+        fn get_lines(&self, url: &str) -> impl Future<Output = Result<MockLines, u16>> + Send {
+            assert_eq!(url, "http://daemon/events");
+
+            ready(Ok(MockLines(VecDeque::from([
+                r#"data: {"timestamp":"2024-01-01T00:00:00Z","event":"snapshot","locked":true,"sat_lock":true,"pll_lock":true,"loss_count":0}"#,
+                "",
+                ": keepalive",
+                "",
+                r#"data: {"timestamp":"2024-01-01T00:00:05Z","event":"lock_lost","locked":false,"sat_lock":true,"pll_lock":false,"loss_count":0}"#,
+                "",
+            ]))))
+        }
+    }
+
+    impl AsyncLines for MockLines {
+        type TransportError = u16;
+
+        fn next_line(&mut self) -> impl Future<Output = Result<Option<String>, u16>> + Send {
+            ready(Ok(self.0.pop_front().map(str::to_owned)))
+        }
+    }
+
+    /// Run a future which never waits, as the mock transport's don't
+    fn block_on<T>(future: impl Future<Output = T>) -> T {
+        match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("mock futures should be ready at once"),
+        }
+    }
+
+    #[test]
+    fn async_client_reads_status_and_reports_transport_errors() {
+        let client = AsyncClient::new(MockTransport, "http://daemon/");
+
+        let status = block_on(client.get_status()).expect("expected success from status");
+        assert_eq!(status.loss_count, 1);
+        assert!(status.stale);
+        assert_eq!(status.stale_for_secs, Some(12.5));
+
+        assert!(matches!(
+            block_on(client.get_config()),
+            Err(ClientError::Transport(503))
+        ));
+    }
+
+    #[test]
+    fn async_client_watches_events_until_the_stream_ends() {
+        let client = AsyncClient::new(MockTransport, "http://daemon");
+
+        let mut events = block_on(client.watch_events()).expect("expected success from events");
+
+        let first = block_on(events.next()).unwrap().unwrap();
+        assert_eq!(first.event, LockEventKind::Snapshot);
+
+        let second = block_on(events.next()).unwrap().unwrap();
+        assert_eq!(second.event, LockEventKind::LockLost);
+        assert!(!second.locked);
+
+        assert!(block_on(events.next()).is_none());
+    }
+}
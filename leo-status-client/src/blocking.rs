@@ -0,0 +1,168 @@
+use leo_status_api::{ConfigResponse, DeviceResponse, LockEvent, LockStatusResponse};
+use serde::de::DeserializeOwned;
+use ureq::{Agent, BodyReader};
+
+use std::{
+    io::{BufRead, BufReader, Lines},
+    time::Duration,
+};
+
+use crate::{endpoint, parse_event_line, ClientError};
+
+/// How long a request to the daemon may take before it fails
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A client for a running leo-status daemon, which blocks until each request completes. Only available with the
+/// `blocking` feature.
+pub struct Client {
+    agent: Agent,
+    url: String,
+}
+
+impl Client {
+    /// Reach the daemon serving HTTP at `url`, such as `http://localhost:8000`
+    pub fn new(url: &str) -> Self {
+        Client {
+            agent: Agent::config_builder()
+                .timeout_global(Some(REQUEST_TIMEOUT))
+                .build()
+                .into(),
+            url: url.to_owned(),
+        }
+    }
+
+    /// GET an endpoint of the daemon, parsing its JSON body
+    fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, ClientError<ureq::Error>> {
+        let body = self
+            .agent
+            .get(&endpoint(&self.url, path))
+            .call()
+            .and_then(|mut response| response.body_mut().read_to_vec())
+            .map_err(ClientError::Transport)?;
+
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    /// The latest lock status read by the daemon, from `/status`
+    pub fn get_status(&self) -> Result<LockStatusResponse, ClientError<ureq::Error>> {
+        self.get("/status")
+    }
+
+    /// The latest configuration read by the daemon, from `/config`
+    pub fn get_config(&self) -> Result<ConfigResponse, ClientError<ureq::Error>> {
+        self.get("/config")
+    }
+
+    /// The GPSDO the daemon is reading, from `/device`
+    pub fn get_device(&self) -> Result<DeviceResponse, ClientError<ureq::Error>> {
+        self.get("/device")
+    }
+
+    /// Follow the lock events of the daemon, from `/events`. The first event is the current lock state, and the stream
+    /// then waits for each change, so it has no timeout once connected.
+    pub fn watch_events(&self) -> Result<Events, ClientError<ureq::Error>> {
+        let response = self
+            .agent
+            .get(&endpoint(&self.url, "/events"))
+            .header("Accept", "text/event-stream")
+            .config()
+            .timeout_global(None)
+            .timeout_connect(Some(REQUEST_TIMEOUT))
+            .build()
+            .call()
+            .map_err(ClientError::Transport)?;
+
+        Ok(Events {
+            lines: BufReader::new(response.into_body().into_reader()).lines(),
+        })
+    }
+}
+
+/// The lock events streamed by the daemon, ending when the daemon closes the stream
+pub struct Events {
+    lines: Lines<BufReader<BodyReader<'static>>>,
+}
+
+impl Iterator for Events {
+    type Item = Result<LockEvent, ClientError<ureq::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(error) => return Some(Err(ClientError::Transport(ureq::Error::Io(error)))),
+            };
+
+            if let Some(event) = parse_event_line(&line) {
+                return Some(event.map_err(ClientError::from));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use leo_status_api::LockEventKind;
+    use tiny_http::{Response, Server};
+
+    use super::{Client, ClientError};
+
+    /// Serve a status and an event stream of two events, answering anything else with 503 as a daemon does before its
+    /// first reading
+    fn serve_daemon() -> String {
+        let server = Server::http("127.0.0.1:0").expect("failed to start test server");
+        let url = format!("http://{}", server.server_addr().to_ip().unwrap());
+
+        std::thread::spawn(move || {
+            for request in server.incoming_requests() {
+                let response = match request.url() {
+                    "/status" => Response::from_string(
+                        r#"{"loss_count":3,"sat_lock":true,"pll_lock":true,"locked":true,"stale":false,"stale_since":null,"stale_for_secs":null}"#,
+                    ),
+                    "/events" => Response::from_string(concat!(
+                        "data: {\"timestamp\":\"2024-01-01T00:00:00Z\",\"event\":\"snapshot\",\"locked\":true,\"sat_lock\":true,\"pll_lock\":true,\"loss_count\":3}\n\n",
+                        ": keepalive\n\n",
+                        "data: {\"timestamp\":\"2024-01-01T00:00:05Z\",\"event\":\"sat_lock_lost\",\"locked\":false,\"sat_lock\":false,\"pll_lock\":true,\"loss_count\":4}\n\n",
+                    )),
+                    _ => Response::from_string("config not read yet").with_status_code(503),
+                };
+
+                request.respond(response).expect("failed to respond");
+            }
+        });
+
+        url
+    }
+
+    #[test]
+    fn client_reads_status_and_reports_unavailable_endpoints() {
+        let client = Client::new(&serve_daemon());
+
+        let status = client.get_status().expect("expected success from status");
+        assert_eq!(status.loss_count, 3);
+        assert!(status.locked);
+        assert!(!status.stale);
+
+        assert!(matches!(
+            client.get_config(),
+            Err(ClientError::Transport(ureq::Error::StatusCode(503)))
+        ));
+    }
+
+    #[test]
+    fn client_watches_events_until_the_stream_ends() {
+        let client = Client::new(&serve_daemon());
+
+        let events = client
+            .watch_events()
+            .expect("expected success from events")
+            .collect::<Result<Vec<_>, _>>()
+            .expect("expected valid events");
+
+        assert_eq!(
+            events.iter().map(|event| event.event).collect::<Vec<_>>(),
+            [LockEventKind::Snapshot, LockEventKind::SatLockLost]
+        );
+        assert_eq!(events[1].loss_count, 4);
+    }
+}
@@ -0,0 +1,77 @@
+//! A client for the HTTP API of a running leo-status daemon, returning the payloads of `leo-status-api`
+
+#[cfg(feature = "async")]
+pub use async_client::{AsyncClient, AsyncEvents, AsyncLines, AsyncTransport};
+#[cfg(feature = "blocking")]
+pub use blocking::{Client, Events};
+pub use leo_status_api as api;
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+use leo_status_api::LockEvent;
+use thiserror::Error;
+
+#[cfg(feature = "async")]
+mod async_client;
+#[cfg(feature = "blocking")]
+mod blocking;
+
+#[derive(Debug, Error)]
+/// An error which stopped a request to the daemon, either from the HTTP client making it, or because the daemon's
+/// response could not be parsed
+pub enum ClientError<TransportError> {
+    #[error("request to leo-status failed: {0}")]
+    Transport(TransportError),
+
+    #[error("invalid response from leo-status: {0}")]
+    InvalidResponse(#[from] serde_json::Error),
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+/// Join the base URL of the daemon, such as `http://localhost:8000`, with the path of an endpoint
+fn endpoint(url: &str, path: &str) -> String {
+    format!("{}{}", url.trim_end_matches('/'), path)
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+/// Parse a line of the `/events` server-sent event stream, `None` for lines which don't carry an event, such as the
+/// keepalive comments and the blank line after each event
+fn parse_event_line(line: &str) -> Option<Result<LockEvent, serde_json::Error>> {
+    let data = line.strip_prefix("data:")?;
+
+    Some(serde_json::from_str(data.trim_start()))
+}
+
+#[cfg(all(test, any(feature = "blocking", feature = "async")))]
+mod test {
+    use leo_status_api::LockEventKind;
+
+    use super::{endpoint, parse_event_line};
+
+    #[test]
+    fn endpoint_joins_urls_with_and_without_trailing_slash() {
+        assert_eq!(
+            endpoint("http://localhost:8000", "/status"),
+            "http://localhost:8000/status"
+        );
+        assert_eq!(
+            endpoint("http://localhost:8000/", "/status"),
+            "http://localhost:8000/status"
+        );
+    }
+
+    #[test]
+    fn parse_event_line_skips_lines_without_data() {
+        assert!(parse_event_line(": keepalive").is_none());
+        assert!(parse_event_line("").is_none());
+
+        let event = parse_event_line(
+            r#"data: {"timestamp":"2024-01-01T00:00:00Z","event":"lock_lost","locked":false,"sat_lock":false,"pll_lock":true,"loss_count":2}"#,
+        )
+        .expect("expected an event")
+        .expect("expected a valid event");
+        assert_eq!(event.event, LockEventKind::LockLost);
+        assert_eq!(event.loss_count, 2);
+
+        assert!(parse_event_line("data: {").unwrap().is_err());
+    }
+}
@@ -0,0 +1,263 @@
+//! An async variant of the driver, for polling the GPSDO from async services without spawning blocking threads. Only
+//! available with the `async` feature.
+
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+use core::future::Future;
+
+use crate::{
+    check_config_report_size, parse_config_report, parse_status_report,
+    reports::{CONFIG_REPORT_BUFFER_LEN, CONFIG_REPORT_ID, STATUS_REPORT_LEN},
+    FirmwareVersion, GpsdoConfig, GpsdoError, GpsdoModel, GpsdoStatus,
+};
+
+/// `Send` everywhere but on `wasm32`, where the futures of browser APIs such as WebHID can't be sent between threads,
+/// and there is only the one thread to run them on
+#[cfg(not(target_arch = "wasm32"))]
+pub trait MaybeSend: Send {}
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send> MaybeSend for T {}
+
+/// `Send` everywhere but on `wasm32`, where the futures of browser APIs such as WebHID can't be sent between threads,
+/// and there is only the one thread to run them on
+#[cfg(target_arch = "wasm32")]
+pub trait MaybeSend {}
+#[cfg(target_arch = "wasm32")]
+impl<T> MaybeSend for T {}
+
+/// The async counterpart of `UsbInterface`, for USB backends which don't block. Its futures are `Send`, other than on
+/// `wasm32`, see `MaybeSend`.
+pub trait AsyncUsbInterface {
+    type InterfaceError;
+
+    /// Read a set of bytes from the device, storing them in the passed buffer. The number of stored bytes should be returned
+    fn hid_read(
+        &self,
+        buf: &mut [u8],
+    ) -> impl Future<Output = Result<usize, Self::InterfaceError>> + MaybeSend;
+
+    /// Get a feature report from the device, in the same layout as `UsbInterface::hid_get_feature_report`
+    fn hid_get_feature_report(
+        &self,
+        report_id: u8,
+        buf: &mut [u8],
+    ) -> impl Future<Output = Result<usize, Self::InterfaceError>> + MaybeSend;
+
+    /// Send a feature report to the device, in the same layout as `UsbInterface::hid_send_feature_report`. Only
+    /// available with the `write` feature.
+    #[cfg(feature = "write")]
+    fn hid_send_feature_report(
+        &self,
+        report_id: u8,
+        data: &[u8],
+    ) -> impl Future<Output = Result<(), Self::InterfaceError>> + MaybeSend;
+
+    /// Get the serial number of the device. If no serial number exists on the device, then `Option::None`. Only
+    /// available with the `alloc` feature.
+    #[cfg(feature = "alloc")]
+    fn serial_number(
+        &self,
+    ) -> impl Future<Output = Result<Option<String>, Self::InterfaceError>> + MaybeSend;
+
+    /// Get the firmware version of the device as a binary coded decimal, such as `0x0105` for 1.05. Backends which
+    /// cannot determine it should return `Option::None`, which the default implementation does.
+    fn firmware_version(
+        &self,
+    ) -> impl Future<Output = Result<Option<u16>, Self::InterfaceError>> + MaybeSend {
+        async { Ok(None) }
+    }
+}
+
+/// The async counterpart of `GpsdoDevice`, a GPSDO reached through an `AsyncUsbInterface`
+pub struct AsyncGpsdoDevice<Interface: AsyncUsbInterface> {
+    interface: Interface,
+    model: GpsdoModel,
+}
+
+impl<Interface: AsyncUsbInterface> AsyncGpsdoDevice<Interface> {
+    /// Create a new AsyncGpsdoDevice, from the given AsyncUsbInterface, assuming it is a standard two output GPSDO
+    pub fn new(interface: Interface) -> Self {
+        Self::with_model(interface, GpsdoModel::Standard)
+    }
+
+    /// Create a new AsyncGpsdoDevice of a known model, from the given AsyncUsbInterface
+    pub fn with_model(interface: Interface, model: GpsdoModel) -> Self {
+        AsyncGpsdoDevice { interface, model }
+    }
+
+    /// The AsyncUsbInterface the device is reached through
+    pub fn interface(&self) -> &Interface {
+        &self.interface
+    }
+
+    /// Give up the device, returning its AsyncUsbInterface
+    pub fn into_interface(self) -> Interface {
+        self.interface
+    }
+
+    /// Retrieve the serial number of the GPSDO. Only available with the `alloc` feature.
+    #[cfg(feature = "alloc")]
+    pub async fn serial_number(
+        &self,
+    ) -> Result<Option<String>, GpsdoError<Interface::InterfaceError>> {
+        self.interface
+            .serial_number()
+            .await
+            .map_err(|source| GpsdoError::SerialNumberError { source })
+    }
+
+    /// Retrieve the firmware version of the GPSDO, if the interface can determine it
+    pub async fn firmware_version(
+        &self,
+    ) -> Result<Option<FirmwareVersion>, GpsdoError<Interface::InterfaceError>> {
+        self.interface
+            .firmware_version()
+            .await
+            .map(|version| version.map(FirmwareVersion))
+            .map_err(|source| GpsdoError::FirmwareVersionError { source })
+    }
+
+    /// Retrieve the config of the GPSDO
+    pub async fn config(&self) -> Result<GpsdoConfig, GpsdoError<Interface::InterfaceError>> {
+        let mut buf = [0u8; CONFIG_REPORT_BUFFER_LEN];
+
+        let size = self
+            .interface
+            .hid_get_feature_report(CONFIG_REPORT_ID, &mut buf)
+            .await
+            .map_err(|source| GpsdoError::FeatureReportError {
+                report_id: CONFIG_REPORT_ID,
+                source,
+            })?;
+        check_config_report_size(size)?;
+
+        Ok(parse_config_report(self.model, &buf))
+    }
+
+    /// Write a configuration to the GPSDO, refusing one which fails `GpsdoConfig::validate`, see
+    /// `GpsdoDevice::write_config`. Only available with the `write` feature.
+    #[cfg(feature = "write")]
+    pub async fn write_config(
+        &self,
+        config: &GpsdoConfig,
+    ) -> Result<(), GpsdoError<Interface::InterfaceError>> {
+        if let Some(error) = config.violations().next() {
+            return Err(GpsdoError::InvalidConfigError(error));
+        }
+
+        self.interface
+            .hid_send_feature_report(CONFIG_REPORT_ID, &config.to_report_bytes())
+            .await
+            .map_err(|source| GpsdoError::FeatureReportWriteError {
+                report_id: CONFIG_REPORT_ID,
+                source,
+            })
+    }
+
+    /// Retrieve the status of the GPSDO
+    pub async fn status(&self) -> Result<GpsdoStatus, GpsdoError<Interface::InterfaceError>> {
+        let mut buf = [0u8; STATUS_REPORT_LEN];
+        let read_count = self
+            .interface
+            .hid_read(&mut buf)
+            .await
+            .map_err(|source| GpsdoError::StatusReadError { source })?;
+
+        parse_status_report(&buf[..read_count])
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::{AsyncGpsdoDevice, AsyncUsbInterface};
+    use crate::GpsdoModel;
+
+    use core::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    /// Run a future which never has to wait, as the test interface answers straight away
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+
+        match future
+            .as_mut()
+            .poll(&mut Context::from_waker(Waker::noop()))
+        {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("test future was not ready"),
+        }
+    }
+
+    struct TestAsyncUsbInterface(&'static [u8], &'static [u8]);
+
+    impl AsyncUsbInterface for TestAsyncUsbInterface {
+        type InterfaceError = std::io::Error;
+
+        async fn hid_read(&self, buf: &mut [u8]) -> Result<usize, Self::InterfaceError> {
+            buf[..self.0.len()].copy_from_slice(self.0);
+            Ok(self.0.len())
+        }
+
+        async fn hid_get_feature_report(
+            &self,
+            _report_id: u8,
+            buf: &mut [u8],
+        ) -> Result<usize, Self::InterfaceError> {
+            buf[..self.1.len()].copy_from_slice(self.1);
+            Ok(self.1.len())
+        }
+
+        #[cfg(feature = "write")]
+        async fn hid_send_feature_report(
+            &self,
+            _report_id: u8,
+            _data: &[u8],
+        ) -> Result<(), Self::InterfaceError> {
+            Ok(())
+        }
+
+        async fn serial_number(&self) -> Result<Option<String>, Self::InterfaceError> {
+            Ok(Some("ABC123".to_owned()))
+        }
+    }
+
+    static CONFIG_REPORT: [u8; 21] = [
+        0x03, 0x00, 0xAB, 0x90, 0x41, 0x1D, 0x00, 0x00, 0x06, 0xFF, 0x0E, 0x00, 0x07, 0x09, 0x00,
+        0x00, 0x13, 0x00, 0x00, 0x00, 0x0F,
+    ];
+
+    #[test]
+    fn async_gpsdo_device_reads_status_and_config() {
+        let device = AsyncGpsdoDevice::with_model(
+            TestAsyncUsbInterface(&[0x03, 0x01], &CONFIG_REPORT),
+            GpsdoModel::Standard,
+        );
+
+        let status = block_on(device.status()).expect("expected success from status");
+        assert_eq!(status.loss_count(), 3);
+        assert!(!status.sat_locked());
+        assert!(status.pll_locked());
+
+        let config = block_on(device.config()).expect("expected success from config");
+        assert_eq!(config.checked_fout1(), Ok(50_000_000));
+        #[cfg(feature = "write")]
+        block_on(device.write_config(&config)).expect("expected success from write_config");
+
+        assert_eq!(
+            block_on(device.serial_number()).unwrap().as_deref(),
+            Some("ABC123")
+        );
+        assert_eq!(block_on(device.firmware_version()).unwrap(), None);
+    }
+
+    #[test]
+    fn async_gpsdo_device_rejects_short_reports() {
+        let device = AsyncGpsdoDevice::new(TestAsyncUsbInterface(&[0x03], &[0x03, 0x02]));
+
+        assert!(block_on(device.status()).is_err());
+        assert!(block_on(device.config()).is_err());
+    }
+}
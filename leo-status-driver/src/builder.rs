@@ -0,0 +1,251 @@
+use crate::{
+    planner::DividerPlan, Bandwidth, DriveLevel, GpsdoConfig, GpsdoConfigError, GpsdoModel,
+};
+
+#[derive(Debug, Clone)]
+/// Builds a `GpsdoConfig` from chosen dividers, outputs and drive level, such as to prepare one for
+/// `GpsdoDevice::write_config`. The fin, dividers, drive level and bandwidth must be set, the outputs default to
+/// enabled and the skew to 0. `nc2_ls` is only needed on models with output2.
+pub struct GpsdoConfigBuilder {
+    model: GpsdoModel,
+    output1: bool,
+    output2: bool,
+    level: Option<DriveLevel>,
+    fin: Option<u32>,
+    n3: Option<u32>,
+    n2_hs: Option<u8>,
+    n2_ls: Option<u32>,
+    n1_hs: Option<u8>,
+    nc1_ls: Option<u32>,
+    nc2_ls: Option<u32>,
+    skew: u8,
+    bandwidth: Option<Bandwidth>,
+}
+
+impl GpsdoConfigBuilder {
+    pub fn new(model: GpsdoModel) -> Self {
+        GpsdoConfigBuilder {
+            model,
+            output1: true,
+            output2: true,
+            level: None,
+            fin: None,
+            n3: None,
+            n2_hs: None,
+            n2_ls: None,
+            n1_hs: None,
+            nc1_ls: None,
+            nc2_ls: None,
+            skew: 0,
+            bandwidth: None,
+        }
+    }
+
+    pub fn output1(mut self, enabled: bool) -> Self {
+        self.output1 = enabled;
+        self
+    }
+
+    /// Enable or disable output2, ignored by models with a single output
+    pub fn output2(mut self, enabled: bool) -> Self {
+        self.output2 = enabled;
+        self
+    }
+
+    pub fn drive_level(mut self, level: DriveLevel) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    pub fn fin(mut self, fin: u32) -> Self {
+        self.fin = Some(fin);
+        self
+    }
+
+    pub fn n3(mut self, n3: u32) -> Self {
+        self.n3 = Some(n3);
+        self
+    }
+
+    pub fn n2_hs(mut self, n2_hs: u8) -> Self {
+        self.n2_hs = Some(n2_hs);
+        self
+    }
+
+    pub fn n2_ls(mut self, n2_ls: u32) -> Self {
+        self.n2_ls = Some(n2_ls);
+        self
+    }
+
+    pub fn n1_hs(mut self, n1_hs: u8) -> Self {
+        self.n1_hs = Some(n1_hs);
+        self
+    }
+
+    pub fn nc1_ls(mut self, nc1_ls: u32) -> Self {
+        self.nc1_ls = Some(nc1_ls);
+        self
+    }
+
+    pub fn nc2_ls(mut self, nc2_ls: u32) -> Self {
+        self.nc2_ls = Some(nc2_ls);
+        self
+    }
+
+    /// Set fin and every divider from a plan, such as one made by `planner::plan`
+    pub fn plan(self, plan: &DividerPlan) -> Self {
+        let builder = self
+            .fin(plan.fin())
+            .n3(plan.n3())
+            .n2_hs(plan.n2_hs())
+            .n2_ls(plan.n2_ls())
+            .n1_hs(plan.n1_hs())
+            .nc1_ls(plan.nc1_ls());
+
+        match plan.nc2_ls() {
+            Some(nc2_ls) => builder.nc2_ls(nc2_ls),
+            None => builder,
+        }
+    }
+
+    pub fn skew(mut self, skew: u8) -> Self {
+        self.skew = skew;
+        self
+    }
+
+    pub fn bandwidth(mut self, bandwidth: Bandwidth) -> Self {
+        self.bandwidth = Some(bandwidth);
+        self
+    }
+
+    /// Build the config, or an error for the first value which is missing or fails `GpsdoConfig::validate`
+    pub fn build(self) -> Result<GpsdoConfig, GpsdoConfigError> {
+        let level = required("level", self.level)?;
+        let fin = required("fin", self.fin)?;
+        let n3 = required("n3", self.n3)?;
+        let n2_hs = required("n2_hs", self.n2_hs)?;
+        let n2_ls = required("n2_ls", self.n2_ls)?;
+        let n1_hs = required("n1_hs", self.n1_hs)?;
+        let nc1_ls = required("nc1_ls", self.nc1_ls)?;
+
+        // Models without output2 never use nc2_ls, so it is stored the same as nc1_ls
+        let nc2_ls = if self.model.has_output2() {
+            required("nc2_ls", self.nc2_ls)?
+        } else {
+            nc1_ls
+        };
+
+        let bandwidth = required("bandwidth", self.bandwidth)?;
+
+        let config = GpsdoConfig {
+            model: self.model,
+            output1: self.output1,
+            output2: self.output2 && self.model.has_output2(),
+            level: level.raw(),
+            fin,
+            n3,
+            n2_hs,
+            n2_ls,
+            n1_hs,
+            nc1_ls,
+            nc2_ls,
+            skew: self.skew,
+            bw: bandwidth.raw(),
+        };
+
+        match config.violations().next() {
+            Some(error) => Err(error),
+            None => Ok(config),
+        }
+    }
+}
+
+fn required<T>(name: &'static str, value: Option<T>) -> Result<T, GpsdoConfigError> {
+    value.ok_or(GpsdoConfigError::Missing { name })
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::GpsdoConfigBuilder;
+    use crate::{planner, Bandwidth, DriveLevel, GpsdoConfigError, GpsdoModel};
+
+    /// A builder set up with the config the GPSDO ships with, 50 MHz on output1 and 25 MHz on output2
+    fn gpsdo_builder() -> GpsdoConfigBuilder {
+        GpsdoConfigBuilder::new(GpsdoModel::Standard)
+            .drive_level(DriveLevel::Ma8)
+            .fin(4_296_875)
+            .n3(30)
+            .n2_hs(10)
+            .n2_ls(3840)
+            .n1_hs(11)
+            .nc1_ls(10)
+            .nc2_ls(20)
+            .bandwidth(Bandwidth::Bwsel(15))
+    }
+
+    #[test]
+    fn build_produces_a_valid_config() {
+        let config = gpsdo_builder()
+            .output2(false)
+            .skew(3)
+            .build()
+            .expect("expected a config");
+
+        assert!(config.output1());
+        assert_eq!(config.output2(), Some(false));
+        assert_eq!(config.drive_level(), Some(DriveLevel::Ma8));
+        assert_eq!(config.skew(), 3);
+        assert_eq!(config.bandwidth(), Bandwidth::Bwsel(15));
+        assert_eq!(config.checked_fout1(), Ok(50_000_000));
+        assert_eq!(config.checked_fout2(), Ok(Some(25_000_000)));
+    }
+
+    #[test]
+    fn build_uses_a_plan_and_ignores_output2_on_the_mini() {
+        let plans = planner::plan(4_296_875, 10_000_000, None).expect("expected a plan");
+
+        let config = GpsdoConfigBuilder::new(GpsdoModel::Mini)
+            .plan(&plans[0])
+            .drive_level(DriveLevel::Ma32)
+            .bandwidth(Bandwidth::Bwsel(4))
+            .build()
+            .expect("expected a config");
+
+        assert_eq!(config.output2(), None);
+        assert_eq!(config.nc2_ls(), None);
+        assert_eq!(config.checked_fout1(), Ok(10_000_000));
+    }
+
+    #[test]
+    fn build_rejects_missing_and_out_of_range_values() {
+        assert_eq!(
+            GpsdoConfigBuilder::new(GpsdoModel::Standard)
+                .drive_level(DriveLevel::Ma8)
+                .fin(4_296_875)
+                .build()
+                .unwrap_err(),
+            GpsdoConfigError::Missing { name: "n3" }
+        );
+        assert_eq!(
+            gpsdo_builder().n1_hs(12).build().unwrap_err(),
+            GpsdoConfigError::DividerOutOfRange {
+                name: "n1_hs",
+                value: 12,
+                min: 4,
+                max: 11
+            }
+        );
+        assert_eq!(
+            gpsdo_builder().nc2_ls(21).build().unwrap_err(),
+            GpsdoConfigError::OddDivider {
+                name: "nc2_ls",
+                value: 21,
+                allow_one: true
+            }
+        );
+        assert!(matches!(
+            gpsdo_builder().n2_ls(4000).build().unwrap_err(),
+            GpsdoConfigError::FrequencyOutOfRange { name: "fosc", .. }
+        ));
+    }
+}
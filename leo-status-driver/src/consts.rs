@@ -6,3 +6,18 @@ pub const PID_LEO_BODNAR_GPSDO: u16 = 0x2210;
 
 // USB Product Identifier for the Leo Bodnar GPSDO
 pub const PID_LEO_BODNAR_MINI_GPSDO: u16 = 0x2211;
+
+// Lowest and highest values of the N1_HS and N2_HS high speed dividers of the Si5328
+pub const HS_DIVIDER_MIN: u8 = 4;
+pub const HS_DIVIDER_MAX: u8 = 11;
+
+// Lowest and highest phase detector frequency (f3) of the Si5328, in Hz
+pub const F3_MIN: u64 = 2_000;
+pub const F3_MAX: u64 = 2_000_000;
+
+// Lowest and highest oscillator frequency (fosc) of the Si5328, in Hz
+pub const FOSC_MIN: u64 = 4_850_000_000;
+pub const FOSC_MAX: u64 = 5_670_000_000;
+
+// Highest output frequency the GPSDOs are specified for, in Hz, below the highest the Si5328 can produce
+pub const FOUT_MAX: u64 = 800_000_000;
@@ -0,0 +1,201 @@
+//! Watch for Leo Bodnar GPSDOs being attached and detached, so applications can react without enumerating devices
+//! themselves. Only available with the `hidapi` feature.
+
+use hidapi::{HidApi, HidError};
+
+use std::{
+    collections::{btree_map::Entry, BTreeMap},
+    sync::mpsc::{self, RecvTimeoutError, Sender},
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use crate::{consts::VID_LEO_BONDAR, interface::GpsdoHidApiInterface, GpsdoDeviceInfo};
+
+/// A GPSDO appearing or disappearing
+#[derive(Debug, Clone)]
+pub enum HotplugEvent {
+    Attached(GpsdoDeviceInfo),
+    Detached(GpsdoDeviceInfo),
+}
+
+impl HotplugEvent {
+    /// The device which was attached or detached
+    pub fn info(&self) -> &GpsdoDeviceInfo {
+        match self {
+            HotplugEvent::Attached(info) | HotplugEvent::Detached(info) => info,
+        }
+    }
+}
+
+/// The GPSDOs seen by the last enumeration, by path, which tells apart devices without a serial number
+#[derive(Default)]
+struct AttachedDevices {
+    devices: BTreeMap<String, GpsdoDeviceInfo>,
+}
+
+impl AttachedDevices {
+    /// Record the devices found by an enumeration, returning the events which take the previous one to it, detaches
+    /// first
+    fn update(&mut self, found: impl IntoIterator<Item = GpsdoDeviceInfo>) -> Vec<HotplugEvent> {
+        let found = found
+            .into_iter()
+            .map(|info| (info.path().to_owned(), info))
+            .collect::<BTreeMap<_, _>>();
+
+        // A device replaced by another at the same path between enumerations is reported as detached and attached
+        let detached = self
+            .devices
+            .iter()
+            .filter(|(path, info)| {
+                found
+                    .get(*path)
+                    .is_none_or(|found| found.serial_number() != info.serial_number())
+            })
+            .map(|(path, _)| path.clone())
+            .collect::<Vec<_>>();
+        let mut events = detached
+            .iter()
+            .filter_map(|path| self.devices.remove(path))
+            .map(HotplugEvent::Detached)
+            .collect::<Vec<_>>();
+
+        for (path, info) in found {
+            if let Entry::Vacant(entry) = self.devices.entry(path) {
+                events.push(HotplugEvent::Attached(info.clone()));
+                entry.insert(info);
+            }
+        }
+
+        events
+    }
+}
+
+/// Enumerates the GPSDOs on a thread at an interval, calling back with each one attached or detached. The devices
+/// attached when it starts are reported as attached by the first enumeration. The thread stops when the watcher is
+/// dropped.
+pub struct HotplugWatcher {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl HotplugWatcher {
+    /// Start watching, enumerating every `interval` and calling `callback` on the watcher's thread for each event.
+    /// Enumeration errors after the first stop the watcher, as they mean hidapi can no longer see any device.
+    pub fn spawn<F>(interval: Duration, mut callback: F) -> Result<Self, HidError>
+    where
+        F: FnMut(HotplugEvent) + Send + 'static,
+    {
+        let mut hid_api = HidApi::new_without_enumerate()?;
+        enumerate(&mut hid_api)?;
+
+        let (stop, stopped) = mpsc::channel();
+        let thread = std::thread::Builder::new()
+            .name("gpsdo-hotplug".to_owned())
+            .spawn(move || {
+                let mut attached = AttachedDevices::default();
+
+                loop {
+                    let found = hid_api
+                        .device_list()
+                        .filter(|descriptor| GpsdoHidApiInterface::is_supported_vid_pid(descriptor))
+                        .map(GpsdoHidApiInterface::device_info);
+                    for event in attached.update(found) {
+                        callback(event);
+                    }
+
+                    match stopped.recv_timeout(interval) {
+                        Err(RecvTimeoutError::Timeout) => {}
+                        _ => return,
+                    }
+
+                    if enumerate(&mut hid_api).is_err() {
+                        return;
+                    }
+                }
+            })
+            .expect("failed to spawn hotplug thread");
+
+        Ok(HotplugWatcher {
+            stop: Some(stop),
+            thread: Some(thread),
+        })
+    }
+
+    /// Stop watching, waiting for the thread to finish any callback it is in
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        drop(self.stop.take());
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for HotplugWatcher {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Enumerate the devices of Leo Bodnar, rather than every HID device on the host
+fn enumerate(hid_api: &mut HidApi) -> Result<(), HidError> {
+    hid_api.reset_devices()?;
+    hid_api.add_devices(VID_LEO_BONDAR, 0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AttachedDevices, HotplugEvent};
+    use crate::{
+        consts::{PID_LEO_BODNAR_GPSDO, VID_LEO_BONDAR},
+        GpsdoDeviceInfo,
+    };
+
+    fn info(path: &str, serial_number: &str) -> GpsdoDeviceInfo {
+        GpsdoDeviceInfo::new(
+            VID_LEO_BONDAR,
+            PID_LEO_BODNAR_GPSDO,
+            Some(serial_number.to_owned()),
+            path.to_owned(),
+            None,
+            0,
+        )
+    }
+
+    fn describe(events: &[HotplugEvent]) -> Vec<(bool, &str)> {
+        events
+            .iter()
+            .map(|event| {
+                (
+                    matches!(event, HotplugEvent::Attached(_)),
+                    event.info().serial_number().unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn attached_devices_reports_changes_between_enumerations() {
+        let mut attached = AttachedDevices::default();
+
+        let events = attached.update([info("/dev/hidraw0", "AAAA"), info("/dev/hidraw1", "BBBB")]);
+        assert_eq!(describe(&events), [(true, "AAAA"), (true, "BBBB")]);
+
+        let events = attached.update([info("/dev/hidraw0", "AAAA"), info("/dev/hidraw1", "BBBB")]);
+        assert!(events.is_empty());
+
+        let events = attached.update([info("/dev/hidraw1", "BBBB"), info("/dev/hidraw2", "CCCC")]);
+        assert_eq!(describe(&events), [(false, "AAAA"), (true, "CCCC")]);
+
+        let events = attached.update([info("/dev/hidraw1", "DDDD"), info("/dev/hidraw2", "CCCC")]);
+        assert_eq!(describe(&events), [(false, "BBBB"), (true, "DDDD")]);
+
+        let events = attached.update([]);
+        assert_eq!(describe(&events), [(false, "DDDD"), (false, "CCCC")]);
+    }
+}
@@ -1,17 +1,45 @@
 use hidapi::{DeviceInfo, HidApi, HidDevice, HidError};
 
+#[cfg(target_os = "linux")]
+use super::sysfs::port_path;
 use crate::{
     consts::{PID_LEO_BODNAR_GPSDO, PID_LEO_BODNAR_MINI_GPSDO, VID_LEO_BONDAR},
-    UsbInterface,
+    GpsdoDeviceInfo, UsbInterface,
 };
 
 pub struct GpsdoHidApiInterface<'a> {
-    driver: &'a HidDevice,
+    driver: Driver<'a>,
+}
+
+/// The HidDevice an interface uses, which it either borrows or owns
+enum Driver<'a> {
+    Borrowed(&'a HidDevice),
+    Owned(HidDevice),
+}
+
+impl core::ops::Deref for Driver<'_> {
+    type Target = HidDevice;
+
+    fn deref(&self) -> &HidDevice {
+        match self {
+            Driver::Borrowed(driver) => driver,
+            Driver::Owned(driver) => driver,
+        }
+    }
 }
 
 impl<'a> GpsdoHidApiInterface<'a> {
     pub fn new(driver: &'a HidDevice) -> Self {
-        Self { driver }
+        Self {
+            driver: Driver::Borrowed(driver),
+        }
+    }
+
+    /// Create an interface which owns its HidDevice, such as to keep a `GpsdoDevice` in long-lived state
+    pub fn owned(driver: HidDevice) -> GpsdoHidApiInterface<'static> {
+        GpsdoHidApiInterface {
+            driver: Driver::Owned(driver),
+        }
     }
 
     pub fn is_supported_vid_pid(descriptor: &DeviceInfo) -> bool {
@@ -21,27 +49,94 @@ impl<'a> GpsdoHidApiInterface<'a> {
             && (product_id == PID_LEO_BODNAR_GPSDO || product_id == PID_LEO_BODNAR_MINI_GPSDO)
     }
 
-    pub fn find_gpsdo<'b>(
-        hid_api: &'b HidApi,
-        serial_number: Option<String>,
-    ) -> Option<&'b DeviceInfo> {
-        match serial_number {
-            // Look for a device that matches the serial number and is from Leo Bodnar
-            Some(serial_number) => hid_api.device_list().find(|&descriptor| {
-                descriptor.vendor_id() == VID_LEO_BONDAR
-                    && descriptor
-                        .serial_number()
-                        .is_some_and(|device_serial| device_serial == serial_number)
-            }),
-
-            // Look for any device that is a GPSDO
-            None => hid_api
-                .device_list()
-                .find(|&descriptor| Self::is_supported_vid_pid(descriptor)),
-        }
+    /// Describe a device found while enumerating, including where it is attached to the host
+    pub fn device_info(descriptor: &DeviceInfo) -> GpsdoDeviceInfo {
+        let path = descriptor.path().to_string_lossy().into_owned();
+        let port_path = port_path(&path);
+
+        GpsdoDeviceInfo::new(
+            descriptor.vendor_id(),
+            descriptor.product_id(),
+            descriptor.serial_number().map(str::to_owned),
+            path,
+            port_path,
+            descriptor.interface_number(),
+        )
+        .with_product_strings(
+            descriptor.manufacturer_string().map(str::to_owned),
+            descriptor.product_string().map(str::to_owned),
+        )
+    }
+
+    /// Describe the open device, including where it is attached to the host
+    pub fn info(&self) -> Result<GpsdoDeviceInfo, HidError> {
+        Ok(Self::device_info(&self.driver.get_device_info()?))
+    }
+
+    pub fn find_gpsdo(hid_api: &HidApi, serial_number: Option<String>) -> Option<&DeviceInfo> {
+        Self::find_gpsdos(hid_api, serial_number.as_deref()).next()
+    }
+
+    /// Find every device which matches, in the order they were enumerated
+    pub fn find_gpsdos<'h, 's>(
+        hid_api: &'h HidApi,
+        serial_number: Option<&'s str>,
+    ) -> impl Iterator<Item = &'h DeviceInfo> + use<'h, 's> {
+        hid_api
+            .device_list()
+            .filter(move |&descriptor| match serial_number {
+                // Look for a device that matches the serial number and is from Leo Bodnar
+                Some(serial_number) => {
+                    descriptor.vendor_id() == VID_LEO_BONDAR
+                        && descriptor
+                            .serial_number()
+                            .is_some_and(|device_serial| device_serial == serial_number)
+                }
+
+                // Look for any device that is a GPSDO
+                None => Self::is_supported_vid_pid(descriptor),
+            })
+    }
+
+    /// Find every matching device and try to open it, so devices which can't be opened, such as for lack of
+    /// permission, are reported rather than skipped
+    pub fn probe(hid_api: &HidApi, serial_number: Option<&str>) -> Vec<GpsdoProbe> {
+        Self::find_gpsdos(hid_api, serial_number)
+            .map(|descriptor| GpsdoProbe {
+                info: Self::device_info(descriptor),
+                error: descriptor.open_device(hid_api).err(),
+            })
+            .collect()
     }
 }
 
+/// A GPSDO found while enumerating, and the error which stopped it being opened if it is not accessible
+#[derive(Debug)]
+pub struct GpsdoProbe {
+    info: GpsdoDeviceInfo,
+    error: Option<HidError>,
+}
+
+impl GpsdoProbe {
+    pub fn info(&self) -> &GpsdoDeviceInfo {
+        &self.info
+    }
+
+    /// The error from opening the device, or `None` if it could be opened
+    pub fn error(&self) -> Option<&HidError> {
+        self.error.as_ref()
+    }
+
+    pub fn is_accessible(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn port_path(_path: &str) -> Option<String> {
+    None
+}
+
 impl<'a> UsbInterface for GpsdoHidApiInterface<'a> {
     type InterfaceError = HidError;
 
@@ -53,6 +148,16 @@ impl<'a> UsbInterface for GpsdoHidApiInterface<'a> {
         self.driver.get_serial_number_string()
     }
 
+    /// The GPSDO reports no firmware version in its HID reports, so the release number of its USB device descriptor
+    /// is used, which the firmware sets to its version
+    fn firmware_version(&self) -> Result<Option<u16>, Self::InterfaceError> {
+        Ok(Some(self.driver.get_device_info()?.release_number()))
+    }
+
+    fn device_info(&self) -> Result<Option<GpsdoDeviceInfo>, Self::InterfaceError> {
+        self.info().map(Some)
+    }
+
     fn hid_get_feature_report(
         &self,
         report_id: u8,
@@ -73,4 +178,17 @@ impl<'a> UsbInterface for GpsdoHidApiInterface<'a> {
 
         Ok(size)
     }
+
+    #[cfg(feature = "write")]
+    fn hid_send_feature_report(
+        &self,
+        report_id: u8,
+        data: &[u8],
+    ) -> Result<(), Self::InterfaceError> {
+        let mut buf = Vec::with_capacity(data.len() + 1);
+        buf.push(report_id);
+        buf.extend_from_slice(data);
+
+        self.driver.send_feature_report(&buf)
+    }
 }
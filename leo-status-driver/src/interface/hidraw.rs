@@ -0,0 +1,288 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Read},
+    os::fd::AsRawFd,
+    path::{Path, PathBuf},
+};
+
+use super::sysfs::port_path;
+use crate::{
+    consts::{PID_LEO_BODNAR_GPSDO, PID_LEO_BODNAR_MINI_GPSDO, VID_LEO_BONDAR},
+    GpsdoDeviceInfo, UsbInterface,
+};
+
+/// The hidraw ioctl numbers for getting and setting a feature report of `len` bytes, `HIDIOCGFEATURE` and
+/// `HIDIOCSFEATURE` from linux/hidraw.h, in the ioctl encoding of most architectures
+fn hidiocgfeature(len: usize) -> libc::c_ulong {
+    ioc_read_write(0x07, len)
+}
+
+#[cfg(feature = "write")]
+fn hidiocsfeature(len: usize) -> libc::c_ulong {
+    ioc_read_write(0x06, len)
+}
+
+fn ioc_read_write(nr: libc::c_ulong, len: usize) -> libc::c_ulong {
+    const IOC_READ_WRITE: libc::c_ulong = 3;
+    const HIDRAW_TYPE: libc::c_ulong = b'H' as libc::c_ulong;
+
+    (IOC_READ_WRITE << 30) | ((len as libc::c_ulong & 0x3FFF) << 16) | (HIDRAW_TYPE << 8) | nr
+}
+
+/// A GPSDO opened through its `/dev/hidraw*` node directly, without hidapi or any other C library, for minimal
+/// container images and static musl builds. Only available on Linux.
+pub struct GpsdoHidrawInterface {
+    file: File,
+    path: PathBuf,
+}
+
+impl GpsdoHidrawInterface {
+    /// Open a hidraw node, such as `/dev/hidraw0`, for reading and writing
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+
+        Ok(Self {
+            file,
+            path: path.to_owned(),
+        })
+    }
+
+    /// The hidraw node the interface was opened from
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Find every hidraw node of a GPSDO, or with a serial number every Leo Bodnar device with that serial number, in
+    /// the order of their node numbers. Devices are matched by the vendor and product identifiers in sysfs, so they
+    /// are found even if they can't be opened.
+    pub fn find_gpsdos(serial_number: Option<&str>) -> io::Result<Vec<GpsdoDeviceInfo>> {
+        let mut nodes = vec![];
+        for entry in fs::read_dir("/sys/class/hidraw")? {
+            let name = entry?.file_name().to_string_lossy().into_owned();
+            let Some(number) = name
+                .strip_prefix("hidraw")
+                .and_then(|number| number.parse::<u32>().ok())
+            else {
+                continue;
+            };
+
+            nodes.push((number, name));
+        }
+        nodes.sort();
+
+        Ok(nodes
+            .into_iter()
+            .filter_map(|(_, name)| device_info(&name))
+            .filter(|info| match serial_number {
+                Some(serial_number) => {
+                    info.vendor_id() == VID_LEO_BONDAR
+                        && info.serial_number() == Some(serial_number)
+                }
+                None => {
+                    info.vendor_id() == VID_LEO_BONDAR
+                        && (info.product_id() == PID_LEO_BODNAR_GPSDO
+                            || info.product_id() == PID_LEO_BODNAR_MINI_GPSDO)
+                }
+            })
+            .collect())
+    }
+
+    /// The name of the node in sysfs, such as `hidraw0`
+    fn name(&self) -> io::Result<&str> {
+        self.path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| io::Error::other("hidraw path has no node name"))
+    }
+}
+
+/// Describe a hidraw node from sysfs, `None` if it isn't a USB HID device
+fn device_info(name: &str) -> Option<GpsdoDeviceInfo> {
+    let uevent = fs::read_to_string(format!("/sys/class/hidraw/{}/device/uevent", name)).ok()?;
+    let uevent = parse_uevent(&uevent)?;
+
+    let path = format!("/dev/{}", name);
+    let port_path = port_path(&path);
+    let interface_number = usb_attribute(name, "bInterfaceNumber")
+        .and_then(|number| i32::from_str_radix(&number, 16).ok())
+        .unwrap_or(-1);
+
+    Some(
+        GpsdoDeviceInfo::new(
+            uevent.vendor_id,
+            uevent.product_id,
+            uevent.serial_number,
+            path,
+            port_path,
+            interface_number,
+        )
+        .with_product_strings(
+            usb_attribute(name, "manufacturer"),
+            usb_attribute(name, "product"),
+        ),
+    )
+}
+
+/// Read an attribute of the USB interface or device a hidraw node belongs to, such as `bcdDevice`, from the nearest
+/// ancestor of its HID device in sysfs which has it
+fn usb_attribute(name: &str, attribute: &str) -> Option<String> {
+    let device = fs::canonicalize(format!("/sys/class/hidraw/{}/device", name)).ok()?;
+
+    device
+        .ancestors()
+        .skip(1)
+        .take_while(|ancestor| ancestor.starts_with("/sys/devices"))
+        .find_map(|ancestor| fs::read_to_string(ancestor.join(attribute)).ok())
+        .map(|value| value.trim().to_owned())
+}
+
+/// The identity of a HID device, from the `uevent` of its sysfs directory
+#[derive(Debug, PartialEq)]
+struct HidUevent {
+    vendor_id: u16,
+    product_id: u16,
+    serial_number: Option<String>,
+}
+
+/// Parse the `HID_ID=<bus>:<vendor>:<product>` and `HID_UNIQ=<serial number>` lines of a uevent, returning `None` for
+/// devices which aren't on USB
+fn parse_uevent(uevent: &str) -> Option<HidUevent> {
+    const BUS_USB: &str = "0003";
+
+    let mut id = None;
+    let mut serial_number = None;
+    for line in uevent.lines() {
+        if let Some(value) = line.strip_prefix("HID_ID=") {
+            id = Some(value);
+        } else if let Some(value) = line.strip_prefix("HID_UNIQ=") {
+            serial_number = Some(value).filter(|value| !value.is_empty());
+        }
+    }
+
+    let mut parts = id?.split(':');
+    if parts.next()? != BUS_USB {
+        return None;
+    }
+
+    Some(HidUevent {
+        vendor_id: u16::try_from(u32::from_str_radix(parts.next()?, 16).ok()?).ok()?,
+        product_id: u16::try_from(u32::from_str_radix(parts.next()?, 16).ok()?).ok()?,
+        serial_number: serial_number.map(str::to_owned),
+    })
+}
+
+impl UsbInterface for GpsdoHidrawInterface {
+    type InterfaceError = io::Error;
+
+    fn hid_read(&self, buf: &mut [u8]) -> Result<usize, Self::InterfaceError> {
+        (&self.file).read(buf)
+    }
+
+    /// The report is returned as the kernel gives it, like `GpsdoHidApiInterface` on Linux
+    fn hid_get_feature_report(
+        &self,
+        report_id: u8,
+        buf: &mut [u8],
+    ) -> Result<usize, Self::InterfaceError> {
+        assert!(!buf.is_empty());
+        buf[0] = report_id;
+
+        // SAFETY: the ioctl writes at most buf.len() bytes, the length encoded in its number
+        let size = unsafe {
+            libc::ioctl(
+                self.file.as_raw_fd(),
+                hidiocgfeature(buf.len()) as _,
+                buf.as_mut_ptr(),
+            )
+        };
+        if size < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(size as usize)
+    }
+
+    #[cfg(feature = "write")]
+    fn hid_send_feature_report(
+        &self,
+        report_id: u8,
+        data: &[u8],
+    ) -> Result<(), Self::InterfaceError> {
+        let mut buf = Vec::with_capacity(data.len() + 1);
+        buf.push(report_id);
+        buf.extend_from_slice(data);
+
+        // SAFETY: the ioctl reads at most buf.len() bytes, the length encoded in its number
+        let result = unsafe {
+            libc::ioctl(
+                self.file.as_raw_fd(),
+                hidiocsfeature(buf.len()) as _,
+                buf.as_ptr(),
+            )
+        };
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    fn serial_number(&self) -> Result<Option<String>, Self::InterfaceError> {
+        let uevent =
+            fs::read_to_string(format!("/sys/class/hidraw/{}/device/uevent", self.name()?))?;
+
+        Ok(parse_uevent(&uevent).and_then(|uevent| uevent.serial_number))
+    }
+
+    /// The release number of the USB device descriptor, which the firmware sets to its version, as with
+    /// `GpsdoHidApiInterface`
+    fn firmware_version(&self) -> Result<Option<u16>, Self::InterfaceError> {
+        Ok(usb_attribute(self.name()?, "bcdDevice")
+            .and_then(|version| u16::from_str_radix(&version, 16).ok()))
+    }
+
+    /// Described from sysfs, like the devices found by `find_gpsdos`
+    fn device_info(&self) -> Result<Option<GpsdoDeviceInfo>, Self::InterfaceError> {
+        Ok(device_info(self.name()?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ioc_read_write, parse_uevent, HidUevent};
+
+    #[test]
+    fn hidraw_feature_ioctl_numbers_match_the_kernel_headers() {
+        // HIDIOCGFEATURE(61) and HIDIOCSFEATURE(61) as computed by linux/hidraw.h
+        assert_eq!(ioc_read_write(0x07, 61), 0xC03D_4807);
+        assert_eq!(ioc_read_write(0x06, 61), 0xC03D_4806);
+    }
+
+    #[test]
+    fn parse_uevent_reads_usb_hid_identity() {
+        let uevent = "DRIVER=hid-generic\nHID_ID=0003:00001DD2:00002210\nHID_NAME=Leo Bodnar GPS Reference Clock\nHID_PHYS=usb-0000:00:14.0-2/input0\nHID_UNIQ=AAAA-BBBB\nMODALIAS=hid:b0003g0001v00001DD2p00002210\n";
+
+        assert_eq!(
+            parse_uevent(uevent),
+            Some(HidUevent {
+                vendor_id: 0x1DD2,
+                product_id: 0x2210,
+                serial_number: Some("AAAA-BBBB".to_owned()),
+            })
+        );
+    }
+
+    #[test]
+    fn parse_uevent_skips_devices_off_usb() {
+        assert_eq!(
+            parse_uevent("HID_ID=0005:0000046D:0000B016\nHID_UNIQ=\n"),
+            None
+        );
+        assert_eq!(
+            parse_uevent("HID_ID=0003:00001DD2:00002210\nHID_UNIQ=\n")
+                .and_then(|uevent| uevent.serial_number),
+            None
+        );
+    }
+}
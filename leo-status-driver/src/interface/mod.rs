@@ -1,5 +1,35 @@
 #[cfg(feature = "hidapi")]
 mod hidapi;
+#[cfg(all(feature = "hidraw", target_os = "linux"))]
+mod hidraw;
+#[cfg(feature = "nusb")]
+mod nusb;
+#[cfg(feature = "std")]
+mod reconnecting;
+#[cfg(feature = "remote")]
+mod remote;
+#[cfg(feature = "std")]
+mod retrying;
+#[cfg(feature = "rusb")]
+mod rusb;
+#[cfg(all(target_os = "linux", any(feature = "hidapi", feature = "hidraw")))]
+mod sysfs;
+#[cfg(all(feature = "webhid", target_arch = "wasm32"))]
+mod webhid;
 
 #[cfg(feature = "hidapi")]
 pub use hidapi::GpsdoHidApiInterface;
+#[cfg(all(feature = "hidraw", target_os = "linux"))]
+pub use hidraw::GpsdoHidrawInterface;
+#[cfg(feature = "nusb")]
+pub use nusb::GpsdoNusbInterface;
+#[cfg(feature = "std")]
+pub use reconnecting::{ReconnectingError, ReconnectingInterface};
+#[cfg(feature = "remote")]
+pub use remote::{GpsdoRemoteInterface, RemoteInterfaceError};
+#[cfg(feature = "std")]
+pub use retrying::RetryingInterface;
+#[cfg(feature = "rusb")]
+pub use rusb::GpsdoRusbInterface;
+#[cfg(all(feature = "webhid", target_arch = "wasm32"))]
+pub use webhid::{GpsdoWebHidInterface, WebHidError};
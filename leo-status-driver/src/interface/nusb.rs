@@ -0,0 +1,231 @@
+#[cfg(feature = "write")]
+use nusb::transfer::ControlOut;
+use nusb::{
+    descriptors::TransferType,
+    transfer::{ControlIn, ControlType, Direction, In, Interrupt, Recipient},
+    DeviceInfo, Endpoint, Interface, MaybeFuture,
+};
+
+use std::{io, sync::Mutex, time::Duration};
+
+use crate::{
+    consts::{PID_LEO_BODNAR_GPSDO, PID_LEO_BODNAR_MINI_GPSDO, VID_LEO_BONDAR},
+    GpsdoDeviceInfo, UsbInterface,
+};
+
+/// The HID class code of an interface
+const CLASS_HID: u8 = 0x03;
+
+/// The HID class requests for getting and setting a report, see the HID specification section 7.2
+const HID_GET_REPORT: u8 = 0x01;
+#[cfg(feature = "write")]
+const HID_SET_REPORT: u8 = 0x09;
+
+/// The report type of a feature report, in the high byte of wValue
+const REPORT_TYPE_FEATURE: u16 = 0x03;
+
+/// How long a transfer may take before it fails, enough for the GPSDO to send its next status report
+const TRANSFER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A GPSDO reached through nusb, which talks to the USB stack of the OS directly from Rust, so a program using it links
+/// neither hidapi nor libusb, for cross-compiling to routers and minimal containers. Like `GpsdoRusbInterface` it makes
+/// raw USB transfers, and opening it detaches the kernel's HID driver from the GPSDO for as long as it is open.
+pub struct GpsdoNusbInterface {
+    info: DeviceInfo,
+    interface: Interface,
+    endpoint_in: Mutex<Endpoint<Interrupt, In>>,
+}
+
+impl GpsdoNusbInterface {
+    /// Open a GPSDO found while enumerating, claiming its HID interface
+    pub fn new(info: DeviceInfo) -> io::Result<Self> {
+        let number = info
+            .interfaces()
+            .find(|interface| interface.class() == CLASS_HID)
+            .map(|interface| interface.interface_number())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "gpsdo has no hid interface"))?;
+
+        let interface = info
+            .open()
+            .wait()?
+            .detach_and_claim_interface(number)
+            .wait()?;
+
+        // The status reports arrive on the interrupt IN endpoint of the HID interface
+        let address = interface
+            .descriptor()
+            .and_then(|descriptor| {
+                descriptor
+                    .endpoints()
+                    .find(|endpoint| {
+                        endpoint.direction() == Direction::In
+                            && endpoint.transfer_type() == TransferType::Interrupt
+                    })
+                    .map(|endpoint| endpoint.address())
+            })
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::NotFound, "gpsdo has no interrupt endpoint")
+            })?;
+        let endpoint_in = interface.endpoint::<Interrupt, In>(address)?;
+
+        Ok(Self {
+            info,
+            interface,
+            endpoint_in: Mutex::new(endpoint_in),
+        })
+    }
+
+    /// Find every device which matches, in the order they were enumerated, like `GpsdoHidApiInterface::find_gpsdos`
+    pub fn find_gpsdos(serial_number: Option<&str>) -> io::Result<Vec<DeviceInfo>> {
+        Ok(nusb::list_devices()
+            .wait()?
+            .filter(|info| match serial_number {
+                Some(serial_number) => {
+                    info.vendor_id() == VID_LEO_BONDAR
+                        && info.serial_number() == Some(serial_number)
+                }
+                None => {
+                    info.vendor_id() == VID_LEO_BONDAR
+                        && (info.product_id() == PID_LEO_BODNAR_GPSDO
+                            || info.product_id() == PID_LEO_BODNAR_MINI_GPSDO)
+                }
+            })
+            .collect())
+    }
+
+    /// Open the first GPSDO which can be opened, or the one with the given serial number. Returns `None` if there is no
+    /// such device.
+    pub fn open(serial_number: Option<&str>) -> io::Result<Option<Self>> {
+        Ok(Self::find_gpsdos(serial_number)?
+            .into_iter()
+            .find_map(|info| Self::new(info).ok()))
+    }
+
+    /// Describe a device found while enumerating, including where it is attached to the host. The path is the bus and
+    /// device address, such as `1:004`, and the port path the bus and ports, such as `1-2.3`.
+    pub fn device_info(info: &DeviceInfo) -> GpsdoDeviceInfo {
+        let ports: Vec<String> = info.port_chain().iter().map(u8::to_string).collect();
+        let port_path =
+            (!ports.is_empty()).then(|| format!("{}-{}", info.bus_id(), ports.join(".")));
+
+        let interface_number = info
+            .interfaces()
+            .find(|interface| interface.class() == CLASS_HID)
+            .map_or(-1, |interface| interface.interface_number().into());
+
+        GpsdoDeviceInfo::new(
+            info.vendor_id(),
+            info.product_id(),
+            info.serial_number().map(str::to_owned),
+            format!("{}:{:03}", info.bus_id(), info.device_address()),
+            port_path,
+            interface_number,
+        )
+        .with_product_strings(
+            info.manufacturer_string().map(str::to_owned),
+            info.product_string().map(str::to_owned),
+        )
+    }
+
+    /// The nusb interface of the GPSDO
+    pub fn interface(&self) -> &Interface {
+        &self.interface
+    }
+}
+
+impl UsbInterface for GpsdoNusbInterface {
+    type InterfaceError = io::Error;
+
+    fn hid_read(&self, buf: &mut [u8]) -> Result<usize, Self::InterfaceError> {
+        let mut endpoint = self
+            .endpoint_in
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+
+        // Interrupt transfers are read a whole packet at a time
+        let request = endpoint.allocate(endpoint.max_packet_size());
+        let report = endpoint
+            .transfer_blocking(request, TRANSFER_TIMEOUT)
+            .into_result()?;
+
+        let len = report.len().min(buf.len());
+        buf[..len].copy_from_slice(&report[..len]);
+
+        Ok(len)
+    }
+
+    /// Numbered reports arrive with their report id first, which is removed to match the other backends
+    fn hid_get_feature_report(
+        &self,
+        report_id: u8,
+        buf: &mut [u8],
+    ) -> Result<usize, Self::InterfaceError> {
+        let report = self
+            .interface
+            .control_in(
+                ControlIn {
+                    control_type: ControlType::Class,
+                    recipient: Recipient::Interface,
+                    request: HID_GET_REPORT,
+                    value: (REPORT_TYPE_FEATURE << 8) | report_id as u16,
+                    index: self.interface.interface_number().into(),
+                    length: buf.len().min(u16::MAX as usize) as u16,
+                },
+                TRANSFER_TIMEOUT,
+            )
+            .wait()?;
+
+        let data = match report.split_first() {
+            Some((_, data)) if report_id != 0 => data,
+            _ => &report[..],
+        };
+        let len = data.len().min(buf.len());
+        buf[..len].copy_from_slice(&data[..len]);
+
+        Ok(len)
+    }
+
+    #[cfg(feature = "write")]
+    fn hid_send_feature_report(
+        &self,
+        report_id: u8,
+        data: &[u8],
+    ) -> Result<(), Self::InterfaceError> {
+        // Numbered reports are sent with their report id first, unnumbered ones without it
+        let mut buf = Vec::with_capacity(data.len() + 1);
+        if report_id != 0 {
+            buf.push(report_id);
+        }
+        buf.extend_from_slice(data);
+
+        self.interface
+            .control_out(
+                ControlOut {
+                    control_type: ControlType::Class,
+                    recipient: Recipient::Interface,
+                    request: HID_SET_REPORT,
+                    value: (REPORT_TYPE_FEATURE << 8) | report_id as u16,
+                    index: self.interface.interface_number().into(),
+                    data: &buf,
+                },
+                TRANSFER_TIMEOUT,
+            )
+            .wait()?;
+
+        Ok(())
+    }
+
+    fn serial_number(&self) -> Result<Option<String>, Self::InterfaceError> {
+        Ok(self.info.serial_number().map(str::to_owned))
+    }
+
+    /// The release number of the USB device descriptor, which the firmware sets to its version, as with
+    /// `GpsdoHidApiInterface`
+    fn firmware_version(&self) -> Result<Option<u16>, Self::InterfaceError> {
+        Ok(Some(self.info.device_version()))
+    }
+
+    fn device_info(&self) -> Result<Option<GpsdoDeviceInfo>, Self::InterfaceError> {
+        Ok(Some(Self::device_info(&self.info)))
+    }
+}
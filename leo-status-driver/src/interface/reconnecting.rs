@@ -0,0 +1,268 @@
+use thiserror::Error;
+
+use std::{
+    sync::{Mutex, MutexGuard},
+    time::Duration,
+};
+
+use crate::{GpsdoDeviceInfo, UsbInterface};
+
+/// How long to wait before re-opening the device, unless set with `with_retry_delay`
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// How many times a call is retried after an error, unless set with `with_retries`
+const DEFAULT_RETRIES: u32 = 3;
+
+#[derive(Debug, Error)]
+/// An error from a `ReconnectingInterface`, once its retries have run out
+pub enum ReconnectingError<InterfaceError> {
+    #[error("{0}")]
+    Interface(InterfaceError),
+
+    #[error("re-opened a different gpsdo, with serial number {found:?} rather than {expected:?}")]
+    DifferentDevice {
+        expected: String,
+        found: Option<String>,
+    },
+}
+
+/// Wraps another interface, and whenever a call to it fails, drops it, re-opens the device through `open` and retries
+/// the call. The serial number of the first device opened is remembered, and a re-opened device with another serial
+/// number is rejected, so a wrapper never silently moves to a different GPSDO. Only available with the `std` feature.
+pub struct ReconnectingInterface<F, Interface> {
+    open: F,
+    interface: Mutex<Option<Interface>>,
+    serial_number: Mutex<Option<String>>,
+    retry_delay: Duration,
+    retries: u32,
+}
+
+impl<F, Interface> ReconnectingInterface<F, Interface>
+where
+    F: Fn() -> Result<Interface, Interface::InterfaceError>,
+    Interface: UsbInterface,
+{
+    /// Wrap the interfaces returned by `open`, which should find and open the device, such as by serial number. The
+    /// device is opened on the first call.
+    pub fn new(open: F) -> Self {
+        ReconnectingInterface {
+            open,
+            interface: Mutex::new(None),
+            serial_number: Mutex::new(None),
+            retry_delay: DEFAULT_RETRY_DELAY,
+            retries: DEFAULT_RETRIES,
+        }
+    }
+
+    /// Wait this long before each attempt to re-open the device
+    pub fn with_retry_delay(mut self, retry_delay: Duration) -> Self {
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// Retry a failed call this many times, re-opening the device before each, before returning the error
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    fn interface(&self) -> MutexGuard<'_, Option<Interface>> {
+        self.interface
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+
+    /// Open the device, checking it is the one opened first
+    fn open(&self) -> Result<Interface, ReconnectingError<Interface::InterfaceError>> {
+        let interface = (self.open)().map_err(ReconnectingError::Interface)?;
+        let found = interface
+            .serial_number()
+            .map_err(ReconnectingError::Interface)?;
+
+        let mut serial_number = self
+            .serial_number
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        match (&*serial_number, found) {
+            (Some(expected), found) if found.as_ref() != Some(expected) => {
+                Err(ReconnectingError::DifferentDevice {
+                    expected: expected.clone(),
+                    found,
+                })
+            }
+            (_, found) => {
+                *serial_number = serial_number.take().or(found);
+                Ok(interface)
+            }
+        }
+    }
+
+    /// Make a call to the interface, opening it first if it isn't open, and re-opening it after each error until the
+    /// retries run out. The interface stays locked throughout, so calls from other threads wait for the reconnect.
+    fn call<T>(
+        &self,
+        mut call: impl FnMut(&Interface) -> Result<T, Interface::InterfaceError>,
+    ) -> Result<T, ReconnectingError<Interface::InterfaceError>> {
+        let mut interface = self.interface();
+        let mut attempt = 0;
+
+        loop {
+            let result = match &*interface {
+                Some(open) => call(open).map_err(ReconnectingError::Interface),
+                None => self.open().and_then(|open| {
+                    let result = call(&open).map_err(ReconnectingError::Interface);
+                    *interface = Some(open);
+                    result
+                }),
+            };
+
+            match result {
+                Ok(value) => return Ok(value),
+                Err(error) if attempt >= self.retries => {
+                    *interface = None;
+                    return Err(error);
+                }
+                Err(_) => {
+                    *interface = None;
+                    attempt += 1;
+                    std::thread::sleep(self.retry_delay);
+                }
+            }
+        }
+    }
+}
+
+impl<F, Interface> UsbInterface for ReconnectingInterface<F, Interface>
+where
+    F: Fn() -> Result<Interface, Interface::InterfaceError>,
+    Interface: UsbInterface,
+{
+    type InterfaceError = ReconnectingError<Interface::InterfaceError>;
+
+    fn hid_read(&self, buf: &mut [u8]) -> Result<usize, Self::InterfaceError> {
+        self.call(|interface| interface.hid_read(buf))
+    }
+
+    fn hid_get_feature_report(
+        &self,
+        report_id: u8,
+        buf: &mut [u8],
+    ) -> Result<usize, Self::InterfaceError> {
+        self.call(|interface| interface.hid_get_feature_report(report_id, buf))
+    }
+
+    #[cfg(feature = "write")]
+    fn hid_send_feature_report(
+        &self,
+        report_id: u8,
+        data: &[u8],
+    ) -> Result<(), Self::InterfaceError> {
+        self.call(|interface| interface.hid_send_feature_report(report_id, data))
+    }
+
+    fn serial_number(&self) -> Result<Option<String>, Self::InterfaceError> {
+        self.call(|interface| interface.serial_number())
+    }
+
+    fn firmware_version(&self) -> Result<Option<u16>, Self::InterfaceError> {
+        self.call(|interface| interface.firmware_version())
+    }
+
+    fn device_info(&self) -> Result<Option<GpsdoDeviceInfo>, Self::InterfaceError> {
+        self.call(|interface| interface.device_info())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::Cell, io, time::Duration};
+
+    use super::{ReconnectingError, ReconnectingInterface};
+    use crate::UsbInterface;
+
+    /// One opening of a device, whose reads fail if it was unplugged
+    struct Opened {
+        serial_number: &'static str,
+        unplugged: bool,
+    }
+
+    impl UsbInterface for Opened {
+        type InterfaceError = io::Error;
+
+        fn hid_read(&self, buf: &mut [u8]) -> Result<usize, Self::InterfaceError> {
+            if self.unplugged {
+                return Err(io::Error::other("unplugged"));
+            }
+
+            buf[..2].copy_from_slice(&[4, 0]);
+            Ok(2)
+        }
+
+        fn hid_get_feature_report(
+            &self,
+            _report_id: u8,
+            _buf: &mut [u8],
+        ) -> Result<usize, Self::InterfaceError> {
+            Err(io::Error::from(io::ErrorKind::Unsupported))
+        }
+
+        #[cfg(feature = "write")]
+        fn hid_send_feature_report(
+            &self,
+            _report_id: u8,
+            _data: &[u8],
+        ) -> Result<(), Self::InterfaceError> {
+            Err(io::Error::from(io::ErrorKind::Unsupported))
+        }
+
+        fn serial_number(&self) -> Result<Option<String>, Self::InterfaceError> {
+            Ok(Some(self.serial_number.to_owned()))
+        }
+    }
+
+    #[test]
+    fn reconnecting_interface_reopens_after_an_error() {
+        let opens = Cell::new(0);
+        let interface = ReconnectingInterface::new(|| {
+            opens.set(opens.get() + 1);
+            Ok(Opened {
+                serial_number: "AAAA-BBBB",
+                unplugged: opens.get() == 1,
+            })
+        })
+        .with_retry_delay(Duration::ZERO);
+
+        let mut buf = [0u8; 2];
+        assert_eq!(interface.hid_read(&mut buf).unwrap(), 2);
+        assert_eq!(buf, [4, 0]);
+        assert_eq!(opens.get(), 2);
+
+        interface.hid_read(&mut buf).unwrap();
+        assert_eq!(opens.get(), 2);
+    }
+
+    #[test]
+    fn reconnecting_interface_rejects_a_different_device() {
+        let opens = Cell::new(0);
+        let interface = ReconnectingInterface::new(|| {
+            opens.set(opens.get() + 1);
+            Ok(Opened {
+                serial_number: if opens.get() == 1 {
+                    "AAAA-BBBB"
+                } else {
+                    "CCCC-DDDD"
+                },
+                unplugged: opens.get() == 1,
+            })
+        })
+        .with_retry_delay(Duration::ZERO)
+        .with_retries(2);
+
+        assert!(matches!(
+            interface.hid_read(&mut [0u8; 2]),
+            Err(ReconnectingError::DifferentDevice { expected, found })
+                if expected == "AAAA-BBBB" && found.as_deref() == Some("CCCC-DDDD")
+        ));
+        assert_eq!(opens.get(), 3);
+    }
+}
@@ -0,0 +1,247 @@
+use thiserror::Error;
+use ureq::Agent;
+
+use std::time::Duration;
+
+use crate::{GpsdoDeviceInfo, UsbInterface};
+
+/// How long a request to the remote instance may take before it fails, enough for it to wait for a status report
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Error)]
+/// An error which stopped a request to the remote instance
+pub enum RemoteInterfaceError {
+    #[error("request to remote instance failed: {0}")]
+    Http(#[from] ureq::Error),
+
+    #[error("invalid response from remote instance: {0}")]
+    InvalidResponse(String),
+}
+
+/// A GPSDO attached to another host, reached through the device proxy of the leo-status instance running there. Every
+/// request carries the proxy token as a bearer token, so use an `https` URL, such as through a reverse proxy, wherever
+/// the network can't be trusted.
+pub struct GpsdoRemoteInterface {
+    agent: Agent,
+    url: String,
+    token: String,
+}
+
+impl GpsdoRemoteInterface {
+    /// Reach the GPSDO of the instance serving HTTP at `url`, such as `http://lab-pi:8000`, with its proxy token
+    pub fn new(url: &str, token: &str) -> Self {
+        GpsdoRemoteInterface {
+            agent: Agent::config_builder()
+                .timeout_global(Some(REQUEST_TIMEOUT))
+                .build()
+                .into(),
+            url: url.trim_end_matches('/').to_owned(),
+            token: token.to_owned(),
+        }
+    }
+
+    fn endpoint(&self, path: &str) -> String {
+        format!("{}/proxy/{}", self.url, path)
+    }
+
+    fn authorization(&self) -> String {
+        format!("Bearer {}", self.token)
+    }
+
+    /// GET an endpoint of the proxy, returning its body, or `None` if the proxy answered 204 No Content
+    fn get(&self, path: &str) -> Result<Option<Vec<u8>>, RemoteInterfaceError> {
+        let mut response = self
+            .agent
+            .get(&self.endpoint(path))
+            .header("Authorization", &self.authorization())
+            .call()?;
+
+        if response.status() == 204 {
+            return Ok(None);
+        }
+
+        Ok(Some(response.body_mut().read_to_vec()?))
+    }
+
+    /// GET an endpoint of the proxy which answers with text, `None` if it answered 204 No Content
+    fn get_text(&self, path: &str) -> Result<Option<String>, RemoteInterfaceError> {
+        self.get(path)?
+            .map(|body| {
+                String::from_utf8(body).map_err(|_| {
+                    RemoteInterfaceError::InvalidResponse(format!("{} is not utf-8", path))
+                })
+            })
+            .transpose()
+    }
+
+    /// GET an endpoint of the proxy which answers with a report, copying it into buf
+    fn get_report(&self, path: &str, buf: &mut [u8]) -> Result<usize, RemoteInterfaceError> {
+        let report = self.get(path)?.unwrap_or_default();
+
+        let len = report.len().min(buf.len());
+        buf[..len].copy_from_slice(&report[..len]);
+
+        Ok(len)
+    }
+}
+
+impl UsbInterface for GpsdoRemoteInterface {
+    type InterfaceError = RemoteInterfaceError;
+
+    fn hid_read(&self, buf: &mut [u8]) -> Result<usize, Self::InterfaceError> {
+        self.get_report("input-report", buf)
+    }
+
+    fn hid_get_feature_report(
+        &self,
+        report_id: u8,
+        buf: &mut [u8],
+    ) -> Result<usize, Self::InterfaceError> {
+        self.get_report(&format!("feature-report/{}", report_id), buf)
+    }
+
+    #[cfg(feature = "write")]
+    fn hid_send_feature_report(
+        &self,
+        report_id: u8,
+        data: &[u8],
+    ) -> Result<(), Self::InterfaceError> {
+        self.agent
+            .post(&self.endpoint(&format!("feature-report/{}", report_id)))
+            .header("Authorization", &self.authorization())
+            .header("Content-Type", "application/octet-stream")
+            .send(data)?;
+
+        Ok(())
+    }
+
+    fn serial_number(&self) -> Result<Option<String>, Self::InterfaceError> {
+        self.get_text("serial-number")
+    }
+
+    fn firmware_version(&self) -> Result<Option<u16>, Self::InterfaceError> {
+        self.get_text("firmware-version")?
+            .map(|version| {
+                version.trim().parse().map_err(|_| {
+                    RemoteInterfaceError::InvalidResponse(format!(
+                        "firmware version {:?} is not a number",
+                        version
+                    ))
+                })
+            })
+            .transpose()
+    }
+
+    /// Describes the GPSDO as it is attached to the remote host
+    fn device_info(&self) -> Result<Option<GpsdoDeviceInfo>, Self::InterfaceError> {
+        self.get("device-info")?
+            .map(|body| {
+                serde_json::from_slice(&body).map_err(|error| {
+                    RemoteInterfaceError::InvalidResponse(format!(
+                        "device info is not valid: {}",
+                        error
+                    ))
+                })
+            })
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tiny_http::{Response, Server};
+
+    use super::{GpsdoRemoteInterface, RemoteInterfaceError};
+    use crate::{consts, GpsdoDevice, GpsdoDeviceInfo};
+
+    /// Serve the proxy endpoints with a status and config report of the GPSDO, until the server is dropped
+    fn serve_proxy() -> (String, std::thread::JoinHandle<()>) {
+        let server = Server::http("127.0.0.1:0").expect("failed to start test server");
+        let url = format!("http://{}", server.server_addr().to_ip().unwrap());
+
+        let handle = std::thread::spawn(move || {
+            for request in server.incoming_requests() {
+                let authorized = request.headers().iter().any(|header| {
+                    header.field.equiv("Authorization") && header.value == "Bearer secret"
+                });
+
+                let response = match (authorized, request.url()) {
+                    (false, _) => Response::from_data(vec![]).with_status_code(401),
+                    (true, "/proxy/input-report") => Response::from_data(vec![0x03, 0x01]),
+                    (true, "/proxy/feature-report/9") => {
+                        let mut report = vec![0u8; 61];
+                        report[..21].copy_from_slice(&[
+                            0x03, 0x00, 0xAB, 0x90, 0x41, 0x1D, 0x00, 0x00, 0x06, 0xFF, 0x0E, 0x00,
+                            0x07, 0x09, 0x00, 0x00, 0x13, 0x00, 0x00, 0x00, 0x0F,
+                        ]);
+                        Response::from_data(report)
+                    }
+                    (true, "/proxy/serial-number") => Response::from_data(b"AAAA-BBBB".to_vec()),
+                    (true, "/proxy/firmware-version") => {
+                        Response::from_data(vec![]).with_status_code(204)
+                    }
+                    (true, "/proxy/device-info") => Response::from_data(
+                        serde_json::to_vec(
+                            &GpsdoDeviceInfo::new(
+                                consts::VID_LEO_BONDAR,
+                                consts::PID_LEO_BODNAR_GPSDO,
+                                Some("AAAA-BBBB".to_owned()),
+                                "/dev/hidraw0".to_owned(),
+                                Some("1-2".to_owned()),
+                                0,
+                            )
+                            .with_product_strings(Some("Leo Bodnar".to_owned()), None),
+                        )
+                        .unwrap(),
+                    ),
+                    (true, "/proxy/stop") => return,
+                    _ => Response::from_data(vec![]).with_status_code(404),
+                };
+
+                request.respond(response).expect("failed to respond");
+            }
+        });
+
+        (url, handle)
+    }
+
+    #[test]
+    fn gpsdo_remote_interface_reads_through_the_proxy() {
+        let (url, handle) = serve_proxy();
+
+        let device = GpsdoDevice::new(GpsdoRemoteInterface::new(&format!("{}/", url), "secret"));
+
+        let status = device.status().expect("expected success from status");
+        assert_eq!(status.loss_count(), 3);
+        assert!(!status.sat_locked());
+
+        let config = device.config().expect("expected success from config");
+        assert_eq!(config.checked_fout1(), Ok(50_000_000));
+
+        assert_eq!(
+            device.serial_number().unwrap().as_deref(),
+            Some("AAAA-BBBB")
+        );
+        assert_eq!(device.firmware_version().unwrap(), None);
+
+        let info = device
+            .device_info()
+            .expect("expected success from device_info")
+            .expect("expected device info");
+        assert_eq!(info.product_id(), consts::PID_LEO_BODNAR_GPSDO);
+        assert_eq!(info.path(), "/dev/hidraw0");
+        assert_eq!(info.port_path(), Some("1-2"));
+        assert_eq!(info.manufacturer(), Some("Leo Bodnar"));
+
+        let unauthorized = GpsdoDevice::new(GpsdoRemoteInterface::new(&url, "wrong"));
+        assert!(matches!(
+            unauthorized.status(),
+            Err(crate::GpsdoError::StatusReadError {
+                source: RemoteInterfaceError::Http(ureq::Error::StatusCode(401))
+            })
+        ));
+
+        let _ = GpsdoRemoteInterface::new(&url, "secret").get("stop");
+        handle.join().expect("test server panicked");
+    }
+}
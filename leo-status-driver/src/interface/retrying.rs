@@ -0,0 +1,200 @@
+use std::time::Duration;
+
+use crate::{GpsdoDeviceInfo, UsbInterface};
+
+/// How many times a call is made before its error is returned, unless set with `with_attempts`
+const DEFAULT_ATTEMPTS: u32 = 3;
+
+/// How long to wait before the first retry, unless set with `with_backoff`
+const DEFAULT_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Wraps another interface, making each call again when it fails, for transient errors such as those on long USB runs
+/// behind hubs. The wait before each retry doubles from the backoff. Unlike `ReconnectingInterface` the device is not
+/// re-opened, so use this for errors the open device recovers from. Only available with the `std` feature.
+pub struct RetryingInterface<Interface: UsbInterface> {
+    interface: Interface,
+    attempts: u32,
+    backoff: Duration,
+    retry_if: fn(&Interface::InterfaceError) -> bool,
+}
+
+impl<Interface: UsbInterface> RetryingInterface<Interface> {
+    /// Retry every failed call to `interface`
+    pub fn new(interface: Interface) -> Self {
+        RetryingInterface {
+            interface,
+            attempts: DEFAULT_ATTEMPTS,
+            backoff: DEFAULT_BACKOFF,
+            retry_if: |_| true,
+        }
+    }
+
+    /// Make each call at most this many times, including the first, before returning the last error
+    pub fn with_attempts(mut self, attempts: u32) -> Self {
+        self.attempts = attempts.max(1);
+        self
+    }
+
+    /// Wait this long before the first retry, doubling for each retry after
+    pub fn with_backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Only retry errors for which `retry_if` is true, returning any other at once
+    pub fn with_retry_if(mut self, retry_if: fn(&Interface::InterfaceError) -> bool) -> Self {
+        self.retry_if = retry_if;
+        self
+    }
+
+    /// The wrapped interface
+    pub fn interface(&self) -> &Interface {
+        &self.interface
+    }
+
+    fn call<T>(
+        &self,
+        mut call: impl FnMut(&Interface) -> Result<T, Interface::InterfaceError>,
+    ) -> Result<T, Interface::InterfaceError> {
+        let mut backoff = self.backoff;
+        let mut attempt = 1;
+
+        loop {
+            match call(&self.interface) {
+                Err(error) if attempt < self.attempts && (self.retry_if)(&error) => {
+                    std::thread::sleep(backoff);
+                    backoff *= 2;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl<Interface: UsbInterface> UsbInterface for RetryingInterface<Interface> {
+    type InterfaceError = Interface::InterfaceError;
+
+    fn hid_read(&self, buf: &mut [u8]) -> Result<usize, Self::InterfaceError> {
+        self.call(|interface| interface.hid_read(buf))
+    }
+
+    fn hid_get_feature_report(
+        &self,
+        report_id: u8,
+        buf: &mut [u8],
+    ) -> Result<usize, Self::InterfaceError> {
+        self.call(|interface| interface.hid_get_feature_report(report_id, buf))
+    }
+
+    #[cfg(feature = "write")]
+    fn hid_send_feature_report(
+        &self,
+        report_id: u8,
+        data: &[u8],
+    ) -> Result<(), Self::InterfaceError> {
+        self.call(|interface| interface.hid_send_feature_report(report_id, data))
+    }
+
+    fn serial_number(&self) -> Result<Option<String>, Self::InterfaceError> {
+        self.call(|interface| interface.serial_number())
+    }
+
+    fn firmware_version(&self) -> Result<Option<u16>, Self::InterfaceError> {
+        self.call(|interface| interface.firmware_version())
+    }
+
+    fn device_info(&self) -> Result<Option<GpsdoDeviceInfo>, Self::InterfaceError> {
+        self.call(|interface| interface.device_info())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::Cell, io, time::Duration};
+
+    use super::RetryingInterface;
+    use crate::UsbInterface;
+
+    /// Fails its first `failures` reads with the given kind of error
+    struct Flaky {
+        failures: Cell<u32>,
+        kind: io::ErrorKind,
+        reads: Cell<u32>,
+    }
+
+    impl Flaky {
+        fn new(failures: u32, kind: io::ErrorKind) -> Self {
+            Flaky {
+                failures: Cell::new(failures),
+                kind,
+                reads: Cell::new(0),
+            }
+        }
+    }
+
+    impl UsbInterface for Flaky {
+        type InterfaceError = io::Error;
+
+        fn hid_read(&self, buf: &mut [u8]) -> Result<usize, Self::InterfaceError> {
+            self.reads.set(self.reads.get() + 1);
+            if self.failures.get() > 0 {
+                self.failures.set(self.failures.get() - 1);
+                return Err(io::Error::from(self.kind));
+            }
+
+            buf[0] = 7;
+            Ok(1)
+        }
+
+        fn hid_get_feature_report(
+            &self,
+            _report_id: u8,
+            _buf: &mut [u8],
+        ) -> Result<usize, Self::InterfaceError> {
+            Err(io::Error::from(io::ErrorKind::Unsupported))
+        }
+
+        #[cfg(feature = "write")]
+        fn hid_send_feature_report(
+            &self,
+            _report_id: u8,
+            _data: &[u8],
+        ) -> Result<(), Self::InterfaceError> {
+            Err(io::Error::from(io::ErrorKind::Unsupported))
+        }
+
+        fn serial_number(&self) -> Result<Option<String>, Self::InterfaceError> {
+            Ok(None)
+        }
+    }
+
+    #[test]
+    fn retrying_interface_retries_until_attempts_run_out() {
+        let interface = RetryingInterface::new(Flaky::new(2, io::ErrorKind::TimedOut))
+            .with_backoff(Duration::ZERO);
+
+        let mut buf = [0u8; 1];
+        assert_eq!(interface.hid_read(&mut buf).unwrap(), 1);
+        assert_eq!(buf, [7]);
+        assert_eq!(interface.interface().reads.get(), 3);
+
+        let interface = RetryingInterface::new(Flaky::new(3, io::ErrorKind::TimedOut))
+            .with_backoff(Duration::ZERO);
+        assert!(interface.hid_read(&mut buf).is_err());
+        assert_eq!(interface.interface().reads.get(), 3);
+    }
+
+    #[test]
+    fn retrying_interface_returns_other_errors_at_once() {
+        let interface = RetryingInterface::new(Flaky::new(1, io::ErrorKind::NotFound))
+            .with_backoff(Duration::ZERO)
+            .with_retry_if(|error| error.kind() == io::ErrorKind::TimedOut);
+
+        assert_eq!(
+            interface.hid_read(&mut [0u8; 1]).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+        assert_eq!(interface.interface().reads.get(), 1);
+    }
+}
@@ -0,0 +1,240 @@
+use rusb::{
+    request_type, Device, DeviceHandle, Direction, Recipient, RequestType, TransferType, UsbContext,
+};
+
+use std::time::Duration;
+
+use crate::{
+    consts::{PID_LEO_BODNAR_GPSDO, PID_LEO_BODNAR_MINI_GPSDO, VID_LEO_BONDAR},
+    GpsdoDeviceInfo, UsbInterface,
+};
+
+/// The HID class code of an interface
+const CLASS_HID: u8 = 0x03;
+
+/// The HID class requests for getting and setting a report, see the HID specification section 7.2
+const HID_GET_REPORT: u8 = 0x01;
+#[cfg(feature = "write")]
+const HID_SET_REPORT: u8 = 0x09;
+
+/// The report type of a feature report, in the high byte of wValue
+const REPORT_TYPE_FEATURE: u16 = 0x03;
+
+/// How long a transfer may take before it fails, enough for the GPSDO to send its next status report
+const TRANSFER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A GPSDO reached through raw libusb transfers rather than the HID stack of the OS, for platforms without hidapi, or
+/// where the hidraw device is held by another process. Opening the interface detaches the kernel's HID driver from the
+/// GPSDO for as long as the interface is open.
+pub struct GpsdoRusbInterface<T: UsbContext> {
+    handle: DeviceHandle<T>,
+    interface: u8,
+    endpoint_in: u8,
+}
+
+impl<T: UsbContext> GpsdoRusbInterface<T> {
+    /// Claim the HID interface of an open GPSDO, detaching the kernel's driver from it where the platform allows
+    pub fn new(handle: DeviceHandle<T>) -> rusb::Result<Self> {
+        let (interface, endpoint_in) = hid_interface(&handle.device())?;
+
+        match handle.set_auto_detach_kernel_driver(true) {
+            Ok(()) | Err(rusb::Error::NotSupported) => {}
+            Err(error) => return Err(error),
+        }
+        handle.claim_interface(interface)?;
+
+        Ok(Self {
+            handle,
+            interface,
+            endpoint_in,
+        })
+    }
+
+    /// Open the first GPSDO which can be opened, or the one with the given serial number, like
+    /// `GpsdoHidApiInterface::find_gpsdos`. Returns `None` if there is no such device.
+    pub fn open(context: &T, serial_number: Option<&str>) -> rusb::Result<Option<Self>> {
+        for device in context.devices()?.iter() {
+            let descriptor = device.device_descriptor()?;
+            let product_id = descriptor.product_id();
+
+            let matches = match serial_number {
+                Some(_) => descriptor.vendor_id() == VID_LEO_BONDAR,
+                None => {
+                    descriptor.vendor_id() == VID_LEO_BONDAR
+                        && (product_id == PID_LEO_BODNAR_GPSDO
+                            || product_id == PID_LEO_BODNAR_MINI_GPSDO)
+                }
+            };
+            if !matches {
+                continue;
+            }
+
+            let Ok(handle) = device.open() else {
+                continue;
+            };
+            if let Some(serial_number) = serial_number {
+                if handle
+                    .read_serial_number_string_ascii(&descriptor)
+                    .ok()
+                    .as_deref()
+                    != Some(serial_number)
+                {
+                    continue;
+                }
+            }
+
+            return Self::new(handle).map(Some);
+        }
+
+        Ok(None)
+    }
+
+    /// The libusb handle of the GPSDO
+    pub fn handle(&self) -> &DeviceHandle<T> {
+        &self.handle
+    }
+}
+
+impl<T: UsbContext> Drop for GpsdoRusbInterface<T> {
+    fn drop(&mut self) {
+        // Auto detach reattaches the kernel's driver once the interface is released
+        let _ = self.handle.release_interface(self.interface);
+    }
+}
+
+/// Find the HID interface of a device and its interrupt IN endpoint, which carries the status reports
+fn hid_interface<T: UsbContext>(device: &Device<T>) -> rusb::Result<(u8, u8)> {
+    let config = device.active_config_descriptor()?;
+
+    config
+        .interfaces()
+        .flat_map(|interface| interface.descriptors())
+        .filter(|descriptor| descriptor.class_code() == CLASS_HID)
+        .find_map(|descriptor| {
+            descriptor
+                .endpoint_descriptors()
+                .find(|endpoint| {
+                    endpoint.direction() == Direction::In
+                        && endpoint.transfer_type() == TransferType::Interrupt
+                })
+                .map(|endpoint| (descriptor.interface_number(), endpoint.address()))
+        })
+        .ok_or(rusb::Error::NotFound)
+}
+
+impl<T: UsbContext> UsbInterface for GpsdoRusbInterface<T> {
+    type InterfaceError = rusb::Error;
+
+    fn hid_read(&self, buf: &mut [u8]) -> Result<usize, Self::InterfaceError> {
+        self.handle
+            .read_interrupt(self.endpoint_in, buf, TRANSFER_TIMEOUT)
+    }
+
+    /// Numbered reports arrive with their report id first, which is removed to match the other backends
+    fn hid_get_feature_report(
+        &self,
+        report_id: u8,
+        buf: &mut [u8],
+    ) -> Result<usize, Self::InterfaceError> {
+        let size = self.handle.read_control(
+            request_type(Direction::In, RequestType::Class, Recipient::Interface),
+            HID_GET_REPORT,
+            (REPORT_TYPE_FEATURE << 8) | report_id as u16,
+            self.interface as u16,
+            buf,
+            TRANSFER_TIMEOUT,
+        )?;
+
+        if report_id != 0 && size > 0 {
+            buf.copy_within(1..size, 0);
+            return Ok(size - 1);
+        }
+
+        Ok(size)
+    }
+
+    #[cfg(feature = "write")]
+    fn hid_send_feature_report(
+        &self,
+        report_id: u8,
+        data: &[u8],
+    ) -> Result<(), Self::InterfaceError> {
+        // Numbered reports are sent with their report id first, unnumbered ones without it
+        let mut buf = Vec::with_capacity(data.len() + 1);
+        if report_id != 0 {
+            buf.push(report_id);
+        }
+        buf.extend_from_slice(data);
+
+        self.handle.write_control(
+            request_type(Direction::Out, RequestType::Class, Recipient::Interface),
+            HID_SET_REPORT,
+            (REPORT_TYPE_FEATURE << 8) | report_id as u16,
+            self.interface as u16,
+            &buf,
+            TRANSFER_TIMEOUT,
+        )?;
+
+        Ok(())
+    }
+
+    fn serial_number(&self) -> Result<Option<String>, Self::InterfaceError> {
+        let descriptor = self.handle.device().device_descriptor()?;
+        if descriptor.serial_number_string_index().is_none() {
+            return Ok(None);
+        }
+
+        self.handle
+            .read_serial_number_string_ascii(&descriptor)
+            .map(Some)
+    }
+
+    /// The release number of the USB device descriptor, which the firmware sets to its version, as with
+    /// `GpsdoHidApiInterface`
+    fn firmware_version(&self) -> Result<Option<u16>, Self::InterfaceError> {
+        let version = self.handle.device().device_descriptor()?.device_version();
+
+        // rusb decodes the BCD release number, so encode it back into the raw form hidapi reports
+        let major = version.major() as u16;
+        Ok(Some(
+            (major / 10) << 12
+                | (major % 10) << 8
+                | (version.minor() as u16) << 4
+                | version.sub_minor() as u16,
+        ))
+    }
+
+    /// The path is the bus and device address libusb opened the GPSDO at, such as `001:004` as taken by `lsusb -s`
+    fn device_info(&self) -> Result<Option<GpsdoDeviceInfo>, Self::InterfaceError> {
+        let device = self.handle.device();
+        let descriptor = device.device_descriptor()?;
+
+        // A string the descriptor has no index for isn't on the device
+        let string = |index: Option<u8>| {
+            index
+                .map(|index| self.handle.read_string_descriptor_ascii(index))
+                .transpose()
+        };
+        let serial_number = string(descriptor.serial_number_string_index())?;
+        let manufacturer = string(descriptor.manufacturer_string_index())?;
+        let product = string(descriptor.product_string_index())?;
+
+        let ports = device.port_numbers()?;
+        let port_path = (!ports.is_empty()).then(|| {
+            let ports: Vec<String> = ports.iter().map(u8::to_string).collect();
+            format!("{}-{}", device.bus_number(), ports.join("."))
+        });
+
+        Ok(Some(
+            GpsdoDeviceInfo::new(
+                descriptor.vendor_id(),
+                descriptor.product_id(),
+                serial_number,
+                format!("{:03}:{:03}", device.bus_number(), device.address()),
+                port_path,
+                self.interface.into(),
+            )
+            .with_product_strings(manufacturer, product),
+        ))
+    }
+}
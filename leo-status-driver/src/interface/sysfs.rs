@@ -0,0 +1,21 @@
+/// Find the USB port path of a hidraw device, by following its sysfs link up to the USB interface it belongs to. The
+/// interface directory is named `<bus>-<port path>:<config>.<interface>`, such as `1-2.3:1.0`.
+pub(crate) fn port_path(path: &str) -> Option<String> {
+    let name = path.strip_prefix("/dev/")?;
+    let device = std::fs::canonicalize(format!("/sys/class/hidraw/{}/device", name)).ok()?;
+
+    device
+        .ancestors()
+        .filter_map(|ancestor| ancestor.file_name()?.to_str())
+        .find_map(|component| {
+            let (port_path, interface) = component.split_once(':')?;
+
+            let is_port_path = port_path.contains('-')
+                && port_path
+                    .chars()
+                    .all(|c| c.is_ascii_digit() || c == '-' || c == '.');
+            let is_interface = interface.chars().all(|c| c.is_ascii_digit() || c == '.');
+
+            (is_port_path && is_interface).then(|| port_path.to_owned())
+        })
+}
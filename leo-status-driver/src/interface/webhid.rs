@@ -0,0 +1,219 @@
+use js_sys::{DataView, Uint8Array};
+use thiserror::Error;
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use web_sys::{HidDevice, HidDeviceFilter, HidDeviceRequestOptions, HidInputReportEvent};
+
+use std::{
+    cell::RefCell,
+    future::poll_fn,
+    rc::Rc,
+    task::{Poll, Waker},
+};
+
+use crate::{
+    consts::{PID_LEO_BODNAR_GPSDO, PID_LEO_BODNAR_MINI_GPSDO, VID_LEO_BONDAR},
+    AsyncUsbInterface, GpsdoDeviceInfo,
+};
+
+#[derive(Debug, Error)]
+/// An error which stopped a GPSDO being reached through WebHID
+pub enum WebHidError {
+    #[error("webhid is not available, it needs a secure context in a browser which supports it")]
+    Unavailable,
+
+    #[error("webhid request failed: {0}")]
+    Browser(String),
+}
+
+impl From<JsValue> for WebHidError {
+    fn from(value: JsValue) -> Self {
+        let message = match value.dyn_ref::<js_sys::Error>() {
+            Some(error) => String::from(error.message()),
+            None => value.as_string().unwrap_or_else(|| format!("{:?}", value)),
+        };
+
+        WebHidError::Browser(message)
+    }
+}
+
+/// The input reports the browser hands the page as the GPSDO sends them, waiting for `hid_read`
+#[derive(Default)]
+struct InputReports {
+    /// The latest status report the GPSDO sent which hasn't been read yet
+    unread: Option<Vec<u8>>,
+
+    /// The read waiting for the next report, if any
+    waker: Option<Waker>,
+}
+
+/// A GPSDO reached through WebHID, for reading it from a browser page in a `wasm32` build with `AsyncGpsdoDevice`.
+/// Only available with the `webhid` feature, and on `wasm32` targets, built with `--cfg=web_sys_unstable_apis` as
+/// web-sys requires for WebHID.
+///
+/// `hid_read` waits for the next status report the GPSDO sends, as a blocking read does on other backends, and feature
+/// reports are fetched from and sent to the GPSDO as they're asked for. Serial numbers and firmware versions aren't
+/// exposed by WebHID, so are `None`.
+pub struct GpsdoWebHidInterface {
+    device: HidDevice,
+    input: Rc<RefCell<InputReports>>,
+    _on_input_report: Closure<dyn FnMut(HidInputReportEvent)>,
+}
+
+impl GpsdoWebHidInterface {
+    /// Ask the user to choose a GPSDO, then open it. The browser only shows the chooser in response to a user gesture,
+    /// such as a click. Returns `None` if no GPSDO was chosen.
+    pub async fn request() -> Result<Option<Self>, WebHidError> {
+        let navigator = web_sys::window()
+            .ok_or(WebHidError::Unavailable)?
+            .navigator();
+        let hid = navigator.hid();
+        if JsValue::from(&hid).is_undefined() {
+            return Err(WebHidError::Unavailable);
+        }
+
+        let filters: Vec<HidDeviceFilter> = [PID_LEO_BODNAR_GPSDO, PID_LEO_BODNAR_MINI_GPSDO]
+            .into_iter()
+            .map(|product_id| {
+                let filter = HidDeviceFilter::new();
+                filter.set_vendor_id(VID_LEO_BONDAR.into());
+                filter.set_product_id(product_id);
+                filter
+            })
+            .collect();
+
+        let devices = hid
+            .request_device(&HidDeviceRequestOptions::new(&filters))
+            .await?;
+        let Some(device) = devices.into_iter().next() else {
+            return Ok(None);
+        };
+
+        Ok(Some(Self::open(device).await?))
+    }
+
+    /// Open a GPSDO the user has already granted the page, such as one from `navigator.hid.getDevices()`, and listen
+    /// for its status reports
+    pub async fn open(device: HidDevice) -> Result<Self, WebHidError> {
+        if !device.opened() {
+            device.open().await?;
+        }
+
+        let input = Rc::new(RefCell::new(InputReports::default()));
+        let on_input_report = {
+            let input = input.clone();
+            Closure::new(move |event: HidInputReportEvent| {
+                let mut input = input.borrow_mut();
+                input.unread = Some(data_view_bytes(&event.data()));
+                if let Some(waker) = input.waker.take() {
+                    waker.wake();
+                }
+            })
+        };
+        device.set_oninputreport(Some(on_input_report.as_ref().unchecked_ref()));
+
+        Ok(GpsdoWebHidInterface {
+            device,
+            input,
+            _on_input_report: on_input_report,
+        })
+    }
+
+    /// The WebHID device of the GPSDO
+    pub fn device(&self) -> &HidDevice {
+        &self.device
+    }
+
+    /// Describe the GPSDO as far as WebHID can, for `GpsdoModel::from_product_id`
+    pub fn device_info(&self) -> GpsdoDeviceInfo {
+        GpsdoDeviceInfo::new(
+            self.device.vendor_id(),
+            self.device.product_id(),
+            None,
+            "webhid".to_owned(),
+            None,
+            -1,
+        )
+        .with_product_strings(None, Some(self.device.product_name()))
+    }
+}
+
+impl Drop for GpsdoWebHidInterface {
+    fn drop(&mut self) {
+        self.device.set_oninputreport(None);
+
+        // Closing can't be waited for here, and the device is gone either way
+        let _ = self.device.close();
+    }
+}
+
+/// Copy the bytes a DataView covers out of the JavaScript heap
+fn data_view_bytes(view: &DataView) -> Vec<u8> {
+    Uint8Array::new_with_byte_offset_and_length(
+        &view.buffer(),
+        view.byte_offset() as u32,
+        view.byte_length() as u32,
+    )
+    .to_vec()
+}
+
+/// Copy as much of a report as fits into `buf`, returning how much was copied
+fn copy_report(report: &[u8], buf: &mut [u8]) -> usize {
+    let len = report.len().min(buf.len());
+    buf[..len].copy_from_slice(&report[..len]);
+
+    len
+}
+
+impl AsyncUsbInterface for GpsdoWebHidInterface {
+    type InterfaceError = WebHidError;
+
+    /// Waits for the next status report the GPSDO sends, which it sends as its status changes
+    async fn hid_read(&self, buf: &mut [u8]) -> Result<usize, Self::InterfaceError> {
+        let report = poll_fn(|context| {
+            let mut input = self.input.borrow_mut();
+            match input.unread.take() {
+                Some(report) => Poll::Ready(report),
+                None => {
+                    input.waker = Some(context.waker().clone());
+                    Poll::Pending
+                }
+            }
+        })
+        .await;
+
+        Ok(copy_report(&report, buf))
+    }
+
+    async fn hid_get_feature_report(
+        &self,
+        report_id: u8,
+        buf: &mut [u8],
+    ) -> Result<usize, Self::InterfaceError> {
+        let report = data_view_bytes(&self.device.receive_feature_report(report_id).await?);
+
+        // Browsers include the report id before the data of numbered reports, which `AsyncUsbInterface` leaves out
+        let report = match report.split_first() {
+            Some((&id, data)) if report_id != 0 && id == report_id => data,
+            _ => &report,
+        };
+
+        Ok(copy_report(report, buf))
+    }
+
+    #[cfg(feature = "write")]
+    async fn hid_send_feature_report(
+        &self,
+        report_id: u8,
+        data: &[u8],
+    ) -> Result<(), Self::InterfaceError> {
+        self.device
+            .send_feature_report_with_u8_slice(report_id, &mut data.to_vec())?
+            .await?;
+
+        Ok(())
+    }
+
+    async fn serial_number(&self) -> Result<Option<String>, Self::InterfaceError> {
+        Ok(None)
+    }
+}
@@ -1,17 +1,132 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec::Vec};
 use thiserror::Error;
 
+use crate::reports::{
+    CONFIG_LEN, CONFIG_REPORT_BUFFER_LEN, CONFIG_REPORT_ID, CONFIG_REPORT_LEN, STATUS_REPORT_LEN,
+};
+
+#[cfg(feature = "async")]
+pub use async_device::{AsyncGpsdoDevice, AsyncUsbInterface, MaybeSend};
+pub use builder::GpsdoConfigBuilder;
+pub use num_rational::Ratio;
+
+#[cfg(feature = "async")]
+mod async_device;
+mod builder;
 pub mod consts;
+#[cfg(feature = "hidapi")]
+pub mod hotplug;
 pub mod interface;
+#[cfg(feature = "test-util")]
+pub mod mock;
+pub mod planner;
+pub mod quality;
+pub mod reports;
+pub mod sdr;
+#[cfg(feature = "sim")]
+pub mod sim;
+pub mod state;
+pub mod tracker;
 
 #[derive(Debug, Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// An error occurred while accessing information from the GPSDO, this could either be from the underlying UsbInterface,
-/// or from the parsing logic in the leo-status-driver library.
+/// naming the call which failed, or from the parsing logic in the leo-status-driver library.
 pub enum GpsdoError<InterfaceError> {
-    #[error("underlying usb interface errored: {0}")]
-    UsbInterfaceError(#[from] InterfaceError),
+    #[error("failed to read status report: {source}")]
+    StatusReadError { source: InterfaceError },
+
+    #[error("failed to read feature report {report_id}: {source}")]
+    FeatureReportError {
+        report_id: u8,
+        source: InterfaceError,
+    },
+
+    #[error("failed to send feature report {report_id}: {source}")]
+    FeatureReportWriteError {
+        report_id: u8,
+        source: InterfaceError,
+    },
+
+    #[error("failed to read serial number: {source}")]
+    SerialNumberError { source: InterfaceError },
+
+    #[error("failed to read firmware version: {source}")]
+    FirmwareVersionError { source: InterfaceError },
+
+    #[error("failed to read device info: {source}")]
+    DeviceInfoError { source: InterfaceError },
 
     #[error("received less data than expected from device, expected {expected:?}, received {received:?}")]
     ShortDataError { expected: usize, received: usize },
+
+    #[error("serial number is {len} bytes long, which does not fit in a buffer of {capacity}")]
+    SerialNumberTooLong { len: usize, capacity: usize },
+
+    #[error("serial number is not valid utf-8")]
+    SerialNumberNotUtf8,
+
+    #[error("refusing to write invalid configuration: {0}")]
+    InvalidConfigError(GpsdoConfigError),
+}
+
+impl<InterfaceError> GpsdoError<InterfaceError> {
+    /// The error of the underlying UsbInterface, `None` if the error came from the parsing logic instead
+    pub fn interface_error(&self) -> Option<&InterfaceError> {
+        match self {
+            GpsdoError::StatusReadError { source }
+            | GpsdoError::FeatureReportError { source, .. }
+            | GpsdoError::FeatureReportWriteError { source, .. }
+            | GpsdoError::SerialNumberError { source }
+            | GpsdoError::FirmwareVersionError { source }
+            | GpsdoError::DeviceInfoError { source } => Some(source),
+            GpsdoError::ShortDataError { .. }
+            | GpsdoError::SerialNumberTooLong { .. }
+            | GpsdoError::SerialNumberNotUtf8
+            | GpsdoError::InvalidConfigError(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+/// The configuration read from the GPSDO cannot produce valid frequencies, usually because the report held garbage, or
+/// a configuration being built is invalid.
+pub enum GpsdoConfigError {
+    #[error("divider {name} is {value}, outside of {min} to {max}")]
+    DividerOutOfRange {
+        name: &'static str,
+        value: u32,
+        min: u32,
+        max: u32,
+    },
+
+    #[error("divider {name} is {value}, it must be {}", if *.allow_one { "1 or even" } else { "even" })]
+    OddDivider {
+        name: &'static str,
+        value: u32,
+        allow_one: bool,
+    },
+
+    #[error("drive level {value} is not a level the model supports")]
+    UnknownDriveLevel { value: u8 },
+
+    #[error("{name} must be set")]
+    Missing { name: &'static str },
+
+    #[error("frequency {name} is {value} Hz, outside of {min} to {max} Hz")]
+    FrequencyOutOfRange {
+        name: &'static str,
+        value: u64,
+        min: u64,
+        max: u64,
+    },
 }
 
 /// The UsbInterface trait allows for use of different USB backends, such as hidapi.
@@ -29,97 +144,604 @@ pub trait UsbInterface {
         buf: &mut [u8],
     ) -> Result<usize, Self::InterfaceError>;
 
-    /// Get the serial number of the device. If no serial number exists on the device, then `Option::None`
+    /// Send a feature report to the device. `data` holds the report data, without the report id byte. Only available
+    /// with the `write` feature.
+    #[cfg(feature = "write")]
+    fn hid_send_feature_report(
+        &self,
+        report_id: u8,
+        data: &[u8],
+    ) -> Result<(), Self::InterfaceError>;
+
+    /// Get the serial number of the device. If no serial number exists on the device, then `Option::None`. Only
+    /// available with the `alloc` feature.
+    #[cfg(feature = "alloc")]
     fn serial_number(&self) -> Result<Option<String>, Self::InterfaceError>;
+
+    /// Get the firmware version of the device as a binary coded decimal, such as `0x0105` for 1.05. Backends which
+    /// cannot determine it should return `Option::None`, which the default implementation does.
+    fn firmware_version(&self) -> Result<Option<u16>, Self::InterfaceError> {
+        Ok(None)
+    }
+
+    /// Describe the open device and where it is attached to the host, with its USB identifiers, product strings, path
+    /// and interface number. Backends which cannot describe it should return `Option::None`, which the default
+    /// implementation does. Only available with the `alloc` feature.
+    #[cfg(feature = "alloc")]
+    fn device_info(&self) -> Result<Option<GpsdoDeviceInfo>, Self::InterfaceError> {
+        Ok(None)
+    }
+
+    /// Get the serial number of the device without allocating, storing its UTF-8 bytes at the start of buf and
+    /// returning its length. If the serial number is longer than buf nothing is stored, and the returned length is the
+    /// size of buffer it needs. Backends which cannot allocate should override this; the default implementation copies
+    /// the result of `serial_number`, and without the `alloc` feature there is no default.
+    #[cfg(not(feature = "alloc"))]
+    fn serial_number_into(&self, buf: &mut [u8]) -> Result<Option<usize>, Self::InterfaceError>;
+
+    /// Get the serial number of the device without allocating, storing its UTF-8 bytes at the start of buf and
+    /// returning its length. If the serial number is longer than buf nothing is stored, and the returned length is the
+    /// size of buffer it needs. Backends which cannot allocate should override this; the default implementation copies
+    /// the result of `serial_number`, and without the `alloc` feature there is no default.
+    #[cfg(feature = "alloc")]
+    fn serial_number_into(&self, buf: &mut [u8]) -> Result<Option<usize>, Self::InterfaceError> {
+        let Some(serial_number) = self.serial_number()? else {
+            return Ok(None);
+        };
+
+        if let Some(stored) = buf.get_mut(..serial_number.len()) {
+            stored.copy_from_slice(serial_number.as_bytes());
+        }
+
+        Ok(Some(serial_number.len()))
+    }
+}
+
+/// A borrowed interface is an interface too, so a `GpsdoDevice` can either own its interface or borrow one
+impl<T: UsbInterface + ?Sized> UsbInterface for &T {
+    type InterfaceError = T::InterfaceError;
+
+    fn hid_read(&self, buf: &mut [u8]) -> Result<usize, Self::InterfaceError> {
+        (**self).hid_read(buf)
+    }
+
+    fn hid_get_feature_report(
+        &self,
+        report_id: u8,
+        buf: &mut [u8],
+    ) -> Result<usize, Self::InterfaceError> {
+        (**self).hid_get_feature_report(report_id, buf)
+    }
+
+    #[cfg(feature = "write")]
+    fn hid_send_feature_report(
+        &self,
+        report_id: u8,
+        data: &[u8],
+    ) -> Result<(), Self::InterfaceError> {
+        (**self).hid_send_feature_report(report_id, data)
+    }
+
+    #[cfg(feature = "alloc")]
+    fn serial_number(&self) -> Result<Option<String>, Self::InterfaceError> {
+        (**self).serial_number()
+    }
+
+    fn firmware_version(&self) -> Result<Option<u16>, Self::InterfaceError> {
+        (**self).firmware_version()
+    }
+
+    #[cfg(feature = "alloc")]
+    fn device_info(&self) -> Result<Option<GpsdoDeviceInfo>, Self::InterfaceError> {
+        (**self).device_info()
+    }
+
+    fn serial_number_into(&self, buf: &mut [u8]) -> Result<Option<usize>, Self::InterfaceError> {
+        (**self).serial_number_into(buf)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+/// The model of a Leo Bodnar GPSDO, which determines the features the device has, and the limits
+/// `GpsdoConfig::violations` checks a config of the model against.
+pub enum GpsdoModel {
+    /// The Precision GPS Reference Clock, the dual output model
+    Standard,
+
+    /// The Mini Precision GPS Reference Clock, with a single output
+    Mini,
+}
+
+impl GpsdoModel {
+    /// Identify the model from its USB product identifier, returning `None` if it is not a known GPSDO
+    pub fn from_product_id(product_id: u16) -> Option<Self> {
+        match product_id {
+            consts::PID_LEO_BODNAR_GPSDO => Some(GpsdoModel::Standard),
+            consts::PID_LEO_BODNAR_MINI_GPSDO => Some(GpsdoModel::Mini),
+            _ => None,
+        }
+    }
+
+    /// Whether the model has a second output
+    pub fn has_output2(&self) -> bool {
+        match self {
+            GpsdoModel::Standard => true,
+            GpsdoModel::Mini => false,
+        }
+    }
+
+    /// The highest frequency the outputs of the model are specified for, in Hz
+    pub fn max_fout(&self) -> u64 {
+        match self {
+            GpsdoModel::Standard => consts::FOUT_MAX,
+            GpsdoModel::Mini => consts::FOUT_MAX,
+        }
+    }
+
+    /// The drive levels the outputs of the model can be set to, lowest first
+    pub fn drive_levels(&self) -> &'static [DriveLevel] {
+        match self {
+            GpsdoModel::Standard => &DriveLevel::ALL,
+            GpsdoModel::Mini => &DriveLevel::ALL,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// The drive level of the outputs of a GPSDO, the current the output buffers can source
+pub enum DriveLevel {
+    Ma8,
+    Ma16,
+    Ma24,
+    Ma32,
+}
+
+impl DriveLevel {
+    /// Every drive level, lowest first
+    pub const ALL: [DriveLevel; 4] = [
+        DriveLevel::Ma8,
+        DriveLevel::Ma16,
+        DriveLevel::Ma24,
+        DriveLevel::Ma32,
+    ];
+
+    /// Decode the level as stored in the config of the GPSDO, returning `None` if it is not a known level
+    pub fn from_raw(raw: u8) -> Option<Self> {
+        match raw {
+            0 => Some(DriveLevel::Ma8),
+            1 => Some(DriveLevel::Ma16),
+            2 => Some(DriveLevel::Ma24),
+            3 => Some(DriveLevel::Ma32),
+            _ => None,
+        }
+    }
+
+    /// The level as stored in the config of the GPSDO
+    pub fn raw(&self) -> u8 {
+        match self {
+            DriveLevel::Ma8 => 0,
+            DriveLevel::Ma16 => 1,
+            DriveLevel::Ma24 => 2,
+            DriveLevel::Ma32 => 3,
+        }
+    }
+
+    /// Find the level which drives the given current, returning `None` if there is no such level
+    pub fn from_milliamps(milliamps: u8) -> Option<Self> {
+        match milliamps {
+            8 => Some(DriveLevel::Ma8),
+            16 => Some(DriveLevel::Ma16),
+            24 => Some(DriveLevel::Ma24),
+            32 => Some(DriveLevel::Ma32),
+            _ => None,
+        }
+    }
+
+    /// The current the level drives, in milliamps
+    pub fn milliamps(&self) -> u8 {
+        match self {
+            DriveLevel::Ma8 => 8,
+            DriveLevel::Ma16 => 16,
+            DriveLevel::Ma24 => 24,
+            DriveLevel::Ma32 => 32,
+        }
+    }
+}
+
+impl core::fmt::Display for DriveLevel {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} mA", self.milliamps())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// The current the outputs of a GPSDO are driven at, as read from its config
+pub enum DriveCurrent {
+    /// A known drive level, in milliamps
+    Milliamps(u8),
+
+    /// A stored level which isn't a known drive level, with its raw value
+    Unknown(u8),
+}
+
+impl DriveCurrent {
+    /// The current in milliamps, or `None` if the level is unknown
+    pub fn milliamps(&self) -> Option<u8> {
+        match self {
+            DriveCurrent::Milliamps(milliamps) => Some(*milliamps),
+            DriveCurrent::Unknown(_) => None,
+        }
+    }
+}
+
+/// The current, such as `8 mA`, or `unknown drive level 7`
+impl core::fmt::Display for DriveCurrent {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DriveCurrent::Milliamps(milliamps) => write!(f, "{} mA", milliamps),
+            DriveCurrent::Unknown(raw) => write!(f, "unknown drive level {}", raw),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// The loop bandwidth mode of the Si5328 PLL in the GPSDO, its 4 bit BWSEL setting. The bandwidth each setting gives
+/// depends on the frequency plan, see Skyworks DSPLLsim, and only some settings are valid for a given plan.
+pub enum Bandwidth {
+    /// A BWSEL setting, from 0 to 15
+    Bwsel(u8),
+
+    /// A value which doesn't fit in BWSEL, as stored in the config, kept so it can be inspected or written back
+    Raw(u8),
+}
+
+impl Bandwidth {
+    /// The largest BWSEL setting
+    pub const BWSEL_MAX: u8 = 0x0F;
+
+    /// Decode the bandwidth as stored in the config of the GPSDO
+    pub fn from_raw(raw: u8) -> Self {
+        if raw <= Self::BWSEL_MAX {
+            Bandwidth::Bwsel(raw)
+        } else {
+            Bandwidth::Raw(raw)
+        }
+    }
+
+    /// The bandwidth as stored in the config of the GPSDO
+    pub fn raw(&self) -> u8 {
+        match *self {
+            Bandwidth::Bwsel(bwsel) | Bandwidth::Raw(bwsel) => bwsel,
+        }
+    }
+
+    /// The BWSEL setting, or `None` if the value doesn't fit in BWSEL
+    pub fn bwsel(&self) -> Option<u8> {
+        match *self {
+            Bandwidth::Bwsel(bwsel) if bwsel <= Self::BWSEL_MAX => Some(bwsel),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+/// The firmware version of a GPSDO, stored as a binary coded decimal such as `0x0105` for 1.05
+pub struct FirmwareVersion(u16);
+
+impl FirmwareVersion {
+    pub fn from_bcd(bcd: u16) -> Self {
+        FirmwareVersion(bcd)
+    }
+
+    pub fn bcd(&self) -> u16 {
+        self.0
+    }
+
+    pub fn major(&self) -> u8 {
+        bcd_to_u8((self.0 >> 8) as u8)
+    }
+
+    pub fn minor(&self) -> u8 {
+        bcd_to_u8(self.0 as u8)
+    }
+}
+
+impl core::fmt::Display for FirmwareVersion {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:x}.{:02x}", self.0 >> 8, self.0 & 0xFF)
+    }
 }
 
-pub struct GpsdoDevice<'a, Interface: UsbInterface> {
-    interface: &'a Interface,
+fn bcd_to_u8(bcd: u8) -> u8 {
+    (bcd >> 4) * 10 + (bcd & 0x0F)
 }
 
-impl<'a, Interface: UsbInterface> GpsdoDevice<'a, Interface> {
-    /// Create a new GpsdoDevice, from the given UsbInterface
-    pub fn new(interface: &'a Interface) -> Self {
-        GpsdoDevice { interface }
+/// A GPSDO reached through a UsbInterface. The interface may be owned, such as to keep the device in long-lived
+/// state or move it to another thread, or borrowed by passing a reference to it.
+pub struct GpsdoDevice<Interface: UsbInterface> {
+    interface: Interface,
+    model: GpsdoModel,
+}
+
+impl<Interface: UsbInterface> GpsdoDevice<Interface> {
+    /// Create a new GpsdoDevice, from the given UsbInterface, assuming it is a standard two output GPSDO
+    pub fn new(interface: Interface) -> Self {
+        Self::with_model(interface, GpsdoModel::Standard)
+    }
+
+    /// Create a new GpsdoDevice of a known model, from the given UsbInterface
+    pub fn with_model(interface: Interface, model: GpsdoModel) -> Self {
+        GpsdoDevice { interface, model }
     }
 
-    /// Retrieve the serial number of the GPSDO
+    /// The model of the GPSDO, which determines the features it has
+    pub fn model(&self) -> GpsdoModel {
+        self.model
+    }
+
+    /// The UsbInterface the device is reached through
+    pub fn interface(&self) -> &Interface {
+        &self.interface
+    }
+
+    /// Give up the device, returning its UsbInterface
+    pub fn into_interface(self) -> Interface {
+        self.interface
+    }
+
+    /// Retrieve the serial number of the GPSDO. Only available with the `alloc` feature, see `serial_number_into`.
+    #[cfg(feature = "alloc")]
     pub fn serial_number(&self) -> Result<Option<String>, GpsdoError<Interface::InterfaceError>> {
-        Ok(self.interface.serial_number()?)
+        self.interface
+            .serial_number()
+            .map_err(|source| GpsdoError::SerialNumberError { source })
+    }
+
+    /// Retrieve the serial number of the GPSDO without allocating, storing it in buf, see
+    /// `UsbInterface::serial_number_into`. Fails with `SerialNumberTooLong` if buf is too small to hold it.
+    pub fn serial_number_into<'b>(
+        &self,
+        buf: &'b mut [u8],
+    ) -> Result<Option<&'b str>, GpsdoError<Interface::InterfaceError>> {
+        let Some(len) = self
+            .interface
+            .serial_number_into(buf)
+            .map_err(|source| GpsdoError::SerialNumberError { source })?
+        else {
+            return Ok(None);
+        };
+
+        let capacity = buf.len();
+        let stored = buf
+            .get(..len)
+            .ok_or(GpsdoError::SerialNumberTooLong { len, capacity })?;
+
+        core::str::from_utf8(stored)
+            .map(Some)
+            .map_err(|_| GpsdoError::SerialNumberNotUtf8)
+    }
+
+    /// Retrieve the firmware version of the GPSDO, if the interface can determine it
+    pub fn firmware_version(
+        &self,
+    ) -> Result<Option<FirmwareVersion>, GpsdoError<Interface::InterfaceError>> {
+        self.interface
+            .firmware_version()
+            .map(|version| version.map(FirmwareVersion))
+            .map_err(|source| GpsdoError::FirmwareVersionError { source })
+    }
+
+    /// Describe the GPSDO and where it is attached, if the interface can, see `UsbInterface::device_info`. Only
+    /// available with the `alloc` feature.
+    #[cfg(feature = "alloc")]
+    pub fn device_info(
+        &self,
+    ) -> Result<Option<GpsdoDeviceInfo>, GpsdoError<Interface::InterfaceError>> {
+        self.interface
+            .device_info()
+            .map_err(|source| GpsdoError::DeviceInfoError { source })
+    }
+
+    /// Read the raw config feature report of the GPSDO
+    fn config_report(
+        &self,
+    ) -> Result<[u8; CONFIG_REPORT_BUFFER_LEN], GpsdoError<Interface::InterfaceError>> {
+        let mut buf = [0u8; CONFIG_REPORT_BUFFER_LEN];
+
+        let size = self
+            .interface
+            .hid_get_feature_report(CONFIG_REPORT_ID, &mut buf)
+            .map_err(|source| GpsdoError::FeatureReportError {
+                report_id: CONFIG_REPORT_ID,
+                source,
+            })?;
+        check_config_report_size(size)?;
+
+        Ok(buf)
     }
 
     /// Retrieve the config of the GPSDO
     pub fn config(&self) -> Result<GpsdoConfig, GpsdoError<Interface::InterfaceError>> {
-        let mut buf = [0u8; 61];
+        Ok(parse_config_report(self.model, &self.config_report()?))
+    }
 
-        let size = self.interface.hid_get_feature_report(9, &mut buf)?;
-        if size < 21 {
-            return Err(GpsdoError::ShortDataError {
-                expected: 21,
-                received: size,
-            });
-        }
-
-        let output1 = buf[0] & 0x01 != 0;
-        let output2 = buf[0] & 0x02 != 0;
-        let level = buf[1];
-        let fin = u32::from_le_bytes(buf[2..6].try_into().unwrap()) & 0x00FFFFFF;
-        let n3 = (u32::from_le_bytes(buf[5..9].try_into().unwrap()) & 0x00FFFFFF) + 1;
-        let n2_hs = buf[8] + 4;
-        let n2_ls = (u32::from_le_bytes(buf[9..13].try_into().unwrap()) & 0x00FFFFFF) + 1;
-        let n1_hs = buf[12] + 4;
-        let nc1_ls = (u32::from_le_bytes(buf[13..17].try_into().unwrap()) & 0x00FFFFFF) + 1;
-        let nc2_ls = (u32::from_le_bytes(buf[16..20].try_into().unwrap()) & 0x00FFFFFF) + 1;
-        let skew = buf[19];
-        let bw = buf[20];
-
-        Ok(GpsdoConfig {
-            output1,
-            output2,
-            level,
-            fin,
-            n3,
-            n2_hs,
-            n2_ls,
-            n1_hs,
-            nc1_ls,
-            nc2_ls,
-            skew,
-            bw,
+    /// Write a configuration to the GPSDO, in the same layout as it is read. Configurations which fail
+    /// `GpsdoConfig::validate` are refused rather than written. Only available with the `write` feature.
+    #[cfg(feature = "write")]
+    pub fn write_config(
+        &self,
+        config: &GpsdoConfig,
+    ) -> Result<(), GpsdoError<Interface::InterfaceError>> {
+        if let Some(error) = config.violations().next() {
+            return Err(GpsdoError::InvalidConfigError(error));
+        }
+
+        self.interface
+            .hid_send_feature_report(CONFIG_REPORT_ID, &config.to_report_bytes())
+            .map_err(|source| GpsdoError::FeatureReportWriteError {
+                report_id: CONFIG_REPORT_ID,
+                source,
+            })
+    }
+
+    /// Enable or disable the outputs of the GPSDO, leaving those given as `None` as they are. Only the output bits of
+    /// the config are changed, the rest is written back exactly as it was read. `output2` is ignored by models with a
+    /// single output. Only available with the `write` feature.
+    #[cfg(feature = "write")]
+    pub fn set_outputs(
+        &self,
+        output1: Option<bool>,
+        output2: Option<bool>,
+    ) -> Result<(), GpsdoError<Interface::InterfaceError>> {
+        let output2 = output2.filter(|_| self.model.has_output2());
+
+        self.update_config_report(|buf| {
+            for (enabled, bit) in [(output1, 0x01), (output2, 0x02)] {
+                match enabled {
+                    Some(true) => buf[0] |= bit,
+                    Some(false) => buf[0] &= !bit,
+                    None => {}
+                }
+            }
         })
     }
 
+    /// Set the drive level of the outputs of the GPSDO. Only the level is changed, the rest of the config is written
+    /// back exactly as it was read. Only available with the `write` feature.
+    #[cfg(feature = "write")]
+    pub fn set_drive_level(
+        &self,
+        level: DriveLevel,
+    ) -> Result<(), GpsdoError<Interface::InterfaceError>> {
+        self.update_config_report(|buf| buf[1] = level.raw())
+    }
+
+    /// Set the skew between the outputs of the GPSDO, in steps of one period of the PLL oscillator, see
+    /// `GpsdoConfig::checked_skew_ps` for the offset this gives. Only the skew is changed, the rest of the config is
+    /// written back exactly as it was read. Only available with the `write` feature.
+    #[cfg(feature = "write")]
+    pub fn set_skew(&self, skew: u8) -> Result<(), GpsdoError<Interface::InterfaceError>> {
+        self.update_config_report(|buf| buf[19] = skew)
+    }
+
+    /// Set the loop bandwidth mode of the PLL in the GPSDO. Only the bandwidth is changed, the rest of the config is
+    /// written back exactly as it was read. Only available with the `write` feature.
+    #[cfg(feature = "write")]
+    pub fn set_bandwidth(
+        &self,
+        bandwidth: Bandwidth,
+    ) -> Result<(), GpsdoError<Interface::InterfaceError>> {
+        self.update_config_report(|buf| buf[20] = bandwidth.raw())
+    }
+
+    /// Read the raw config feature report, change it and write it back
+    #[cfg(feature = "write")]
+    fn update_config_report(
+        &self,
+        update: impl FnOnce(&mut [u8; CONFIG_REPORT_BUFFER_LEN]),
+    ) -> Result<(), GpsdoError<Interface::InterfaceError>> {
+        let mut buf = self.config_report()?;
+        update(&mut buf);
+
+        self.interface
+            .hid_send_feature_report(CONFIG_REPORT_ID, &buf[..CONFIG_REPORT_LEN])
+            .map_err(|source| GpsdoError::FeatureReportWriteError {
+                report_id: CONFIG_REPORT_ID,
+                source,
+            })
+    }
+
     /// Retrieve the status of the GPSDO
     pub fn status(&self) -> Result<GpsdoStatus, GpsdoError<Interface::InterfaceError>> {
-        let mut buf = [0u8; 2];
-        let read_count = self.interface.hid_read(&mut buf)?;
+        let mut buf = [0u8; STATUS_REPORT_LEN];
+        let read_count = self
+            .interface
+            .hid_read(&mut buf)
+            .map_err(|source| GpsdoError::StatusReadError { source })?;
+
+        parse_status_report(&buf[..read_count])
+    }
+}
 
-        let read_bytes = &buf[..read_count];
+/// Check a config report of `size` bytes is long enough to hold the whole config
+fn check_config_report_size<E>(size: usize) -> Result<(), GpsdoError<E>> {
+    if size < CONFIG_LEN {
+        return Err(GpsdoError::ShortDataError {
+            expected: CONFIG_LEN,
+            received: size,
+        });
+    }
 
-        if read_count < 2 {
-            return Err(GpsdoError::ShortDataError {
-                expected: 2,
-                received: read_count,
-            });
-        }
+    Ok(())
+}
 
-        let loss_count = read_bytes[0];
-        let sat_lock = read_bytes[1] & 0x01 == 0;
-        let pll_lock = read_bytes[1] & 0x02 == 0;
-        let locked = read_bytes[1] & 0x03 == 0;
+/// Parse the config from a config feature report, the inverse of `GpsdoConfig::to_report_bytes`
+fn parse_config_report(model: GpsdoModel, buf: &[u8; CONFIG_REPORT_BUFFER_LEN]) -> GpsdoConfig {
+    let output1 = buf[0] & 0x01 != 0;
+    let output2 = buf[0] & 0x02 != 0;
+    let level = buf[1];
+    let fin = u32::from_le_bytes(buf[2..6].try_into().unwrap()) & 0x00FFFFFF;
+    let n3 = (u32::from_le_bytes(buf[5..9].try_into().unwrap()) & 0x00FFFFFF) + 1;
+    let n2_hs = buf[8].wrapping_add(4);
+    let n2_ls = (u32::from_le_bytes(buf[9..13].try_into().unwrap()) & 0x00FFFFFF) + 1;
+    let n1_hs = buf[12].wrapping_add(4);
+    let nc1_ls = (u32::from_le_bytes(buf[13..17].try_into().unwrap()) & 0x00FFFFFF) + 1;
+    let nc2_ls = (u32::from_le_bytes(buf[16..20].try_into().unwrap()) & 0x00FFFFFF) + 1;
+    let skew = buf[19];
+    let bw = buf[20];
+
+    GpsdoConfig {
+        model,
+        output1,
+        output2,
+        level,
+        fin,
+        n3,
+        n2_hs,
+        n2_ls,
+        n1_hs,
+        nc1_ls,
+        nc2_ls,
+        skew,
+        bw,
+    }
+}
 
-        Ok(GpsdoStatus {
-            loss_count,
-            sat_lock,
-            pll_lock,
-            locked,
-        })
+/// Parse the status from the bytes of a status report
+fn parse_status_report<E>(read_bytes: &[u8]) -> Result<GpsdoStatus, GpsdoError<E>> {
+    if read_bytes.len() < STATUS_REPORT_LEN {
+        return Err(GpsdoError::ShortDataError {
+            expected: STATUS_REPORT_LEN,
+            received: read_bytes.len(),
+        });
     }
+
+    let loss_count = read_bytes[0];
+    let sat_lock = read_bytes[1] & 0x01 == 0;
+    let pll_lock = read_bytes[1] & 0x02 == 0;
+    let locked = read_bytes[1] & 0x03 == 0;
+
+    Ok(GpsdoStatus {
+        loss_count,
+        sat_lock,
+        pll_lock,
+        locked,
+    })
 }
 
-#[derive(Debug)]
-/// Configuration parameters of a Leo Bodnar GPSDO, including derived information such as the output frequencies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+/// Configuration parameters of a Leo Bodnar GPSDO, including derived information such as the output frequencies.
+/// Parameters of the second output are `None` on models without one. With the `serde` feature a config can be
+/// deserialized, which like reading one from a GPSDO doesn't validate it, see `validate`.
 pub struct GpsdoConfig {
+    model: GpsdoModel,
     output1: bool,
     output2: bool,
     level: u8,
@@ -135,18 +757,78 @@ pub struct GpsdoConfig {
 }
 
 impl GpsdoConfig {
+    /// Parse a config from the data of a config feature report of a GPSDO of the given model, without the report id,
+    /// such as one captured from a device. No USB I/O is involved, so this suits fuzzing, testing and replaying logs.
+    /// Like a config read from a GPSDO, it isn't validated, see `validate`.
+    pub fn from_report_bytes(
+        model: GpsdoModel,
+        bytes: &[u8],
+    ) -> Result<Self, GpsdoError<core::convert::Infallible>> {
+        check_config_report_size(bytes.len())?;
+
+        let mut buf = [0u8; CONFIG_REPORT_BUFFER_LEN];
+        let size = bytes.len().min(buf.len());
+        buf[..size].copy_from_slice(&bytes[..size]);
+
+        Ok(parse_config_report(model, &buf))
+    }
+
+    /// Encode the config in the layout of a config feature report, without the report id, the inverse of
+    /// `from_report_bytes`. The 21 bytes of the config are followed by zeroes up to the 60 bytes the GPSDO expects.
+    pub fn to_report_bytes(&self) -> [u8; CONFIG_REPORT_LEN] {
+        let mut buf = [0u8; CONFIG_REPORT_LEN];
+
+        buf[0] = u8::from(self.output1) | u8::from(self.output2) << 1;
+        buf[1] = self.level;
+        buf[2..5].copy_from_slice(&self.fin.to_le_bytes()[..3]);
+        buf[5..8].copy_from_slice(&self.n3.wrapping_sub(1).to_le_bytes()[..3]);
+        buf[8] = self.n2_hs.wrapping_sub(4);
+        buf[9..12].copy_from_slice(&self.n2_ls.wrapping_sub(1).to_le_bytes()[..3]);
+        buf[12] = self.n1_hs.wrapping_sub(4);
+        buf[13..16].copy_from_slice(&self.nc1_ls.wrapping_sub(1).to_le_bytes()[..3]);
+        buf[16..19].copy_from_slice(&self.nc2_ls.wrapping_sub(1).to_le_bytes()[..3]);
+        buf[19] = self.skew;
+        buf[20] = self.bw;
+
+        buf
+    }
+
+    /// Start building a config for a model, see `GpsdoConfigBuilder`
+    pub fn builder(model: GpsdoModel) -> GpsdoConfigBuilder {
+        GpsdoConfigBuilder::new(model)
+    }
+
+    /// The model of the GPSDO the config was read from
+    pub fn model(&self) -> GpsdoModel {
+        self.model
+    }
+
     pub fn output1(&self) -> bool {
         self.output1
     }
 
-    pub fn output2(&self) -> bool {
-        self.output2
+    pub fn output2(&self) -> Option<bool> {
+        self.model.has_output2().then_some(self.output2)
     }
 
+    /// The drive level as stored in the config, see `drive_level` for its meaning
     pub fn level(&self) -> u8 {
         self.level
     }
 
+    /// The drive level of the outputs, or `None` if the stored level is not a known level
+    pub fn drive_level(&self) -> Option<DriveLevel> {
+        DriveLevel::from_raw(self.level)
+    }
+
+    /// The current the outputs are driven at, so every consumer reports the level in the same units
+    pub fn level_ma(&self) -> DriveCurrent {
+        match self.drive_level() {
+            Some(level) => DriveCurrent::Milliamps(level.milliamps()),
+            None => DriveCurrent::Unknown(self.level),
+        }
+    }
+
     pub fn fin(&self) -> u32 {
         self.fin
     }
@@ -171,132 +853,625 @@ impl GpsdoConfig {
         self.nc1_ls
     }
 
-    pub fn nc2_ls(&self) -> u32 {
-        self.nc2_ls
+    pub fn nc2_ls(&self) -> Option<u32> {
+        self.model.has_output2().then_some(self.nc2_ls)
     }
 
     pub fn skew(&self) -> u8 {
         self.skew
     }
 
+    /// The PLL bandwidth as stored in the config, see `bandwidth` for its meaning
     pub fn bw(&self) -> u8 {
         self.bw
     }
 
+    /// The loop bandwidth mode of the PLL
+    pub fn bandwidth(&self) -> Bandwidth {
+        Bandwidth::from_raw(self.bw)
+    }
+
+    /// The frequency of fin after division by n3. See `checked_f3` for a version which validates the result.
     pub fn f3(&self) -> u32 {
         self.fin / self.n3
     }
 
+    /// The frequency of the output of the PLL. See `checked_fosc` for a version which validates the dividers.
     pub fn fosc(&self) -> u64 {
         self.fin as u64 * (self.n2_hs as u64 * self.n2_ls as u64) / self.n3 as u64
     }
 
+    /// The frequency of output1. Panics if a high speed divider is zero, see `checked_fout1` for a version which
+    /// validates the dividers instead.
     pub fn fout1(&self) -> u64 {
         self.fosc() / (self.n1_hs as u64 * self.nc1_ls as u64)
     }
 
-    pub fn fout2(&self) -> u64 {
-        self.fosc() / (self.n1_hs as u64 * self.nc2_ls as u64)
+    /// The frequency of output2. Panics if a high speed divider is zero, see `checked_fout2` for a version which
+    /// validates the dividers instead.
+    pub fn fout2(&self) -> Option<u64> {
+        self.model
+            .has_output2()
+            .then(|| self.fosc() / (self.n1_hs as u64 * self.nc2_ls as u64))
     }
-}
-
-#[derive(Debug)]
-/// Status of a Leo Bodnar GPSDO, showing the lock status of the system, and how many times the system has lost it's lock
-pub struct GpsdoStatus {
-    loss_count: u8,
-    sat_lock: bool,
-    pll_lock: bool,
-    locked: bool,
-}
 
-impl GpsdoStatus {
-    pub fn loss_count(&self) -> u8 {
-        self.loss_count
+    /// The exact frequency of fin after division by n3, in Hz, including any fraction `f3` truncates. Panics if n3 is
+    /// zero.
+    pub fn f3_exact(&self) -> Ratio<u64> {
+        Ratio::new(self.fin as u64, self.n3 as u64)
     }
 
-    pub fn sat_locked(&self) -> bool {
-        self.sat_lock
+    /// The exact frequency of the output of the PLL, in Hz, including any fraction `fosc` truncates. Panics if n3 is
+    /// zero.
+    pub fn fosc_exact(&self) -> Ratio<u64> {
+        self.f3_exact() * (self.n2_hs as u64 * self.n2_ls as u64)
     }
 
-    pub fn pll_locked(&self) -> bool {
-        self.pll_lock
+    /// The exact frequency of output1, in Hz, including any fraction `fout1` truncates. Panics if a divider is zero.
+    pub fn fout1_exact(&self) -> Ratio<u64> {
+        self.fosc_exact() / (self.n1_hs as u64 * self.nc1_ls as u64)
     }
 
-    pub fn locked(&self) -> bool {
-        self.locked
+    /// The exact frequency of output2, in Hz, including any fraction `fout2` truncates. `None` on models without
+    /// output2. Panics if a divider is zero.
+    pub fn fout2_exact(&self) -> Option<Ratio<u64>> {
+        self.model
+            .has_output2()
+            .then(|| self.fosc_exact() / (self.n1_hs as u64 * self.nc2_ls as u64))
     }
-}
 
-#[cfg(test)]
-mod test {
-    use core::panic;
+    /// How far output1 as reported in whole Hz by `fout1` is from the exact frequency. Panics if a divider is zero.
+    pub fn fout1_residual(&self) -> FrequencyResidual {
+        FrequencyResidual::new(self.fout1_exact())
+    }
 
-    use super::{GpsdoDevice, UsbInterface};
+    /// How far output2 as reported in whole Hz by `fout2` is from the exact frequency. `None` on models without
+    /// output2. Panics if a divider is zero.
+    pub fn fout2_residual(&self) -> Option<FrequencyResidual> {
+        self.fout2_exact().map(FrequencyResidual::new)
+    }
 
-    struct TestUsbInterface<'a>(&'a [u8], &'a [u8]);
+    /// The frequency of fin after division by n3, or an error if it is outside the range of the PLL
+    pub fn checked_f3(&self) -> Result<u32, GpsdoConfigError> {
+        let f3 = self.f3();
+        check_frequency("f3", f3 as u64, consts::F3_MIN, consts::F3_MAX)?;
 
-    impl<'a> UsbInterface for TestUsbInterface<'a> {
-        type InterfaceError = std::io::Error;
+        Ok(f3)
+    }
 
-        fn hid_read(&self, buf: &mut [u8]) -> Result<usize, Self::InterfaceError> {
-            buf.copy_from_slice(&self.0);
+    /// The frequency of the output of the PLL, or an error if it or its dividers are outside the range of the PLL
+    pub fn checked_fosc(&self) -> Result<u64, GpsdoConfigError> {
+        self.checked_f3()?;
+        check_hs_divider("n2_hs", self.n2_hs)?;
 
-            Ok(self.0.len())
-        }
+        let fosc = self.fosc();
+        check_frequency("fosc", fosc, consts::FOSC_MIN, consts::FOSC_MAX)?;
 
-        fn hid_get_feature_report(
-            &self,
-            _report_id: u8,
-            buf: &mut [u8],
-        ) -> Result<usize, Self::InterfaceError> {
-            buf.copy_from_slice(&self.1);
+        Ok(fosc)
+    }
 
-            Ok(self.1.len())
-        }
+    /// The approximate time offset the skew sets between the outputs, in picoseconds. Each step of skew is one period of
+    /// the PLL oscillator, about 200 ps, so the offset depends on the frequencies configured. An error if the PLL
+    /// configuration is invalid.
+    pub fn checked_skew_ps(&self) -> Result<f64, GpsdoConfigError> {
+        let fosc = self.checked_fosc()?;
 
-        fn serial_number(&self) -> Result<Option<String>, Self::InterfaceError> {
-            Ok(Some("AAAA-BBBB".to_string()))
-        }
+        Ok(self.skew as f64 * 1e12 / fosc as f64)
     }
 
-    struct TestUsbErrorInterface;
+    /// The frequency of output1, or an error if the PLL configuration is invalid
+    pub fn checked_fout1(&self) -> Result<u64, GpsdoConfigError> {
+        let fosc = self.checked_fosc()?;
+        check_hs_divider("n1_hs", self.n1_hs)?;
 
-    impl UsbInterface for TestUsbErrorInterface {
-        type InterfaceError = std::io::Error;
+        Ok(fosc / (self.n1_hs as u64 * self.nc1_ls as u64))
+    }
 
-        fn hid_read(&self, _buf: &mut [u8]) -> Result<usize, Self::InterfaceError> {
-            Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "error reading data",
-            ))
+    /// The frequency of output2, or an error if the PLL configuration is invalid. `None` on models without output2.
+    pub fn checked_fout2(&self) -> Result<Option<u64>, GpsdoConfigError> {
+        if !self.model.has_output2() {
+            return Ok(None);
         }
 
-        fn serial_number(&self) -> Result<Option<String>, Self::InterfaceError> {
-            Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "error reading serial no",
-            ))
-        }
+        let fosc = self.checked_fosc()?;
+        check_hs_divider("n1_hs", self.n1_hs)?;
 
-        fn hid_get_feature_report(
-            &self,
-            _report_id: u8,
-            _buf: &mut [u8],
-        ) -> Result<usize, Self::InterfaceError> {
-            Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "error getting feature report",
-            ))
-        }
+        Ok(Some(fosc / (self.n1_hs as u64 * self.nc2_ls as u64)))
     }
 
-    #[test]
-    fn gpsdo_device_read_returns_correct_data_pll_locked_sat_locked() {
-        let test_interface = TestUsbInterface(&[23, 0b000], &[]);
-
-        let device = GpsdoDevice::new(&test_interface);
+    /// Check every setting against the legal ranges of the PLL and the config report, returning each violation found,
+    /// or an empty list if the config is safe to write. Only available with the `alloc` feature, see `violations`.
+    #[cfg(feature = "alloc")]
+    pub fn validate(&self) -> Vec<GpsdoConfigError> {
+        self.violations().collect()
+    }
 
-        let status = device.status().expect("expected success from status");
+    /// Each violation `validate` finds, without allocating
+    pub fn violations(&self) -> impl Iterator<Item = GpsdoConfigError> {
+        let has_output2 = self.model.has_output2();
+        let max_fout = self.model.max_fout();
+        // The output frequencies can only be checked once the dividers they come from are valid
+        let check_fout = |name, fout: Option<u64>| {
+            fout.map_or(Ok(()), |fout| check_frequency(name, fout, 1, max_fout))
+        };
+        let output2_check = |check: Result<(), GpsdoConfigError>| {
+            if has_output2 {
+                check
+            } else {
+                Ok(())
+            }
+        };
+
+        [
+            check_frequency("fin", self.fin as u64, 1, FIN_MAX),
+            check_divider("n3", self.n3, 1, planner::N3_MAX),
+            check_hs_divider("n2_hs", self.n2_hs),
+            check_divider("n2_ls", self.n2_ls, 2, planner::LS_DIVIDER_MAX),
+            check_even_divider("n2_ls", self.n2_ls, false),
+            check_hs_divider("n1_hs", self.n1_hs),
+            check_frequency("f3", self.f3() as u64, consts::F3_MIN, consts::F3_MAX),
+            check_frequency("fosc", self.fosc(), consts::FOSC_MIN, consts::FOSC_MAX),
+            check_fout("fout1", self.checked_fout1().ok()),
+            check_fout("fout2", self.checked_fout2().ok().flatten()),
+            DriveLevel::from_raw(self.level)
+                .filter(|level| self.model.drive_levels().contains(level))
+                .map(|_| ())
+                .ok_or(GpsdoConfigError::UnknownDriveLevel { value: self.level }),
+            check_divider("nc1_ls", self.nc1_ls, 1, planner::LS_DIVIDER_MAX),
+            check_even_divider("nc1_ls", self.nc1_ls, true),
+            output2_check(check_divider(
+                "nc2_ls",
+                self.nc2_ls,
+                1,
+                planner::LS_DIVIDER_MAX,
+            )),
+            output2_check(check_even_divider("nc2_ls", self.nc2_ls, true)),
+        ]
+        .into_iter()
+        .filter_map(Result::err)
+    }
+}
+
+/// A summary of the outputs, such as `fout1=50 MHz (on), fout2=25 MHz (off), 8 mA`
+impl core::fmt::Display for GpsdoConfig {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let on_off = |enabled: bool| if enabled { "on" } else { "off" };
+
+        match (self.checked_fout1(), self.checked_fout2()) {
+            (Ok(fout1), Ok(fout2)) => {
+                write!(f, "fout1={} ({})", Frequency(fout1), on_off(self.output1))?;
+                if let (Some(fout2), Some(output2)) = (fout2, self.output2()) {
+                    write!(f, ", fout2={} ({})", Frequency(fout2), on_off(output2))?;
+                }
+            }
+            (Err(error), _) | (_, Err(error)) => write!(f, "invalid configuration: {}", error)?,
+        }
+
+        write!(f, ", {}", self.level_ma())
+    }
+}
+
+/// Formats a frequency in the largest of Hz, kHz and MHz it is at least one of, such as `12.8 MHz`
+struct Frequency(u64);
+
+impl core::fmt::Display for Frequency {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let (scale, unit) = match self.0 {
+            1_000_000.. => (1_000_000, "MHz"),
+            1_000.. => (1_000, "kHz"),
+            _ => (1, "Hz"),
+        };
+
+        let whole = self.0 / scale;
+        let mut fraction = self.0 % scale;
+        if fraction == 0 {
+            return write!(f, "{} {}", whole, unit);
+        }
+
+        let mut digits = scale.ilog10() as usize;
+        while fraction.is_multiple_of(10) {
+            fraction /= 10;
+            digits -= 1;
+        }
+
+        write!(f, "{}.{:0width$} {}", whole, fraction, unit, width = digits)
+    }
+}
+
+/// The highest TCXO frequency which fits in the 24 bits the config report stores it in
+const FIN_MAX: u64 = 0x00FF_FFFF;
+
+fn check_hs_divider(name: &'static str, value: u8) -> Result<(), GpsdoConfigError> {
+    if !(consts::HS_DIVIDER_MIN..=consts::HS_DIVIDER_MAX).contains(&value) {
+        return Err(GpsdoConfigError::DividerOutOfRange {
+            name,
+            value: value as u32,
+            min: consts::HS_DIVIDER_MIN as u32,
+            max: consts::HS_DIVIDER_MAX as u32,
+        });
+    }
+
+    Ok(())
+}
+
+fn check_divider(
+    name: &'static str,
+    value: u32,
+    min: u32,
+    max: u32,
+) -> Result<(), GpsdoConfigError> {
+    if !(min..=max).contains(&value) {
+        return Err(GpsdoConfigError::DividerOutOfRange {
+            name,
+            value,
+            min,
+            max,
+        });
+    }
+
+    Ok(())
+}
+
+/// Check a low speed divider is even, or 1 where `allow_one` is set, as for the output dividers
+fn check_even_divider(
+    name: &'static str,
+    value: u32,
+    allow_one: bool,
+) -> Result<(), GpsdoConfigError> {
+    if !(value.is_multiple_of(2) || allow_one && value == 1) {
+        return Err(GpsdoConfigError::OddDivider {
+            name,
+            value,
+            allow_one,
+        });
+    }
+
+    Ok(())
+}
+
+fn check_frequency(
+    name: &'static str,
+    value: u64,
+    min: u64,
+    max: u64,
+) -> Result<(), GpsdoConfigError> {
+    if !(min..=max).contains(&value) {
+        return Err(GpsdoConfigError::FrequencyOutOfRange {
+            name,
+            value,
+            min,
+            max,
+        });
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The fraction of a Hz lost when an exact frequency of the PLL is reported in whole Hz, so configurations which don't
+/// produce an integer frequency can be flagged
+pub struct FrequencyResidual {
+    exact: Ratio<u64>,
+}
+
+impl FrequencyResidual {
+    fn new(exact: Ratio<u64>) -> Self {
+        FrequencyResidual { exact }
+    }
+
+    /// The exact frequency, in Hz
+    pub fn exact(&self) -> Ratio<u64> {
+        self.exact
+    }
+
+    /// The frequency in whole Hz, rounded down
+    pub fn truncated(&self) -> u64 {
+        self.exact.to_integer()
+    }
+
+    /// Whether the frequency is a whole number of Hz
+    pub fn is_integer(&self) -> bool {
+        self.exact.is_integer()
+    }
+
+    /// The fraction of a Hz lost by `truncated`, from 0 up to but not including 1
+    pub fn hz(&self) -> f64 {
+        let fraction = self.exact.fract();
+
+        *fraction.numer() as f64 / *fraction.denom() as f64
+    }
+
+    /// The fraction lost by `truncated` relative to the exact frequency, in parts per billion
+    pub fn ppb(&self) -> f64 {
+        let exact = *self.exact.numer() as f64 / *self.exact.denom() as f64;
+
+        if exact == 0.0 {
+            return 0.0;
+        }
+
+        self.hz() * 1e9 / exact
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Metadata describing a GPSDO and where it is attached to the host. Only available with the `alloc` feature.
+pub struct GpsdoDeviceInfo {
+    vendor_id: u16,
+    product_id: u16,
+    serial_number: Option<String>,
+    manufacturer: Option<String>,
+    product: Option<String>,
+    path: String,
+    port_path: Option<String>,
+    interface_number: i32,
+}
+
+#[cfg(feature = "alloc")]
+impl GpsdoDeviceInfo {
+    pub fn new(
+        vendor_id: u16,
+        product_id: u16,
+        serial_number: Option<String>,
+        path: String,
+        port_path: Option<String>,
+        interface_number: i32,
+    ) -> Self {
+        GpsdoDeviceInfo {
+            vendor_id,
+            product_id,
+            serial_number,
+            manufacturer: None,
+            product: None,
+            path,
+            port_path,
+            interface_number,
+        }
+    }
+
+    /// Add the manufacturer and product strings of the USB device descriptor, where the backend can read them
+    pub fn with_product_strings(
+        mut self,
+        manufacturer: Option<String>,
+        product: Option<String>,
+    ) -> Self {
+        self.manufacturer = manufacturer;
+        self.product = product;
+        self
+    }
+
+    pub fn vendor_id(&self) -> u16 {
+        self.vendor_id
+    }
+
+    pub fn product_id(&self) -> u16 {
+        self.product_id
+    }
+
+    pub fn serial_number(&self) -> Option<&str> {
+        self.serial_number.as_deref()
+    }
+
+    /// The manufacturer string of the USB device descriptor, such as `Leo Bodnar`
+    pub fn manufacturer(&self) -> Option<&str> {
+        self.manufacturer.as_deref()
+    }
+
+    /// The product string of the USB device descriptor
+    pub fn product(&self) -> Option<&str> {
+        self.product.as_deref()
+    }
+
+    /// The platform specific path the backend uses to open the device, such as `/dev/hidraw0` on Linux
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The USB bus and port path of the device, such as `1-2.3` for port 3 of a hub on port 2 of bus 1, where the
+    /// backend can determine it
+    pub fn port_path(&self) -> Option<&str> {
+        self.port_path.as_deref()
+    }
+
+    /// The USB interface number of the HID interface, or -1 if unknown
+    pub fn interface_number(&self) -> i32 {
+        self.interface_number
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+/// Status of a Leo Bodnar GPSDO, showing the lock status of the system, and how many times the system has lost it's lock
+pub struct GpsdoStatus {
+    loss_count: u8,
+    sat_lock: bool,
+    pll_lock: bool,
+    locked: bool,
+}
+
+impl GpsdoStatus {
+    /// Parse a status from the data of a status input report, such as one captured from a device. No USB I/O is
+    /// involved, so this suits fuzzing, testing and replaying logs.
+    pub fn from_report_bytes(bytes: &[u8]) -> Result<Self, GpsdoError<core::convert::Infallible>> {
+        parse_status_report(bytes)
+    }
+
+    pub fn loss_count(&self) -> u8 {
+        self.loss_count
+    }
+
+    pub fn sat_locked(&self) -> bool {
+        self.sat_lock
+    }
+
+    pub fn pll_locked(&self) -> bool {
+        self.pll_lock
+    }
+
+    pub fn locked(&self) -> bool {
+        self.locked
+    }
+}
+
+/// A summary of the lock state, such as `locked, 3 losses` or `unlocked (no satellite lock), 1 loss`
+impl core::fmt::Display for GpsdoStatus {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match (self.locked, self.sat_lock, self.pll_lock) {
+            (true, _, _) => write!(f, "locked")?,
+            (false, false, false) => write!(f, "unlocked (no satellite or PLL lock)")?,
+            (false, false, true) => write!(f, "unlocked (no satellite lock)")?,
+            (false, true, false) => write!(f, "unlocked (no PLL lock)")?,
+            (false, true, true) => write!(f, "unlocked")?,
+        }
+
+        match self.loss_count {
+            1 => write!(f, ", 1 loss"),
+            loss_count => write!(f, ", {} losses", loss_count),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use core::panic;
+    #[cfg(feature = "write")]
+    use std::cell::RefCell;
+
+    use super::{
+        consts, Bandwidth, DriveCurrent, DriveLevel, GpsdoConfig, GpsdoConfigError, GpsdoDevice,
+        GpsdoDeviceInfo, GpsdoError, GpsdoModel, GpsdoStatus, Ratio, UsbInterface,
+    };
+
+    /// A config feature report for a 50 MHz output1 and 25 MHz output2, from a 4.296875 MHz TCXO
+    fn config_report() -> [u8; 61] {
+        let mut report = [0u8; 61];
+        report[..21].copy_from_slice(&[
+            0x03, 0x00, 0xAB, 0x90, 0x41, 0x1D, 0x00, 0x00, 0x06, 0xFF, 0x0E, 0x00, 0x07, 0x09,
+            0x00, 0x00, 0x13, 0x00, 0x00, 0x00, 0x0F,
+        ]);
+
+        report
+    }
+
+    struct TestUsbInterface<'a>(&'a [u8], &'a [u8]);
+
+    impl<'a> UsbInterface for TestUsbInterface<'a> {
+        type InterfaceError = std::io::Error;
+
+        fn hid_read(&self, buf: &mut [u8]) -> Result<usize, Self::InterfaceError> {
+            buf.copy_from_slice(self.0);
+
+            Ok(self.0.len())
+        }
+
+        fn hid_get_feature_report(
+            &self,
+            _report_id: u8,
+            buf: &mut [u8],
+        ) -> Result<usize, Self::InterfaceError> {
+            buf.copy_from_slice(self.1);
+
+            Ok(self.1.len())
+        }
+
+        fn serial_number(&self) -> Result<Option<String>, Self::InterfaceError> {
+            Ok(Some("AAAA-BBBB".to_string()))
+        }
+
+        #[cfg(feature = "write")]
+        fn hid_send_feature_report(
+            &self,
+            _report_id: u8,
+            _data: &[u8],
+        ) -> Result<(), Self::InterfaceError> {
+            Ok(())
+        }
+
+        fn firmware_version(&self) -> Result<Option<u16>, Self::InterfaceError> {
+            Ok(Some(0x0105))
+        }
+    }
+
+    /// Reads a config report, and records the feature reports sent to it
+    #[cfg(feature = "write")]
+    struct TestUsbWriteInterface<'a> {
+        report: &'a [u8],
+        sent: RefCell<Vec<(u8, Vec<u8>)>>,
+    }
+
+    #[cfg(feature = "write")]
+    impl<'a> UsbInterface for TestUsbWriteInterface<'a> {
+        type InterfaceError = std::io::Error;
+
+        fn hid_read(&self, _buf: &mut [u8]) -> Result<usize, Self::InterfaceError> {
+            Ok(0)
+        }
+
+        fn hid_get_feature_report(
+            &self,
+            _report_id: u8,
+            buf: &mut [u8],
+        ) -> Result<usize, Self::InterfaceError> {
+            buf.copy_from_slice(self.report);
+
+            Ok(self.report.len())
+        }
+
+        fn hid_send_feature_report(
+            &self,
+            report_id: u8,
+            data: &[u8],
+        ) -> Result<(), Self::InterfaceError> {
+            self.sent.borrow_mut().push((report_id, data.to_vec()));
+
+            Ok(())
+        }
+
+        fn serial_number(&self) -> Result<Option<String>, Self::InterfaceError> {
+            Ok(None)
+        }
+    }
+
+    struct TestUsbErrorInterface;
+
+    impl UsbInterface for TestUsbErrorInterface {
+        type InterfaceError = std::io::Error;
+
+        fn hid_read(&self, _buf: &mut [u8]) -> Result<usize, Self::InterfaceError> {
+            Err(std::io::Error::other("error reading data"))
+        }
+
+        fn serial_number(&self) -> Result<Option<String>, Self::InterfaceError> {
+            Err(std::io::Error::other("error reading serial no"))
+        }
+
+        fn hid_get_feature_report(
+            &self,
+            _report_id: u8,
+            _buf: &mut [u8],
+        ) -> Result<usize, Self::InterfaceError> {
+            Err(std::io::Error::other("error getting feature report"))
+        }
+
+        #[cfg(feature = "write")]
+        fn hid_send_feature_report(
+            &self,
+            _report_id: u8,
+            _data: &[u8],
+        ) -> Result<(), Self::InterfaceError> {
+            Err(std::io::Error::other("error sending feature report"))
+        }
+    }
+
+    #[test]
+    fn gpsdo_device_read_returns_correct_data_pll_locked_sat_locked() {
+        let test_interface = TestUsbInterface(&[23, 0b000], &[]);
+
+        let device = GpsdoDevice::new(&test_interface);
+
+        let status = device.status().expect("expected success from status");
 
         assert_eq!(status.loss_count(), 23);
         assert!(status.pll_locked());
@@ -318,6 +1493,633 @@ mod test {
         assert!(!status.locked());
     }
 
+    #[test]
+    fn gpsdo_device_config_returns_correct_data_for_standard_model() {
+        let report = config_report();
+        let test_interface = TestUsbInterface(&[], &report);
+
+        let device = GpsdoDevice::new(&test_interface);
+
+        let config = device.config().expect("expected success from config");
+
+        assert_eq!(config.model(), GpsdoModel::Standard);
+        assert!(config.output1());
+        assert_eq!(config.output2(), Some(true));
+        assert_eq!(config.level(), 0);
+        assert_eq!(config.fin(), 4296875);
+        assert_eq!(config.n3(), 30);
+        assert_eq!(config.n2_hs(), 10);
+        assert_eq!(config.n2_ls(), 3840);
+        assert_eq!(config.n1_hs(), 11);
+        assert_eq!(config.nc1_ls(), 10);
+        assert_eq!(config.nc2_ls(), Some(20));
+        assert_eq!(config.skew(), 0);
+        assert_eq!(config.bw(), 15);
+        assert_eq!(config.fosc(), 5_500_000_000);
+        assert_eq!(config.fout1(), 50_000_000);
+        assert_eq!(config.fout2(), Some(25_000_000));
+    }
+
+    #[test]
+    fn from_report_bytes_parses_captured_reports_without_a_device() {
+        let report = config_report();
+        let test_interface = TestUsbInterface(&[], &report);
+        let device = GpsdoDevice::new(&test_interface);
+
+        let config = GpsdoConfig::from_report_bytes(GpsdoModel::Standard, &report[..21]).unwrap();
+        assert_eq!(config, device.config().unwrap());
+        assert_eq!(config.to_report_bytes(), report[..60]);
+
+        let status = GpsdoStatus::from_report_bytes(&[4, 0x01]).unwrap();
+        assert_eq!(status.loss_count(), 4);
+        assert!(!status.sat_locked());
+        assert!(status.pll_locked());
+        assert!(!status.locked());
+
+        assert!(matches!(
+            GpsdoConfig::from_report_bytes(GpsdoModel::Standard, &report[..20]),
+            Err(GpsdoError::ShortDataError {
+                expected: 21,
+                received: 20
+            })
+        ));
+        assert!(matches!(
+            GpsdoStatus::from_report_bytes(&[4]),
+            Err(GpsdoError::ShortDataError {
+                expected: 2,
+                received: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn gpsdo_device_config_omits_output2_for_mini_model() {
+        let report = config_report();
+        let test_interface = TestUsbInterface(&[], &report);
+
+        let device = GpsdoDevice::with_model(&test_interface, GpsdoModel::Mini);
+        assert_eq!(device.model(), GpsdoModel::Mini);
+        assert!(!device.model().has_output2());
+
+        let config = device.config().expect("expected success from config");
+
+        assert_eq!(config.model(), GpsdoModel::Mini);
+        assert_eq!(config.fout1(), 50_000_000);
+        assert_eq!(config.output2(), None);
+        assert_eq!(config.nc2_ls(), None);
+        assert_eq!(config.fout2(), None);
+    }
+
+    #[test]
+    fn gpsdo_config_violations_check_the_model_limits() {
+        assert!(GpsdoModel::Standard.has_output2());
+        assert!(!GpsdoModel::Mini.has_output2());
+
+        // n1_hs of 4 and nc1_ls of 1 divide the 5.5 GHz fosc to 1.375 GHz
+        let mut report = config_report();
+        report[12] = 0x00;
+        report[13] = 0x00;
+        for model in [GpsdoModel::Standard, GpsdoModel::Mini] {
+            let test_interface = TestUsbInterface(&[], &report);
+            let device = GpsdoDevice::with_model(&test_interface, model);
+
+            assert_eq!(
+                device
+                    .config()
+                    .expect("expected success from config")
+                    .validate(),
+                [GpsdoConfigError::FrequencyOutOfRange {
+                    name: "fout1",
+                    value: 1_375_000_000,
+                    min: 1,
+                    max: model.max_fout(),
+                }]
+            );
+            assert!(model
+                .drive_levels()
+                .contains(&DriveLevel::from_raw(0x03).unwrap()));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "write")]
+    fn gpsdo_device_write_config_sends_the_config_report_back() {
+        let report = config_report();
+        let test_interface = TestUsbWriteInterface {
+            report: &report,
+            sent: RefCell::new(vec![]),
+        };
+
+        let device = GpsdoDevice::new(&test_interface);
+
+        let config = device.config().expect("expected success from config");
+        device
+            .write_config(&config)
+            .expect("expected success from write_config");
+
+        let sent = test_interface.sent.borrow();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].0, 9);
+        assert_eq!(&sent[0].1[..21], &report[..21]);
+        assert!(sent[0].1[21..].iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    #[cfg(feature = "write")]
+    fn gpsdo_device_write_config_refuses_invalid_config() {
+        let mut report = config_report();
+        report[12] = 0xFF;
+        let test_interface = TestUsbWriteInterface {
+            report: &report,
+            sent: RefCell::new(vec![]),
+        };
+
+        let device = GpsdoDevice::new(&test_interface);
+
+        let config = device.config().expect("expected success from config");
+
+        assert!(matches!(
+            device.write_config(&config),
+            Err(GpsdoError::InvalidConfigError(
+                GpsdoConfigError::DividerOutOfRange { name: "n1_hs", .. }
+            ))
+        ));
+        assert!(test_interface.sent.borrow().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "write")]
+    fn gpsdo_device_set_outputs_only_changes_the_output_bits() {
+        let report = config_report();
+        let test_interface = TestUsbWriteInterface {
+            report: &report,
+            sent: RefCell::new(vec![]),
+        };
+
+        let device = GpsdoDevice::new(&test_interface);
+
+        device
+            .set_outputs(None, Some(false))
+            .expect("expected success from set_outputs");
+        device
+            .set_outputs(Some(false), Some(true))
+            .expect("expected success from set_outputs");
+
+        let sent = test_interface.sent.borrow();
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[0].0, 9);
+        assert_eq!(sent[0].1[0], 0x01);
+        assert_eq!(sent[1].1[0], 0x02);
+        assert_eq!(&sent[0].1[1..], &report[1..60]);
+    }
+
+    #[test]
+    #[cfg(feature = "write")]
+    fn gpsdo_device_set_outputs_ignores_output2_for_mini_model() {
+        let report = config_report();
+        let test_interface = TestUsbWriteInterface {
+            report: &report,
+            sent: RefCell::new(vec![]),
+        };
+
+        let device = GpsdoDevice::with_model(&test_interface, GpsdoModel::Mini);
+
+        device
+            .set_outputs(Some(false), Some(false))
+            .expect("expected success from set_outputs");
+
+        let sent = test_interface.sent.borrow();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].1[0], report[0] & !0x01);
+        assert_eq!(sent[0].1[0] & 0x02, report[0] & 0x02);
+        assert_eq!(&sent[0].1[1..], &report[1..60]);
+    }
+
+    #[test]
+    #[cfg(feature = "write")]
+    fn gpsdo_device_set_drive_level_only_changes_the_level() {
+        let report = config_report();
+        let test_interface = TestUsbWriteInterface {
+            report: &report,
+            sent: RefCell::new(vec![]),
+        };
+
+        let device = GpsdoDevice::new(&test_interface);
+
+        device
+            .set_drive_level(DriveLevel::Ma24)
+            .expect("expected success from set_drive_level");
+
+        let sent = test_interface.sent.borrow();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].1[1], 2);
+        assert_eq!(sent[0].1[0], report[0]);
+        assert_eq!(&sent[0].1[2..], &report[2..60]);
+    }
+
+    #[test]
+    #[cfg(feature = "write")]
+    fn gpsdo_device_set_skew_only_changes_the_skew() {
+        let report = config_report();
+        let test_interface = TestUsbWriteInterface {
+            report: &report,
+            sent: RefCell::new(vec![]),
+        };
+
+        let device = GpsdoDevice::new(&test_interface);
+
+        device.set_skew(11).expect("expected success from set_skew");
+
+        let sent = test_interface.sent.borrow();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].1[19], 11);
+        assert_eq!(&sent[0].1[..19], &report[..19]);
+        assert_eq!(&sent[0].1[20..], &report[20..60]);
+    }
+
+    #[test]
+    fn gpsdo_config_checked_skew_ps_uses_the_oscillator_period() {
+        let mut report = config_report();
+        report[19] = 11;
+        let test_interface = TestUsbInterface(&[], &report);
+
+        let device = GpsdoDevice::new(&test_interface);
+
+        let config = device.config().expect("expected success from config");
+
+        // fosc is 5.5 GHz, so each step is 181.8 ps
+        let skew_ps = config
+            .checked_skew_ps()
+            .expect("expected success from checked_skew_ps");
+        assert!((skew_ps - 2000.0).abs() < 0.01);
+    }
+
+    #[test]
+    #[cfg(feature = "write")]
+    fn gpsdo_device_set_bandwidth_only_changes_the_bandwidth() {
+        let report = config_report();
+        let test_interface = TestUsbWriteInterface {
+            report: &report,
+            sent: RefCell::new(vec![]),
+        };
+
+        let device = GpsdoDevice::new(&test_interface);
+
+        device
+            .set_bandwidth(Bandwidth::Bwsel(7))
+            .expect("expected success from set_bandwidth");
+
+        let sent = test_interface.sent.borrow();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].1[20], 7);
+        assert_eq!(&sent[0].1[..20], &report[..20]);
+        assert_eq!(&sent[0].1[21..], &report[21..60]);
+    }
+
+    #[test]
+    fn bandwidth_decodes_bwsel_and_keeps_raw_values() {
+        assert_eq!(Bandwidth::from_raw(15), Bandwidth::Bwsel(15));
+        assert_eq!(Bandwidth::from_raw(15).bwsel(), Some(15));
+
+        assert_eq!(Bandwidth::from_raw(0x80), Bandwidth::Raw(0x80));
+        assert_eq!(Bandwidth::Raw(0x80).bwsel(), None);
+        assert_eq!(Bandwidth::Raw(0x80).raw(), 0x80);
+        assert_eq!(Bandwidth::Bwsel(0x80).bwsel(), None);
+    }
+
+    #[test]
+    fn drive_level_converts_between_raw_and_milliamps() {
+        for (raw, milliamps) in [(0, 8), (1, 16), (2, 24), (3, 32)] {
+            let level = DriveLevel::from_raw(raw).expect("expected a known level");
+
+            assert_eq!(level.milliamps(), milliamps);
+            assert_eq!(DriveLevel::from_milliamps(milliamps), Some(level));
+            assert_eq!(level.raw(), raw);
+        }
+
+        assert_eq!(DriveLevel::from_raw(4), None);
+        for milliamps in [0, 4, 12, 40, 255] {
+            assert_eq!(DriveLevel::from_milliamps(milliamps), None);
+        }
+        assert_eq!(DriveLevel::Ma16.to_string(), "16 mA");
+    }
+
+    #[test]
+    fn level_ma_reports_unknown_levels_with_their_raw_value() {
+        let mut report = config_report();
+        let config = GpsdoConfig::from_report_bytes(GpsdoModel::Standard, &report[..21]).unwrap();
+        assert_eq!(config.level_ma(), DriveCurrent::Milliamps(8));
+        assert_eq!(config.level_ma().milliamps(), Some(8));
+
+        report[1] = 7;
+        let config = GpsdoConfig::from_report_bytes(GpsdoModel::Standard, &report[..21]).unwrap();
+        assert_eq!(config.level_ma(), DriveCurrent::Unknown(7));
+        assert_eq!(config.level_ma().milliamps(), None);
+        assert_eq!(config.level_ma().to_string(), "unknown drive level 7");
+    }
+
+    #[test]
+    fn gpsdo_device_firmware_version_decodes_bcd() {
+        let test_interface = TestUsbInterface(&[], &[]);
+
+        let device = GpsdoDevice::new(&test_interface);
+
+        let version = device
+            .firmware_version()
+            .expect("expected success from firmware_version")
+            .expect("expected a firmware version");
+
+        assert_eq!(version.major(), 1);
+        assert_eq!(version.minor(), 5);
+        assert_eq!(version.to_string(), "1.05");
+    }
+
+    #[test]
+    fn gpsdo_device_info_is_none_for_interfaces_which_cannot_describe_the_device() {
+        let test_interface = TestUsbInterface(&[], &[]);
+
+        let device = GpsdoDevice::new(&test_interface);
+
+        assert!(device
+            .device_info()
+            .expect("expected success from device_info")
+            .is_none());
+    }
+
+    /// An interface which can describe the device, as the hidapi backend can
+    struct DescribedUsbInterface;
+
+    impl UsbInterface for DescribedUsbInterface {
+        type InterfaceError = std::io::Error;
+
+        fn hid_read(&self, _buf: &mut [u8]) -> Result<usize, Self::InterfaceError> {
+            Ok(0)
+        }
+
+        fn hid_get_feature_report(
+            &self,
+            _report_id: u8,
+            _buf: &mut [u8],
+        ) -> Result<usize, Self::InterfaceError> {
+            Ok(0)
+        }
+
+        #[cfg(feature = "write")]
+        fn hid_send_feature_report(
+            &self,
+            _report_id: u8,
+            _data: &[u8],
+        ) -> Result<(), Self::InterfaceError> {
+            Ok(())
+        }
+
+        fn serial_number(&self) -> Result<Option<String>, Self::InterfaceError> {
+            Ok(Some("AAAA-BBBB".to_string()))
+        }
+
+        fn device_info(&self) -> Result<Option<GpsdoDeviceInfo>, Self::InterfaceError> {
+            Ok(Some(GpsdoDeviceInfo::new(
+                consts::VID_LEO_BONDAR,
+                consts::PID_LEO_BODNAR_GPSDO,
+                Some("AAAA-BBBB".to_string()),
+                "/dev/hidraw0".to_string(),
+                Some("1-2".to_string()),
+                0,
+            )))
+        }
+    }
+
+    #[test]
+    fn gpsdo_device_info_is_the_same_through_a_borrowed_interface() {
+        let interface = DescribedUsbInterface;
+
+        let owned = GpsdoDevice::new(DescribedUsbInterface);
+        let borrowed = GpsdoDevice::new(&interface);
+
+        for device in [owned.device_info(), borrowed.device_info()] {
+            let info = device
+                .expect("expected success from device_info")
+                .expect("expected device info");
+
+            assert_eq!(info.product_id(), consts::PID_LEO_BODNAR_GPSDO);
+            assert_eq!(info.serial_number(), Some("AAAA-BBBB"));
+            assert_eq!(info.path(), "/dev/hidraw0");
+        }
+    }
+
+    #[test]
+    fn gpsdo_device_config_checked_frequencies_match_valid_config() {
+        let report = config_report();
+        let test_interface = TestUsbInterface(&[], &report);
+
+        let device = GpsdoDevice::new(&test_interface);
+
+        let config = device.config().expect("expected success from config");
+
+        assert_eq!(config.checked_f3(), Ok(143_229));
+        assert_eq!(config.checked_fosc(), Ok(5_500_000_000));
+        assert_eq!(config.checked_fout1(), Ok(50_000_000));
+        assert_eq!(config.checked_fout2(), Ok(Some(25_000_000)));
+    }
+
+    #[test]
+    fn gpsdo_device_config_checked_frequencies_reject_garbage_dividers() {
+        let mut report = config_report();
+        report[12] = 0xFF;
+        let test_interface = TestUsbInterface(&[], &report);
+
+        let device = GpsdoDevice::new(&test_interface);
+
+        let config = device.config().expect("expected success from config");
+
+        assert_eq!(
+            config.checked_fout1(),
+            Err(GpsdoConfigError::DividerOutOfRange {
+                name: "n1_hs",
+                value: 3,
+                min: 4,
+                max: 11
+            })
+        );
+        assert!(config.checked_fout2().is_err());
+    }
+
+    #[test]
+    fn gpsdo_device_config_checked_frequencies_reject_out_of_range_oscillator() {
+        let mut report = config_report();
+        report[2..5].copy_from_slice(&[0x00, 0x00, 0x00]);
+        let test_interface = TestUsbInterface(&[], &report);
+
+        let device = GpsdoDevice::new(&test_interface);
+
+        let config = device.config().expect("expected success from config");
+
+        assert!(matches!(
+            config.checked_fosc(),
+            Err(GpsdoConfigError::FrequencyOutOfRange { name: "f3", .. })
+        ));
+        assert!(config.checked_fout1().is_err());
+    }
+
+    #[test]
+    fn gpsdo_device_config_exact_frequencies_keep_the_fraction() {
+        let mut report = config_report();
+        // n3 of 29 rather than 30
+        report[5] = 0x1C;
+        let test_interface = TestUsbInterface(&[], &report);
+
+        let device = GpsdoDevice::new(&test_interface);
+
+        let config = device.config().expect("expected success from config");
+
+        assert_eq!(config.f3_exact(), Ratio::new(4_296_875, 29));
+        assert_eq!(config.fosc_exact(), Ratio::new(165_000_000_000, 29));
+        assert_eq!(config.fout1_exact(), Ratio::new(1_500_000_000, 29));
+        assert_eq!(config.fout2_exact(), Some(Ratio::new(750_000_000, 29)));
+        assert_eq!(config.fout1(), 51_724_137);
+        assert_eq!(*config.fout1_exact().floor().numer(), config.fout1());
+
+        let residual = config.fout1_residual();
+        assert!(!residual.is_integer());
+        assert_eq!(residual.truncated(), 51_724_137);
+        assert!((residual.hz() - 27.0 / 29.0).abs() < 1e-9);
+        assert!((residual.ppb() - 18.0).abs() < 0.01);
+
+        let test_interface = TestUsbInterface(&[], &config_report());
+        let device = GpsdoDevice::new(&test_interface);
+        let config = device.config().expect("expected success from config");
+
+        assert_eq!(config.fout1_exact(), Ratio::from_integer(50_000_000));
+        assert!(config.fout1_residual().is_integer());
+        assert_eq!(
+            config.fout2_residual().map(|residual| residual.hz()),
+            Some(0.0)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn gpsdo_config_and_status_round_trip_through_serde() {
+        let test_interface = TestUsbInterface(&[0x03, 0x00], &config_report());
+        let device = GpsdoDevice::new(&test_interface);
+
+        let config = device.config().expect("expected success from config");
+        let json = serde_json::to_string(&config).expect("expected config to serialize");
+        let deserialized: super::GpsdoConfig =
+            serde_json::from_str(&json).expect("expected config to deserialize");
+        assert_eq!(
+            serde_json::to_string(&deserialized).expect("expected config to serialize"),
+            json
+        );
+        assert_eq!(deserialized.checked_fout1(), Ok(50_000_000));
+
+        let status = device.status().expect("expected success from status");
+        let json = serde_json::to_string(&status).expect("expected status to serialize");
+        assert_eq!(
+            serde_json::from_str::<super::GpsdoStatus>(&json)
+                .expect("expected status to deserialize"),
+            status
+        );
+    }
+
+    #[test]
+    fn gpsdo_device_can_own_its_interface() {
+        static STATUS_REPORT: [u8; 2] = [0x03, 0x01];
+
+        let device = GpsdoDevice::new(TestUsbInterface(&STATUS_REPORT, &[]));
+
+        // An owned device has no borrow to outlive, so it can be moved to another thread
+        let status = std::thread::spawn(move || device.status().map(|status| (status, device)))
+            .join()
+            .expect("thread panicked");
+        let (status, device) = status.expect("expected success from status");
+
+        assert_eq!(status.loss_count(), 3);
+        assert_eq!(device.into_interface().0, STATUS_REPORT);
+    }
+
+    #[test]
+    fn gpsdo_config_and_status_display_a_summary() {
+        let test_interface = TestUsbInterface(&[0x03, 0x01], &config_report());
+        let device = GpsdoDevice::new(&test_interface);
+
+        let config = device.config().expect("expected success from config");
+        assert_eq!(
+            config.to_string(),
+            "fout1=50 MHz (on), fout2=25 MHz (on), 8 mA"
+        );
+        assert_eq!(config, config.clone());
+
+        let status = device.status().expect("expected success from status");
+        assert_eq!(status.to_string(), "unlocked (no satellite lock), 3 losses");
+
+        let mut report = config_report();
+        report[0] = 0x01;
+        report[1] = 0x07;
+        let test_interface = TestUsbInterface(&[0x01, 0x00], &report);
+        let device = GpsdoDevice::with_model(&test_interface, GpsdoModel::Mini);
+
+        let mini_config = device.config().expect("expected success from config");
+        assert_eq!(
+            mini_config.to_string(),
+            "fout1=50 MHz (on), unknown drive level 7"
+        );
+        assert_ne!(mini_config, config);
+        assert_eq!(
+            device
+                .status()
+                .expect("expected success from status")
+                .to_string(),
+            "locked, 1 loss"
+        );
+
+        assert_eq!(super::Frequency(12_800_000).to_string(), "12.8 MHz");
+        assert_eq!(super::Frequency(32_768).to_string(), "32.768 kHz");
+        assert_eq!(super::Frequency(10_000_050).to_string(), "10.00005 MHz");
+        assert_eq!(super::Frequency(999).to_string(), "999 Hz");
+    }
+
+    #[test]
+    fn gpsdo_device_config_validate_lists_every_violation() {
+        let test_interface = TestUsbInterface(&[], &config_report());
+        let device = GpsdoDevice::new(&test_interface);
+        assert_eq!(
+            device
+                .config()
+                .expect("expected success from config")
+                .validate(),
+            []
+        );
+
+        let mut report = config_report();
+        report[1] = 0x07;
+        report[12] = 0xFF;
+        report[16] = 0x14;
+        let test_interface = TestUsbInterface(&[], &report);
+        let device = GpsdoDevice::new(&test_interface);
+
+        assert_eq!(
+            device
+                .config()
+                .expect("expected success from config")
+                .validate(),
+            [
+                GpsdoConfigError::DividerOutOfRange {
+                    name: "n1_hs",
+                    value: 3,
+                    min: 4,
+                    max: 11
+                },
+                GpsdoConfigError::UnknownDriveLevel { value: 7 },
+                GpsdoConfigError::OddDivider {
+                    name: "nc2_ls",
+                    value: 21,
+                    allow_one: true
+                },
+            ]
+        );
+    }
+
     #[test]
     fn gpsdo_device_serial_number_returns_serial_number_when_serial_number_is_returned_from_interface(
     ) {
@@ -331,6 +2133,47 @@ mod test {
         assert_eq!(result.unwrap(), Some("AAAA-BBBB".into()));
     }
 
+    #[test]
+    fn gpsdo_device_serial_number_into_copies_serial_number_into_buffer() {
+        let test_interface = TestUsbInterface(&[], &[]);
+
+        let device = GpsdoDevice::new(&test_interface);
+
+        let mut buf = [0u8; 32];
+        let result = device.serial_number_into(&mut buf);
+
+        assert_eq!(result.unwrap(), Some("AAAA-BBBB"));
+    }
+
+    #[test]
+    fn gpsdo_device_serial_number_into_returns_error_when_buffer_is_too_small() {
+        let test_interface = TestUsbInterface(&[], &[]);
+
+        let device = GpsdoDevice::new(&test_interface);
+
+        let mut buf = [0u8; 4];
+        let result = device.serial_number_into(&mut buf);
+
+        assert!(matches!(
+            result,
+            Err(GpsdoError::SerialNumberTooLong {
+                len: 9,
+                capacity: 4
+            })
+        ));
+    }
+
+    #[test]
+    fn usb_interface_serial_number_into_returns_length_needed_when_buffer_is_too_small() {
+        let test_interface = TestUsbInterface(&[], &[]);
+
+        let mut buf = [0u8; 4];
+        let result = test_interface.serial_number_into(&mut buf);
+
+        assert_eq!(result.unwrap(), Some(9));
+        assert_eq!(buf, [0u8; 4]);
+    }
+
     #[test]
     fn gpsdo_device_read_returns_error_when_interface_returns_error() {
         let test_interface = TestUsbErrorInterface {};
@@ -344,7 +2187,7 @@ mod test {
             Err(e) => {
                 assert_eq!(
                     e.to_string(),
-                    "underlying usb interface errored: error reading data"
+                    "failed to read status report: error reading data"
                 );
             }
         }
@@ -365,9 +2208,44 @@ mod test {
             Err(e) => {
                 assert_eq!(
                     e.to_string(),
-                    "underlying usb interface errored: error reading serial no"
+                    "failed to read serial number: error reading serial no"
                 );
             }
         }
     }
+
+    #[test]
+    fn gpsdo_device_errors_name_the_report_which_failed() {
+        let device = GpsdoDevice::new(TestUsbErrorInterface);
+
+        let error = device.config().expect_err("expected error from config");
+        assert!(matches!(
+            error,
+            GpsdoError::FeatureReportError { report_id: 9, .. }
+        ));
+        assert_eq!(
+            error.to_string(),
+            "failed to read feature report 9: error getting feature report"
+        );
+        assert_eq!(
+            error.interface_error().map(|error| error.to_string()),
+            Some("error getting feature report".to_owned())
+        );
+
+        let error = GpsdoStatus::from_report_bytes(&[4]).expect_err("expected short data");
+        assert!(error.interface_error().is_none());
+    }
+
+    #[cfg(feature = "write")]
+    #[test]
+    fn gpsdo_device_write_errors_name_the_report_which_failed() {
+        let device = GpsdoDevice::new(TestUsbErrorInterface);
+        let config = GpsdoConfig::from_report_bytes(GpsdoModel::Standard, &config_report())
+            .expect("expected a valid config report");
+
+        assert!(matches!(
+            device.write_config(&config),
+            Err(GpsdoError::FeatureReportWriteError { report_id: 9, .. })
+        ));
+    }
 }
@@ -0,0 +1,307 @@
+//! A fake `UsbInterface`, for testing applications which use the driver without a GPSDO attached. Only available with
+//! the `test-util` feature.
+
+use thiserror::Error;
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{Mutex, MutexGuard},
+};
+
+use crate::{
+    reports::{CONFIG_REPORT_BUFFER_LEN, CONFIG_REPORT_ID, CONFIG_REPORT_LEN},
+    GpsdoConfig, GpsdoDeviceInfo, UsbInterface,
+};
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+/// An error returned by a `MockUsbInterface`
+pub enum MockError {
+    #[error("no input report is queued")]
+    NoReportQueued,
+
+    #[error("no feature report {0} is set")]
+    NoFeatureReport(u8),
+
+    #[error("{0}")]
+    Injected(String),
+}
+
+/// A fake GPSDO, which reads queued input reports and programmed feature reports, and can be made to fail. Its methods
+/// take `&self`, so it can still be programmed through `GpsdoDevice::interface` once a device owns it.
+#[derive(Default)]
+pub struct MockUsbInterface {
+    state: Mutex<MockState>,
+}
+
+#[derive(Default)]
+struct MockState {
+    reads: VecDeque<Vec<u8>>,
+    feature_reports: BTreeMap<u8, Vec<u8>>,
+    serial_number: Option<String>,
+    firmware_version: Option<u16>,
+    device_info: Option<GpsdoDeviceInfo>,
+    errors: VecDeque<MockError>,
+    #[cfg(feature = "write")]
+    sent: Vec<(u8, Vec<u8>)>,
+}
+
+impl MockUsbInterface {
+    /// A GPSDO with no reports queued or set, no serial number, no firmware version and no device info
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self) -> MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    /// Queue an input report, to be returned by the next `hid_read` which finds no earlier report queued
+    pub fn push_read(&self, report: &[u8]) {
+        self.state().reads.push_back(report.to_vec());
+    }
+
+    /// Queue a status report, as read by `GpsdoDevice::status`
+    pub fn push_status(&self, loss_count: u8, sat_lock: bool, pll_lock: bool) {
+        let flags = u8::from(!sat_lock) | u8::from(!pll_lock) << 1;
+
+        self.push_read(&[loss_count, flags]);
+    }
+
+    /// Set the feature report returned for `report_id` until it is set again or a report with that id is sent
+    pub fn set_feature_report(&self, report_id: u8, report: &[u8]) {
+        self.state()
+            .feature_reports
+            .insert(report_id, report.to_vec());
+    }
+
+    /// Set the config report, as read by `GpsdoDevice::config`
+    pub fn set_config(&self, config: &GpsdoConfig) {
+        let mut report = [0u8; CONFIG_REPORT_BUFFER_LEN];
+        report[..CONFIG_REPORT_LEN].copy_from_slice(&config.to_report_bytes());
+
+        self.set_feature_report(CONFIG_REPORT_ID, &report);
+    }
+
+    pub fn set_serial_number(&self, serial_number: Option<&str>) {
+        self.state().serial_number = serial_number.map(str::to_owned);
+    }
+
+    /// Set the firmware version as a binary coded decimal, such as `0x0105` for 1.05
+    pub fn set_firmware_version(&self, firmware_version: Option<u16>) {
+        self.state().firmware_version = firmware_version;
+    }
+
+    /// Set the description of the device and where it is attached, as read by `GpsdoDevice::device_info`
+    pub fn set_device_info(&self, device_info: Option<GpsdoDeviceInfo>) {
+        self.state().device_info = device_info;
+    }
+
+    /// Fail the next call to the interface, whichever method it is, with `MockError::Injected`. Failures queue up, so
+    /// calling this twice fails the next two calls.
+    pub fn fail_next(&self, message: &str) {
+        self.state()
+            .errors
+            .push_back(MockError::Injected(message.to_owned()));
+    }
+
+    /// The feature reports sent to the interface, in the order they were sent. Only available with the `write`
+    /// feature.
+    #[cfg(feature = "write")]
+    pub fn sent_feature_reports(&self) -> Vec<(u8, Vec<u8>)> {
+        self.state().sent.clone()
+    }
+
+    /// Lock the state, returning the next injected failure if there is one
+    fn call(&self) -> Result<MutexGuard<'_, MockState>, MockError> {
+        let mut state = self.state();
+        match state.errors.pop_front() {
+            Some(error) => Err(error),
+            None => Ok(state),
+        }
+    }
+}
+
+impl UsbInterface for MockUsbInterface {
+    type InterfaceError = MockError;
+
+    fn hid_read(&self, buf: &mut [u8]) -> Result<usize, Self::InterfaceError> {
+        let report = self
+            .call()?
+            .reads
+            .pop_front()
+            .ok_or(MockError::NoReportQueued)?;
+
+        let len = report.len().min(buf.len());
+        buf[..len].copy_from_slice(&report[..len]);
+
+        Ok(len)
+    }
+
+    fn hid_get_feature_report(
+        &self,
+        report_id: u8,
+        buf: &mut [u8],
+    ) -> Result<usize, Self::InterfaceError> {
+        let state = self.call()?;
+        let report = state
+            .feature_reports
+            .get(&report_id)
+            .ok_or(MockError::NoFeatureReport(report_id))?;
+
+        let len = report.len().min(buf.len());
+        buf[..len].copy_from_slice(&report[..len]);
+
+        Ok(len)
+    }
+
+    /// Records the report, and updates the feature report with that id so it reads back as written, like a GPSDO
+    #[cfg(feature = "write")]
+    fn hid_send_feature_report(
+        &self,
+        report_id: u8,
+        data: &[u8],
+    ) -> Result<(), Self::InterfaceError> {
+        let mut state = self.call()?;
+        state.sent.push((report_id, data.to_vec()));
+
+        let report = state.feature_reports.entry(report_id).or_default();
+        if report.len() < data.len() {
+            report.resize(data.len(), 0);
+        }
+        report[..data.len()].copy_from_slice(data);
+
+        Ok(())
+    }
+
+    fn serial_number(&self) -> Result<Option<String>, Self::InterfaceError> {
+        Ok(self.call()?.serial_number.clone())
+    }
+
+    fn firmware_version(&self) -> Result<Option<u16>, Self::InterfaceError> {
+        Ok(self.call()?.firmware_version)
+    }
+
+    fn device_info(&self) -> Result<Option<GpsdoDeviceInfo>, Self::InterfaceError> {
+        Ok(self.call()?.device_info.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MockError, MockUsbInterface};
+    use crate::{
+        consts, Bandwidth, DriveLevel, GpsdoConfig, GpsdoConfigBuilder, GpsdoDevice,
+        GpsdoDeviceInfo, GpsdoError, GpsdoModel,
+    };
+
+    /// The config the GPSDO ships with, 50 MHz on output1 and 25 MHz on output2
+    fn shipped_config() -> GpsdoConfig {
+        GpsdoConfigBuilder::new(GpsdoModel::Standard)
+            .drive_level(DriveLevel::Ma8)
+            .fin(4_296_875)
+            .n3(30)
+            .n2_hs(10)
+            .n2_ls(3840)
+            .n1_hs(11)
+            .nc1_ls(10)
+            .nc2_ls(20)
+            .bandwidth(Bandwidth::Bwsel(15))
+            .skew(3)
+            .build()
+            .expect("expected a valid config")
+    }
+
+    #[test]
+    fn mock_reads_queued_statuses_in_order() {
+        let device = GpsdoDevice::new(MockUsbInterface::new());
+        device.interface().push_status(1, true, true);
+        device.interface().push_status(2, false, true);
+
+        let status = device.status().expect("expected success from status");
+        assert_eq!(status.loss_count(), 1);
+        assert!(status.locked());
+
+        let status = device.status().expect("expected success from status");
+        assert_eq!(status.loss_count(), 2);
+        assert!(!status.sat_locked());
+        assert!(status.pll_locked());
+        assert!(!status.locked());
+
+        assert!(matches!(
+            device.status(),
+            Err(GpsdoError::StatusReadError {
+                source: MockError::NoReportQueued
+            })
+        ));
+    }
+
+    #[test]
+    fn mock_serves_the_config_it_is_given() {
+        let config = shipped_config();
+
+        let device = GpsdoDevice::new(MockUsbInterface::new());
+        device.interface().set_config(&config);
+
+        assert_eq!(
+            device.config().expect("expected success from config"),
+            config
+        );
+    }
+
+    #[test]
+    fn mock_describes_the_device_it_is_given() {
+        let device = GpsdoDevice::new(MockUsbInterface::new());
+        assert!(device.device_info().unwrap().is_none());
+
+        device
+            .interface()
+            .set_device_info(Some(GpsdoDeviceInfo::new(
+                consts::VID_LEO_BONDAR,
+                consts::PID_LEO_BODNAR_MINI_GPSDO,
+                None,
+                "mock".to_owned(),
+                None,
+                -1,
+            )));
+
+        let info = device
+            .device_info()
+            .expect("expected success from device_info")
+            .expect("expected device info");
+        assert_eq!(info.product_id(), consts::PID_LEO_BODNAR_MINI_GPSDO);
+        assert_eq!(info.path(), "mock");
+    }
+
+    #[test]
+    fn mock_fails_the_next_calls_when_told_to() {
+        let device = GpsdoDevice::new(MockUsbInterface::new());
+        device.interface().set_serial_number(Some("AAAA-BBBB"));
+        device.interface().fail_next("unplugged");
+
+        assert!(matches!(
+            device.serial_number(),
+            Err(GpsdoError::SerialNumberError { source: MockError::Injected(message) }) if message == "unplugged"
+        ));
+        assert_eq!(
+            device.serial_number().unwrap().as_deref(),
+            Some("AAAA-BBBB")
+        );
+    }
+
+    #[cfg(feature = "write")]
+    #[test]
+    fn mock_reads_back_written_configs() {
+        let config = shipped_config();
+
+        let device = GpsdoDevice::new(MockUsbInterface::new());
+        device
+            .write_config(&config)
+            .expect("expected success from write_config");
+
+        assert_eq!(device.interface().sent_feature_reports().len(), 1);
+        assert_eq!(
+            device.config().expect("expected success from config"),
+            config
+        );
+    }
+}
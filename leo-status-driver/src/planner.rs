@@ -0,0 +1,268 @@
+//! Plans the dividers of the Si5328 PLL in the GPSDO which produce the output frequencies wanted from a TCXO frequency.
+//!
+//! The PLL divides `fin` by `n3` to get `f3`, multiplies it by `n2_hs * n2_ls` to get `fosc`, then divides `fosc` by
+//! `n1_hs * nc1_ls` for output1 and `n1_hs * nc2_ls` for output2. Only plans which produce the frequencies exactly, and
+//! keep every divider and frequency in the range of the PLL, are returned.
+
+#[cfg(feature = "alloc")]
+use alloc::{vec, vec::Vec};
+use thiserror::Error;
+
+#[cfg(feature = "alloc")]
+use crate::consts;
+
+/// The highest value of the N3 input divider
+pub const N3_MAX: u32 = 1 << 19;
+
+/// The highest value of the N2_LS, NC1_LS and NC2_LS low speed dividers
+pub const LS_DIVIDER_MAX: u32 = 1 << 20;
+
+#[derive(Debug, Error, Clone, PartialEq)]
+/// An error which stopped a plan from being made
+pub enum PlanError {
+    #[error("frequency {name} must not be zero")]
+    ZeroFrequency { name: &'static str },
+
+    #[error(
+        "no dividers produce exactly {fout1} Hz{} from {fin} Hz",
+        and_fout2(fout2)
+    )]
+    NoPlan {
+        fin: u32,
+        fout1: u64,
+        fout2: Option<u64>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A set of PLL dividers which produces the output frequencies asked for
+pub struct DividerPlan {
+    fin: u32,
+    n3: u32,
+    n2_hs: u8,
+    n2_ls: u32,
+    n1_hs: u8,
+    nc1_ls: u32,
+    nc2_ls: Option<u32>,
+}
+
+impl DividerPlan {
+    pub fn fin(&self) -> u32 {
+        self.fin
+    }
+
+    pub fn n3(&self) -> u32 {
+        self.n3
+    }
+
+    pub fn n2_hs(&self) -> u8 {
+        self.n2_hs
+    }
+
+    pub fn n2_ls(&self) -> u32 {
+        self.n2_ls
+    }
+
+    pub fn n1_hs(&self) -> u8 {
+        self.n1_hs
+    }
+
+    pub fn nc1_ls(&self) -> u32 {
+        self.nc1_ls
+    }
+
+    /// The output2 divider, `None` if no output2 frequency was asked for
+    pub fn nc2_ls(&self) -> Option<u32> {
+        self.nc2_ls
+    }
+
+    /// The phase detector frequency, fin after division by n3
+    pub fn f3(&self) -> u64 {
+        self.fin as u64 / self.n3 as u64
+    }
+
+    /// The frequency of the output of the PLL
+    pub fn fosc(&self) -> u64 {
+        self.fin as u64 * self.n2_hs as u64 * self.n2_ls as u64 / self.n3 as u64
+    }
+
+    pub fn fout1(&self) -> u64 {
+        self.fosc() / (self.n1_hs as u64 * self.nc1_ls as u64)
+    }
+
+    pub fn fout2(&self) -> Option<u64> {
+        self.nc2_ls
+            .map(|nc2_ls| self.fosc() / (self.n1_hs as u64 * nc2_ls as u64))
+    }
+}
+
+/// Find every set of dividers which produces exactly `fout1`, and `fout2` if given, from `fin`. The plans are sorted
+/// best first, with the highest phase detector frequency f3, which gives the lowest jitter, then the lowest fosc. Only
+/// available with the `alloc` feature.
+#[cfg(feature = "alloc")]
+pub fn plan(fin: u32, fout1: u64, fout2: Option<u64>) -> Result<Vec<DividerPlan>, PlanError> {
+    for (name, frequency) in [
+        ("fin", Some(fin as u64)),
+        ("fout1", Some(fout1)),
+        ("fout2", fout2),
+    ] {
+        if frequency == Some(0) {
+            return Err(PlanError::ZeroFrequency { name });
+        }
+    }
+
+    let mut plans = vec![];
+
+    for n1_hs in consts::HS_DIVIDER_MIN..=consts::HS_DIVIDER_MAX {
+        let step = fout1 * n1_hs as u64;
+        let nc1_min = consts::FOSC_MIN.div_ceil(step).max(1);
+        let nc1_max = (consts::FOSC_MAX / step).min(LS_DIVIDER_MAX as u64);
+
+        for nc1_ls in (nc1_min..=nc1_max).filter(|&nc| is_valid_ls_divider(nc)) {
+            let fosc = step * nc1_ls;
+
+            let nc2_ls = match fout2 {
+                Some(fout2) => {
+                    let output2_step = fout2 * n1_hs as u64;
+                    if !fosc.is_multiple_of(output2_step)
+                        || !is_valid_ls_divider(fosc / output2_step)
+                    {
+                        continue;
+                    }
+
+                    Some((fosc / output2_step) as u32)
+                }
+                None => None,
+            };
+
+            for n2_hs in consts::HS_DIVIDER_MIN..=consts::HS_DIVIDER_MAX {
+                if let Some((n3, n2_ls)) = feedback_dividers(fin, fosc, n2_hs) {
+                    plans.push(DividerPlan {
+                        fin,
+                        n3,
+                        n2_hs,
+                        n2_ls,
+                        n1_hs,
+                        nc1_ls: nc1_ls as u32,
+                        nc2_ls,
+                    });
+                }
+            }
+        }
+    }
+
+    if plans.is_empty() {
+        return Err(PlanError::NoPlan { fin, fout1, fout2 });
+    }
+
+    plans.sort_by_key(|plan| (core::cmp::Reverse(plan.f3()), plan.fosc(), plan.n3));
+
+    Ok(plans)
+}
+
+/// Find the smallest n3 and its n2_ls which multiply fin up to exactly fosc with the given n2_hs, keeping f3 and the
+/// dividers in range. `fosc * n3 = fin * n2_hs * n2_ls`, so n2_ls / n3 is fixed, and both are a multiple of its
+/// reduced fraction.
+#[cfg(feature = "alloc")]
+fn feedback_dividers(fin: u32, fosc: u64, n2_hs: u8) -> Option<(u32, u32)> {
+    let multiplied = fin as u64 * n2_hs as u64;
+    let divisor = gcd(fosc, multiplied);
+    let (n2_ls_step, n3_step) = (fosc / divisor, multiplied / divisor);
+
+    // n3 is limited by the lowest f3, and must be large enough to keep f3 below the highest
+    let n3_min = (fin as u64).div_ceil(consts::F3_MAX);
+    let n3_max = (fin as u64 / consts::F3_MIN).min(N3_MAX as u64);
+
+    (1..)
+        .map(|k| (n3_step * k, n2_ls_step * k))
+        .take_while(|&(n3, n2_ls)| n3 <= n3_max && n2_ls <= LS_DIVIDER_MAX as u64)
+        .find(|&(n3, n2_ls)| n3 >= n3_min && n2_ls.is_multiple_of(2))
+        .map(|(n3, n2_ls)| (n3 as u32, n2_ls as u32))
+}
+
+/// Describe the output2 frequency of an error, if one was asked for
+fn and_fout2(fout2: &Option<u64>) -> AndFout2 {
+    AndFout2(*fout2)
+}
+
+struct AndFout2(Option<u64>);
+
+impl core::fmt::Display for AndFout2 {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.0 {
+            Some(fout2) => write!(f, " and {} Hz", fout2),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Whether a low speed output divider can be set, which must be 1 or even
+#[cfg(feature = "alloc")]
+fn is_valid_ls_divider(divider: u64) -> bool {
+    divider == 1 || (divider.is_multiple_of(2) && divider <= LS_DIVIDER_MAX as u64)
+}
+
+#[cfg(feature = "alloc")]
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+
+    a
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::{plan, PlanError};
+    use crate::consts;
+
+    /// The TCXO frequency of the GPSDO
+    const FIN: u32 = 4_296_875;
+
+    #[test]
+    fn plan_produces_the_frequencies_exactly_within_range() {
+        let plans = plan(FIN, 10_000_000, Some(25_000_000)).expect("expected a plan");
+
+        for plan in &plans {
+            assert_eq!(plan.fout1(), 10_000_000);
+            assert_eq!(plan.fout2(), Some(25_000_000));
+            assert_eq!(
+                plan.fosc() * plan.n3() as u64,
+                FIN as u64 * plan.n2_hs() as u64 * plan.n2_ls() as u64
+            );
+            assert!((consts::FOSC_MIN..=consts::FOSC_MAX).contains(&plan.fosc()));
+            assert!((consts::F3_MIN..=consts::F3_MAX).contains(&plan.f3()));
+            assert!((consts::HS_DIVIDER_MIN..=consts::HS_DIVIDER_MAX).contains(&plan.n1_hs()));
+            assert!((consts::HS_DIVIDER_MIN..=consts::HS_DIVIDER_MAX).contains(&plan.n2_hs()));
+            assert_eq!(plan.n2_ls() % 2, 0);
+        }
+
+        assert!(plans.windows(2).all(|pair| pair[0].f3() >= pair[1].f3()));
+    }
+
+    #[test]
+    fn plan_finds_the_config_of_the_gpsdo_with_the_highest_f3() {
+        let plans = plan(FIN, 50_000_000, Some(25_000_000)).expect("expected a plan");
+
+        // The GPSDO uses n3 30 and n2_ls 3840, the planner prefers the same ratio with a higher f3
+        assert!(plans.iter().any(|plan| plan.n3() == 3
+            && plan.n2_hs() == 10
+            && plan.n2_ls() == 384
+            && plan.n1_hs() == 11
+            && plan.nc1_ls() == 10
+            && plan.nc2_ls() == Some(20)));
+    }
+
+    #[test]
+    fn plan_rejects_unreachable_and_zero_frequencies() {
+        assert!(matches!(
+            plan(FIN, 10_000_019, None),
+            Err(PlanError::NoPlan { .. })
+        ));
+        assert!(matches!(plan(FIN, 1, None), Err(PlanError::NoPlan { .. })));
+        assert_eq!(
+            plan(FIN, 10_000_000, Some(0)),
+            Err(PlanError::ZeroFrequency { name: "fout2" })
+        );
+    }
+}
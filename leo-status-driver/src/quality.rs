@@ -0,0 +1,188 @@
+//! A 0 - 100 score of how far the output of a GPSDO can be trusted as a reference, from a history of its status.
+//!
+//! The score is worked out from the latest reading, then reduced for every lock loss in the history:
+//!
+//! - Locked for at least [`SETTLE_TIME`]: [`LOCKED_SCORE`]
+//! - Locked for less than [`SETTLE_TIME`]: [`SETTLING_SCORE`]
+//! - In holdover, with the PLL locked but no GPS lock: [`HOLDOVER_SCORE`], less one for every [`HOLDOVER_DECAY`] spent
+//!   in holdover, down to [`HOLDOVER_MIN_SCORE`]
+//! - PLL unlocked, or no readings: 0
+//!
+//! Each increase of the loss count in the history takes [`LOSS_PENALTY`] off the score, up to [`MAX_LOSS_PENALTY`].
+//! A lock which was already held, or lost, at the start of the history is timed from the start of the history, so the
+//! history should cover at least [`SETTLE_TIME`].
+
+use core::time::Duration;
+
+use crate::GpsdoStatus;
+
+/// The score of a GPSDO which has been locked for at least [`SETTLE_TIME`]
+pub const LOCKED_SCORE: u8 = 100;
+
+/// The score of a GPSDO which acquired its lock less than [`SETTLE_TIME`] ago
+pub const SETTLING_SCORE: u8 = 80;
+
+/// How long a lock must be held before the output is considered settled
+pub const SETTLE_TIME: Duration = Duration::from_secs(10 * 60);
+
+/// The score of a GPSDO which has just entered holdover, having lost its GPS lock while its PLL is still locked
+pub const HOLDOVER_SCORE: u8 = 60;
+
+/// How long it takes for the holdover score to drop by one, as the oscillator drifts
+pub const HOLDOVER_DECAY: Duration = Duration::from_secs(60);
+
+/// The lowest score of a GPSDO in holdover
+pub const HOLDOVER_MIN_SCORE: u8 = 10;
+
+/// The score taken off for each lock loss in the history
+pub const LOSS_PENALTY: u8 = 10;
+
+/// The most taken off the score for lock losses in the history
+pub const MAX_LOSS_PENALTY: u8 = 40;
+
+/// A status reading in a history, taken `elapsed` after the start of the history
+#[derive(Debug, Clone, Copy)]
+pub struct StatusSample {
+    pub elapsed: Duration,
+    pub status: GpsdoStatus,
+}
+
+/// Score the quality of the reference from a history of status readings, oldest first. The latest reading is the
+/// current state of the GPSDO.
+pub fn quality_score(history: &[StatusSample]) -> u8 {
+    let Some(latest) = history.last() else {
+        return 0;
+    };
+
+    let state = |sample: &StatusSample| (sample.status.locked(), sample.status.pll_locked());
+
+    // The first reading of the run of readings in the same state as the latest
+    let run_start = history
+        .iter()
+        .rev()
+        .take_while(|sample| state(sample) == state(latest))
+        .last()
+        .unwrap_or(latest);
+    let in_state_for = latest.elapsed.saturating_sub(run_start.elapsed);
+    let state_predates_history = core::ptr::eq(run_start, &history[0]);
+
+    let score = if latest.status.locked() {
+        if in_state_for >= SETTLE_TIME || state_predates_history {
+            LOCKED_SCORE
+        } else {
+            SETTLING_SCORE
+        }
+    } else if latest.status.pll_locked() {
+        let decay = in_state_for.as_secs() / HOLDOVER_DECAY.as_secs();
+
+        HOLDOVER_SCORE
+            .saturating_sub(decay.try_into().unwrap_or(u8::MAX))
+            .max(HOLDOVER_MIN_SCORE)
+    } else {
+        return 0;
+    };
+
+    score.saturating_sub(loss_penalty(history))
+}
+
+/// The penalty for the lock losses in the history. The loss count is a counter, so a drop in it means the GPSDO was
+/// reset, and every loss counted since then is new.
+fn loss_penalty(history: &[StatusSample]) -> u8 {
+    let losses: u32 = history
+        .windows(2)
+        .map(|pair| {
+            let (before, after) = (pair[0].status.loss_count(), pair[1].status.loss_count());
+
+            if after >= before {
+                u32::from(after - before)
+            } else {
+                u32::from(after)
+            }
+        })
+        .sum();
+
+    losses
+        .saturating_mul(LOSS_PENALTY.into())
+        .min(MAX_LOSS_PENALTY.into()) as u8
+}
+
+#[cfg(test)]
+mod test {
+    use core::time::Duration;
+
+    use super::{quality_score, StatusSample};
+    use crate::GpsdoStatus;
+
+    fn sample(minutes: u64, locked: bool, pll_lock: bool, loss_count: u8) -> StatusSample {
+        StatusSample {
+            elapsed: Duration::from_secs(minutes * 60),
+            status: GpsdoStatus {
+                loss_count,
+                sat_lock: locked,
+                pll_lock,
+                locked,
+            },
+        }
+    }
+
+    #[test]
+    fn quality_score_is_zero_without_readings_or_pll_lock() {
+        assert_eq!(quality_score(&[]), 0);
+        assert_eq!(quality_score(&[sample(0, false, false, 0)]), 0);
+    }
+
+    #[test]
+    fn quality_score_is_full_for_a_settled_lock() {
+        assert_eq!(quality_score(&[sample(0, true, true, 2)]), 100);
+        assert_eq!(
+            quality_score(&[
+                sample(0, false, true, 2),
+                sample(1, true, true, 2),
+                sample(11, true, true, 2)
+            ]),
+            100
+        );
+    }
+
+    #[test]
+    fn quality_score_is_reduced_while_a_new_lock_settles() {
+        assert_eq!(
+            quality_score(&[
+                sample(0, false, true, 2),
+                sample(5, true, true, 2),
+                sample(9, true, true, 2)
+            ]),
+            80
+        );
+    }
+
+    #[test]
+    fn quality_score_decays_in_holdover() {
+        let history = [sample(0, true, true, 0), sample(1, false, true, 1)];
+        assert_eq!(quality_score(&history), 50);
+
+        let history = [
+            sample(0, true, true, 0),
+            sample(1, false, true, 1),
+            sample(21, false, true, 1),
+        ];
+        assert_eq!(quality_score(&history), 30);
+
+        let history = [
+            sample(0, true, true, 0),
+            sample(1, false, true, 1),
+            sample(600, false, true, 1),
+        ];
+        assert_eq!(quality_score(&history), 0);
+    }
+
+    #[test]
+    fn quality_score_caps_the_loss_penalty_and_handles_resets() {
+        let history = [
+            sample(0, true, true, 250),
+            sample(20, true, true, 255),
+            sample(40, true, true, 1),
+        ];
+        assert_eq!(quality_score(&history), 60);
+    }
+}
@@ -0,0 +1,62 @@
+//! The HID reports a Leo Bodnar GPSDO supports, with their identifiers and sizes, so callers and backends don't need
+//! to repeat the numbers of the protocol.
+
+/// The identifier of the config feature report
+pub const CONFIG_REPORT_ID: u8 = 9;
+
+/// The bytes of data in the config feature report, without the report id, as the GPSDO expects it written
+pub const CONFIG_REPORT_LEN: usize = 60;
+
+/// The size of the buffer the config feature report is read into, one more than the data, as `UsbInterface` requires
+pub const CONFIG_REPORT_BUFFER_LEN: usize = CONFIG_REPORT_LEN + 1;
+
+/// The leading bytes of the config feature report which hold the config, the rest is unused
+pub const CONFIG_LEN: usize = 21;
+
+/// The bytes in the status input report, the loss count and the lock flags
+pub const STATUS_REPORT_LEN: usize = 2;
+
+/// A HID report of the GPSDO
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Report {
+    /// The input report read from the interrupt endpoint, holding the loss count and the lock flags
+    Status,
+
+    /// Feature report 9, holding the output, drive level and PLL settings
+    Config,
+}
+
+impl Report {
+    /// The report with the given feature report identifier, or `None` if the GPSDO has no such feature report
+    pub fn from_feature_report_id(report_id: u8) -> Option<Self> {
+        match report_id {
+            CONFIG_REPORT_ID => Some(Report::Config),
+            _ => None,
+        }
+    }
+
+    /// The feature report identifier, or `None` for the status input report, which has none
+    pub fn feature_report_id(self) -> Option<u8> {
+        match self {
+            Report::Status => None,
+            Report::Config => Some(CONFIG_REPORT_ID),
+        }
+    }
+
+    /// The bytes of data in the report, without any report id
+    pub fn data_len(self) -> usize {
+        match self {
+            Report::Status => STATUS_REPORT_LEN,
+            Report::Config => CONFIG_REPORT_LEN,
+        }
+    }
+
+    /// A short name for the report, such as `config`
+    pub fn name(self) -> &'static str {
+        match self {
+            Report::Status => "status",
+            Report::Config => "config",
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! Clock domain helpers for software defined radio users, for using an output of the GPSDO as the reference or sample
+//! clock of a radio.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "alloc")]
+use crate::planner::{self, DividerPlan, PlanError};
+
+/// The external reference frequency a radio expects
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RadioReference {
+    pub radio: &'static str,
+    pub frequency: u64,
+}
+
+/// The reference frequencies of common radios
+pub const RADIO_REFERENCES: &[RadioReference] = &[
+    RadioReference {
+        radio: "Ettus USRP",
+        frequency: 10_000_000,
+    },
+    RadioReference {
+        radio: "HackRF One",
+        frequency: 10_000_000,
+    },
+    RadioReference {
+        radio: "SDRplay RSPduo / RSPdx",
+        frequency: 24_000_000,
+    },
+    RadioReference {
+        radio: "RTL-SDR",
+        frequency: 28_800_000,
+    },
+];
+
+/// A sample rate which can be made by dividing a clock by a whole number
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SampleRate {
+    pub divisor: u64,
+    pub rate: u64,
+}
+
+/// How far a clock is from the reference frequency a radio expects
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReferenceError {
+    pub reference: RadioReference,
+
+    /// The clock frequency less the reference frequency, in Hz
+    pub offset_hz: i64,
+
+    /// The offset as a fraction of the reference frequency, in parts per billion
+    pub ppb: f64,
+}
+
+/// The sample rates of at least `min_rate` which divide exactly from a clock of `fout` Hz, fastest first. Only
+/// available with the `alloc` feature.
+#[cfg(feature = "alloc")]
+pub fn sample_rates(fout: u64, min_rate: u64) -> Vec<SampleRate> {
+    let max_divisor = fout / min_rate.max(1);
+
+    (1..=max_divisor)
+        .filter(|divisor| fout.is_multiple_of(*divisor))
+        .map(|divisor| SampleRate {
+            divisor,
+            rate: fout / divisor,
+        })
+        .collect()
+}
+
+/// How far a clock of `fout` Hz is from the reference frequency of a radio
+pub fn reference_error(fout: u64, reference: RadioReference) -> ReferenceError {
+    let offset_hz = fout as i64 - reference.frequency as i64;
+
+    ReferenceError {
+        reference,
+        offset_hz,
+        ppb: offset_hz as f64 * 1e9 / reference.frequency as f64,
+    }
+}
+
+/// How far a clock of `fout` Hz is from the reference of each of the common radios, closest first. Only available
+/// with the `alloc` feature.
+#[cfg(feature = "alloc")]
+pub fn reference_errors(fout: u64) -> Vec<ReferenceError> {
+    let mut errors: Vec<ReferenceError> = RADIO_REFERENCES
+        .iter()
+        .map(|&reference| reference_error(fout, reference))
+        .collect();
+    errors.sort_by_key(|error| error.offset_hz.unsigned_abs());
+
+    errors
+}
+
+/// Plan the dividers which produce exactly the reference frequency of a radio on output1, see `planner::plan`. Only
+/// available with the `alloc` feature.
+#[cfg(feature = "alloc")]
+pub fn plan_reference(
+    fin: u32,
+    reference: RadioReference,
+    fout2: Option<u64>,
+) -> Result<Vec<DividerPlan>, PlanError> {
+    planner::plan(fin, reference.frequency, fout2)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::{plan_reference, reference_errors, sample_rates, SampleRate, RADIO_REFERENCES};
+
+    #[test]
+    fn sample_rates_divide_the_clock_exactly() {
+        let rates = sample_rates(10_000_000, 1_000_000);
+
+        assert_eq!(
+            rates.iter().map(|rate| rate.divisor).collect::<Vec<_>>(),
+            [1, 2, 4, 5, 8, 10]
+        );
+        assert_eq!(
+            rates[3],
+            SampleRate {
+                divisor: 5,
+                rate: 2_000_000
+            }
+        );
+    }
+
+    #[test]
+    fn reference_errors_are_sorted_closest_first() {
+        let errors = reference_errors(10_000_001);
+
+        assert_eq!(errors[0].reference.frequency, 10_000_000);
+        assert_eq!(errors[0].offset_hz, 1);
+        assert!((errors[0].ppb - 100.0).abs() < 1e-9);
+        assert_eq!(errors.last().unwrap().reference.frequency, 28_800_000);
+    }
+
+    #[test]
+    fn plan_reference_produces_every_common_reference() {
+        for &reference in RADIO_REFERENCES {
+            let plans = plan_reference(4_296_875, reference, None).expect("expected a plan");
+
+            assert_eq!(plans[0].fout1(), reference.frequency);
+        }
+    }
+}
@@ -0,0 +1,495 @@
+//! A simulated GPSDO which plays back a scripted scenario, for testing applications which use the driver, or the whole
+//! leo-status daemon, deterministically and without hardware. Only available with the `sim` feature.
+
+use thiserror::Error;
+
+use std::sync::{Mutex, MutexGuard};
+
+use crate::{
+    consts::{PID_LEO_BODNAR_GPSDO, VID_LEO_BONDAR},
+    reports::{CONFIG_LEN, CONFIG_REPORT_BUFFER_LEN, CONFIG_REPORT_ID, CONFIG_REPORT_LEN},
+    GpsdoConfig, GpsdoDeviceInfo, UsbInterface,
+};
+
+/// The config feature report of a GPSDO as it ships, 50 MHz on output1 and 25 MHz on output2
+const SHIPPED_CONFIG_REPORT: [u8; CONFIG_LEN] = [
+    0x03, 0x00, 0xAB, 0x90, 0x41, 0x1D, 0x00, 0x00, 0x06, 0xFF, 0x0E, 0x00, 0x07, 0x09, 0x00, 0x00,
+    0x13, 0x00, 0x00, 0x00, 0x0F,
+];
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+/// An error returned by a `GpsdoSimulator`
+pub enum SimulatorError {
+    #[error("simulated device error")]
+    Scripted,
+
+    #[error("the simulated gpsdo has no feature report {0}")]
+    NoFeatureReport(u8),
+}
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+/// A line of a scenario script which could not be parsed
+pub enum ScenarioError {
+    #[error("line {line}: unknown step {step:?}")]
+    UnknownStep { line: usize, step: String },
+
+    #[error("line {line}: {value:?} is not a number of reads")]
+    InvalidReads { line: usize, value: String },
+
+    #[error("line {line}: {step} takes no number of reads")]
+    UnexpectedReads { line: usize, step: String },
+}
+
+/// A step of a scenario
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// Report a lock state for a number of status reads. The loss count goes up whenever the satellite lock is lost.
+    Status {
+        sat_lock: bool,
+        pll_lock: bool,
+        reads: u32,
+    },
+
+    /// Reboot the GPSDO, which resets its loss count, for one read of the lock state it had
+    Reboot,
+
+    /// Return a status report too short to parse from the next read
+    ShortRead,
+
+    /// Fail the next read
+    Error,
+}
+
+/// A script of steps for a `GpsdoSimulator` to play back, one status read at a time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scenario {
+    steps: Vec<Step>,
+    repeat: bool,
+}
+
+impl Scenario {
+    /// A scenario which plays the steps once and then holds the last lock state
+    pub fn new(steps: Vec<Step>) -> Self {
+        Scenario {
+            steps,
+            repeat: false,
+        }
+    }
+
+    /// Play the steps again from the start once they have all been played, rather than holding the last lock state
+    pub fn repeat(mut self) -> Self {
+        self.repeat = true;
+        self
+    }
+
+    /// Parse a scenario script, with one step per line and `#` starting a comment:
+    ///
+    /// - `locked [reads]`, satellite and PLL locked
+    /// - `holdover [reads]`, satellite lock lost while the PLL stays locked
+    /// - `unlocked [reads]`, satellite and PLL lock lost
+    /// - `pll_unlocked [reads]`, satellite locked but the PLL not
+    /// - `reboot`, `short_read` and `error`, see `Step`
+    /// - `repeat`, to play the script again from the start once it ends
+    ///
+    /// A lock state is reported for one read unless a number of reads is given.
+    pub fn parse(script: &str) -> Result<Self, ScenarioError> {
+        let mut scenario = Scenario::new(vec![]);
+
+        for (index, line) in script.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.split('#').next().unwrap_or_default();
+
+            let mut words = line.split_whitespace();
+            let Some(step) = words.next() else {
+                continue;
+            };
+            let reads = words
+                .next()
+                .map(|value| {
+                    value
+                        .parse::<u32>()
+                        .map_err(|_| ScenarioError::InvalidReads {
+                            line: line_number,
+                            value: value.to_owned(),
+                        })
+                })
+                .transpose()?;
+
+            let (sat_lock, pll_lock) = match step {
+                "locked" => (true, true),
+                "holdover" => (false, true),
+                "unlocked" => (false, false),
+                "pll_unlocked" => (true, false),
+                "reboot" | "short_read" | "error" | "repeat" => {
+                    if reads.is_some() {
+                        return Err(ScenarioError::UnexpectedReads {
+                            line: line_number,
+                            step: step.to_owned(),
+                        });
+                    }
+
+                    match step {
+                        "reboot" => scenario.steps.push(Step::Reboot),
+                        "short_read" => scenario.steps.push(Step::ShortRead),
+                        "error" => scenario.steps.push(Step::Error),
+                        _ => scenario.repeat = true,
+                    }
+                    continue;
+                }
+                _ => {
+                    return Err(ScenarioError::UnknownStep {
+                        line: line_number,
+                        step: step.to_owned(),
+                    })
+                }
+            };
+
+            scenario.steps.push(Step::Status {
+                sat_lock,
+                pll_lock,
+                reads: reads.unwrap_or(1),
+            });
+        }
+
+        Ok(scenario)
+    }
+}
+
+/// A GPSDO which plays back a `Scenario`, advancing one step each time its status is read. Reading the config doesn't
+/// advance the scenario. Its methods take `&self`, so one simulator can be shared by every device opened on it, and
+/// carries on where it was after a scripted error.
+pub struct GpsdoSimulator {
+    state: Mutex<SimulatorState>,
+    serial_number: Option<String>,
+    firmware_version: Option<u16>,
+}
+
+struct SimulatorState {
+    scenario: Scenario,
+
+    /// The step being played and how many reads of it have been played
+    step: usize,
+    reads: u32,
+
+    loss_count: u8,
+    sat_lock: bool,
+    pll_lock: bool,
+    config_report: Vec<u8>,
+}
+
+impl GpsdoSimulator {
+    /// Simulate a GPSDO as it ships, starting out locked, without a serial number or firmware version
+    pub fn new(scenario: Scenario) -> Self {
+        let mut config_report = vec![0u8; CONFIG_REPORT_BUFFER_LEN];
+        config_report[..SHIPPED_CONFIG_REPORT.len()].copy_from_slice(&SHIPPED_CONFIG_REPORT);
+
+        GpsdoSimulator {
+            state: Mutex::new(SimulatorState {
+                scenario,
+                step: 0,
+                reads: 0,
+                loss_count: 0,
+                sat_lock: true,
+                pll_lock: true,
+                config_report,
+            }),
+            serial_number: None,
+            firmware_version: None,
+        }
+    }
+
+    /// Simulate a GPSDO with the given config
+    pub fn with_config(self, config: &GpsdoConfig) -> Self {
+        self.state().config_report[..CONFIG_REPORT_LEN].copy_from_slice(&config.to_report_bytes());
+        self
+    }
+
+    pub fn with_serial_number(mut self, serial_number: &str) -> Self {
+        self.serial_number = Some(serial_number.to_owned());
+        self
+    }
+
+    /// Simulate a GPSDO with the given firmware version, as a binary coded decimal such as `0x0105` for 1.05
+    pub fn with_firmware_version(mut self, firmware_version: u16) -> Self {
+        self.firmware_version = Some(firmware_version);
+        self
+    }
+
+    fn state(&self) -> MutexGuard<'_, SimulatorState> {
+        self.state.lock().unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+impl SimulatorState {
+    /// Play the next read of the scenario, returning the status report to read, or the step which replaces it
+    fn next_read(&mut self) -> Result<[u8; 2], Step> {
+        if self.step >= self.scenario.steps.len() && self.scenario.repeat {
+            self.step = 0;
+        }
+
+        let Some(&step) = self.scenario.steps.get(self.step) else {
+            return Ok(self.status_report());
+        };
+
+        match step {
+            Step::Status {
+                sat_lock,
+                pll_lock,
+                reads,
+            } => {
+                if self.sat_lock && !sat_lock {
+                    self.loss_count = self.loss_count.wrapping_add(1);
+                }
+                self.sat_lock = sat_lock;
+                self.pll_lock = pll_lock;
+
+                self.reads += 1;
+                if self.reads >= reads {
+                    self.step += 1;
+                    self.reads = 0;
+                }
+
+                Ok(self.status_report())
+            }
+            Step::Reboot => {
+                self.loss_count = 0;
+                self.step += 1;
+
+                Ok(self.status_report())
+            }
+            Step::ShortRead | Step::Error => {
+                self.step += 1;
+
+                Err(step)
+            }
+        }
+    }
+
+    fn status_report(&self) -> [u8; 2] {
+        [
+            self.loss_count,
+            u8::from(!self.sat_lock) | u8::from(!self.pll_lock) << 1,
+        ]
+    }
+}
+
+impl UsbInterface for GpsdoSimulator {
+    type InterfaceError = SimulatorError;
+
+    fn hid_read(&self, buf: &mut [u8]) -> Result<usize, Self::InterfaceError> {
+        let mut state = self.state();
+        let report = match state.next_read() {
+            Ok(report) => report,
+            Err(Step::ShortRead) => {
+                buf[0] = state.loss_count;
+                return Ok(1);
+            }
+            Err(_) => return Err(SimulatorError::Scripted),
+        };
+
+        let len = report.len().min(buf.len());
+        buf[..len].copy_from_slice(&report[..len]);
+
+        Ok(len)
+    }
+
+    fn hid_get_feature_report(
+        &self,
+        report_id: u8,
+        buf: &mut [u8],
+    ) -> Result<usize, Self::InterfaceError> {
+        if report_id != CONFIG_REPORT_ID {
+            return Err(SimulatorError::NoFeatureReport(report_id));
+        }
+
+        let state = self.state();
+        let len = state.config_report.len().min(buf.len());
+        buf[..len].copy_from_slice(&state.config_report[..len]);
+
+        Ok(len)
+    }
+
+    /// Writing the config report changes the config read back, like a GPSDO
+    #[cfg(feature = "write")]
+    fn hid_send_feature_report(
+        &self,
+        report_id: u8,
+        data: &[u8],
+    ) -> Result<(), Self::InterfaceError> {
+        if report_id != CONFIG_REPORT_ID {
+            return Err(SimulatorError::NoFeatureReport(report_id));
+        }
+
+        let mut state = self.state();
+        let len = data.len().min(state.config_report.len());
+        state.config_report[..len].copy_from_slice(&data[..len]);
+
+        Ok(())
+    }
+
+    fn serial_number(&self) -> Result<Option<String>, Self::InterfaceError> {
+        Ok(self.serial_number.clone())
+    }
+
+    fn firmware_version(&self) -> Result<Option<u16>, Self::InterfaceError> {
+        Ok(self.firmware_version)
+    }
+
+    /// A standard GPSDO at the path `simulator`, which isn't attached anywhere
+    fn device_info(&self) -> Result<Option<GpsdoDeviceInfo>, Self::InterfaceError> {
+        Ok(Some(GpsdoDeviceInfo::new(
+            VID_LEO_BONDAR,
+            PID_LEO_BODNAR_GPSDO,
+            self.serial_number.clone(),
+            "simulator".to_owned(),
+            None,
+            -1,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{GpsdoSimulator, Scenario, ScenarioError, SimulatorError, Step};
+    use crate::{GpsdoDevice, GpsdoError};
+
+    #[test]
+    fn scenario_parses_steps_and_comments() {
+        let scenario = Scenario::parse(
+            "# lock, then holdover, then reacquire\nlocked 3\nholdover 2  # GPS antenna unplugged\n\nlocked\nreboot\nshort_read\nerror\nrepeat\n",
+        )
+        .expect("expected a valid scenario");
+
+        assert_eq!(
+            scenario,
+            Scenario::new(vec![
+                Step::Status {
+                    sat_lock: true,
+                    pll_lock: true,
+                    reads: 3
+                },
+                Step::Status {
+                    sat_lock: false,
+                    pll_lock: true,
+                    reads: 2
+                },
+                Step::Status {
+                    sat_lock: true,
+                    pll_lock: true,
+                    reads: 1
+                },
+                Step::Reboot,
+                Step::ShortRead,
+                Step::Error,
+            ])
+            .repeat()
+        );
+    }
+
+    #[test]
+    fn scenario_rejects_unknown_steps_and_bad_reads() {
+        assert_eq!(
+            Scenario::parse("locked\nlost 2"),
+            Err(ScenarioError::UnknownStep {
+                line: 2,
+                step: "lost".to_owned()
+            })
+        );
+        assert_eq!(
+            Scenario::parse("locked many"),
+            Err(ScenarioError::InvalidReads {
+                line: 1,
+                value: "many".to_owned()
+            })
+        );
+        assert_eq!(
+            Scenario::parse("reboot 2"),
+            Err(ScenarioError::UnexpectedReads {
+                line: 1,
+                step: "reboot".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn simulator_plays_back_holdover_and_reacquire() {
+        let scenario = Scenario::parse("locked 2\nholdover\nlocked\nholdover\nreboot\nlocked")
+            .expect("expected a valid scenario");
+        let device = GpsdoDevice::new(GpsdoSimulator::new(scenario));
+
+        let statuses = (0..7)
+            .map(|_| {
+                let status = device.status().expect("expected success from status");
+                (
+                    status.loss_count(),
+                    status.sat_locked(),
+                    status.pll_locked(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            statuses,
+            [
+                (0, true, true),
+                (0, true, true),
+                (1, false, true),
+                (1, true, true),
+                (2, false, true),
+                (0, false, true),
+                (0, true, true),
+            ]
+        );
+        assert_eq!(
+            device
+                .config()
+                .expect("expected success from config")
+                .checked_fout1(),
+            Ok(50_000_000)
+        );
+    }
+
+    #[test]
+    fn simulator_injects_short_reads_and_errors_then_carries_on() {
+        let scenario = Scenario::parse("short_read\nerror\nunlocked\nrepeat")
+            .expect("expected a valid scenario");
+        let device = GpsdoDevice::new(GpsdoSimulator::new(scenario));
+
+        assert!(matches!(
+            device.status(),
+            Err(GpsdoError::ShortDataError {
+                expected: 2,
+                received: 1
+            })
+        ));
+        assert!(matches!(
+            device.status(),
+            Err(GpsdoError::StatusReadError {
+                source: SimulatorError::Scripted
+            })
+        ));
+        assert!(!device
+            .status()
+            .expect("expected success from status")
+            .locked());
+
+        // The scenario repeats from the start
+        assert!(device.status().is_err());
+    }
+
+    #[test]
+    fn simulator_describes_itself_as_a_standard_gpsdo() {
+        let device = GpsdoDevice::new(
+            GpsdoSimulator::new(Scenario::parse("locked").unwrap()).with_serial_number("SIM-0001"),
+        );
+
+        let info = device
+            .device_info()
+            .expect("expected success from device_info")
+            .expect("expected the simulator to describe itself");
+        assert_eq!(info.product_id(), crate::consts::PID_LEO_BODNAR_GPSDO);
+        assert_eq!(info.serial_number(), Some("SIM-0001"));
+        assert_eq!(info.path(), "simulator");
+        assert_eq!(info.manufacturer(), None);
+    }
+}
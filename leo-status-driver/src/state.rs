@@ -0,0 +1,239 @@
+//! The lifecycle of a GPSDO as a single state machine, so every consumer of the driver agrees on what state a device is
+//! in, rather than each working it out from the lock bits and errors in its own way.
+//!
+//! A device starts [`DeviceState::Disconnected`], is [`DeviceState::Connected`] once opened, and then follows its status
+//! readings between [`DeviceState::Acquiring`], [`DeviceState::Locked`] and [`DeviceState::Holdover`]. An error talking
+//! to an open device puts it in [`DeviceState::Fault`] until it is read again or found to be gone.
+
+use crate::GpsdoStatus;
+
+/// The state of a GPSDO in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DeviceState {
+    /// No device is open
+    #[default]
+    Disconnected,
+
+    /// The device is open, but hasn't been read yet
+    Connected,
+
+    /// The PLL isn't locked, as after a cold start while the GPSDO searches for satellites
+    Acquiring,
+
+    /// Both satellite and PLL lock are held
+    Locked,
+
+    /// The PLL is locked but satellite lock is lost, so the outputs run on the oscillator alone
+    Holdover,
+
+    /// Talking to the open device failed
+    Fault,
+}
+
+impl DeviceState {
+    pub const ALL: [DeviceState; 6] = [
+        DeviceState::Disconnected,
+        DeviceState::Connected,
+        DeviceState::Acquiring,
+        DeviceState::Locked,
+        DeviceState::Holdover,
+        DeviceState::Fault,
+    ];
+
+    /// The state a status reading puts an open device in
+    pub fn from_status(status: &GpsdoStatus) -> Self {
+        match (status.sat_locked(), status.pll_locked()) {
+            (true, true) => DeviceState::Locked,
+            (false, true) => DeviceState::Holdover,
+            (_, false) => DeviceState::Acquiring,
+        }
+    }
+
+    /// Whether the device is in a state it is only put in by a status reading
+    pub fn has_status(&self) -> bool {
+        matches!(
+            self,
+            DeviceState::Acquiring | DeviceState::Locked | DeviceState::Holdover
+        )
+    }
+
+    /// The name of the state, as serialized
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeviceState::Disconnected => "disconnected",
+            DeviceState::Connected => "connected",
+            DeviceState::Acquiring => "acquiring",
+            DeviceState::Locked => "locked",
+            DeviceState::Holdover => "holdover",
+            DeviceState::Fault => "fault",
+        }
+    }
+}
+
+impl core::fmt::Display for DeviceState {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A change of state, from one state to a different one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StateTransition {
+    pub from: DeviceState,
+    pub to: DeviceState,
+}
+
+/// Follows a GPSDO through its lifecycle, calling back on every change of state. Each input returns the transition it
+/// caused as well, `None` if the state didn't change.
+pub struct DeviceStateMachine<F = fn(StateTransition)> {
+    state: DeviceState,
+    on_transition: F,
+}
+
+impl DeviceStateMachine {
+    /// A state machine for a device which isn't open yet, without a callback
+    pub fn new() -> Self {
+        Self::with_callback(|_| {})
+    }
+}
+
+impl Default for DeviceStateMachine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: FnMut(StateTransition)> DeviceStateMachine<F> {
+    /// A state machine for a device which isn't open yet, calling `on_transition` on every change of state
+    pub fn with_callback(on_transition: F) -> Self {
+        DeviceStateMachine {
+            state: DeviceState::Disconnected,
+            on_transition,
+        }
+    }
+
+    pub fn state(&self) -> DeviceState {
+        self.state
+    }
+
+    /// The device was opened
+    pub fn connected(&mut self) -> Option<StateTransition> {
+        self.transition(DeviceState::Connected)
+    }
+
+    /// The device was read, which also recovers it from a fault
+    pub fn update(&mut self, status: &GpsdoStatus) -> Option<StateTransition> {
+        self.transition(DeviceState::from_status(status))
+    }
+
+    /// Talking to the open device failed. A device which isn't open stays disconnected.
+    pub fn fault(&mut self) -> Option<StateTransition> {
+        match self.state {
+            DeviceState::Disconnected => None,
+            _ => self.transition(DeviceState::Fault),
+        }
+    }
+
+    /// The device was closed, or couldn't be opened
+    pub fn disconnected(&mut self) -> Option<StateTransition> {
+        self.transition(DeviceState::Disconnected)
+    }
+
+    fn transition(&mut self, to: DeviceState) -> Option<StateTransition> {
+        if to == self.state {
+            return None;
+        }
+
+        let transition = StateTransition {
+            from: self.state,
+            to,
+        };
+        self.state = to;
+        (self.on_transition)(transition);
+
+        Some(transition)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use std::{cell::RefCell, vec::Vec};
+
+    use super::{DeviceState, DeviceStateMachine, StateTransition};
+    use crate::GpsdoStatus;
+
+    fn status(sat_lock: bool, pll_lock: bool) -> GpsdoStatus {
+        GpsdoStatus {
+            loss_count: 0,
+            sat_lock,
+            pll_lock,
+            locked: sat_lock && pll_lock,
+        }
+    }
+
+    #[test]
+    fn device_state_follows_the_lock_bits() {
+        assert_eq!(
+            DeviceState::from_status(&status(true, true)),
+            DeviceState::Locked
+        );
+        assert_eq!(
+            DeviceState::from_status(&status(false, true)),
+            DeviceState::Holdover
+        );
+        assert_eq!(
+            DeviceState::from_status(&status(true, false)),
+            DeviceState::Acquiring
+        );
+        assert_eq!(
+            DeviceState::from_status(&status(false, false)),
+            DeviceState::Acquiring
+        );
+    }
+
+    #[test]
+    fn device_state_machine_calls_back_on_every_change_of_state() {
+        let seen = RefCell::new(Vec::new());
+        let mut machine = DeviceStateMachine::with_callback(|transition: StateTransition| {
+            seen.borrow_mut().push(transition.to)
+        });
+
+        assert_eq!(machine.fault(), None);
+        machine.connected();
+        machine.update(&status(false, false));
+        machine.update(&status(true, true));
+        assert_eq!(machine.update(&status(true, true)), None);
+        machine.update(&status(false, true));
+        assert_eq!(
+            machine.fault(),
+            Some(StateTransition {
+                from: DeviceState::Holdover,
+                to: DeviceState::Fault
+            })
+        );
+        machine.update(&status(true, true));
+        machine.disconnected();
+        assert_eq!(machine.state(), DeviceState::Disconnected);
+
+        assert_eq!(
+            *seen.borrow(),
+            [
+                DeviceState::Connected,
+                DeviceState::Acquiring,
+                DeviceState::Locked,
+                DeviceState::Holdover,
+                DeviceState::Fault,
+                DeviceState::Locked,
+                DeviceState::Disconnected,
+            ]
+        );
+    }
+}
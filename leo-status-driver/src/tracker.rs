@@ -0,0 +1,329 @@
+//! Edge detection between successive status readings of a GPSDO, so every consumer of the driver finds the same lock
+//! transitions and lock losses, and the same spells of holdover.
+//!
+//! The loss count of the GPSDO is a `u8`, which wraps around to 0 after 255, and is also reset to 0 when the GPSDO
+//! reboots. A loss count which goes down is taken to have wrapped if that would mean at most [`MAX_WRAP_STEP`] losses
+//! since the previous reading, and otherwise to have been reset, so a GPSDO rebooting with a loss count near 255 may
+//! be mistaken for one wrapping.
+
+use core::time::Duration;
+
+use crate::{state::DeviceState, GpsdoStatus};
+
+/// The most losses between two readings for a loss count which goes down to be taken as wrapping around
+pub const MAX_WRAP_STEP: u8 = 16;
+
+/// A change between two successive status readings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum StatusEvent {
+    /// Both satellite and PLL lock are now held
+    LockAcquired,
+
+    /// Satellite or PLL lock is no longer held
+    LockLost,
+    SatLockAcquired,
+    SatLockLost,
+    PllLockAcquired,
+    PllLockLost,
+
+    /// The loss count went up by this many, including by wrapping around past 255. A loss count which is reset, as it
+    /// is when the GPSDO reboots, isn't an event.
+    LossCountIncreased(u8),
+}
+
+/// Compares each status reading with the previous one, emitting the [`StatusEvent`]s between them
+#[derive(Debug, Clone, Default)]
+pub struct StatusTracker {
+    previous: Option<GpsdoStatus>,
+}
+
+impl StatusTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The reading last given to [`StatusTracker::update`], if any
+    pub fn previous(&self) -> Option<&GpsdoStatus> {
+        self.previous.as_ref()
+    }
+
+    /// Record a new status reading, returning the events since the previous one: the overall lock first, then the
+    /// satellite lock, the PLL lock and the loss count. The first reading has no events.
+    pub fn update(&mut self, status: &GpsdoStatus) -> impl Iterator<Item = StatusEvent> {
+        let events = match self.previous.replace(*status) {
+            Some(previous) => [
+                edge(
+                    previous.locked(),
+                    status.locked(),
+                    StatusEvent::LockAcquired,
+                    StatusEvent::LockLost,
+                ),
+                edge(
+                    previous.sat_locked(),
+                    status.sat_locked(),
+                    StatusEvent::SatLockAcquired,
+                    StatusEvent::SatLockLost,
+                ),
+                edge(
+                    previous.pll_locked(),
+                    status.pll_locked(),
+                    StatusEvent::PllLockAcquired,
+                    StatusEvent::PllLockLost,
+                ),
+                loss_increase(previous.loss_count(), status.loss_count())
+                    .map(StatusEvent::LossCountIncreased),
+            ],
+            None => [None; 4],
+        };
+
+        events.into_iter().flatten()
+    }
+
+    /// Forget the previous reading, such as after the GPSDO is re-opened, so the next reading has no events
+    pub fn reset(&mut self) {
+        self.previous = None;
+    }
+}
+
+/// Tracks the spells of holdover, with the PLL locked but satellite lock lost, across successive status readings. How
+/// long a GPSDO spends in holdover is the key measure of how well its antenna is placed.
+///
+/// A spell is timed from the first reading in holdover to the reading which ends it, or to the latest reading while it
+/// goes on, so readings should be taken often enough for the resolution wanted.
+#[derive(Debug, Clone, Default)]
+pub struct HoldoverTracker {
+    /// When the holdover in progress started, if the GPSDO is in holdover
+    since: Option<Duration>,
+    latest: Duration,
+    longest: Duration,
+}
+
+impl HoldoverTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a new status reading, taken `elapsed` after any fixed start, such as when the GPSDO was opened. Readings
+    /// must be given oldest first.
+    pub fn update(&mut self, status: &GpsdoStatus, elapsed: Duration) {
+        // The spell in progress lasts until this reading, whether or not it is still in holdover
+        if let Some(since) = self.since {
+            self.longest = self.longest.max(elapsed.saturating_sub(since));
+        }
+
+        self.latest = elapsed;
+        if DeviceState::from_status(status) == DeviceState::Holdover {
+            self.since.get_or_insert(elapsed);
+        } else {
+            self.since = None;
+        }
+    }
+
+    /// How long the GPSDO has been in holdover, `None` if it isn't
+    pub fn holdover_duration(&self) -> Option<Duration> {
+        self.since.map(|since| self.latest.saturating_sub(since))
+    }
+
+    /// The longest spell of holdover seen, including the one in progress, zero if there has been none
+    pub fn longest_holdover(&self) -> Duration {
+        self.longest
+    }
+}
+
+/// Accumulates the loss count of successive status readings into a total which never goes down, however many times the
+/// `u8` loss count of the GPSDO wraps around or is reset
+#[derive(Debug, Clone, Default)]
+pub struct LossCounter {
+    previous: Option<u8>,
+    total: u64,
+}
+
+impl LossCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a new status reading, returning the total losses. The total starts from the loss count of the first
+    /// reading, and a reset loss count adds the losses counted since the reset.
+    pub fn update(&mut self, status: &GpsdoStatus) -> u64 {
+        let current = status.loss_count();
+
+        let added = match self.previous.replace(current) {
+            Some(previous) if current >= previous => current - previous,
+            Some(previous) => loss_increase(previous, current).unwrap_or(current),
+            None => current,
+        };
+        self.total += u64::from(added);
+
+        self.total
+    }
+
+    /// The total losses up to the latest reading
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+}
+
+/// How far the loss count went up between two readings, `None` if it didn't or it was reset
+fn loss_increase(previous: u8, current: u8) -> Option<u8> {
+    let step = current.wrapping_sub(previous);
+
+    (current > previous || (current < previous && step <= MAX_WRAP_STEP)).then_some(step)
+}
+
+fn edge(
+    before: bool,
+    after: bool,
+    acquired: StatusEvent,
+    lost: StatusEvent,
+) -> Option<StatusEvent> {
+    match (before, after) {
+        (false, true) => Some(acquired),
+        (true, false) => Some(lost),
+        _ => None,
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use core::time::Duration;
+
+    use super::{HoldoverTracker, LossCounter, StatusEvent, StatusTracker};
+    use crate::GpsdoStatus;
+
+    fn status(sat_lock: bool, pll_lock: bool, loss_count: u8) -> GpsdoStatus {
+        GpsdoStatus {
+            loss_count,
+            sat_lock,
+            pll_lock,
+            locked: sat_lock && pll_lock,
+        }
+    }
+
+    fn events(tracker: &mut StatusTracker, status: GpsdoStatus) -> Vec<StatusEvent> {
+        tracker.update(&status).collect()
+    }
+
+    #[test]
+    fn status_tracker_emits_nothing_for_the_first_or_an_unchanged_reading() {
+        let mut tracker = StatusTracker::new();
+
+        assert_eq!(events(&mut tracker, status(true, true, 2)), vec![]);
+        assert_eq!(events(&mut tracker, status(true, true, 2)), vec![]);
+    }
+
+    #[test]
+    fn status_tracker_emits_lock_transitions_and_loss_count_increases() {
+        let mut tracker = StatusTracker::new();
+        events(&mut tracker, status(true, true, 2));
+
+        assert_eq!(
+            events(&mut tracker, status(false, true, 3)),
+            vec![
+                StatusEvent::LockLost,
+                StatusEvent::SatLockLost,
+                StatusEvent::LossCountIncreased(1),
+            ]
+        );
+        assert_eq!(
+            events(&mut tracker, status(true, false, 5)),
+            vec![
+                StatusEvent::SatLockAcquired,
+                StatusEvent::PllLockLost,
+                StatusEvent::LossCountIncreased(2),
+            ]
+        );
+        assert_eq!(
+            events(&mut tracker, status(true, true, 5)),
+            vec![StatusEvent::LockAcquired, StatusEvent::PllLockAcquired]
+        );
+    }
+
+    #[test]
+    fn status_tracker_ignores_a_falling_loss_count_and_forgets_on_reset() {
+        let mut tracker = StatusTracker::new();
+        events(&mut tracker, status(true, true, 4));
+
+        assert_eq!(events(&mut tracker, status(true, true, 0)), vec![]);
+
+        tracker.reset();
+        assert_eq!(events(&mut tracker, status(false, false, 1)), vec![]);
+    }
+
+    #[test]
+    fn status_tracker_emits_loss_count_increases_across_a_wrap() {
+        let mut tracker = StatusTracker::new();
+        events(&mut tracker, status(true, true, 254));
+
+        assert_eq!(
+            events(&mut tracker, status(true, true, 1)),
+            vec![StatusEvent::LossCountIncreased(3)]
+        );
+    }
+
+    #[test]
+    fn loss_counter_keeps_counting_across_wraps_and_resets() {
+        let mut counter = LossCounter::new();
+        let mut update = |loss_count| counter.update(&status(true, true, loss_count));
+
+        assert_eq!(update(250), 250);
+        assert_eq!(update(255), 255);
+
+        // Wrapped around, 255 -> 0 -> 2
+        assert_eq!(update(2), 258);
+        assert_eq!(update(2), 258);
+
+        // Reset by a reboot, then one loss
+        assert_eq!(update(0), 258);
+        assert_eq!(update(1), 259);
+
+        // Too far down to be a wrap, so a reset with 5 losses since
+        assert_eq!(update(30), 288);
+        assert_eq!(update(5), 293);
+        assert_eq!(counter.total(), 293);
+    }
+
+    #[test]
+    fn holdover_tracker_times_the_current_and_longest_holdover() {
+        let mut tracker = HoldoverTracker::new();
+        let mut update = |minutes: u64, sat_lock: bool, pll_lock: bool| {
+            tracker.update(
+                &status(sat_lock, pll_lock, 0),
+                Duration::from_secs(minutes * 60),
+            );
+            (tracker.holdover_duration(), tracker.longest_holdover())
+        };
+
+        assert_eq!(update(0, true, true), (None, Duration::ZERO));
+        assert_eq!(
+            update(1, false, true),
+            (Some(Duration::ZERO), Duration::ZERO)
+        );
+        assert_eq!(
+            update(5, false, true),
+            (
+                Some(Duration::from_secs(4 * 60)),
+                Duration::from_secs(4 * 60)
+            )
+        );
+
+        // The holdover lasts until the reading which ends it
+        assert_eq!(update(6, true, true), (None, Duration::from_secs(5 * 60)));
+
+        // Losing the PLL lock as well isn't holdover
+        assert_eq!(update(7, false, false), (None, Duration::from_secs(5 * 60)));
+        assert_eq!(
+            update(8, false, true),
+            (Some(Duration::ZERO), Duration::from_secs(5 * 60))
+        );
+        assert_eq!(
+            update(10, false, true),
+            (
+                Some(Duration::from_secs(2 * 60)),
+                Duration::from_secs(5 * 60)
+            )
+        );
+    }
+}
@@ -0,0 +1,180 @@
+//! Tests which run against a real Leo Bodnar GPSDO attached to this machine. They are only built with the `hw-tests`
+//! feature:
+//!
+//! ```shell
+//! cargo test -p leo-status-driver --features hw-tests -- --test-threads=1
+//! ```
+//!
+//! Set `LEO_STATUS_SERIAL_NUMBER` to pick a device when several are attached.
+//!
+//! The tests which write to the GPSDO only run when `LEO_STATUS_HW_WRITE_SERIAL_NUMBER` names the device to write to,
+//! which should be a unit set aside for testing, as the outputs change while they run. They restore what they change.
+#![cfg(feature = "hw-tests")]
+
+use std::time::{Duration, Instant};
+
+use hidapi::{HidApi, HidDevice};
+use leo_status_driver::{interface::GpsdoHidApiInterface, GpsdoDevice};
+
+/// The device sends a status report at least this often
+const STATUS_REPORT_INTERVAL: Duration = Duration::from_secs(2);
+
+fn serial_number() -> Option<String> {
+    std::env::var("LEO_STATUS_SERIAL_NUMBER").ok()
+}
+
+/// The serial number of the GPSDO the write tests may write to, `None` if they should be skipped
+#[cfg(feature = "write")]
+fn write_serial_number() -> Option<String> {
+    std::env::var("LEO_STATUS_HW_WRITE_SERIAL_NUMBER").ok()
+}
+
+fn open_gpsdo() -> HidDevice {
+    let hid_api = HidApi::new().expect("failed to create hidapi context");
+
+    let device = GpsdoHidApiInterface::find_gpsdo(&hid_api, serial_number())
+        .expect("no leo bodnar gpsdo attached, hardware tests need a device");
+
+    device
+        .open_device(&hid_api)
+        .expect("could not open leo bodnar gpsdo usb")
+}
+
+#[test]
+fn hardware_enumeration_finds_a_supported_gpsdo() {
+    let hid_api = HidApi::new().expect("failed to create hidapi context");
+
+    let device = GpsdoHidApiInterface::find_gpsdo(&hid_api, serial_number())
+        .expect("no leo bodnar gpsdo attached, hardware tests need a device");
+
+    assert!(GpsdoHidApiInterface::is_supported_vid_pid(device));
+}
+
+#[test]
+fn hardware_serial_number_matches_enumeration() {
+    let hid_api = HidApi::new().expect("failed to create hidapi context");
+
+    let device = GpsdoHidApiInterface::find_gpsdo(&hid_api, serial_number())
+        .expect("no leo bodnar gpsdo attached, hardware tests need a device");
+    let conn = device
+        .open_device(&hid_api)
+        .expect("could not open leo bodnar gpsdo usb");
+    let interface = GpsdoHidApiInterface::new(&conn);
+    let gpsdo = GpsdoDevice::new(&interface);
+
+    let serial_number = gpsdo.serial_number().expect("failed to read serial number");
+
+    assert_eq!(
+        serial_number.as_deref(),
+        device.serial_number(),
+        "serial number from the open device should match enumeration"
+    );
+}
+
+#[test]
+fn hardware_status_reports_arrive_regularly() {
+    let conn = open_gpsdo();
+    let interface = GpsdoHidApiInterface::new(&conn);
+    let gpsdo = GpsdoDevice::new(&interface);
+
+    // The first read may return a report which was queued before the device was opened
+    gpsdo.status().expect("failed to read status");
+
+    for _ in 0..3 {
+        let start = Instant::now();
+        let status = gpsdo.status().expect("failed to read status");
+
+        assert!(
+            start.elapsed() <= STATUS_REPORT_INTERVAL,
+            "status report took {:?} to arrive",
+            start.elapsed()
+        );
+        assert_eq!(
+            status.locked(),
+            status.sat_locked() && status.pll_locked(),
+            "overall lock should be the combination of the satellite and pll locks"
+        );
+    }
+}
+
+#[test]
+fn hardware_config_is_plausible() {
+    let conn = open_gpsdo();
+    let interface = GpsdoHidApiInterface::new(&conn);
+    let gpsdo = GpsdoDevice::new(&interface);
+
+    let config = gpsdo.config().expect("failed to read config");
+
+    assert!(config.fin() > 0, "fin should not be zero: {:?}", config);
+    assert!(
+        config.checked_fout1().is_ok(),
+        "fout1 should be valid: {:?}",
+        config
+    );
+    assert!(
+        (4..=11).contains(&config.n1_hs()) && (4..=11).contains(&config.n2_hs()),
+        "high speed dividers should be in range: {:?}",
+        config
+    );
+
+    // Reading the config twice without reconfiguring the device should give the same result
+    let again = gpsdo.config().expect("failed to read config");
+    assert_eq!(format!("{:?}", config), format!("{:?}", again));
+}
+
+/// Sets the skew of the GPSDO back to what it was when dropped, so a failed assertion doesn't leave it changed
+#[cfg(feature = "write")]
+struct RestoreSkew<'a> {
+    gpsdo: &'a GpsdoDevice<&'a GpsdoHidApiInterface<'a>>,
+    skew: u8,
+}
+
+#[cfg(feature = "write")]
+impl Drop for RestoreSkew<'_> {
+    fn drop(&mut self) {
+        self.gpsdo
+            .set_skew(self.skew)
+            .expect("failed to restore the skew of the gpsdo");
+    }
+}
+
+#[cfg(feature = "write")]
+#[test]
+fn hardware_config_write_reads_back_and_is_restored() {
+    let Some(serial_number) = write_serial_number() else {
+        eprintln!("skipping, set LEO_STATUS_HW_WRITE_SERIAL_NUMBER to write to a gpsdo");
+        return;
+    };
+
+    let hid_api = HidApi::new().expect("failed to create hidapi context");
+    let conn = GpsdoHidApiInterface::find_gpsdo(&hid_api, Some(serial_number))
+        .expect("the gpsdo named by LEO_STATUS_HW_WRITE_SERIAL_NUMBER is not attached")
+        .open_device(&hid_api)
+        .expect("could not open leo bodnar gpsdo usb");
+    let interface = GpsdoHidApiInterface::new(&conn);
+    let gpsdo = GpsdoDevice::new(&interface);
+
+    let original = gpsdo.config().expect("failed to read config");
+    let skew = original.skew().wrapping_add(1);
+
+    {
+        let _restore = RestoreSkew {
+            gpsdo: &gpsdo,
+            skew: original.skew(),
+        };
+
+        gpsdo.set_skew(skew).expect("failed to write skew");
+
+        let written = gpsdo.config().expect("failed to read config");
+        assert_eq!(written.skew(), skew, "the written skew should read back");
+        assert_eq!(
+            written.fin(),
+            original.fin(),
+            "the rest of the config should be unchanged"
+        );
+        assert_eq!(written.checked_fout1(), original.checked_fout1());
+    }
+
+    let restored = gpsdo.config().expect("failed to read config");
+    assert_eq!(format!("{:?}", restored), format!("{:?}", original));
+}
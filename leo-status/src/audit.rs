@@ -0,0 +1,158 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::PathBuf,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use crate::{persist, time::Timestamp};
+
+/// A persistent, append-only log of every write made to the GPSDO, stored as JSON lines
+#[derive(Debug, Clone)]
+pub(crate) struct AuditLog {
+    path: PathBuf,
+    lock: Arc<Mutex<()>>,
+}
+
+/// A field which a write changed, named by its dotted path such as `pll_params.n1_hs`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub(crate) struct AuditChange {
+    field: String,
+    before: Value,
+    after: Value,
+}
+
+/// One write to the GPSDO, as recorded in the audit log
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct AuditEntry {
+    /// When the write was made, in RFC 3339 format
+    timestamp: String,
+
+    /// Who made the write, such as `http:192.0.2.1` or `cli:alice`
+    source: String,
+
+    /// What was written, such as `apply profile 10mhz`
+    operation: String,
+
+    /// The configuration before the write, where known
+    before: Option<Value>,
+
+    /// The configuration the write set
+    after: Option<Value>,
+
+    /// The fields which differ between before and after
+    changes: Vec<AuditChange>,
+
+    /// Why the write failed, omitted if it succeeded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// A write in progress. The audit log is opened before the write is made, so a write which couldn't be recorded is
+/// never attempted, and other writes wait until this one is recorded.
+pub(crate) struct AuditRecord<'a> {
+    file: File,
+    _guard: MutexGuard<'a, ()>,
+}
+
+impl AuditLog {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        AuditLog {
+            path,
+            lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Open the audit log for a write which is about to be made
+    pub(crate) fn begin(&self) -> io::Result<AuditRecord<'_>> {
+        let guard = self.lock.lock().expect("failed to get audit log mutex");
+        let file = persist::open_append(&self.path)?;
+
+        Ok(AuditRecord {
+            file,
+            _guard: guard,
+        })
+    }
+
+    /// Read every entry in the audit log, oldest first, see `persist::read_json_lines`
+    pub(crate) fn entries(&self) -> io::Result<Vec<AuditEntry>> {
+        persist::read_json_lines(&self.path)
+    }
+}
+
+impl AuditRecord<'_> {
+    /// Append the outcome of the write to the audit log, syncing it to disk before returning
+    pub(crate) fn finish(
+        mut self,
+        source: &str,
+        operation: &str,
+        before: Option<Value>,
+        after: Option<Value>,
+        error: Option<String>,
+    ) -> io::Result<()> {
+        let mut changes = vec![];
+        if let (Some(before), Some(after)) = (&before, &after) {
+            diff("", before, after, &mut changes);
+        }
+
+        let entry = AuditEntry {
+            timestamp: Timestamp::now().format(),
+            source: source.to_owned(),
+            operation: operation.to_owned(),
+            before,
+            after,
+            changes,
+            error,
+        };
+
+        let mut line = serde_json::to_vec(&entry).map_err(io::Error::other)?;
+        line.push(b'\n');
+
+        self.file.write_all(&line)?;
+        self.file.sync_data()
+    }
+}
+
+/// Collect the leaf fields which differ between two JSON values, by their dotted paths
+fn diff(path: &str, before: &Value, after: &Value, changes: &mut Vec<AuditChange>) {
+    match (before, after) {
+        (Value::Object(before), Value::Object(after)) => {
+            let mut keys: Vec<&String> = before.keys().chain(after.keys()).collect();
+            keys.sort();
+            keys.dedup();
+
+            for key in keys {
+                let field = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+
+                diff(
+                    &field,
+                    before.get(key).unwrap_or(&Value::Null),
+                    after.get(key).unwrap_or(&Value::Null),
+                    changes,
+                );
+            }
+        }
+        _ if before != after => changes.push(AuditChange {
+            field: path.to_owned(),
+            before: before.clone(),
+            after: after.clone(),
+        }),
+        _ => {}
+    }
+}
+
+/// The source recorded for writes made from the command line, the user running the command
+pub(crate) fn cli_source() -> String {
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_owned());
+
+    format!("cli:{}", user)
+}
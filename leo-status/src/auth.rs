@@ -0,0 +1,849 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use jsonwebtoken::{jwk::JwkSet, DecodingKey, Validation};
+use serde::Deserialize;
+use sha1::{Digest, Sha1};
+use thiserror::Error;
+use tiny_http::{Header, Request, Response};
+use ureq::Agent;
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Cursor},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::Args;
+
+/// How long the keys of an OIDC provider are used before they are fetched again, to pick up rotated keys
+const JWKS_REFRESH: Duration = Duration::from_secs(60 * 60);
+
+/// The least time between fetches of the keys, whether or not the last one succeeded, so tokens with made up key ids or
+/// a provider which is down can't make the daemon hammer the provider
+const JWKS_MIN_REFRESH: Duration = Duration::from_secs(60);
+
+/// How long the daemon waits for an OIDC provider to respond
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Error)]
+/// Why a request couldn't be authenticated
+pub(crate) enum AuthError {
+    #[error("no credentials given")]
+    Missing,
+
+    #[error("invalid credentials")]
+    Invalid,
+
+    #[error("invalid token: {0}")]
+    Token(#[from] jsonwebtoken::errors::Error),
+
+    #[error("failed to fetch the keys of the oidc provider: {0}")]
+    Jwks(String),
+}
+
+#[derive(Debug, Error)]
+/// An error in the authentication given on the command line
+pub(crate) enum AuthConfigError {
+    #[error("failed to read htpasswd file {}: {source}", path.display())]
+    Read { path: PathBuf, source: io::Error },
+
+    #[error("htpasswd file {} line {line} isn't user:hash", path.display())]
+    Malformed { path: PathBuf, line: usize },
+
+    #[error(
+        "htpasswd file {} has an unsupported hash for user {user}, only bcrypt (htpasswd -B) and SHA-1 (htpasswd -s) are supported",
+        path.display()
+    )]
+    UnsupportedHash { path: PathBuf, user: String },
+}
+
+/// A way of authenticating HTTP requests, such as against a list of users
+pub(crate) trait AuthProvider: Send + Sync {
+    /// Authenticate a request, returning who made it
+    fn authenticate(&self, request: &Request) -> Result<String, AuthError>;
+
+    /// The `WWW-Authenticate` challenge sent with a 401, naming the scheme the provider expects
+    fn challenge(&self) -> &'static str;
+}
+
+/// The credentials of a request given with an authentication scheme, such as the token of `Bearer <token>`
+fn credentials<'r>(request: &'r Request, scheme: &str) -> Option<&'r str> {
+    let header = request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Authorization"))?;
+    let (given_scheme, credentials) = header.value.as_str().split_once(' ')?;
+
+    given_scheme
+        .eq_ignore_ascii_case(scheme)
+        .then(|| credentials.trim())
+}
+
+/// Parse a token named `NAME=TOKEN` on the command line
+pub(crate) fn parse_named_token(named: &str) -> Result<(String, String), String> {
+    match named.split_once('=') {
+        Some((name, token)) if !name.is_empty() && !token.is_empty() => {
+            Ok((name.to_owned(), token.to_owned()))
+        }
+        _ => Err("expected NAME=TOKEN".to_owned()),
+    }
+}
+
+/// Compare secrets in constant time, so they can't be guessed byte by byte
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Bearer tokens given on the command line, each with the name of who presents it
+pub(crate) struct StaticTokens {
+    tokens: Vec<(String, String)>,
+}
+
+impl StaticTokens {
+    /// Tokens named by their position, `token 1` onwards
+    pub(crate) fn new(tokens: Vec<String>) -> Self {
+        StaticTokens {
+            tokens: tokens
+                .into_iter()
+                .enumerate()
+                .map(|(index, token)| (format!("token {}", index + 1), token))
+                .collect(),
+        }
+    }
+
+    /// Tokens with the names given, such as the instances of `--ingest-token`
+    pub(crate) fn named(tokens: Vec<(String, String)>) -> Self {
+        StaticTokens { tokens }
+    }
+}
+
+impl AuthProvider for StaticTokens {
+    fn authenticate(&self, request: &Request) -> Result<String, AuthError> {
+        let token = credentials(request, "Bearer").ok_or(AuthError::Missing)?;
+
+        self.tokens
+            .iter()
+            .find(|(_, known)| constant_time_eq(known.as_bytes(), token.as_bytes()))
+            .map(|(name, _)| name.clone())
+            .ok_or(AuthError::Invalid)
+    }
+
+    fn challenge(&self) -> &'static str {
+        "Bearer"
+    }
+}
+
+/// Users and their password hashes from an htpasswd file, checked against HTTP basic authentication
+pub(crate) struct Htpasswd {
+    users: HashMap<String, String>,
+
+    /// A bcrypt hash at the highest cost of the users', verified in place of a user who isn't known, so the time taken
+    /// doesn't tell which users exist
+    unknown_user_hash: Option<String>,
+}
+
+impl Htpasswd {
+    /// Read an htpasswd file, which may only hold bcrypt and SHA-1 hashes
+    pub(crate) fn load(path: &Path) -> Result<Self, AuthConfigError> {
+        let contents = fs::read_to_string(path).map_err(|source| AuthConfigError::Read {
+            path: path.to_owned(),
+            source,
+        })?;
+
+        Self::parse(path, &contents)
+    }
+
+    /// Parse the contents of the htpasswd file at `path`
+    fn parse(path: &Path, contents: &str) -> Result<Self, AuthConfigError> {
+        let mut users = HashMap::new();
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let Some((user, hash)) = line.split_once(':') else {
+                return Err(AuthConfigError::Malformed {
+                    path: path.to_owned(),
+                    line: index + 1,
+                });
+            };
+            if !(hash.starts_with("$2") || hash.starts_with("{SHA}")) {
+                return Err(AuthConfigError::UnsupportedHash {
+                    path: path.to_owned(),
+                    user: user.to_owned(),
+                });
+            }
+
+            users.insert(user.to_owned(), hash.to_owned());
+        }
+
+        let unknown_user_hash = users
+            .values()
+            .filter_map(|hash| bcrypt_cost(hash))
+            .max()
+            .map(|cost| bcrypt::hash("", cost).expect("failed to hash with a valid bcrypt cost"));
+
+        Ok(Htpasswd {
+            users,
+            unknown_user_hash,
+        })
+    }
+
+    /// Whether the password is the user's
+    fn verify(&self, user: &str, password: &str) -> bool {
+        match self.users.get(user) {
+            Some(hash) => match hash.strip_prefix("{SHA}") {
+                Some(digest) => constant_time_eq(
+                    STANDARD.encode(Sha1::digest(password)).as_bytes(),
+                    digest.as_bytes(),
+                ),
+                None => bcrypt::verify(password, hash).unwrap_or(false),
+            },
+            None => {
+                if let Some(hash) = &self.unknown_user_hash {
+                    let _ = bcrypt::verify(password, hash);
+                }
+
+                false
+            }
+        }
+    }
+}
+
+/// The cost of a bcrypt hash, such as 5 for `$2y$05$...`, `None` for other hashes
+fn bcrypt_cost(hash: &str) -> Option<u32> {
+    let mut parts = hash.strip_prefix("$2")?.split('$');
+    parts.next()?;
+
+    parts
+        .next()?
+        .parse()
+        .ok()
+        .filter(|cost| (4..=31).contains(cost))
+}
+
+impl AuthProvider for Htpasswd {
+    fn authenticate(&self, request: &Request) -> Result<String, AuthError> {
+        let credentials = credentials(request, "Basic").ok_or(AuthError::Missing)?;
+        let credentials = STANDARD
+            .decode(credentials)
+            .ok()
+            .and_then(|credentials| String::from_utf8(credentials).ok())
+            .ok_or(AuthError::Invalid)?;
+        let (user, password) = credentials.split_once(':').ok_or(AuthError::Invalid)?;
+
+        self.verify(user, password)
+            .then(|| user.to_owned())
+            .ok_or(AuthError::Invalid)
+    }
+
+    fn challenge(&self) -> &'static str {
+        "Basic realm=\"leo-status\""
+    }
+}
+
+/// The claims of an OIDC token the daemon uses, once the token has been validated
+#[derive(Deserialize)]
+struct Claims {
+    sub: String,
+}
+
+/// The part of an OIDC provider's discovery document the daemon uses
+#[derive(Deserialize)]
+struct Discovery {
+    jwks_uri: String,
+}
+
+/// The signing keys of an OIDC provider, and when they were fetched
+struct FetchedKeys {
+    keys: JwkSet,
+    fetched: Instant,
+}
+
+/// The keys of an OIDC provider last fetched, and the state of fetching them again
+#[derive(Default)]
+struct KeyCache {
+    keys: Option<FetchedKeys>,
+
+    /// When the keys were last fetched, or failed to be
+    attempted: Option<Instant>,
+
+    /// Whether a request is fetching the keys, which other requests don't wait for
+    fetching: bool,
+}
+
+/// OIDC access tokens, bearer JWTs validated against the signing keys an identity provider publishes, so an existing
+/// identity provider can be used without an authenticating reverse proxy. The keys are found through the provider's
+/// discovery document, and fetched again every hour, or sooner for a token signed with a key which isn't known. The keys
+/// last fetched are used while they are being fetched again, or can't be.
+pub(crate) struct Oidc {
+    issuer: String,
+    audience: String,
+    agent: Agent,
+    keys: Mutex<KeyCache>,
+}
+
+impl Oidc {
+    pub(crate) fn new(issuer: String, audience: String) -> Self {
+        Oidc {
+            issuer,
+            audience,
+            agent: Agent::config_builder()
+                .timeout_global(Some(REQUEST_TIMEOUT))
+                .build()
+                .into(),
+            keys: Mutex::new(KeyCache::default()),
+        }
+    }
+
+    /// The key a token was signed with, fetching the keys if they are out of date or the key isn't known
+    fn key(&self, kid: Option<&str>) -> Result<DecodingKey, AuthError> {
+        self.key_with(kid, || self.fetch_keys())
+    }
+
+    /// See `key`, fetching the keys with `fetch_keys`. The keys are fetched without holding the lock on them, so other
+    /// requests meanwhile use the keys last fetched, or fail, rather than waiting for the provider.
+    fn key_with(
+        &self,
+        kid: Option<&str>,
+        fetch_keys: impl FnOnce() -> Result<JwkSet, AuthError>,
+    ) -> Result<DecodingKey, AuthError> {
+        let find = |keys: &JwkSet| match kid {
+            Some(kid) => keys.find(kid).cloned(),
+            None if keys.keys.len() == 1 => keys.keys.first().cloned(),
+            None => None,
+        };
+
+        let cached = {
+            let mut cache = self.keys.lock().unwrap();
+
+            let cached = cache.keys.as_ref().and_then(|keys| find(&keys.keys));
+            let fresh = cache
+                .keys
+                .as_ref()
+                .is_some_and(|keys| keys.fetched.elapsed() < JWKS_REFRESH);
+            let throttled = cache
+                .attempted
+                .is_some_and(|attempted| attempted.elapsed() < JWKS_MIN_REFRESH);
+
+            match cached {
+                Some(jwk) if fresh => return Ok(DecodingKey::from_jwk(&jwk)?),
+                cached if throttled || cache.fetching => {
+                    return Ok(DecodingKey::from_jwk(&cached.ok_or(AuthError::Invalid)?)?)
+                }
+                cached => {
+                    cache.fetching = true;
+                    cached
+                }
+            }
+        };
+
+        let fetched = fetch_keys();
+
+        let mut cache = self.keys.lock().unwrap();
+        cache.fetching = false;
+        cache.attempted = Some(Instant::now());
+
+        let jwk = match fetched {
+            Ok(fetched) => {
+                let jwk = find(&fetched);
+                cache.keys = Some(FetchedKeys {
+                    keys: fetched,
+                    fetched: Instant::now(),
+                });
+
+                jwk.ok_or(AuthError::Invalid)?
+            }
+            Err(error) => {
+                tracing::warn!("{}", error);
+                cached.ok_or(error)?
+            }
+        };
+
+        Ok(DecodingKey::from_jwk(&jwk)?)
+    }
+
+    fn fetch_keys(&self) -> Result<JwkSet, AuthError> {
+        let get_json = |url: &str| {
+            self.agent
+                .get(url)
+                .call()
+                .and_then(|mut response| response.body_mut().read_to_string())
+                .map_err(|error| AuthError::Jwks(error.to_string()))
+        };
+
+        let discovery = get_json(&format!(
+            "{}/.well-known/openid-configuration",
+            self.issuer.trim_end_matches('/')
+        ))?;
+        let discovery: Discovery = serde_json::from_str(&discovery)
+            .map_err(|error| AuthError::Jwks(format!("invalid discovery document: {}", error)))?;
+
+        serde_json::from_str(&get_json(&discovery.jwks_uri)?)
+            .map_err(|error| AuthError::Jwks(format!("invalid key set: {}", error)))
+    }
+}
+
+impl AuthProvider for Oidc {
+    fn authenticate(&self, request: &Request) -> Result<String, AuthError> {
+        let token = credentials(request, "Bearer").ok_or(AuthError::Missing)?;
+        let header = jsonwebtoken::decode_header(token)?;
+
+        // The algorithm must suit the key, which jsonwebtoken checks, so a token can't pick a weaker one
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[&self.audience]);
+
+        let key = self.key(header.kid.as_deref())?;
+        let claims = jsonwebtoken::decode::<Claims>(token, &key, &validation)?.claims;
+
+        Ok(claims.sub)
+    }
+
+    fn challenge(&self) -> &'static str {
+        "Bearer"
+    }
+}
+
+/// The authentication providers every HTTP request other than to the device proxy must pass one of
+pub(crate) struct Auth {
+    providers: Vec<Box<dyn AuthProvider>>,
+}
+
+impl Auth {
+    /// The providers configured by the command line arguments, `None` if there are none, so requests aren't
+    /// authenticated
+    pub(crate) fn from_args(args: &Args) -> Result<Option<Self>, AuthConfigError> {
+        let mut providers: Vec<Box<dyn AuthProvider>> = vec![];
+
+        if !args.auth_token.is_empty() {
+            providers.push(Box::new(StaticTokens::new(args.auth_token.clone())));
+        }
+        if let Some(path) = &args.auth_htpasswd {
+            providers.push(Box::new(Htpasswd::load(path)?));
+        }
+        if let (Some(issuer), Some(audience)) = (&args.auth_oidc_issuer, &args.auth_oidc_audience) {
+            providers.push(Box::new(Oidc::new(issuer.clone(), audience.clone())));
+        }
+
+        Ok((!providers.is_empty()).then_some(Auth { providers }))
+    }
+
+    /// Authenticate a request with the first provider which accepts it, returning who made it. Otherwise the error of
+    /// a provider the request gave credentials for is returned, or `Missing` if it gave none.
+    pub(crate) fn authenticate(&self, request: &Request) -> Result<String, AuthError> {
+        let mut error = AuthError::Missing;
+
+        for provider in &self.providers {
+            match provider.authenticate(request) {
+                Ok(principal) => return Ok(principal),
+                Err(AuthError::Missing) => {}
+                Err(provider_error) => error = provider_error,
+            }
+        }
+
+        Err(error)
+    }
+
+    /// The 401 for a request which couldn't be authenticated, challenging it for each scheme the providers expect
+    pub(crate) fn unauthorized(&self) -> Response<Cursor<Vec<u8>>> {
+        let mut challenges: Vec<&str> = vec![];
+        for provider in &self.providers {
+            if !challenges.contains(&provider.challenge()) {
+                challenges.push(provider.challenge());
+            }
+        }
+
+        challenges.into_iter().fold(
+            Response::from_string("Unauthorized").with_status_code(401),
+            |response, challenge| {
+                response.with_header(
+                    Header::from_bytes("WWW-Authenticate", challenge)
+                        .expect("failed to set WWW-Authenticate header"),
+                )
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use jsonwebtoken::{jwk::JwkSet, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+    use sha1::{Digest, Sha1};
+    use tiny_http::{Request, TestRequest};
+
+    use std::{
+        cell::Cell,
+        path::Path,
+        time::{Duration, Instant},
+    };
+
+    use super::{
+        bcrypt_cost, credentials, Auth, AuthConfigError, AuthError, AuthProvider, Claims,
+        FetchedKeys, Htpasswd, KeyCache, Oidc,
+    };
+
+    fn request(authorization: Option<&str>) -> Request {
+        let request = TestRequest::new();
+        match authorization {
+            Some(value) => request.with_header(
+                tiny_http::Header::from_bytes("Authorization", value).expect("valid header"),
+            ),
+            None => request,
+        }
+        .into()
+    }
+
+    #[test]
+    fn credentials_are_given_for_the_matching_scheme() {
+        assert_eq!(
+            credentials(&request(Some("Bearer abc")), "Bearer"),
+            Some("abc")
+        );
+        assert_eq!(
+            credentials(&request(Some("bearer  abc ")), "Bearer"),
+            Some("abc")
+        );
+        assert_eq!(credentials(&request(Some("Basic abc")), "Bearer"), None);
+        assert_eq!(credentials(&request(Some("Bearer")), "Bearer"), None);
+        assert_eq!(credentials(&request(None), "Bearer"), None);
+    }
+
+    #[test]
+    fn htpasswd_skips_comments_and_blank_lines() {
+        let htpasswd = Htpasswd::parse(
+            Path::new("htpasswd"),
+            "# operators\n\nalice:{SHA}W6ph5Mm5Pz8GgiULbPgzG37mj9g=\n",
+        )
+        .expect("valid htpasswd");
+
+        assert_eq!(htpasswd.users.len(), 1);
+        assert!(htpasswd.unknown_user_hash.is_none());
+    }
+
+    #[test]
+    fn htpasswd_rejects_malformed_lines() {
+        let error = Htpasswd::parse(
+            Path::new("htpasswd"),
+            "alice:{SHA}W6ph5Mm5Pz8GgiULbPgzG37mj9g=\nbob\n",
+        )
+        .err()
+        .expect("line 2 is malformed");
+
+        assert!(matches!(error, AuthConfigError::Malformed { line: 2, .. }));
+    }
+
+    #[test]
+    fn htpasswd_rejects_unsupported_hashes() {
+        let error = Htpasswd::parse(Path::new("htpasswd"), "alice:$apr1$salt$hash\n")
+            .err()
+            .expect("md5 hashes are unsupported");
+
+        assert!(matches!(error, AuthConfigError::UnsupportedHash { user, .. } if user == "alice"));
+    }
+
+    #[test]
+    fn htpasswd_verifies_sha_and_bcrypt_passwords() {
+        let sha = STANDARD.encode(Sha1::digest("secret"));
+        let bcrypt = bcrypt::hash("secret", 4).expect("valid cost");
+        let htpasswd = Htpasswd::parse(
+            Path::new("htpasswd"),
+            &format!("alice:{{SHA}}{}\nbob:{}\n", sha, bcrypt),
+        )
+        .expect("valid htpasswd");
+
+        assert!(htpasswd.verify("alice", "secret"));
+        assert!(!htpasswd.verify("alice", "wrong"));
+        assert!(htpasswd.verify("bob", "secret"));
+        assert!(!htpasswd.verify("bob", "wrong"));
+        assert!(!htpasswd.verify("carol", "secret"));
+    }
+
+    #[test]
+    fn htpasswd_verifies_unknown_users_at_the_cost_of_known_ones() {
+        let htpasswd = Htpasswd::parse(
+            Path::new("htpasswd"),
+            &format!(
+                "alice:{}\nbob:{}\n",
+                bcrypt::hash("secret", 4).expect("valid cost"),
+                bcrypt::hash("secret", 5).expect("valid cost")
+            ),
+        )
+        .expect("valid htpasswd");
+
+        assert_eq!(
+            htpasswd.unknown_user_hash.as_deref().and_then(bcrypt_cost),
+            Some(5)
+        );
+    }
+
+    #[test]
+    fn htpasswd_authenticates_basic_credentials() {
+        let htpasswd = Htpasswd::parse(
+            Path::new("htpasswd"),
+            &format!("alice:{{SHA}}{}\n", STANDARD.encode(Sha1::digest("secret"))),
+        )
+        .expect("valid htpasswd");
+        let basic = |credentials: &str| format!("Basic {}", STANDARD.encode(credentials));
+
+        assert_eq!(
+            htpasswd
+                .authenticate(&request(Some(&basic("alice:secret"))))
+                .expect("valid credentials"),
+            "alice"
+        );
+        assert!(matches!(
+            htpasswd.authenticate(&request(Some(&basic("alice:wrong")))),
+            Err(AuthError::Invalid)
+        ));
+        assert!(matches!(
+            htpasswd.authenticate(&request(Some("Basic !"))),
+            Err(AuthError::Invalid)
+        ));
+        assert!(matches!(
+            htpasswd.authenticate(&request(None)),
+            Err(AuthError::Missing)
+        ));
+    }
+
+    /// A provider which always gives the same outcome
+    struct Fixed(fn() -> Result<String, AuthError>);
+
+    impl AuthProvider for Fixed {
+        fn authenticate(&self, _request: &Request) -> Result<String, AuthError> {
+            (self.0)()
+        }
+
+        fn challenge(&self) -> &'static str {
+            "Bearer"
+        }
+    }
+
+    fn authenticate(providers: Vec<Fixed>) -> Result<String, AuthError> {
+        let auth = Auth {
+            providers: providers
+                .into_iter()
+                .map(|provider| Box::new(provider) as Box<dyn AuthProvider>)
+                .collect(),
+        };
+
+        auth.authenticate(&request(None))
+    }
+
+    #[test]
+    fn auth_accepts_a_request_any_provider_accepts() {
+        let result = authenticate(vec![
+            Fixed(|| Err(AuthError::Invalid)),
+            Fixed(|| Ok("alice".to_owned())),
+        ]);
+
+        assert_eq!(result.expect("second provider accepts"), "alice");
+    }
+
+    #[test]
+    fn auth_prefers_the_error_of_a_provider_given_credentials() {
+        assert!(matches!(
+            authenticate(vec![
+                Fixed(|| Err(AuthError::Missing)),
+                Fixed(|| Err(AuthError::Missing))
+            ]),
+            Err(AuthError::Missing)
+        ));
+        assert!(matches!(
+            authenticate(vec![
+                Fixed(|| Err(AuthError::Missing)),
+                Fixed(|| Err(AuthError::Invalid))
+            ]),
+            Err(AuthError::Invalid)
+        ));
+        assert!(matches!(
+            authenticate(vec![
+                Fixed(|| Err(AuthError::Invalid)),
+                Fixed(|| Err(AuthError::Missing))
+            ]),
+            Err(AuthError::Invalid)
+        ));
+        assert!(matches!(
+            authenticate(vec![
+                Fixed(|| Err(AuthError::Invalid)),
+                Fixed(|| Err(AuthError::Jwks("unreachable".to_owned())))
+            ]),
+            Err(AuthError::Jwks(_))
+        ));
+    }
+
+    /// A key set of shared secrets, each named by its key id and used as its secret
+    fn key_set(kids: &[&str]) -> JwkSet {
+        let keys: Vec<_> = kids
+            .iter()
+            .map(|kid| {
+                serde_json::json!({
+                    "kty": "oct",
+                    "kid": kid,
+                    "alg": "HS256",
+                    "k": base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(kid),
+                })
+            })
+            .collect();
+
+        serde_json::from_value(serde_json::json!({ "keys": keys })).expect("valid key set")
+    }
+
+    /// Whether a key is the one with the given key id, by whether it verifies a token signed with its secret
+    fn is_key(key: &DecodingKey, kid: &str) -> bool {
+        let token = jsonwebtoken::encode(
+            &Header::new(Algorithm::HS256),
+            &serde_json::json!({ "sub": "alice" }),
+            &EncodingKey::from_secret(kid.as_bytes()),
+        )
+        .expect("failed to sign token");
+
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.required_spec_claims.clear();
+        validation.validate_exp = false;
+
+        jsonwebtoken::decode::<Claims>(&token, key, &validation).is_ok()
+    }
+
+    fn oidc_with_keys(kids: &[&str], age: Duration) -> Oidc {
+        let oidc = Oidc::new("https://issuer".to_owned(), "leo-status".to_owned());
+        let fetched = Instant::now()
+            .checked_sub(age)
+            .expect("the clock started long enough ago");
+        *oidc.keys.lock().unwrap() = KeyCache {
+            keys: Some(FetchedKeys {
+                keys: key_set(kids),
+                fetched,
+            }),
+            attempted: Some(fetched),
+            fetching: false,
+        };
+
+        oidc
+    }
+
+    #[test]
+    fn oidc_selects_the_key_by_id() {
+        let oidc = oidc_with_keys(&["a", "b"], Duration::ZERO);
+        let fetch = || -> Result<JwkSet, AuthError> { panic!("keys are fresh") };
+
+        assert!(is_key(
+            &oidc.key_with(Some("b"), fetch).expect("known key"),
+            "b"
+        ));
+        assert!(is_key(
+            &oidc.key_with(Some("a"), fetch).expect("known key"),
+            "a"
+        ));
+    }
+
+    #[test]
+    fn oidc_uses_the_only_key_for_tokens_without_a_key_id() {
+        let fetch = || -> Result<JwkSet, AuthError> { panic!("keys are fresh") };
+
+        let oidc = oidc_with_keys(&["a"], Duration::ZERO);
+        assert!(is_key(&oidc.key_with(None, fetch).expect("only key"), "a"));
+
+        let oidc = oidc_with_keys(&["a", "b"], Duration::ZERO);
+        assert!(matches!(
+            oidc.key_with(None, fetch),
+            Err(AuthError::Invalid)
+        ));
+    }
+
+    #[test]
+    fn oidc_throttles_fetches_for_unknown_keys() {
+        let fetches = Cell::new(0);
+        let fetch = || {
+            fetches.set(fetches.get() + 1);
+            Ok(key_set(&["a", "b"]))
+        };
+
+        // Keys fetched within the last minute aren't fetched again for a key which isn't known
+        let oidc = oidc_with_keys(&["a"], Duration::from_secs(10));
+        assert!(matches!(
+            oidc.key_with(Some("b"), fetch),
+            Err(AuthError::Invalid)
+        ));
+        assert_eq!(fetches.get(), 0);
+
+        // Older keys are, picking up a rotated key
+        let oidc = oidc_with_keys(&["a"], Duration::from_secs(120));
+        assert!(is_key(
+            &oidc.key_with(Some("b"), fetch).expect("rotated key"),
+            "b"
+        ));
+        assert_eq!(fetches.get(), 1);
+
+        // Which is then known without another fetch
+        assert!(is_key(
+            &oidc.key_with(Some("b"), fetch).expect("known key"),
+            "b"
+        ));
+        assert_eq!(fetches.get(), 1);
+    }
+
+    #[test]
+    fn oidc_fetches_keys_again_once_they_are_out_of_date() {
+        let fetches = Cell::new(0);
+        let fetch = || {
+            fetches.set(fetches.get() + 1);
+            Ok(key_set(&["a"]))
+        };
+
+        let oidc = oidc_with_keys(&["a"], Duration::from_secs(2 * 60 * 60));
+        assert!(is_key(
+            &oidc.key_with(Some("a"), fetch).expect("known key"),
+            "a"
+        ));
+        assert_eq!(fetches.get(), 1);
+    }
+
+    #[test]
+    fn oidc_throttles_failed_fetches_and_keeps_using_the_keys_it_has() {
+        let fetches = Cell::new(0);
+        let fetch = || {
+            fetches.set(fetches.get() + 1);
+            Err(AuthError::Jwks("provider is down".to_owned()))
+        };
+
+        let oidc = oidc_with_keys(&["a"], Duration::from_secs(2 * 60 * 60));
+        assert!(is_key(
+            &oidc.key_with(Some("a"), fetch).expect("known key"),
+            "a"
+        ));
+        assert_eq!(fetches.get(), 1);
+
+        // The failed fetch isn't retried for a minute
+        assert!(is_key(
+            &oidc.key_with(Some("a"), fetch).expect("known key"),
+            "a"
+        ));
+        assert!(matches!(
+            oidc.key_with(Some("b"), fetch),
+            Err(AuthError::Invalid)
+        ));
+        assert_eq!(fetches.get(), 1);
+    }
+
+    #[test]
+    fn oidc_uses_the_keys_it_has_while_they_are_being_fetched() {
+        let fetch = || -> Result<JwkSet, AuthError> { panic!("keys are being fetched") };
+
+        let oidc = oidc_with_keys(&["a"], Duration::from_secs(2 * 60 * 60));
+        oidc.keys.lock().unwrap().fetching = true;
+
+        assert!(is_key(
+            &oidc.key_with(Some("a"), fetch).expect("known key"),
+            "a"
+        ));
+        assert!(matches!(
+            oidc.key_with(Some("b"), fetch),
+            Err(AuthError::Invalid)
+        ));
+    }
+}
@@ -0,0 +1,45 @@
+use std::sync::{
+    mpsc::{self, Receiver, Sender},
+    Arc, Mutex,
+};
+
+/// Sends each published value to every current subscriber. Subscribers which have gone away are dropped on the next
+/// publish.
+pub(crate) struct Broadcast<T> {
+    subscribers: Arc<Mutex<Vec<Sender<T>>>>,
+}
+
+impl<T> Clone for Broadcast<T> {
+    fn clone(&self) -> Self {
+        Broadcast {
+            subscribers: self.subscribers.clone(),
+        }
+    }
+}
+
+impl<T: Clone> Broadcast<T> {
+    pub(crate) fn new() -> Self {
+        Broadcast {
+            subscribers: Arc::new(Mutex::new(vec![])),
+        }
+    }
+
+    /// Receive every value published from now on
+    pub(crate) fn subscribe(&self) -> Receiver<T> {
+        let (sender, receiver) = mpsc::channel();
+
+        self.subscribers
+            .lock()
+            .expect("failed to get subscribers mutex")
+            .push(sender);
+
+        receiver
+    }
+
+    pub(crate) fn publish(&self, value: &T) {
+        self.subscribers
+            .lock()
+            .expect("failed to get subscribers mutex")
+            .retain(|subscriber| subscriber.send(value.clone()).is_ok());
+    }
+}
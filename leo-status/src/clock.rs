@@ -0,0 +1,181 @@
+use leo_status_driver::state::DeviceState;
+use thiserror::Error;
+
+use std::{fs, io, path::PathBuf, process::Command, str::FromStr, time::Duration};
+
+use crate::{
+    journal::{Journal, JournalEventKind},
+    metrics::Metrics,
+    snapshot::Snapshot,
+    time::Timestamp,
+};
+
+#[derive(Debug, Error)]
+/// An error which stopped the offset of the host clock being measured
+pub(crate) enum ClockError {
+    #[error("failed to read offset: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("chronyc failed: {0}")]
+    Chronyc(String),
+
+    #[error("invalid offset {0:?}")]
+    InvalidOffset(String),
+}
+
+/// Where the offset of the host clock from its reference is measured, given on the command line as `chrony` or
+/// `file:<path>`
+#[derive(Debug, Clone)]
+pub(crate) enum ClockSourceSpec {
+    /// The system time offset chrony reports in its tracking data
+    Chrony,
+
+    /// A file holding the offset in seconds, kept up to date by another tool, such as one measuring a PPS device
+    File(PathBuf),
+}
+
+impl FromStr for ClockSourceSpec {
+    type Err = String;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        match source.split_once(':') {
+            None if source == "chrony" => Ok(ClockSourceSpec::Chrony),
+            Some(("file", path)) if !path.is_empty() => Ok(ClockSourceSpec::File(path.into())),
+            _ => Err(format!(
+                "unknown clock source {:?}, expected chrony or file:<path>",
+                source
+            )),
+        }
+    }
+}
+
+impl ClockSourceSpec {
+    pub(crate) fn source(&self) -> Box<dyn ClockSource> {
+        match self {
+            ClockSourceSpec::Chrony => Box::new(ChronySource),
+            ClockSourceSpec::File(path) => Box::new(FileSource(path.clone())),
+        }
+    }
+}
+
+/// A measurement of how far a disciplined clock on the host is from its reference, compared with the lock state of
+/// the GPSDO to see whether losing lock disturbed it
+pub(crate) trait ClockSource: Send {
+    /// The name of the source, used as the label of its metrics
+    fn name(&self) -> &'static str;
+
+    /// The current offset of the clock, in seconds
+    fn offset(&mut self) -> Result<f64, ClockError>;
+}
+
+/// Reads the system time offset from `chronyc -c tracking`
+struct ChronySource;
+
+impl ClockSource for ChronySource {
+    fn name(&self) -> &'static str {
+        "chrony"
+    }
+
+    fn offset(&mut self) -> Result<f64, ClockError> {
+        let output = Command::new("chronyc").args(["-c", "tracking"]).output()?;
+        if !output.status.success() {
+            return Err(ClockError::Chronyc(
+                String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+            ));
+        }
+
+        parse_chrony_tracking(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+/// The system time field of the CSV tracking data, the offset of the system clock from NTP time in seconds
+fn parse_chrony_tracking(tracking: &str) -> Result<f64, ClockError> {
+    let system_time = tracking.trim().split(',').nth(4).unwrap_or_default();
+
+    parse_offset(system_time)
+}
+
+/// Reads the offset in seconds from the first word of a file
+struct FileSource(PathBuf);
+
+impl ClockSource for FileSource {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    fn offset(&mut self) -> Result<f64, ClockError> {
+        let contents = fs::read_to_string(&self.0)?;
+
+        parse_offset(contents.split_whitespace().next().unwrap_or_default())
+    }
+}
+
+fn parse_offset(offset: &str) -> Result<f64, ClockError> {
+    offset
+        .parse()
+        .ok()
+        .filter(|offset: &f64| offset.is_finite())
+        .ok_or_else(|| ClockError::InvalidOffset(offset.to_owned()))
+}
+
+/// Correlates the offset of the host clock with the lock state of the GPSDO, finding the largest offset while the
+/// GPSDO is unlocked
+pub(crate) struct ClockTracker {
+    source: Box<dyn ClockSource>,
+
+    /// When the GPSDO lost lock and the largest absolute offset seen since, while it is unlocked
+    unlocked: Option<(Timestamp, f64)>,
+}
+
+impl ClockTracker {
+    pub(crate) fn new(source: Box<dyn ClockSource>) -> Self {
+        ClockTracker {
+            source,
+            unlocked: None,
+        }
+    }
+
+    /// Measure the offset for a snapshot. Once the GPSDO regains lock, the largest offset while it was unlocked is
+    /// recorded in the journal.
+    pub(crate) fn update(&mut self, journal: &Journal, metrics: &Metrics, snapshot: &Snapshot) {
+        let name = self.source.name();
+
+        // A failed measurement leaves the offset metric as NaN rather than the last offset measured
+        let offset = self
+            .source
+            .offset()
+            .inspect_err(|error| {
+                tracing::warn!("failed to measure {} clock offset: {}", name, error)
+            })
+            .ok();
+        metrics
+            .clock_offset
+            .with_label_values(&[name])
+            .set(offset.unwrap_or(f64::NAN));
+
+        if snapshot.state != DeviceState::Locked {
+            let (_, max_offset) = self.unlocked.get_or_insert((snapshot.timestamp, 0.0));
+            if let Some(offset) = offset {
+                *max_offset = max_offset.max(offset.abs());
+            }
+
+            metrics
+                .clock_offset_unlocked_max
+                .with_label_values(&[name])
+                .set(*max_offset);
+        } else if let Some((since, max_offset)) = self.unlocked.take() {
+            let unlocked_for = snapshot.timestamp.duration_since(&since);
+
+            journal.record_snapshot(
+                snapshot,
+                JournalEventKind::ClockOffsetWhileUnlocked,
+                Some(format!(
+                    "largest {} offset {:e} s while unlocked for {}",
+                    name,
+                    max_offset,
+                    humantime::format_duration(Duration::from_secs(unlocked_for.as_secs()))
+                )),
+            );
+        }
+    }
+}
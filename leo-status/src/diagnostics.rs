@@ -0,0 +1,256 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use leo_status_driver::state::DeviceState;
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use crate::{
+    journal::{Journal, JournalEventKind},
+    snapshot::Snapshot,
+    time::Timestamp,
+};
+
+/// How long a GPSDO may go without locking after it is opened before it is diagnosed as never locking. A cold
+/// start takes up to about 15 minutes with a good antenna.
+const NEVER_LOCKED_AFTER: Duration = Duration::from_secs(20 * 60);
+
+/// How far back losses of lock are counted to find flapping
+const FLAPPING_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// The losses of lock within the window which count as flapping
+const FLAPPING_LOSSES: usize = 4;
+
+/// How long lock must stay lost after being held to count as a long drop
+const LONG_DROP_AFTER: Duration = Duration::from_secs(30 * 60);
+
+/// How long the GPSDO may stay acquiring while satellites are locked before the GPSDO itself is suspected
+const PLL_UNLOCKED_AFTER: Duration = Duration::from_secs(10 * 60);
+
+/// The latest diagnosis, shared between the analyzer, the HTTP server and the sinks
+pub(crate) type SharedDiagnosis = Arc<RwLock<Option<Diagnosis>>>;
+
+/// A pattern in the lock losses of a GPSDO
+#[derive(Serialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum LossPattern {
+    /// Not locked since the GPSDO was opened or rebooted
+    NeverLocked,
+
+    /// Lock keeps being lost and regained
+    Flapping,
+
+    /// Lock was held, then lost for a long time
+    LongDrop,
+
+    /// The GPSDO stays acquiring, its PLL unlocked, while satellites are locked
+    PllUnlocked,
+}
+
+/// The likely cause of a pattern of lock losses
+#[derive(Serialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum LikelyCause {
+    /// The antenna or its cable is disconnected, shorted or damaged
+    AntennaFault,
+
+    /// The antenna sees too little of the sky, or the signal suffers from multipath
+    PoorSkyView,
+
+    /// The GPSDO or its configuration is at fault
+    DeviceIssue,
+}
+
+impl LossPattern {
+    fn likely_cause(self) -> LikelyCause {
+        match self {
+            LossPattern::NeverLocked | LossPattern::LongDrop => LikelyCause::AntennaFault,
+            LossPattern::Flapping => LikelyCause::PoorSkyView,
+            LossPattern::PllUnlocked => LikelyCause::DeviceIssue,
+        }
+    }
+}
+
+/// What the lock losses of the GPSDO suggest is wrong with it, served at `/diagnostics`
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub(crate) struct Diagnosis {
+    /// When the reading the diagnosis was made from was taken, in RFC 3339 format
+    #[schemars(with = "String")]
+    timestamp: Timestamp,
+
+    /// The pattern in the lock losses, `null` if there is none
+    pattern: Option<LossPattern>,
+
+    /// The likely cause of the pattern, `null` if there is none
+    likely_cause: Option<LikelyCause>,
+
+    /// The pattern and what to check, in words
+    summary: String,
+
+    /// When the pattern was first seen, in RFC 3339 format, `null` if there is none
+    #[schemars(with = "Option<String>")]
+    since: Option<Timestamp>,
+
+    /// The number of times the GPSDO left the locked state in the last hour
+    sat_lock_losses_last_hour: usize,
+}
+
+/// Classifies the pattern of lock losses in successive snapshots into a likely cause, as users otherwise do by eye.
+/// Lock is the `Locked` state of the GPSDO's state machine, so the diagnosis agrees with the state served elsewhere.
+#[derive(Default)]
+pub(crate) struct LossAnalyzer {
+    /// When the GPSDO was opened, or last rebooted
+    opened: Option<Timestamp>,
+    locked_since_opened: bool,
+    loss_count: Option<u8>,
+    state: Option<DeviceState>,
+
+    /// When lock was lost, if it is lost now after being held
+    lost_at: Option<Timestamp>,
+
+    /// When the GPSDO started acquiring, if it is acquiring now while satellites are locked
+    pll_unlocked_at: Option<Timestamp>,
+
+    /// When lock was lost, within the flapping window
+    losses: VecDeque<Timestamp>,
+
+    /// The pattern last found, and when it was first seen
+    pattern: Option<(LossPattern, Timestamp)>,
+}
+
+impl LossAnalyzer {
+    /// Record a new reading, returning the diagnosis of the readings up to it
+    pub(crate) fn update(&mut self, snapshot: &Snapshot) -> Diagnosis {
+        let now = snapshot.timestamp;
+        let status = &snapshot.status;
+
+        // A falling loss count means the GPSDO rebooted, so like a newly opened one it has to lock from cold
+        let rebooted = self
+            .loss_count
+            .is_some_and(|loss_count| status.loss_count() < loss_count);
+        self.loss_count = Some(status.loss_count());
+        if snapshot.first_read || rebooted || self.opened.is_none() {
+            self.opened = Some(now);
+            self.locked_since_opened = false;
+            self.state = None;
+            self.lost_at = None;
+        }
+
+        if snapshot.state == DeviceState::Locked {
+            self.locked_since_opened = true;
+            self.lost_at = None;
+        } else if self.state == Some(DeviceState::Locked) {
+            self.lost_at = Some(now);
+            self.losses.push_back(now);
+        }
+        self.state = Some(snapshot.state);
+
+        while self
+            .losses
+            .front()
+            .is_some_and(|lost| now.duration_since(lost) > FLAPPING_WINDOW)
+        {
+            self.losses.pop_front();
+        }
+
+        // Acquiring covers any reading without PLL lock, of which only those with satellites locked point at the GPSDO
+        self.pll_unlocked_at = (snapshot.state == DeviceState::Acquiring && status.sat_locked())
+            .then(|| self.pll_unlocked_at.unwrap_or(now));
+
+        let pattern = self.classify(now);
+        self.pattern = pattern.map(|pattern| match self.pattern {
+            Some((previous, since)) if previous == pattern => (pattern, since),
+            _ => (pattern, now),
+        });
+
+        Diagnosis {
+            timestamp: now,
+            pattern,
+            likely_cause: pattern.map(LossPattern::likely_cause),
+            summary: self.summary(now),
+            since: self.pattern.map(|(_, since)| since),
+            sat_lock_losses_last_hour: self.losses.len(),
+        }
+    }
+
+    /// The pattern in the readings up to `now`, the most telling first
+    fn classify(&self, now: Timestamp) -> Option<LossPattern> {
+        let held_for = |since: Option<Timestamp>, threshold: Duration| {
+            since.is_some_and(|since| now.duration_since(&since) >= threshold)
+        };
+
+        // A GPSDO stuck acquiring with satellites locked never locks either, but it is the GPSDO which is at fault
+        if held_for(self.pll_unlocked_at, PLL_UNLOCKED_AFTER) {
+            Some(LossPattern::PllUnlocked)
+        } else if !self.locked_since_opened && held_for(self.opened, NEVER_LOCKED_AFTER) {
+            Some(LossPattern::NeverLocked)
+        } else if self.losses.len() >= FLAPPING_LOSSES {
+            Some(LossPattern::Flapping)
+        } else if held_for(self.lost_at, LONG_DROP_AFTER) {
+            Some(LossPattern::LongDrop)
+        } else {
+            None
+        }
+    }
+
+    fn summary(&self, now: Timestamp) -> String {
+        let elapsed = |since: Option<Timestamp>| {
+            let elapsed = since.map_or(Duration::ZERO, |since| now.duration_since(&since));
+            humantime::format_duration(Duration::from_secs(elapsed.as_secs() / 60 * 60))
+        };
+
+        match self.pattern.map(|(pattern, _)| pattern) {
+            None => "no pattern in the lock losses".to_owned(),
+            Some(LossPattern::NeverLocked) => format!(
+                "not locked in the {} since the GPSDO was opened, check the antenna is connected and its cable isn't damaged",
+                elapsed(self.opened)
+            ),
+            Some(LossPattern::Flapping) => format!(
+                "lock lost {} times in the last hour, the antenna may have a poor view of the sky",
+                self.losses.len()
+            ),
+            Some(LossPattern::LongDrop) => format!(
+                "lock lost for {} after being held, check the antenna and its cable",
+                elapsed(self.lost_at)
+            ),
+            Some(LossPattern::PllUnlocked) => format!(
+                "the GPSDO has been acquiring for {} with satellites locked, check the configuration and the GPSDO",
+                elapsed(self.pll_unlocked_at)
+            ),
+        }
+    }
+}
+
+/// Analyze a snapshot, sharing the diagnosis, and logging and journaling it whenever the pattern changes
+pub(crate) fn update(
+    analyzer: &mut LossAnalyzer,
+    shared: &SharedDiagnosis,
+    journal: &Journal,
+    snapshot: &Snapshot,
+) {
+    let diagnosis = analyzer.update(snapshot);
+
+    let previous = shared
+        .read()
+        .unwrap()
+        .as_ref()
+        .and_then(|diagnosis| diagnosis.pattern);
+    if diagnosis.pattern != previous {
+        if diagnosis.pattern.is_some() {
+            tracing::warn!("{}", diagnosis.summary);
+        } else {
+            tracing::info!("lock loss pattern cleared");
+        }
+        journal.record_snapshot(
+            snapshot,
+            JournalEventKind::DiagnosisChanged,
+            Some(diagnosis.summary.clone()),
+        );
+    }
+
+    *shared.write().unwrap() = Some(diagnosis);
+}
@@ -1,120 +1,176 @@
-use leo_status_driver::{GpsdoConfig, GpsdoStatus};
-use serde::Serialize;
-
-#[derive(Serialize, Debug)]
-pub(crate) struct LockStatusResponse {
-    /// The number of times that the GPS lock has been lost since reboot
-    loss_count: u8,
-
-    /// Whether the GPSDO has a lock on a GPS signal
-    sat_lock: bool,
-
-    /// Whether the PLL is locked to the configured frequencies
-    pll_lock: bool,
+pub(crate) use leo_status_api::{
+    ConfigResponse, DeviceResponse, LockStatusResponse, SnapshotResponse, VersionResponse,
+};
+use leo_status_driver::state::DeviceState;
+use serde::{Serialize, Serializer};
+
+use crate::{
+    diagnostics::Diagnosis, maintenance::MaintenanceResponse, snapshot::Snapshot,
+    startup::StartupReport, time::Timestamp,
+};
+
+/// A status or config read from the GPSDO, as it is served. It is marked stale once the device stops responding, along
+/// with the state the device was left in, and how long it has been stale is measured whenever it is served.
+pub(crate) struct Served<T> {
+    response: T,
+    stale: Option<(Timestamp, DeviceState)>,
+}
 
-    /// Whether the system is locked overall
-    locked: bool,
+/// A response with fields saying whether it is out of date
+pub(crate) trait Stale {
+    fn set_stale(&mut self, since: String, for_secs: f64, state: DeviceState);
 }
 
-impl From<GpsdoStatus> for LockStatusResponse {
-    fn from(value: GpsdoStatus) -> Self {
-        LockStatusResponse {
-            loss_count: value.loss_count(),
-            sat_lock: value.sat_locked(),
-            pll_lock: value.pll_locked(),
-            locked: value.locked(),
-        }
+impl Stale for LockStatusResponse {
+    fn set_stale(&mut self, since: String, for_secs: f64, state: DeviceState) {
+        self.state = Some(state.into());
+        self.stale = true;
+        self.stale_since = Some(since);
+        self.stale_for_secs = Some(for_secs);
     }
 }
 
-#[derive(Serialize, Debug)]
-pub(crate) struct PllParamsResponse {
-    /// The frequency produced by the GPSDO TCXO
-    fin: u32,
+impl Stale for ConfigResponse {
+    fn set_stale(&mut self, since: String, for_secs: f64, _state: DeviceState) {
+        self.stale = true;
+        self.stale_since = Some(since);
+        self.stale_for_secs = Some(for_secs);
+    }
+}
 
-    /// The divisor of fin before it enters the PLL
-    n3: u32,
-    /// The first divisor on the feedback loop
-    n2_hs: u8,
+impl Stale for SnapshotResponse {
+    fn set_stale(&mut self, since: String, for_secs: f64, state: DeviceState) {
+        self.status.set_stale(since.clone(), for_secs, state);
+        self.config.set_stale(since.clone(), for_secs, state);
 
-    /// The second divisor on the feedback loop
-    n2_ls: u32,
+        self.stale = true;
+        self.stale_since = Some(since);
+        self.stale_for_secs = Some(for_secs);
+    }
+}
 
-    /// The shared divisor on the output from the PLL
-    n1_hs: u8,
+impl<T> Served<T> {
+    pub(crate) fn new(response: T) -> Self {
+        Served {
+            response,
+            stale: None,
+        }
+    }
 
-    /// The divisor after n1_hs, heading to port one
-    nc1_ls: u32,
+    /// The response as it was read, without the fields saying whether it is out of date
+    pub(crate) fn response(&self) -> &T {
+        &self.response
+    }
 
-    /// The divisor after n1_hs, heading to port two
-    nc2_ls: u32,
+    /// Build another response from this one, out of date for as long as this one is
+    pub(crate) fn map<U>(&self, build: impl FnOnce(&T) -> U) -> Served<U> {
+        Served {
+            response: build(&self.response),
+            stale: self.stale,
+        }
+    }
 
-    /// The skew between port one and port two, 0 - 255
-    skew: u8,
+    /// Mark the response stale since the device stopped responding, keeping when that was if it already is
+    pub(crate) fn mark_stale(&mut self, since: Timestamp, state: DeviceState) {
+        let since = self.stale.map_or(since, |(since, _)| since);
+        self.stale = Some((since, state));
+    }
+}
 
-    /// The PLL bandwidth mode
-    bw: u8,
+impl<T: Stale + Clone + Serialize> Serialize for Served<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let Some((since, state)) = &self.stale else {
+            return self.response.serialize(serializer);
+        };
 
-    /// The frequency of fin after division by n3
-    f3: u32,
+        let mut response = self.response.clone();
+        response.set_stale(since.format(), since.age_secs(), *state);
+        response.serialize(serializer)
+    }
+}
 
-    /// The frequency of the output of the PLL
-    fosc: u64,
+/// The GPSDO in use, as it is served. Its uptime is measured whenever it is served.
+pub(crate) struct ServedDevice {
+    response: DeviceResponse,
+    booted: Option<Timestamp>,
 }
 
-impl From<GpsdoConfig> for PllParamsResponse {
-    fn from(value: GpsdoConfig) -> Self {
-        PllParamsResponse {
-            fin: value.fin(),
-            n3: value.n3(),
-            n2_hs: value.n2_hs(),
-            n2_ls: value.n2_ls(),
-            n1_hs: value.n1_hs(),
-            nc1_ls: value.nc1_ls(),
-            nc2_ls: value.nc2_ls(),
-            skew: value.skew(),
-            bw: value.bw(),
-            f3: value.f3(),
-            fosc: value.fosc(),
+impl ServedDevice {
+    pub(crate) fn new(response: DeviceResponse) -> Self {
+        ServedDevice {
+            response,
+            booted: None,
         }
     }
-}
-
-#[derive(Serialize)]
-pub(crate) struct ConfigResponse {
-    /// Whether the output1 port of the GPSDO is active
-    output1: bool,
-
-    /// Whether the output2 port of the GPSDO is active
-    output2: bool,
 
-    /// The drive level of the signal in milliamps
-    level: u8,
+    /// Set when the GPSDO is estimated to have booted
+    pub(crate) fn set_booted(&mut self, booted: Timestamp) {
+        self.booted = Some(booted);
+    }
 
-    pll_params: PllParamsResponse,
+    /// The device as served now, with its uptime measured
+    pub(crate) fn response(&self) -> DeviceResponse {
+        DeviceResponse {
+            uptime_secs_estimate: self.booted.map(|booted| booted.age_secs()),
+            ..self.response.clone()
+        }
+    }
+}
 
-    /// The frequency output on output1
-    fout1: u64,
+impl Serialize for ServedDevice {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.response().serialize(serializer)
+    }
+}
 
-    /// The frequency output on output2
-    fout2: u64,
+impl From<&Snapshot> for LockStatusResponse {
+    fn from(value: &Snapshot) -> Self {
+        LockStatusResponse {
+            state: Some(value.state.into()),
+            poll_seq: Some(value.poll_seq),
+            ..(&value.status).into()
+        }
+    }
 }
 
-impl From<GpsdoConfig> for ConfigResponse {
-    fn from(value: GpsdoConfig) -> Self {
+impl From<&Snapshot> for ConfigResponse {
+    fn from(value: &Snapshot) -> Self {
         ConfigResponse {
-            output1: value.output1(),
-            output2: value.output2(),
-            level: match value.level() {
-                0 => 8,
-                1 => 16,
-                2 => 24,
-                3 => 32,
-                _ => 0,
-            },
-            fout1: value.fout1(),
-            fout2: value.fout2(),
-            pll_params: value.into(),
+            poll_seq: Some(value.poll_seq),
+            ..(&value.config).into()
         }
     }
 }
+
+impl From<&Snapshot> for SnapshotResponse {
+    fn from(value: &Snapshot) -> Self {
+        SnapshotResponse {
+            timestamp: value.timestamp.format(),
+            status: value.into(),
+            config: value.into(),
+            device: None,
+            warnings: value
+                .config
+                .violations()
+                .map(|violation| violation.to_string())
+                .collect(),
+            stale: false,
+            stale_since: None,
+            stale_for_secs: None,
+        }
+    }
+}
+
+/// Build the JSON Schemas of the payloads served by the HTTP API, keyed by endpoint name
+pub(crate) fn schemas() -> serde_json::Value {
+    serde_json::json!({
+        "status": schemars::schema_for!(LockStatusResponse),
+        "config": schemars::schema_for!(ConfigResponse),
+        "device": schemars::schema_for!(DeviceResponse),
+        "snapshot": schemars::schema_for!(SnapshotResponse),
+        "diagnostics": schemars::schema_for!(Diagnosis),
+        "maintenance": schemars::schema_for!(MaintenanceResponse),
+        "startup": schemars::schema_for!(StartupReport),
+        "version": schemars::schema_for!(VersionResponse),
+    })
+}
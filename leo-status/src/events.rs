@@ -0,0 +1,200 @@
+use clap::Args as ClapArgs;
+use leo_status_client::Client;
+use leo_status_driver::{
+    tracker::{StatusEvent, StatusTracker},
+    GpsdoDevice, GpsdoStatus,
+};
+
+use serde_json::{Map, Value};
+
+use std::time::{Duration, Instant};
+
+use crate::{exit::ExitCode, poll, snapshot::Snapshot, time::Timestamp, Args};
+
+pub(crate) use leo_status_api::{LockEvent, LockEventKind};
+
+/// How often a stream of changes only sends an event in full
+const FULL_EVENT_INTERVAL: Duration = Duration::from_secs(300);
+
+impl From<&Snapshot> for LockEvent {
+    /// The lock state of a snapshot, as sent to clients when they first connect
+    fn from(value: &Snapshot) -> Self {
+        LockEvent {
+            state: Some(value.state.into()),
+            poll_seq: Some(value.poll_seq),
+            ..LockEvent::new(
+                LockEventKind::Snapshot,
+                &value.status,
+                value.timestamp.format(),
+            )
+        }
+    }
+}
+
+/// Detects lock transitions between successive status readings
+#[derive(Default)]
+pub(crate) struct LockTracker {
+    status: StatusTracker,
+}
+
+impl LockTracker {
+    /// Record a new status reading, returning the transitions since the previous one, tagged with the poll cycle of the
+    /// reading if it has one
+    pub(crate) fn update(
+        &mut self,
+        status: &GpsdoStatus,
+        timestamp: Timestamp,
+        poll_seq: Option<u64>,
+    ) -> Vec<LockEvent> {
+        self.status
+            .update(status)
+            .filter_map(|event| {
+                let kind = match event {
+                    StatusEvent::LockAcquired => LockEventKind::LockAcquired,
+                    StatusEvent::LockLost => LockEventKind::LockLost,
+                    StatusEvent::SatLockAcquired => LockEventKind::SatLockAcquired,
+                    StatusEvent::SatLockLost => LockEventKind::SatLockLost,
+                    StatusEvent::PllLockAcquired => LockEventKind::PllLockAcquired,
+                    StatusEvent::PllLockLost => LockEventKind::PllLockLost,
+                    StatusEvent::LossCountIncreased(_) => return None,
+                };
+
+                Some(LockEvent {
+                    poll_seq,
+                    ..LockEvent::new(kind, status, timestamp.format())
+                })
+            })
+            .collect()
+    }
+}
+
+/// Format an event as a server-sent event
+pub(crate) fn sse_message(event: &LockEvent) -> String {
+    format!(
+        "data: {}\n\n",
+        serde_json::to_string(event).expect("failed to serialize event")
+    )
+}
+
+/// Formats the events of a stream opened with `?changes_only=true`, leaving out the fields which haven't changed since
+/// the previous event, apart from `event`. The first event, and the first after each `FULL_EVENT_INTERVAL`, are sent
+/// in full, so a client which missed a change is corrected.
+#[derive(Default)]
+pub(crate) struct ChangesOnly {
+    previous: Option<Map<String, Value>>,
+    last_full: Option<Instant>,
+}
+
+impl ChangesOnly {
+    /// Format an event as a server-sent event holding only what changed
+    pub(crate) fn sse_message(&mut self, event: &LockEvent) -> String {
+        let Value::Object(fields) = serde_json::to_value(event).expect("failed to serialize event")
+        else {
+            unreachable!("events serialize as objects");
+        };
+
+        let full = self
+            .last_full
+            .is_none_or(|last_full| last_full.elapsed() >= FULL_EVENT_INTERVAL);
+        let message = match &self.previous {
+            Some(previous) if !full => fields
+                .iter()
+                .filter(|(key, value)| *key == "event" || previous.get(*key) != Some(*value))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+            _ => {
+                self.last_full = Some(Instant::now());
+                fields.clone()
+            }
+        };
+        self.previous = Some(fields);
+
+        format!("data: {}\n\n", Value::Object(message))
+    }
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub(crate) struct EventsArgs {
+    #[arg(
+        long,
+        help = "Keep printing lock transitions as they happen, rather than printing the current lock state and exiting"
+    )]
+    follow: bool,
+
+    #[arg(
+        long,
+        default_value = "127.0.0.1:8080",
+        conflicts_with = "direct",
+        help = "HTTP host of the running leo-status daemon to read events from"
+    )]
+    daemon: String,
+
+    #[arg(
+        long,
+        help = "Poll the GPSDO directly instead of connecting to a running daemon"
+    )]
+    direct: bool,
+}
+
+/// Print lock events from a running daemon, or from the GPSDO directly
+pub(crate) fn events(args: &Args, events_args: &EventsArgs) {
+    let result = if events_args.direct {
+        follow_device(args, events_args.follow)
+    } else {
+        follow_daemon(&events_args.daemon, events_args.follow)
+    };
+
+    if let Err(error) = result {
+        eprintln!("{}", error);
+        ExitCode::Failure.exit();
+    }
+}
+
+/// Read the server-sent event stream of a running daemon, printing each event
+fn follow_daemon(daemon: &str, follow: bool) -> Result<(), String> {
+    let events = Client::new(&format!("http://{}", daemon))
+        .watch_events()
+        .map_err(|error| format!("failed to read events from {}: {}", daemon, error))?;
+
+    for event in events {
+        let event = event.map_err(|error| error.to_string())?;
+        println!("{}", event);
+
+        if !follow {
+            return Ok(());
+        }
+    }
+
+    Err("daemon closed the connection".to_owned())
+}
+
+/// Poll the GPSDO directly, printing the lock state and then each transition
+fn follow_device(args: &Args, follow: bool) -> Result<(), String> {
+    let (interface, info) = poll::open(args).map_err(|error| error.to_string())?;
+    let gpsdo = GpsdoDevice::with_model(&interface, poll::model(&info));
+
+    let mut tracker = LockTracker::default();
+
+    let status = gpsdo.status().map_err(|error| error.to_string())?;
+    let timestamp = Timestamp::now();
+    tracker.update(&status, timestamp, None);
+    println!(
+        "{}",
+        LockEvent::new(LockEventKind::Snapshot, &status, timestamp.format())
+    );
+
+    if !follow {
+        return Ok(());
+    }
+
+    let interval = args.interval.unwrap_or(Duration::from_secs(1));
+
+    loop {
+        std::thread::sleep(interval);
+
+        let status = gpsdo.status().map_err(|error| error.to_string())?;
+        for event in tracker.update(&status, Timestamp::now(), None) {
+            println!("{}", event);
+        }
+    }
+}
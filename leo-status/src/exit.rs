@@ -0,0 +1,105 @@
+use std::fmt::Write;
+
+/// The codes leo-status exits with, so wrapper scripts and service managers can branch on why it stopped. Each is
+/// listed at the end of `--help`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ExitCode {
+    /// A failure without a code of its own
+    Failure,
+
+    /// The command line arguments were invalid, as clap reports them
+    Usage,
+
+    /// No GPSDO matching the arguments was found
+    DeviceNotFound,
+
+    /// A GPSDO was found but couldn't be opened, usually for lack of permission, or privileges couldn't be dropped
+    Permission,
+
+    /// A profile or other configuration file was missing or invalid
+    ConfigInvalid,
+
+    /// The GPSDO was opened but reading it failed
+    PollFailure,
+
+    /// The config of the GPSDO doesn't match the golden capture given to `verify`
+    Mismatch,
+
+    /// The GPSDO is already in use by another leo-status process
+    Locked,
+
+    /// A daemon component died or stalled and couldn't be restarted (EX_UNAVAILABLE)
+    Unavailable,
+
+    /// A thread panicked (EX_SOFTWARE)
+    Panic,
+}
+
+impl ExitCode {
+    /// Every exit code, in the order `--help` lists them
+    const ALL: [ExitCode; 10] = [
+        ExitCode::Failure,
+        ExitCode::Usage,
+        ExitCode::DeviceNotFound,
+        ExitCode::Permission,
+        ExitCode::ConfigInvalid,
+        ExitCode::PollFailure,
+        ExitCode::Mismatch,
+        ExitCode::Locked,
+        ExitCode::Unavailable,
+        ExitCode::Panic,
+    ];
+
+    pub(crate) fn code(self) -> i32 {
+        match self {
+            ExitCode::Failure => 1,
+            ExitCode::Usage => 2,
+            ExitCode::DeviceNotFound => 10,
+            ExitCode::Permission => 11,
+            ExitCode::ConfigInvalid => 12,
+            ExitCode::PollFailure => 13,
+            ExitCode::Mismatch => 14,
+            ExitCode::Locked => 15,
+            ExitCode::Unavailable => 69,
+            ExitCode::Panic => 70,
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            ExitCode::Failure => "any other failure",
+            ExitCode::Usage => "invalid command line arguments",
+            ExitCode::DeviceNotFound => "no GPSDO was found",
+            ExitCode::Permission => {
+                "the GPSDO couldn't be opened, usually for lack of permission, or privileges couldn't be dropped"
+            }
+            ExitCode::ConfigInvalid => "a profile or other configuration file is missing or invalid",
+            ExitCode::PollFailure => "reading the GPSDO failed",
+            ExitCode::Mismatch => "the GPSDO doesn't match the golden capture given to verify",
+            ExitCode::Locked => "the GPSDO is already in use by another leo-status",
+            ExitCode::Unavailable => "a daemon component died or stalled and couldn't be restarted",
+            ExitCode::Panic => "a thread panicked",
+        }
+    }
+
+    /// Exit the process with this code
+    pub(crate) fn exit(self) -> ! {
+        std::process::exit(self.code())
+    }
+
+    /// The list of exit codes shown at the end of `--help`
+    pub(crate) fn help() -> String {
+        let mut help = "Exit codes:\n".to_owned();
+        for exit_code in ExitCode::ALL {
+            writeln!(
+                help,
+                "  {:>3}  {}",
+                exit_code.code(),
+                exit_code.description()
+            )
+            .expect("failed to format exit codes");
+        }
+
+        help
+    }
+}
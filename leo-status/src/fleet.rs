@@ -0,0 +1,232 @@
+use clap::{Args as ClapArgs, Subcommand};
+use leo_status_client::{
+    api::{ConfigResponse, DeviceResponse, DeviceState, LockStatusResponse},
+    Client,
+};
+use serde::{Deserialize, Serialize};
+
+use std::{fs, path::PathBuf, thread};
+
+use crate::{
+    exit::ExitCode,
+    messages::{self, Message},
+};
+
+#[derive(ClapArgs, Debug, Clone)]
+pub(crate) struct FleetArgs {
+    #[arg(
+        long,
+        help = "TOML file listing the daemons to reach, each as a [[target]] with a name and the url of its HTTP API"
+    )]
+    targets: PathBuf,
+
+    #[command(subcommand)]
+    command: FleetCommand,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum FleetCommand {
+    /// Print the lock status of every target as a table
+    Status {
+        #[arg(long, help = "Print the statuses as JSON instead of a table")]
+        json: bool,
+    },
+
+    /// Check every target is reachable, fresh and locked, printing those which aren't and exiting with 1 if any
+    Check,
+
+    /// Print the status, config and device of every target as JSON
+    Export,
+}
+
+/// The targets file
+#[derive(Deserialize)]
+struct Targets {
+    #[serde(rename = "target", default)]
+    targets: Vec<Target>,
+}
+
+/// A daemon of the fleet
+#[derive(Deserialize, Debug, Clone)]
+struct Target {
+    name: String,
+
+    /// The base URL of its HTTP API, such as `http://site-a:8000`
+    url: String,
+}
+
+/// What was read from a target, with the error which stopped it being read, if any
+#[derive(Serialize)]
+struct TargetReport {
+    name: String,
+    url: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<LockStatusResponse>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    config: Option<ConfigResponse>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    device: Option<DeviceResponse>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl TargetReport {
+    /// Read a target, and its config and device as well if `details` is set
+    fn read(target: &Target, details: bool) -> Self {
+        let client = Client::new(&target.url);
+        let mut report = TargetReport {
+            name: target.name.clone(),
+            url: target.url.clone(),
+            status: None,
+            config: None,
+            device: None,
+            error: None,
+        };
+
+        let result = (|| {
+            report.status = Some(client.get_status()?);
+            if details {
+                report.config = Some(client.get_config()?);
+                report.device = Some(client.get_device()?);
+            }
+
+            Ok::<_, leo_status_client::ClientError<_>>(())
+        })();
+        report.error = result.err().map(|error| error.to_string());
+
+        report
+    }
+
+    /// Why the target fails `check`, `None` if it passes
+    fn problem(&self) -> Option<String> {
+        match (&self.status, &self.error) {
+            (_, Some(error)) => Some(error.clone()),
+            (None, None) => Some(messages::text(Message::Unknown).to_owned()),
+            (Some(status), None) if status.stale => {
+                Some(messages::text(Message::FleetStaleData).to_owned())
+            }
+            (Some(status), None) if !locked(status) => {
+                Some(messages::text(Message::FleetUnlocked).to_owned())
+            }
+            (Some(_), None) => None,
+        }
+    }
+}
+
+/// Whether the target's GPSDO is in the locked state, by the locked flag for daemons too old to serve the state
+fn locked(status: &LockStatusResponse) -> bool {
+    status
+        .state
+        .map_or(status.locked, |state| state == DeviceState::Locked)
+}
+
+/// Read every target of the fleet at once, in the order of the targets file
+fn read_fleet(targets: &[Target], details: bool) -> Vec<TargetReport> {
+    thread::scope(|scope| {
+        let readers: Vec<_> = targets
+            .iter()
+            .map(|target| scope.spawn(move || TargetReport::read(target, details)))
+            .collect();
+
+        readers
+            .into_iter()
+            .map(|reader| reader.join().expect("fleet reader panicked"))
+            .collect()
+    })
+}
+
+/// Reach every daemon listed in the targets file concurrently, and print one report of them all
+pub(crate) fn fleet(fleet_args: &FleetArgs) {
+    let targets: Targets = fs::read_to_string(&fleet_args.targets)
+        .map_err(|error| error.to_string())
+        .and_then(|targets| toml::from_str(&targets).map_err(|error| error.to_string()))
+        .unwrap_or_else(|error| {
+            eprintln!(
+                "failed to read targets {}: {}",
+                fleet_args.targets.display(),
+                error
+            );
+            ExitCode::ConfigInvalid.exit();
+        });
+
+    match fleet_args.command {
+        FleetCommand::Status { json: true } => {
+            print_json(&read_fleet(&targets.targets, false));
+        }
+        FleetCommand::Status { json: false } => {
+            print_table(&read_fleet(&targets.targets, false));
+        }
+        FleetCommand::Check => {
+            let reports = read_fleet(&targets.targets, false);
+
+            let mut failed = false;
+            for report in &reports {
+                if let Some(problem) = report.problem() {
+                    println!("{}\t{}", report.name, problem);
+                    failed = true;
+                }
+            }
+
+            if failed {
+                ExitCode::Failure.exit();
+            }
+            println!(
+                "{} {}",
+                reports.len(),
+                messages::text(Message::FleetAllLocked)
+            );
+        }
+        FleetCommand::Export => print_json(&read_fleet(&targets.targets, true)),
+    }
+}
+
+fn print_json(reports: &[TargetReport]) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(reports).expect("failed to serialize fleet report")
+    );
+}
+
+/// Print the statuses as a table, one target per line, with the error in place of the status of targets which couldn't
+/// be read
+fn print_table(reports: &[TargetReport]) {
+    println!(
+        "{}\t{}\t{}\t{}\t{}\t{}",
+        messages::text(Message::FleetTarget),
+        messages::text(Message::FleetLocked),
+        messages::text(Message::FleetSatLock),
+        messages::text(Message::FleetPllLock),
+        messages::text(Message::LossCount),
+        messages::text(Message::FleetStale),
+    );
+
+    let yes_no = |value: bool| {
+        messages::text(if value {
+            Message::FleetYes
+        } else {
+            Message::FleetNo
+        })
+    };
+    for report in reports {
+        match (&report.status, &report.error) {
+            (Some(status), None) => println!(
+                "{}\t{}\t{}\t{}\t{}\t{}",
+                report.name,
+                yes_no(locked(status)),
+                yes_no(status.sat_lock),
+                yes_no(status.pll_lock),
+                status.loss_count,
+                yes_no(status.stale),
+            ),
+            (_, error) => println!(
+                "{}\t{}",
+                report.name,
+                error.as_deref().unwrap_or(messages::text(Message::Unknown))
+            ),
+        }
+    }
+}
@@ -0,0 +1,1042 @@
+use leo_status_driver::state::DeviceState;
+use prometheus::{Encoder, TextEncoder};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use std::{
+    io::{self, Cursor, Write},
+    net::SocketAddr,
+    path::Path,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError},
+        Arc, Mutex, RwLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "write")]
+use crate::{
+    audit::AuditLog,
+    writes::{DeviceWrites, WriteError},
+};
+use crate::{
+    auth::Auth,
+    broadcast::Broadcast,
+    diagnostics::SharedDiagnosis,
+    dto::{
+        self, ConfigResponse, LockStatusResponse, Served, ServedDevice, SnapshotResponse, Stale,
+        VersionResponse,
+    },
+    events::{self, ChangesOnly, LockEvent},
+    ingest::Remotes,
+    journal::Journal,
+    maintenance::Maintenance,
+    metrics::Metrics,
+    persist,
+    poll::PolledDevice,
+    profile::{ProfileError, ProfileStore},
+    proxy::DeviceProxy,
+    schedule::PollTrigger,
+    snapshot::Snapshot,
+    startup::StartupReport,
+    supervisor::Heartbeat,
+    time::Timestamp,
+};
+
+/// How long the server waits for a request before updating its heartbeat
+const RECV_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How often an idle event stream sends a comment, so clients and proxies can tell the connection is alive
+const EVENTS_KEEPALIVE: Duration = Duration::from_secs(15);
+
+/// How many requests which touch the GPSDO may wait for the device thread, besides the one it is handling
+const DEVICE_QUEUE_LENGTH: usize = 4;
+
+/// The state shared between the poll loop and the HTTP server
+#[derive(Clone)]
+pub(crate) struct HttpContext {
+    /// The reading of the latest poll cycle, which the status, config and snapshot responses are built from
+    pub(crate) latest: Arc<RwLock<Option<Served<Snapshot>>>>,
+    pub(crate) device: Arc<RwLock<Option<ServedDevice>>>,
+    pub(crate) metrics: Metrics,
+    pub(crate) events: Broadcast<LockEvent>,
+
+    /// The event streams being served, and the most which may be served at once
+    pub(crate) event_streams: Arc<AtomicUsize>,
+    pub(crate) event_stream_limit: usize,
+    pub(crate) startup: Arc<RwLock<Option<StartupReport>>>,
+
+    /// The running version, and the latest release once the release feed has been read
+    pub(crate) version: Arc<RwLock<VersionResponse>>,
+    pub(crate) profiles: ProfileStore,
+    pub(crate) journal: Journal,
+
+    /// The readings pushed by other instances, `None` unless the ingest endpoint is enabled
+    pub(crate) remotes: Option<Remotes>,
+
+    /// The latest diagnosis of the lock losses, `None` unless `--diagnostics` is given
+    pub(crate) diagnosis: Option<SharedDiagnosis>,
+
+    /// The providers every request other than to the device proxy must authenticate with, `None` if none are
+    /// configured
+    pub(crate) auth: Option<Arc<Auth>>,
+
+    /// The device proxy, `None` unless a proxy token is given
+    pub(crate) proxy: Option<DeviceProxy>,
+    #[cfg(feature = "write")]
+    pub(crate) audit: AuditLog,
+
+    /// The lock writes to the GPSDO are made behind, shared with the poll loop so it reads the device after each write
+    #[cfg(feature = "write")]
+    pub(crate) writes: DeviceWrites,
+
+    /// Whether writes to the GPSDO are served without authentication, with `--allow-writes`
+    #[cfg(feature = "write")]
+    pub(crate) allow_writes: bool,
+
+    /// Planned maintenance, started and ended at `/maintenance`
+    pub(crate) maintenance: Maintenance,
+
+    /// Wakes the poll loop for `POST /poll`
+    pub(crate) poll_trigger: PollTrigger,
+
+    /// Hands requests which touch the GPSDO to the poll loop
+    pub(crate) device_queue: DeviceQueue,
+}
+
+impl HttpContext {
+    /// Flag the last data received as stale, because the device stopped responding at the given time, leaving it in
+    /// `state`
+    pub(crate) fn mark_stale(&self, since: Timestamp, state: DeviceState) {
+        if let Some(latest) = self.latest.write().unwrap().as_mut() {
+            latest.mark_stale(since, state);
+        }
+    }
+}
+
+/// Respond with the JSON serialization of the latest value, trimmed to the `?fields=` selected, or 503 if no value has
+/// been read yet
+fn json_response<T: Serialize>(
+    value: &RwLock<Option<T>>,
+    name: &str,
+    fields: Option<&str>,
+) -> Response<Cursor<Vec<u8>>> {
+    match value
+        .read()
+        .unwrap_or_else(|_| panic!("failed to get {} mutex", name))
+        .as_ref()
+    {
+        Some(value) => json_body(value, fields),
+        None => {
+            Response::from_string("Service Unavailable - data not ready yet").with_status_code(503)
+        }
+    }
+}
+
+/// Respond with the JSON serialization of a response built from the latest snapshot, trimmed to the `?fields=`
+/// selected, or 503 if the GPSDO hasn't been read yet
+fn snapshot_response<T: Stale + Clone + Serialize>(
+    context: &HttpContext,
+    fields: Option<&str>,
+    response: impl FnOnce(&Snapshot) -> T,
+) -> Response<Cursor<Vec<u8>>> {
+    match context.latest.read().unwrap().as_ref() {
+        Some(latest) => json_body(&latest.map(response), fields),
+        None => {
+            Response::from_string("Service Unavailable - data not ready yet").with_status_code(503)
+        }
+    }
+}
+
+/// Respond with the JSON serialization of a value, trimmed to the `?fields=` selected
+fn json_body<T: Serialize>(value: &T, fields: Option<&str>) -> Response<Cursor<Vec<u8>>> {
+    let body = match fields.filter(|fields| !fields.is_empty()) {
+        Some(fields) => {
+            // Commas are the only character a field list needs which clients may percent-encode
+            let fields = fields.replace("%2C", ",").replace("%2c", ",");
+            let fields: Vec<&str> = fields
+                .split(',')
+                .filter(|field| !field.is_empty())
+                .collect();
+
+            serde_json::to_vec(&select_fields(
+                serde_json::to_value(value).expect("failed to serialize response"),
+                &fields,
+            ))
+        }
+        None => serde_json::to_vec(value),
+    };
+
+    Response::from_data(body.expect("failed to serialize response"))
+        .with_header(Header::from_str("Content-Type: application/json").unwrap())
+}
+
+/// Trim a JSON value to the comma separated fields of `?fields=`, such as `locked,loss_count`, where a field nested in
+/// an object is given by its dotted path, such as `status.locked`. A list has each of its items trimmed. Fields which
+/// don't exist, including paths which index into a list such as `required.0`, are left out, rather than failing the
+/// request. Where fields overlap, such as `status,status.locked`, the widest selection wins.
+fn select_fields(value: Value, fields: &[&str]) -> Value {
+    match value {
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .map(|item| select_fields(item, fields))
+                .collect(),
+        ),
+        Value::Object(object) => {
+            let mut selected = Map::new();
+
+            for field in fields {
+                let path: Vec<&str> = field.split('.').collect();
+                let Some(found) = find_field(&object, &path) else {
+                    continue;
+                };
+                insert_field(&mut selected, &path, found.clone());
+            }
+
+            Value::Object(selected)
+        }
+        value => value,
+    }
+}
+
+/// Find a field by its path through nested objects, `None` if a part of the path is missing or isn't an object
+fn find_field<'a>(object: &'a Map<String, Value>, path: &[&str]) -> Option<&'a Value> {
+    let (last, parents) = path.split_last()?;
+
+    let mut object = object;
+    for part in parents {
+        let Some(Value::Object(inner)) = object.get(*part) else {
+            return None;
+        };
+        object = inner;
+    }
+
+    object.get(*last)
+}
+
+/// Insert a field at its path through nested objects, creating the objects along the way. A field already selected
+/// whole by a shorter path is left as it is.
+fn insert_field(selected: &mut Map<String, Value>, path: &[&str], value: Value) {
+    let Some((last, parents)) = path.split_last() else {
+        return;
+    };
+
+    let mut target = selected;
+    for part in parents {
+        let entry = target
+            .entry(*part)
+            .or_insert_with(|| Value::Object(Map::new()));
+        let Value::Object(object) = entry else {
+            return;
+        };
+        target = object;
+    }
+
+    target.insert((*last).to_owned(), value);
+}
+
+/// Route the profile endpoints: `GET /profiles`, `GET /profiles/<name>` and `POST /profiles/<name>/apply`
+fn profiles_response(
+    context: &HttpContext,
+    request: &Request,
+    path: &str,
+    fields: Option<&str>,
+) -> Response<Cursor<Vec<u8>>> {
+    let store = &context.profiles;
+    let path = path.trim_start_matches("/profiles").trim_matches('/');
+
+    let result = match (request.method(), path.split_once('/')) {
+        (Method::Get, None) if path.is_empty() => {
+            store.list().map(|profiles| json_body(&profiles, fields))
+        }
+        (Method::Get, None) => store.load(path).map(|profile| json_body(&profile, fields)),
+        #[cfg(feature = "write")]
+        (Method::Post, Some((name, "apply"))) => {
+            let source = match request.remote_addr() {
+                Some(addr) => format!("http:{}", addr.ip()),
+                None => "http:unknown".to_owned(),
+            };
+
+            let (store, audit, writes, name, fields) = (
+                store.clone(),
+                context.audit.clone(),
+                context.writes.clone(),
+                name.to_owned(),
+                fields.map(str::to_owned),
+            );
+            return context.device_queue.handle(move |gpsdo| {
+                store
+                    .apply_to(&name, gpsdo, &audit, &writes, &source)
+                    .map(|profile| json_body(&profile, fields.as_deref()))
+                    .unwrap_or_else(profile_error_response)
+            });
+        }
+        (_, None) | (_, Some((_, "apply"))) => {
+            return Response::from_string("Method Not Allowed").with_status_code(405)
+        }
+        _ => return Response::from_string("Not Found").with_status_code(404),
+    };
+
+    result.unwrap_or_else(profile_error_response)
+}
+
+/// Whether a request writes to the GPSDO, such as `POST /profiles/<name>/apply`
+#[cfg(feature = "write")]
+fn is_device_write(method: &Method, path: &str) -> bool {
+    *method == Method::Post
+        && path
+            .strip_prefix("/profiles/")
+            .is_some_and(|path| path.trim_end_matches('/').ends_with("/apply"))
+}
+
+/// Answer a write to the GPSDO with 403 unless requests must be authenticated, or writes were allowed without
+/// authentication with `--allow-writes`, so a client which can merely reach the port can't reprogram the GPSDO
+#[cfg(feature = "write")]
+fn forbidden_write(
+    request: &Request,
+    authenticated: bool,
+    allow_writes: bool,
+) -> Option<Response<Cursor<Vec<u8>>>> {
+    let path = request.url().split('?').next().unwrap_or_default();
+    if authenticated || allow_writes || !is_device_write(request.method(), path) {
+        return None;
+    }
+
+    Some(
+        Response::from_string(
+            "Forbidden - writes to the GPSDO need an --auth-* provider or --allow-writes",
+        )
+        .with_status_code(403),
+    )
+}
+
+/// Handle `/maintenance`, serving whether maintenance is in progress, starting it for `?duration=` on `POST` and
+/// ending it on `DELETE`
+fn maintenance_response(
+    context: &HttpContext,
+    request: &Request,
+    url: &str,
+) -> Response<Cursor<Vec<u8>>> {
+    match request.method() {
+        Method::Get => {}
+        Method::Post => {
+            let Some(duration) = query_param(url, "duration")
+                .and_then(|duration| humantime::parse_duration(duration).ok())
+            else {
+                return Response::from_string(
+                    "Bad Request - expected a duration such as ?duration=2h",
+                )
+                .with_status_code(400);
+            };
+
+            context.maintenance.start(duration);
+        }
+        Method::Delete => context.maintenance.end(),
+        _ => return Response::from_string("Method Not Allowed").with_status_code(405),
+    }
+
+    json_body(&context.maintenance.response(), query_param(url, "fields"))
+}
+
+/// Whether a request is to the ingest endpoint
+fn is_ingest(url: &str) -> bool {
+    url == "/ingest" || url.starts_with("/ingest?")
+}
+
+/// Handle `POST /ingest`, storing a reading pushed by another instance as that of who pushed it, see `Remotes::ingest`
+fn ingest_response(
+    context: &HttpContext,
+    request: &mut Request,
+    identity: Option<String>,
+) -> Response<Cursor<Vec<u8>>> {
+    let Some(remotes) = &context.remotes else {
+        return Response::from_string("Not Found").with_status_code(404);
+    };
+    if *request.method() != Method::Post {
+        return Response::from_string("Method Not Allowed").with_status_code(405);
+    }
+
+    match remotes.ingest(request, identity) {
+        Ok(()) => Response::from_string("").with_status_code(204),
+        Err((status_code, message)) => Response::from_string(message).with_status_code(status_code),
+    }
+}
+
+/// Handle a request to the device proxy at `/proxy/`, see `DeviceProxy`
+fn proxy_response(context: &HttpContext, request: &mut Request) -> Response<Cursor<Vec<u8>>> {
+    let Some(proxy) = &context.proxy else {
+        return Response::from_string("Not Found").with_status_code(404);
+    };
+
+    let proxy_request = match proxy.parse(request) {
+        Ok(proxy_request) => proxy_request,
+        Err(response) => return response,
+    };
+
+    let proxy = proxy.clone();
+    #[cfg(feature = "write")]
+    let (audit, writes) = (context.audit.clone(), context.writes.clone());
+    context.device_queue.handle(move |gpsdo| {
+        proxy.handle(
+            proxy_request,
+            gpsdo,
+            #[cfg(feature = "write")]
+            &audit,
+            #[cfg(feature = "write")]
+            &writes,
+        )
+    })
+}
+
+fn profile_error_response(error: ProfileError) -> Response<Cursor<Vec<u8>>> {
+    #[cfg(feature = "write")]
+    if let ProfileError::Write(error) = &error {
+        return write_error_response(error);
+    }
+
+    let status_code = match error {
+        ProfileError::InvalidName(_) | ProfileError::NotFound(_) => 404,
+        #[cfg(feature = "write")]
+        ProfileError::InvalidConfig { .. } => 422,
+        #[cfg(feature = "write")]
+        ProfileError::Device(_) | ProfileError::NotApplied => 502,
+        _ => 500,
+    };
+
+    Response::from_string(error.to_string()).with_status_code(status_code)
+}
+
+/// Answer a write which couldn't start with 429, telling the client when to try again
+#[cfg(feature = "write")]
+pub(crate) fn write_error_response(error: &WriteError) -> Response<Cursor<Vec<u8>>> {
+    Response::from_string(error.to_string())
+        .with_status_code(429)
+        .with_header(Header::from_str(&format!("Retry-After: {}", error.retry_after())).unwrap())
+}
+
+/// The work of a request which touches the GPSDO, run on the poll loop's device
+type DeviceWork = Box<dyn FnOnce(&PolledDevice) -> Response<Cursor<Vec<u8>>> + Send>;
+
+/// A request which touches the GPSDO, waiting for the poll loop
+struct DeviceJob {
+    work: DeviceWork,
+
+    /// When the client is answered with 504, after which the job is dropped rather than started
+    expires: Instant,
+    respond: mpsc::Sender<Response<Cursor<Vec<u8>>>>,
+}
+
+/// Hands requests which touch the GPSDO to the poll loop, which runs them one at a time between polls on the device it
+/// has open, so no request opens the GPSDO alongside it or reads it in the middle of a poll. A request which hasn't
+/// finished within the deadline is answered with 504 so a wedged HID transaction can't hold the server and its client
+/// forever, and carries on in the background as a HID transaction can't be cancelled. The requests behind it wait in a
+/// queue of `DEVICE_QUEUE_LENGTH`, and are answered with 503 once it is full.
+#[derive(Clone)]
+pub(crate) struct DeviceQueue {
+    sender: SyncSender<DeviceJob>,
+    deadline: Duration,
+    wake: PollTrigger,
+}
+
+/// The poll loop's end of the `DeviceQueue`, shared with the poll loop each time the supervisor starts it
+#[derive(Clone)]
+pub(crate) struct DeviceJobs {
+    receiver: Arc<Mutex<Receiver<DeviceJob>>>,
+}
+
+impl DeviceQueue {
+    /// Create the queue, waking the poll loop with `wake` whenever a request is queued
+    pub(crate) fn new(deadline: Duration, wake: PollTrigger) -> (Self, DeviceJobs) {
+        let (sender, receiver) = mpsc::sync_channel::<DeviceJob>(DEVICE_QUEUE_LENGTH);
+
+        (
+            DeviceQueue {
+                sender,
+                deadline,
+                wake,
+            },
+            DeviceJobs {
+                receiver: Arc::new(Mutex::new(receiver)),
+            },
+        )
+    }
+
+    /// Handle a request which touches the GPSDO on the poll loop, answering 504 if it hasn't finished within the
+    /// deadline, or 503 if the queue is full
+    fn handle(
+        &self,
+        work: impl FnOnce(&PolledDevice) -> Response<Cursor<Vec<u8>>> + Send + 'static,
+    ) -> Response<Cursor<Vec<u8>>> {
+        let (respond, receiver) = mpsc::channel();
+        let job = DeviceJob {
+            work: Box::new(work),
+            expires: Instant::now() + self.deadline,
+            respond,
+        };
+
+        match self.sender.try_send(job) {
+            Ok(()) => self.wake.wake_for_jobs(),
+            Err(TrySendError::Full(_)) => {
+                let retry_after = self.deadline.as_secs().max(1);
+
+                return Response::from_string("Too many requests waiting on the GPSDO")
+                    .with_status_code(503)
+                    .with_header(
+                        Header::from_str(&format!("Retry-After: {}", retry_after)).unwrap(),
+                    );
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                return Response::from_string("The poll loop has stopped").with_status_code(500)
+            }
+        }
+
+        receiver.recv_timeout(self.deadline).unwrap_or_else(|_| {
+            Response::from_string(format!(
+                "The GPSDO did not respond within {}",
+                humantime::format_duration(self.deadline)
+            ))
+            .with_status_code(504)
+        })
+    }
+}
+
+impl DeviceJobs {
+    /// Run the requests waiting on the GPSDO against the device the poll loop has open
+    pub(crate) fn run(&self, gpsdo: &PolledDevice) {
+        while let Some(job) = self.next() {
+            // The receiver is gone if the deadline passed, in which case nobody is waiting for the response
+            let _ = job.respond.send((job.work)(gpsdo));
+        }
+    }
+
+    /// Answer the requests waiting on the GPSDO with 503 while the poll loop has no device open
+    pub(crate) fn refuse(&self) {
+        while let Some(job) = self.next() {
+            let _ = job
+                .respond
+                .send(Response::from_string("No GPSDO is connected").with_status_code(503));
+        }
+    }
+
+    /// The next request waiting, dropping those whose client has already been answered with 504
+    fn next(&self) -> Option<DeviceJob> {
+        self.receiver
+            .lock()
+            .unwrap()
+            .try_iter()
+            .find(|job| Instant::now() < job.expires)
+    }
+}
+
+/// Stream lock events to the client as server-sent events on a thread of its own, starting with the current lock
+/// state. The response is written by hand because tiny_http buffers chunked responses, which would hold back events.
+fn stream_events(request: Request, context: &HttpContext) {
+    let streams = context.event_streams.clone();
+    let active = streams.fetch_add(1, Ordering::SeqCst);
+    if active >= context.event_stream_limit {
+        streams.fetch_sub(1, Ordering::SeqCst);
+
+        let response = Response::from_string("Too many event streams").with_status_code(503);
+        if let Err(error) = request.respond(response) {
+            tracing::warn!("failed to respond to http request: {}", error);
+        }
+        return;
+    }
+
+    let receiver = context.events.subscribe();
+    let lock_state = context
+        .latest
+        .read()
+        .unwrap()
+        .as_ref()
+        .map(|latest| LockEvent::from(latest.response()));
+    let mut changes_only =
+        (query_param(request.url(), "changes_only") == Some("true")).then(ChangesOnly::default);
+    let mut message = move |event: &LockEvent| match &mut changes_only {
+        Some(changes_only) => changes_only.sse_message(event),
+        None => events::sse_message(event),
+    };
+
+    let spawned = std::thread::Builder::new()
+        .name("http events".to_owned())
+        .spawn(move || {
+            let mut writer = request.into_writer();
+
+            let result = (|| {
+                write!(
+                    writer,
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n"
+                )?;
+
+                if let Some(lock_state) = &lock_state {
+                    write!(writer, "{}", message(lock_state))?;
+                }
+                writer.flush()?;
+
+                loop {
+                    match receiver.recv_timeout(EVENTS_KEEPALIVE) {
+                        Ok(event) => write!(writer, "{}", message(&event))?,
+                        Err(RecvTimeoutError::Timeout) => write!(writer, ":keepalive\n\n")?,
+                        Err(RecvTimeoutError::Disconnected) => return Ok(()),
+                    }
+                    writer.flush()?;
+                }
+            })();
+
+            // The client going away is the normal way for an event stream to end
+            if let Err::<(), std::io::Error>(error) = result {
+                if error.kind() != std::io::ErrorKind::BrokenPipe {
+                    tracing::warn!("event stream failed: {}", error);
+                }
+            }
+
+            streams.fetch_sub(1, Ordering::SeqCst);
+        });
+
+    if let Err(error) = spawned {
+        context.event_streams.fetch_sub(1, Ordering::SeqCst);
+        tracing::error!("failed to spawn event stream thread: {}", error);
+    }
+}
+
+/// The value of a query parameter of a URL. Values aren't percent-decoded, which the daemon's parameters don't need.
+pub(crate) fn query_param<'u>(url: &'u str, name: &str) -> Option<&'u str> {
+    let (_, query) = url.split_once('?')?;
+
+    query
+        .split('&')
+        .filter_map(|param| param.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+/// Write the port the server is bound to into the port file, replacing it atomically so readers never see it empty
+fn write_port_file(port_file: &Path, port: u16) -> io::Result<()> {
+    persist::write_atomic(port_file, format!("{}\n", port))
+}
+
+/// Serve HTTP requests until the server fails, updating the heartbeat while it is healthy. The bound address is logged
+/// and its port written to `port_file`, so a port of 0 can be used to listen on any free port. Requests are handled by
+/// up to `workers` at once, see `Workers`.
+pub(crate) fn serve(
+    http_host: SocketAddr,
+    port_file: Option<&Path>,
+    workers: usize,
+    context: &HttpContext,
+    heartbeat: &Heartbeat,
+) {
+    let server = match Server::http(http_host) {
+        Ok(server) => server,
+        Err(error) => {
+            tracing::error!("failed to listen on {}: {}", http_host, error);
+            return;
+        }
+    };
+
+    let bound_addr = server.server_addr().to_ip().unwrap_or(http_host);
+    tracing::info!("listening on http://{}", bound_addr);
+
+    if let Some(port_file) = port_file {
+        if let Err(error) = write_port_file(port_file, bound_addr.port()) {
+            tracing::error!("failed to write port to {}: {}", port_file.display(), error);
+            return;
+        }
+    }
+
+    let workers = match Workers::spawn(workers, context) {
+        Ok(workers) => workers,
+        Err(error) => {
+            tracing::error!("failed to spawn http worker thread: {}", error);
+            return;
+        }
+    };
+
+    loop {
+        heartbeat.beat();
+
+        let request = match server.recv_timeout(RECV_TIMEOUT) {
+            Ok(Some(request)) => request,
+            Ok(None) => continue,
+            Err(error) => {
+                tracing::error!("failed to receive http request: {}", error);
+                return;
+            }
+        };
+
+        workers.handle(request);
+    }
+}
+
+/// The threads requests are handled on, so a client which is slow to send its body or to read the response can't hold
+/// up the server, whose heartbeat only shows that it is accepting requests. There is a fixed number of them, so a flood
+/// of requests can't start a thread each, and a request arriving while every worker is busy is answered with 503.
+/// The workers stop once the server does.
+struct Workers {
+    sender: SyncSender<Request>,
+
+    /// The requests being handled or waiting for a worker, never more than there are workers
+    busy: Arc<AtomicUsize>,
+    count: usize,
+}
+
+impl Workers {
+    fn spawn(count: usize, context: &HttpContext) -> io::Result<Self> {
+        let (sender, receiver) = mpsc::sync_channel::<Request>(count);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let busy = Arc::new(AtomicUsize::new(0));
+
+        for _ in 0..count {
+            let receiver = receiver.clone();
+            let busy = busy.clone();
+            let context = context.clone();
+
+            thread::Builder::new()
+                .name("http worker".to_owned())
+                .spawn(move || loop {
+                    let request = receiver.lock().unwrap().recv();
+                    let Ok(request) = request else {
+                        return;
+                    };
+
+                    handle_request(request, &context);
+                    busy.fetch_sub(1, Ordering::SeqCst);
+                })?;
+        }
+
+        Ok(Workers {
+            sender,
+            busy,
+            count,
+        })
+    }
+
+    /// Hand a request to a worker, or answer it with 503 if every worker is busy
+    fn handle(&self, request: Request) {
+        if self.busy.fetch_add(1, Ordering::SeqCst) >= self.count {
+            self.busy.fetch_sub(1, Ordering::SeqCst);
+
+            let response = Response::from_string("Too many requests in progress")
+                .with_status_code(503)
+                .with_header(Header::from_str("Retry-After: 1").unwrap());
+            if let Err(error) = request.respond(response) {
+                tracing::warn!("failed to respond to http request: {}", error);
+            }
+            return;
+        }
+
+        // The queue has room for a request per worker, so it is never full here
+        if let Err(error) = self.sender.try_send(request) {
+            self.busy.fetch_sub(1, Ordering::SeqCst);
+            tracing::error!("failed to hand http request to a worker: {}", error);
+        }
+    }
+}
+
+/// Authenticate a request and route it to its endpoint
+fn handle_request(mut request: Request, context: &HttpContext) {
+    // The device proxy authenticates its requests with the proxy token instead, as does the ingest endpoint given
+    // ingest tokens
+    let own_tokens = request.url().starts_with("/proxy/")
+        || (is_ingest(request.url()) && context.remotes.as_ref().is_some_and(Remotes::has_tokens));
+
+    let mut identity = None;
+    if let Some(auth) = context.auth.as_ref().filter(|_| !own_tokens) {
+        match auth.authenticate(&request) {
+            Ok(principal) => identity = Some(principal),
+            Err(error) => {
+                tracing::debug!(url = request.url(), "request not authenticated: {}", error);
+                if let Err(error) = request.respond(auth.unauthorized()) {
+                    tracing::warn!("failed to respond to http request: {}", error);
+                }
+                return;
+            }
+        }
+    }
+
+    #[cfg(feature = "write")]
+    if let Some(response) = forbidden_write(&request, context.auth.is_some(), context.allow_writes)
+    {
+        if let Err(error) = request.respond(response) {
+            tracing::warn!("failed to respond to http request: {}", error);
+        }
+        return;
+    }
+
+    if matches!(
+        request.url().split('?').next(),
+        Some("/events" | "/events/")
+    ) {
+        stream_events(request, context);
+        return;
+    }
+
+    // Reading the pushed body needs the request mutably, so the ingest endpoint is handled before the others
+    if is_ingest(request.url()) {
+        let response = ingest_response(context, &mut request, identity);
+        if let Err(error) = request.respond(response) {
+            tracing::warn!("failed to respond to http request: {}", error);
+        }
+        return;
+    }
+
+    if request.url().starts_with("/proxy/") {
+        let response = proxy_response(context, &mut request);
+        if let Err(error) = request.respond(response) {
+            tracing::warn!("failed to respond to http request: {}", error);
+        }
+        return;
+    }
+
+    let url = request.url().to_owned();
+    let (path, _) = url.split_once('?').unwrap_or((&url, ""));
+    let fields = query_param(&url, "fields");
+
+    let response: Response<_> = match path {
+        "/config" | "/config/" => {
+            snapshot_response(context, fields, |snapshot| ConfigResponse::from(snapshot))
+        }
+        "/status" | "/status/" => snapshot_response(context, fields, |snapshot| {
+            LockStatusResponse::from(snapshot)
+        }),
+        "/device" | "/device/" => json_response(&context.device, "device", fields),
+        "/snapshot" | "/snapshot/" => snapshot_response(context, fields, |snapshot| {
+            let device = context
+                .device
+                .read()
+                .unwrap()
+                .as_ref()
+                .map(ServedDevice::response);
+
+            SnapshotResponse {
+                device,
+                ..snapshot.into()
+            }
+        }),
+        "/startup" | "/startup/" => json_response(&context.startup, "startup", fields),
+        "/schema" | "/schema/" => json_body(&dto::schemas(), fields),
+        "/version" | "/version/" => json_body(&*context.version.read().unwrap(), fields),
+        "/events/history" | "/events/history/" => match context.journal.entries() {
+            Ok(entries) => json_body(&entries, fields),
+            Err(error) => Response::from_string(format!("Failed to read journal: {}", error))
+                .with_status_code(500),
+        },
+        #[cfg(feature = "write")]
+        "/audit" | "/audit/" => match context.audit.entries() {
+            Ok(entries) => json_body(&entries, fields),
+            Err(error) => Response::from_string(format!("Failed to read audit log: {}", error))
+                .with_status_code(500),
+        },
+        "/poll" | "/poll/" if *request.method() == Method::Post => {
+            context.poll_trigger.trigger();
+            Response::from_string("").with_status_code(204)
+        }
+        "/poll" | "/poll/" => Response::from_string("Method Not Allowed").with_status_code(405),
+        "/maintenance" | "/maintenance/" => maintenance_response(context, &request, &url),
+        "/remotes" | "/remotes/" => match &context.remotes {
+            Some(remotes) => json_body(&remotes.readings(), fields),
+            None => Response::from_string("Not Found").with_status_code(404),
+        },
+        "/diagnostics" | "/diagnostics/" => match &context.diagnosis {
+            Some(diagnosis) => json_response(diagnosis, "diagnosis", fields),
+            None => Response::from_string("Not Found").with_status_code(404),
+        },
+        path if path == "/profiles" || path.starts_with("/profiles/") => {
+            profiles_response(context, &request, path, fields)
+        }
+        "/metrics" | "/metrics/" => match context.metrics.encode() {
+            Ok(buffer) => Response::from_data(buffer).with_header(
+                Header::from_bytes("Content-Type", TextEncoder::new().format_type())
+                    .expect("failed to set Content-Type header"),
+            ),
+            Err(error) => {
+                tracing::error!("failed to encode metrics: {}", error);
+
+                Response::from_data("Failed to encode metrics").with_status_code(500)
+            }
+        },
+
+        _ => Response::from_string("Not Found").with_status_code(404),
+    };
+
+    if let Err(error) = request.respond(response) {
+        tracing::warn!("failed to respond to http request: {}", error);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use leo_status_driver::{
+        sim::{GpsdoSimulator, Scenario},
+        GpsdoDevice,
+    };
+    use serde_json::json;
+    use tiny_http::Response;
+    #[cfg(feature = "write")]
+    use tiny_http::{Method, TestRequest};
+
+    #[cfg(feature = "write")]
+    use super::forbidden_write;
+    use super::{select_fields, DeviceQueue, DEVICE_QUEUE_LENGTH};
+    use crate::{
+        poll::{PolledDevice, PolledInterface},
+        schedule::{PollTrigger, Wake},
+        supervisor::Heartbeat,
+    };
+
+    #[cfg(feature = "write")]
+    fn request(method: Method, path: &str) -> tiny_http::Request {
+        TestRequest::new()
+            .with_method(method)
+            .with_path(path)
+            .into()
+    }
+
+    #[test]
+    #[cfg(feature = "write")]
+    fn unauthenticated_profile_applies_are_refused() {
+        let apply = request(Method::Post, "/profiles/lab/apply");
+
+        let response = forbidden_write(&apply, false, false).expect("the apply should be refused");
+        assert_eq!(response.status_code().0, 403);
+
+        assert!(forbidden_write(&apply, true, false).is_none());
+        assert!(forbidden_write(&apply, false, true).is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "write")]
+    fn reads_are_not_refused_without_authentication() {
+        assert!(forbidden_write(&request(Method::Get, "/profiles/lab"), false, false).is_none());
+        assert!(forbidden_write(&request(Method::Get, "/profiles"), false, false).is_none());
+        assert!(forbidden_write(&request(Method::Post, "/poll"), false, false).is_none());
+    }
+
+    /// A device to run requests on, as the poll loop would
+    fn polled_device() -> PolledDevice {
+        let simulator = Box::leak(Box::new(GpsdoSimulator::new(Scenario::new(vec![]))));
+
+        GpsdoDevice::new(PolledInterface::Simulator(simulator))
+    }
+
+    #[test]
+    fn device_queue_answers_503_once_full_and_drops_expired_requests() {
+        let trigger = PollTrigger::default();
+        let (queue, jobs) = DeviceQueue::new(Duration::from_millis(50), trigger.clone());
+        let started = Arc::new(AtomicUsize::new(0));
+
+        // Nothing runs the requests while the poll loop is held up, such as by a GPSDO which stops responding
+        for _ in 0..DEVICE_QUEUE_LENGTH {
+            let started = started.clone();
+            let response = queue.handle(move |_| {
+                started.fetch_add(1, Ordering::SeqCst);
+                Response::from_string("")
+            });
+            assert_eq!(response.status_code().0, 504);
+        }
+
+        let response = queue.handle(|_| Response::from_string(""));
+        assert_eq!(response.status_code().0, 503);
+
+        // The poll loop drops the expired requests rather than running them
+        let wake = trigger.wait(Some(Instant::now()), &Heartbeat::new());
+        assert_eq!(wake, Wake::Jobs);
+        jobs.run(&polled_device());
+        assert_eq!(started.load(Ordering::SeqCst), 0);
+
+        // The queue has room again, and the next request runs on the poll loop's device
+        let poll_loop = thread::spawn(move || {
+            let gpsdo = polled_device();
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while trigger.wait(Some(deadline), &Heartbeat::new()) != Wake::Jobs {}
+            jobs.run(&gpsdo);
+        });
+        let response = queue.handle(|gpsdo| match gpsdo.serial_number() {
+            Ok(_) => Response::from_string("answered"),
+            Err(_) => Response::from_string("").with_status_code(502),
+        });
+        poll_loop.join().unwrap();
+        assert_eq!(response.status_code().0, 200);
+    }
+
+    #[test]
+    fn device_queue_requests_are_refused_without_a_device() {
+        let trigger = PollTrigger::default();
+        let (queue, jobs) = DeviceQueue::new(Duration::from_secs(5), trigger.clone());
+
+        let reconnecting = thread::spawn(move || {
+            while trigger.wait(None, &Heartbeat::new()) != Wake::Jobs {}
+            jobs.refuse();
+        });
+        let response = queue.handle(|_| Response::from_string("answered"));
+        reconnecting.join().unwrap();
+
+        assert_eq!(response.status_code().0, 503);
+    }
+
+    #[test]
+    fn select_fields_keeps_the_fields_selected() {
+        let value = json!({"locked": true, "loss_count": 3, "status": {"locked": true, "sat_locked": false}});
+
+        assert_eq!(
+            select_fields(value, &["loss_count", "status.sat_locked"]),
+            json!({"loss_count": 3, "status": {"sat_locked": false}})
+        );
+    }
+
+    #[test]
+    fn select_fields_trims_each_item_of_a_list() {
+        let value = json!([{"id": 1, "name": "a"}, {"id": 2, "name": "b"}]);
+
+        assert_eq!(select_fields(value, &["id"]), json!([{"id": 1}, {"id": 2}]));
+    }
+
+    #[test]
+    fn select_fields_leaves_out_missing_fields() {
+        let value = json!({"locked": true, "status": {"locked": true}});
+
+        assert_eq!(
+            select_fields(value, &["missing", "status.missing", "locked.missing"]),
+            json!({})
+        );
+    }
+
+    #[test]
+    fn select_fields_leaves_out_list_indexes() {
+        let value = json!({"status": {"required": ["locked", "loss_count"]}});
+
+        assert_eq!(
+            select_fields(value.clone(), &["status.required.0"]),
+            json!({})
+        );
+        assert_eq!(
+            select_fields(value, &["status.required", "status.required.0"]),
+            json!({"status": {"required": ["locked", "loss_count"]}})
+        );
+    }
+
+    #[test]
+    fn select_fields_keeps_the_widest_of_overlapping_fields() {
+        let value = json!({"status": {"locked": true, "sat_locked": false}});
+        let whole = json!({"status": {"locked": true, "sat_locked": false}});
+
+        assert_eq!(
+            select_fields(value.clone(), &["status", "status.locked"]),
+            whole
+        );
+        assert_eq!(select_fields(value, &["status.locked", "status"]), whole);
+    }
+}
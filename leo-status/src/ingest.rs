@@ -0,0 +1,200 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tiny_http::Request;
+
+use std::{
+    collections::BTreeMap,
+    io::Read,
+    sync::{Arc, RwLock},
+};
+
+use crate::{
+    auth::{AuthProvider, StaticTokens},
+    dto::LockStatusResponse,
+    metrics::Metrics,
+    time::Timestamp,
+};
+
+/// The most remote instances kept, so pushes from an unbounded number of names can't exhaust memory
+const MAX_REMOTES: usize = 256;
+
+/// The largest body accepted by the ingest endpoint
+const MAX_BODY_LENGTH: u64 = 64 * 1024;
+
+/// The readings pushed by other leo-status instances to `POST /ingest`, keyed by instance, so one daemon can serve
+/// the lock state of a small fleet
+#[derive(Clone)]
+pub(crate) struct Remotes {
+    readings: Arc<RwLock<BTreeMap<String, RemoteReading>>>,
+    metrics: Metrics,
+
+    /// The tokens given with `--ingest-token`, each naming the instance which pushes with it, `None` if pushes
+    /// authenticate with the `--auth-*` providers instead
+    tokens: Option<Arc<StaticTokens>>,
+}
+
+/// A reading as its webhook sink delivers it, see `dto::SnapshotResponse`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct PushedSnapshot {
+    /// When the remote read its GPSDO
+    timestamp: String,
+    status: LockStatusResponse,
+
+    /// The config, kept as it was pushed
+    config: Value,
+}
+
+/// The latest reading pushed by a remote instance
+#[derive(Serialize, Debug, Clone)]
+struct RemoteReading {
+    /// When the reading was pushed, in RFC 3339 format
+    received: Timestamp,
+
+    #[serde(flatten)]
+    snapshot: PushedSnapshot,
+}
+
+impl Remotes {
+    pub(crate) fn new(metrics: Metrics, tokens: Vec<(String, String)>) -> Self {
+        Remotes {
+            readings: Arc::new(RwLock::new(BTreeMap::new())),
+            metrics,
+            tokens: (!tokens.is_empty()).then(|| Arc::new(StaticTokens::named(tokens))),
+        }
+    }
+
+    /// Whether pushes authenticate with `--ingest-token` rather than the `--auth-*` providers
+    pub(crate) fn has_tokens(&self) -> bool {
+        self.tokens.is_some()
+    }
+
+    /// The latest reading of every remote instance, keyed by instance
+    pub(crate) fn readings(&self) -> Value {
+        let readings = self.readings.read().expect("failed to get remotes lock");
+
+        serde_json::to_value(&*readings).expect("failed to serialize remotes")
+    }
+
+    /// Handle `POST /ingest`, storing the reading in the body as that of the instance the push authenticated as: the
+    /// name of its `--ingest-token`, or otherwise `identity`, who the `--auth-*` providers authenticated it as. A push
+    /// which authenticated as neither is refused, so nobody can report, or overwrite, the lock state of an instance.
+    pub(crate) fn ingest(
+        &self,
+        request: &mut Request,
+        identity: Option<String>,
+    ) -> Result<(), (u16, String)> {
+        let instance = match (&self.tokens, identity) {
+            (Some(tokens), _) => tokens
+                .authenticate(request)
+                .map_err(|error| (401, format!("Unauthorized - {}", error)))?,
+            (None, Some(identity)) => identity,
+            (None, None) => {
+                return Err((
+                    403,
+                    "Forbidden - pushes need an --ingest-token or an --auth-* provider".to_owned(),
+                ))
+            }
+        };
+
+        let mut body = vec![];
+        request
+            .as_reader()
+            .take(MAX_BODY_LENGTH + 1)
+            .read_to_end(&mut body)
+            .map_err(|error| (400, format!("failed to read body: {}", error)))?;
+        if body.len() as u64 > MAX_BODY_LENGTH {
+            return Err((413, "body is too large".to_owned()));
+        }
+
+        let snapshot: PushedSnapshot = serde_json::from_slice(&body)
+            .map_err(|error| (400, format!("invalid reading: {}", error)))?;
+
+        let mut readings = self.readings.write().expect("failed to get remotes lock");
+        if !readings.contains_key(&instance) && readings.len() >= MAX_REMOTES {
+            return Err((507, format!("already tracking {} instances", MAX_REMOTES)));
+        }
+
+        let received = Timestamp::now();
+        self.metrics
+            .set_remote_status(&instance, &snapshot.status, received);
+        readings.insert(instance, RemoteReading { received, snapshot });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tiny_http::{Header, Method, Request, TestRequest};
+
+    use super::Remotes;
+    use crate::metrics::{Metrics, StaleMetricsPolicy};
+
+    const READING: &str = r#"{"timestamp": "2024-01-01T00:00:00Z", "status": {"loss_count": 0, "sat_lock": true, "pll_lock": true, "locked": true}, "config": {}}"#;
+
+    fn push(path: &str, token: Option<&str>) -> Request {
+        let request = TestRequest::new()
+            .with_method(Method::Post)
+            .with_path(path)
+            .with_body(READING);
+        match token {
+            Some(token) => request.with_header(
+                Header::from_bytes("Authorization", format!("Bearer {}", token))
+                    .expect("valid header"),
+            ),
+            None => request,
+        }
+        .into()
+    }
+
+    fn remotes(tokens: &[(&str, &str)]) -> Remotes {
+        let tokens = tokens
+            .iter()
+            .map(|(name, token)| (name.to_string(), token.to_string()))
+            .collect();
+
+        Remotes::new(Metrics::new(StaleMetricsPolicy::default()), tokens)
+    }
+
+    #[test]
+    fn unauthenticated_pushes_are_refused() {
+        let without_auth = remotes(&[]);
+        let error = without_auth
+            .ingest(&mut push("/ingest?instance=site-a", None), None)
+            .unwrap_err();
+        assert_eq!(error.0, 403);
+
+        // Given ingest tokens, pushes must present one even if they authenticated otherwise
+        let with_tokens = remotes(&[("site-a", "secret")]);
+        for token in [None, Some("guess")] {
+            let error = with_tokens
+                .ingest(&mut push("/ingest", token), Some("alice".to_owned()))
+                .unwrap_err();
+            assert_eq!(error.0, 401);
+        }
+        assert_eq!(with_tokens.readings(), serde_json::json!({}));
+    }
+
+    #[test]
+    fn pushes_are_named_by_who_authenticated() {
+        let instances = |remotes: &Remotes| -> Vec<String> {
+            let readings = remotes.readings();
+            readings.as_object().unwrap().keys().cloned().collect()
+        };
+
+        let with_tokens = remotes(&[("site-a", "secret-a"), ("site-b", "secret-b")]);
+        with_tokens
+            .ingest(&mut push("/ingest?instance=site-a", Some("secret-b")), None)
+            .unwrap();
+        assert_eq!(instances(&with_tokens), ["site-b"]);
+
+        let with_auth = remotes(&[]);
+        with_auth
+            .ingest(
+                &mut push("/ingest?instance=site-a", None),
+                Some("alice".to_owned()),
+            )
+            .unwrap();
+        assert_eq!(instances(&with_auth), ["alice"]);
+    }
+}
@@ -0,0 +1,156 @@
+use leo_status_driver::GpsdoDeviceInfo;
+use thiserror::Error;
+
+use std::{
+    fs::{self, File, OpenOptions, TryLockError},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// The lock files this process holds, keyed by device, kept open until it exits
+static HELD: Mutex<Vec<(String, File)>> = Mutex::new(vec![]);
+
+#[derive(Debug, Error)]
+/// An error which stopped this process from locking a GPSDO for itself
+pub(crate) enum InstanceLockError {
+    #[error(
+        "gpsdo {device} is already in use by another leo-status, process {}, see lock file {}",
+        holder.as_deref().unwrap_or("unknown"),
+        path.display()
+    )]
+    Held {
+        device: String,
+        holder: Option<String>,
+        path: PathBuf,
+    },
+
+    #[error("failed to lock gpsdo {device} with lock file {}: {source}", path.display())]
+    Io {
+        device: String,
+        path: PathBuf,
+        source: io::Error,
+    },
+}
+
+/// Take the advisory lock on a GPSDO, keyed by its serial number, or its path if it has none, so two leo-status
+/// processes can't use the same device at once. The lock file in `dir` holds the id of the process holding it, and
+/// the lock is held until this process exits or calls `unlock`. Returns whether the lock was taken by this call, rather
+/// than already held.
+pub(crate) fn lock(dir: &Path, info: &GpsdoDeviceInfo) -> Result<bool, InstanceLockError> {
+    let device = device_key(info);
+
+    let mut held = HELD.lock().unwrap();
+    if held.iter().any(|(key, _)| *key == device) {
+        return Ok(false);
+    }
+
+    let path = dir.join(format!("leo-status-{}.lock", file_name_key(&device)));
+    let io_error = |source| InstanceLockError::Io {
+        device: device.clone(),
+        path: path.clone(),
+        source,
+    };
+
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)
+        .map_err(io_error)?;
+
+    match file.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => {
+            let holder = fs::read_to_string(&path)
+                .ok()
+                .map(|holder| holder.trim().to_owned())
+                .filter(|holder| !holder.is_empty());
+
+            return Err(InstanceLockError::Held {
+                device,
+                holder,
+                path,
+            });
+        }
+        Err(TryLockError::Error(error)) => return Err(io_error(error)),
+    }
+
+    file.set_len(0)
+        .and_then(|_| writeln!(file, "{}", std::process::id()))
+        .map_err(io_error)?;
+
+    held.push((device, file));
+    Ok(true)
+}
+
+/// Release the lock on a GPSDO taken by `lock`, such as when it couldn't be opened after all. The lock file is left in
+/// place for the next process to lock.
+pub(crate) fn unlock(info: &GpsdoDeviceInfo) {
+    let device = device_key(info);
+    HELD.lock().unwrap().retain(|(key, _)| *key != device);
+}
+
+/// The device a lock is keyed by
+fn device_key(info: &GpsdoDeviceInfo) -> String {
+    info.serial_number().unwrap_or(info.path()).to_owned()
+}
+
+/// A device key usable in a file name, with anything other than letters, digits, `-` and `_` replaced, so a path such
+/// as `/dev/hidraw0` can't escape the lock directory
+fn file_name_key(device: &str) -> String {
+    device
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{lock, unlock};
+
+    use leo_status_driver::GpsdoDeviceInfo;
+
+    use std::fs::{self, File, TryLockError};
+
+    #[test]
+    fn devices_are_locked_until_unlocked() {
+        let dir =
+            std::env::temp_dir().join(format!("leo-status-test-instance-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("failed to create test directory");
+
+        let info = GpsdoDeviceInfo::new(
+            0x1dd2,
+            0x2210,
+            Some("TEST/1".to_owned()),
+            "/dev/hidraw9".to_owned(),
+            None,
+            0,
+        );
+        let path = dir.join("leo-status-TEST_1.lock");
+
+        assert!(lock(&dir, &info).expect("failed to lock"));
+        assert!(!lock(&dir, &info).expect("failed to lock again"));
+        assert_eq!(
+            fs::read_to_string(&path).expect("failed to read lock file"),
+            format!("{}\n", std::process::id())
+        );
+
+        // Another open of the lock file, as by another process, can't take the lock while it is held
+        let other = File::open(&path).expect("failed to open lock file");
+        assert!(matches!(other.try_lock(), Err(TryLockError::WouldBlock)));
+
+        unlock(&info);
+        other.try_lock().expect("lock was released");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,245 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use std::{io, sync::Arc};
+
+use crate::{
+    dto::ConfigResponse,
+    events::{LockEventKind, LockTracker},
+    history::HistoryStore,
+    snapshot::Snapshot,
+    time::Timestamp,
+};
+
+/// An append-only journal of discrete events, such as lock transitions and reconnects, so there is an authoritative
+/// timeline for post-incident review
+#[derive(Debug, Clone)]
+pub(crate) struct Journal {
+    /// Where the entries are kept, `None` if the journal is disabled, such as with `--low-resource`
+    store: Option<Arc<dyn HistoryStore>>,
+}
+
+/// What happened, as recorded in the journal
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum JournalEventKind {
+    DaemonStarted,
+    DeviceOpened,
+    DeviceLost,
+    ConfigChanged,
+    LockAcquired,
+    LockLost,
+    SatLockAcquired,
+    SatLockLost,
+    PllLockAcquired,
+    PllLockLost,
+    ClockOffsetWhileUnlocked,
+    DiagnosisChanged,
+    MaintenanceStarted,
+    MaintenanceEnded,
+}
+
+impl JournalEventKind {
+    /// The journal event for a lock transition, `None` for the snapshot sent to new event stream clients
+    fn from_lock_event(kind: LockEventKind) -> Option<Self> {
+        match kind {
+            LockEventKind::Snapshot => None,
+            LockEventKind::LockAcquired => Some(JournalEventKind::LockAcquired),
+            LockEventKind::LockLost => Some(JournalEventKind::LockLost),
+            LockEventKind::SatLockAcquired => Some(JournalEventKind::SatLockAcquired),
+            LockEventKind::SatLockLost => Some(JournalEventKind::SatLockLost),
+            LockEventKind::PllLockAcquired => Some(JournalEventKind::PllLockAcquired),
+            LockEventKind::PllLockLost => Some(JournalEventKind::PllLockLost),
+        }
+    }
+}
+
+/// A field which changed between two configs read from the GPSDO
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub(crate) struct ConfigChange {
+    /// The dotted path of the field, such as `pll_params.nc1_ls`
+    pub(crate) field: String,
+
+    /// The value before the change, `null` if there was none
+    pub(crate) before: Value,
+
+    /// The value after the change, `null` if there is none
+    pub(crate) after: Value,
+}
+
+/// One event, as recorded in the journal
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct JournalEntry {
+    /// When the event happened, in RFC 3339 format
+    timestamp: String,
+
+    event: JournalEventKind,
+
+    /// More about the event, such as the error which lost the device or the fields of a config change
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+
+    /// The old and new value of each field of a config change
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    changes: Vec<ConfigChange>,
+
+    /// The poll cycle of the reading the event was seen in, see `LockStatusResponse::poll_seq`. Numbers restart with
+    /// the daemon, so they only match responses from the run which recorded the event.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    poll_seq: Option<u64>,
+}
+
+impl Journal {
+    pub(crate) fn new(store: Arc<dyn HistoryStore>) -> Self {
+        Journal { store: Some(store) }
+    }
+
+    /// A journal which records nothing and has no entries
+    pub(crate) fn disabled() -> Self {
+        Journal { store: None }
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.store.is_some()
+    }
+
+    /// Append an event to the journal. A failure to record it is logged rather than stopping the daemon.
+    pub(crate) fn record(
+        &self,
+        timestamp: Timestamp,
+        event: JournalEventKind,
+        detail: Option<String>,
+    ) {
+        self.record_entry(JournalEntry {
+            timestamp: timestamp.format(),
+            event,
+            detail,
+            changes: vec![],
+            poll_seq: None,
+        });
+    }
+
+    /// Append an event seen in a reading of the GPSDO to the journal, with the time and poll cycle of the reading
+    pub(crate) fn record_snapshot(
+        &self,
+        snapshot: &Snapshot,
+        event: JournalEventKind,
+        detail: Option<String>,
+    ) {
+        self.record_entry(JournalEntry {
+            timestamp: snapshot.timestamp.format(),
+            event,
+            detail,
+            changes: vec![],
+            poll_seq: Some(snapshot.poll_seq),
+        });
+    }
+
+    /// Append a config change seen in a reading of the GPSDO to the journal, with the changed fields as its detail
+    fn record_config_change(&self, snapshot: &Snapshot, changes: Vec<ConfigChange>) {
+        let fields: Vec<&str> = changes.iter().map(|change| change.field.as_str()).collect();
+
+        self.record_entry(JournalEntry {
+            timestamp: snapshot.timestamp.format(),
+            event: JournalEventKind::ConfigChanged,
+            detail: Some(fields.join(", ")),
+            changes,
+            poll_seq: Some(snapshot.poll_seq),
+        });
+    }
+
+    fn record_entry(&self, entry: JournalEntry) {
+        let Some(store) = &self.store else {
+            return;
+        };
+
+        if let Err(error) = store.append(&entry) {
+            tracing::error!(
+                "failed to record event in journal {}: {}",
+                store.location(),
+                error
+            );
+        }
+    }
+
+    /// Read every entry in the journal, oldest first
+    pub(crate) fn entries(&self) -> io::Result<Vec<JournalEntry>> {
+        match &self.store {
+            Some(store) => store.entries(),
+            None => Ok(vec![]),
+        }
+    }
+}
+
+/// Finds the events in successive snapshots which belong in the journal
+#[derive(Default)]
+pub(crate) struct JournalTracker {
+    lock: LockTracker,
+    config: Option<Value>,
+}
+
+impl JournalTracker {
+    /// Record the lock transitions of a snapshot, and any change in the config since the previous snapshot
+    pub(crate) fn update(&mut self, journal: &Journal, snapshot: &Snapshot) {
+        for lock_event in self.lock.update(&snapshot.status, snapshot.timestamp, None) {
+            if let Some(event) = JournalEventKind::from_lock_event(lock_event.event) {
+                journal.record_snapshot(snapshot, event, None);
+            }
+        }
+
+        let config = config_value(snapshot);
+        if let Some(previous) = self.config.replace(config.clone()) {
+            let changes = config_changes(&previous, &config);
+
+            if !changes.is_empty() {
+                journal.record_config_change(snapshot, changes);
+            }
+        }
+    }
+}
+
+/// The config read in a snapshot as served, without the reading's poll cycle, for comparing with `config_changes`
+pub(crate) fn config_value(snapshot: &Snapshot) -> Value {
+    serde_json::to_value(ConfigResponse::from(&snapshot.config))
+        .expect("failed to serialize config")
+}
+
+/// The leaf fields which differ between two configs, as given by `config_value`, in order of their paths
+pub(crate) fn config_changes(before: &Value, after: &Value) -> Vec<ConfigChange> {
+    let mut changes = vec![];
+    changed_fields("", before, after, &mut changes);
+
+    changes
+}
+
+/// Collect the leaf fields which differ between two JSON values
+fn changed_fields(path: &str, before: &Value, after: &Value, changed: &mut Vec<ConfigChange>) {
+    match (before, after) {
+        (Value::Object(before), Value::Object(after)) => {
+            let mut keys: Vec<&String> = before.keys().chain(after.keys()).collect();
+            keys.sort();
+            keys.dedup();
+
+            for key in keys {
+                let field = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+
+                changed_fields(
+                    &field,
+                    before.get(key).unwrap_or(&Value::Null),
+                    after.get(key).unwrap_or(&Value::Null),
+                    changed,
+                );
+            }
+        }
+        _ if before != after => changed.push(ConfigChange {
+            field: path.to_owned(),
+            before: before.clone(),
+            after: after.clone(),
+        }),
+        _ => {}
+    }
+}
@@ -0,0 +1,77 @@
+#[cfg(feature = "hidapi")]
+use hidapi::HidApi;
+#[cfg(feature = "hidapi")]
+use leo_status_driver::interface::GpsdoHidApiInterface;
+#[cfg(all(feature = "nusb", not(feature = "hidapi")))]
+use leo_status_driver::interface::GpsdoNusbInterface;
+use leo_status_driver::GpsdoDeviceInfo;
+
+use crate::{
+    exit::ExitCode,
+    messages::{self, Message},
+    Args,
+};
+
+/// List the GPSDOs attached to the host which match the command line arguments, including those which can't be opened
+pub(crate) fn list(args: &Args) {
+    let probes = probe(args.serial_number.as_deref());
+    if probes.is_empty() {
+        eprintln!("{}", messages::text(Message::NotFound));
+        ExitCode::DeviceNotFound.exit();
+    }
+
+    for (info, error) in probes {
+        let unknown = messages::text(Message::Unknown);
+        let access = match error {
+            None => messages::text(Message::Accessible).to_owned(),
+            Some(error) => format!("{}: {}", messages::text(Message::NotAccessible), error),
+        };
+
+        println!(
+            "{}\t{} {}\t{} {}\t{}",
+            info.path(),
+            messages::text(Message::SerialNumber),
+            info.serial_number().unwrap_or(unknown),
+            messages::text(Message::Port),
+            info.port_path().unwrap_or(unknown),
+            access
+        );
+    }
+}
+
+/// Find every matching GPSDO and try to open it, with the error which stopped it being opened if it is not accessible
+#[cfg(feature = "hidapi")]
+fn probe(serial_number: Option<&str>) -> Vec<(GpsdoDeviceInfo, Option<String>)> {
+    let hid_api = HidApi::new().unwrap_or_else(|error| {
+        eprintln!("failed to create hidapi context: {}", error);
+        ExitCode::Failure.exit();
+    });
+
+    GpsdoHidApiInterface::probe(&hid_api, serial_number)
+        .into_iter()
+        .map(|probe| {
+            let error = probe.error().map(ToString::to_string);
+            (probe.info().clone(), error)
+        })
+        .collect()
+}
+
+/// Find every matching GPSDO and try to open it, with the error which stopped it being opened if it is not accessible
+#[cfg(all(feature = "nusb", not(feature = "hidapi")))]
+fn probe(serial_number: Option<&str>) -> Vec<(GpsdoDeviceInfo, Option<String>)> {
+    let devices = GpsdoNusbInterface::find_gpsdos(serial_number).unwrap_or_else(|error| {
+        eprintln!("failed to list usb devices: {}", error);
+        ExitCode::Failure.exit();
+    });
+
+    devices
+        .into_iter()
+        .map(|device| {
+            let info = GpsdoNusbInterface::device_info(&device);
+            let error = GpsdoNusbInterface::new(device)
+                .err()
+                .map(|error| error.to_string());
+            (info, error)
+        })
+        .collect()
+}
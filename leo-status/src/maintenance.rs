@@ -0,0 +1,249 @@
+use chrono::Utc;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use crate::{
+    journal::{Journal, JournalEventKind},
+    metrics::Metrics,
+    schedule::PollWindow,
+    snapshot::Snapshot,
+    time::Timestamp,
+};
+
+/// Planned maintenance, such as antenna work, during which the GPSDO is still polled but its lock losses aren't
+/// alerted on or counted against its availability. It is in progress within any `--maintenance-window`, and while
+/// started with `POST /maintenance`.
+#[derive(Debug, Clone)]
+pub(crate) struct Maintenance {
+    windows: Vec<PollWindow>,
+
+    /// When the maintenance started with `POST /maintenance` ends, if any was started
+    until: Arc<RwLock<Option<Timestamp>>>,
+}
+
+/// Whether maintenance is in progress, served at `/maintenance`
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub(crate) struct MaintenanceResponse {
+    /// Whether maintenance is in progress, so alerting and availability accounting are suspended
+    active: bool,
+
+    /// When the maintenance started with `POST /maintenance` ends, in RFC 3339 format, `null` if none is in progress
+    #[schemars(with = "Option<String>")]
+    until: Option<Timestamp>,
+
+    /// The daily maintenance windows, as HH:MM-HH:MM in UTC
+    windows: Vec<String>,
+}
+
+impl Maintenance {
+    pub(crate) fn new(windows: Vec<PollWindow>) -> Self {
+        Maintenance {
+            windows,
+            until: Arc::default(),
+        }
+    }
+
+    /// Whether maintenance is in progress now
+    pub(crate) fn active(&self) -> bool {
+        let now = Utc::now().time();
+
+        self.started_until().is_some() || self.windows.iter().any(|window| window.contains(now))
+    }
+
+    /// Start maintenance now, lasting for `duration`
+    pub(crate) fn start(&self, duration: Duration) {
+        *self.until.write().unwrap() = Some(Timestamp::now().after(duration));
+    }
+
+    /// End the maintenance started with `start`, leaving the maintenance windows as they are
+    pub(crate) fn end(&self) {
+        *self.until.write().unwrap() = None;
+    }
+
+    pub(crate) fn response(&self) -> MaintenanceResponse {
+        MaintenanceResponse {
+            active: self.active(),
+            until: self.started_until(),
+            windows: self.windows.iter().map(PollWindow::to_string).collect(),
+        }
+    }
+
+    /// When the maintenance started with `start` ends, `None` if it has ended
+    fn started_until(&self) -> Option<Timestamp> {
+        let now = Timestamp::now();
+
+        self.until
+            .read()
+            .unwrap()
+            .filter(|until| !until.duration_since(&now).is_zero())
+    }
+}
+
+/// A clock which stops during maintenance, timing spells such as holdover by the snapshots taken outside it, so a spell
+/// which spans maintenance isn't credited with the time accounting was suspended
+#[derive(Debug, Default)]
+pub(crate) struct AccountedTime {
+    elapsed: Duration,
+
+    /// When the previous snapshot outside maintenance was taken, `None` before the first and during maintenance, so
+    /// the clock starts again from the first snapshot after it
+    previous: Option<Timestamp>,
+}
+
+impl AccountedTime {
+    /// The time elapsed outside maintenance as of a snapshot taken at `timestamp`, `None` if it was taken during
+    /// maintenance
+    pub(crate) fn update(&mut self, timestamp: Timestamp, maintenance: bool) -> Option<Duration> {
+        if maintenance {
+            self.previous = None;
+            return None;
+        }
+
+        if let Some(previous) = self.previous.replace(timestamp) {
+            self.elapsed += timestamp.duration_since(&previous);
+        }
+
+        Some(self.elapsed)
+    }
+}
+
+/// Follows maintenance starting and ending between successive snapshots
+#[derive(Default)]
+pub(crate) struct MaintenanceTracker {
+    active: bool,
+}
+
+impl MaintenanceTracker {
+    /// Record whether maintenance is in progress as of a snapshot, logging and journaling it starting or ending
+    pub(crate) fn update(
+        &mut self,
+        maintenance: &Maintenance,
+        journal: &Journal,
+        metrics: &Metrics,
+        snapshot: &Snapshot,
+    ) {
+        let active = maintenance.active();
+        metrics.maintenance.set(active.into());
+
+        if active == self.active {
+            return;
+        }
+        self.active = active;
+
+        let event = if active {
+            tracing::info!("maintenance started, suspending alerting and availability accounting");
+            JournalEventKind::MaintenanceStarted
+        } else {
+            tracing::info!("maintenance ended");
+            JournalEventKind::MaintenanceEnded
+        };
+        journal.record_snapshot(snapshot, event, None);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AccountedTime, Maintenance};
+    use crate::{schedule::PollWindow, time::Timestamp};
+
+    use chrono::{TimeDelta, Utc};
+    use leo_status_driver::{tracker::HoldoverTracker, GpsdoStatus};
+
+    use std::time::Duration;
+
+    /// A daily window from `start` to `end` minutes after now, which spans midnight where now is close to it
+    fn window(start: i64, end: i64) -> PollWindow {
+        let now = Utc::now().time();
+        let at = |minutes| {
+            (now + TimeDelta::minutes(minutes))
+                .format("%H:%M")
+                .to_string()
+        };
+
+        format!("{}-{}", at(start), at(end))
+            .parse()
+            .expect("valid window")
+    }
+
+    #[test]
+    fn maintenance_is_active_until_it_ends() {
+        let maintenance = Maintenance::new(vec![]);
+        assert!(!maintenance.active());
+
+        maintenance.start(Duration::from_secs(3600));
+        assert!(maintenance.active());
+        assert!(maintenance.response().until.is_some());
+
+        maintenance.end();
+        assert!(!maintenance.active());
+        assert!(maintenance.response().until.is_none());
+
+        // Maintenance which has run its course is over without being ended
+        maintenance.start(Duration::ZERO);
+        assert!(!maintenance.active());
+    }
+
+    #[test]
+    fn maintenance_is_active_within_its_windows() {
+        assert!(Maintenance::new(vec![window(-60, 60)]).active());
+        assert!(!Maintenance::new(vec![window(60, 120)]).active());
+        assert!(Maintenance::new(vec![window(60, 120), window(-5, 5)]).active());
+
+        // Ending the maintenance started with POST /maintenance leaves the windows in force
+        let maintenance = Maintenance::new(vec![window(-60, 60)]);
+        maintenance.end();
+        assert!(maintenance.active());
+    }
+
+    #[test]
+    fn accounted_time_stops_during_maintenance() {
+        let start = Timestamp::now();
+        let mut time = AccountedTime::default();
+
+        let mut update = |secs, maintenance| {
+            time.update(start.after(Duration::from_secs(secs)), maintenance)
+                .map(|elapsed| elapsed.as_secs())
+        };
+        assert_eq!(update(0, false), Some(0));
+        assert_eq!(update(10, false), Some(10));
+        assert_eq!(update(20, true), None);
+        assert_eq!(update(3600, true), None);
+
+        // The clock starts again from the first snapshot after maintenance
+        assert_eq!(update(3610, false), Some(10));
+        assert_eq!(update(3620, false), Some(20));
+    }
+
+    #[test]
+    fn holdover_spanning_maintenance_leaves_it_out() {
+        let holdover = GpsdoStatus::from_report_bytes(&[1, 0x01]).unwrap();
+        let locked = GpsdoStatus::from_report_bytes(&[1, 0x00]).unwrap();
+
+        let start = Timestamp::now();
+        let mut time = AccountedTime::default();
+        let mut tracker = HoldoverTracker::new();
+
+        // Holdover for 60s, then an hour of maintenance, then 30s more of holdover before the GPSDO locks again
+        for (secs, status, maintenance) in [
+            (0, &holdover, false),
+            (60, &holdover, false),
+            (120, &holdover, true),
+            (3660, &holdover, true),
+            (3720, &holdover, false),
+            (3750, &locked, false),
+        ] {
+            if let Some(elapsed) = time.update(start.after(Duration::from_secs(secs)), maintenance)
+            {
+                tracker.update(status, elapsed);
+            }
+        }
+
+        assert_eq!(tracker.longest_holdover(), Duration::from_secs(90));
+        assert_eq!(tracker.holdover_duration(), None);
+    }
+}
@@ -0,0 +1,162 @@
+use clap::ValueEnum;
+
+use std::sync::OnceLock;
+
+/// The language of the human-readable output, such as the device list and soak test reports. Machine-readable output,
+/// such as JSON and metrics, is the same in every language.
+#[derive(ValueEnum, Debug, Clone, Copy, Default)]
+pub(crate) enum Lang {
+    /// English
+    #[default]
+    En,
+
+    /// German
+    De,
+
+    /// Japanese
+    Ja,
+}
+
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+/// Set the language of human-readable output. This should be called once at startup, before any output is produced.
+pub(crate) fn init(lang: Lang) {
+    LANG.set(lang).expect("language has already been set");
+}
+
+/// The strings of the message catalog
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Message {
+    NotFound,
+    SerialNumber,
+    Port,
+    Unknown,
+    Accessible,
+    NotAccessible,
+    SoakTitle,
+    Device,
+    Started,
+    Finished,
+    Duration,
+    Reads,
+    Failed,
+    DeviceAvailability,
+    LockAvailability,
+    LossEvents,
+    ReadLatency,
+    Min,
+    Mean,
+    Max,
+    NoSuccessfulReads,
+    Outages,
+    Kind,
+    Start,
+    End,
+    Ongoing,
+    OutageDevice,
+    OutageLock,
+    NoOutages,
+    Time,
+    LossCount,
+    NoLossEvents,
+    FleetTarget,
+    FleetLocked,
+    FleetSatLock,
+    FleetPllLock,
+    FleetStale,
+    FleetYes,
+    FleetNo,
+    FleetUnlocked,
+    FleetStaleData,
+    FleetAllLocked,
+    VerifyCaptured,
+    VerifyMatches,
+    VerifyExpected,
+    VerifyFound,
+}
+
+/// Look up a message in the configured language
+pub(crate) fn text(message: Message) -> &'static str {
+    let (en, de, ja) = match message {
+        Message::NotFound => (
+            "could not find leo bodnar gpsdo",
+            "kein Leo Bodnar GPSDO gefunden",
+            "Leo Bodnar GPSDOが見つかりません",
+        ),
+        Message::SerialNumber => ("serial number", "Seriennummer", "シリアル番号"),
+        Message::Port => ("port", "Port", "ポート"),
+        Message::Unknown => ("unknown", "unbekannt", "不明"),
+        Message::Accessible => ("accessible", "zugänglich", "アクセス可能"),
+        Message::NotAccessible => (
+            "found but not accessible",
+            "gefunden, aber nicht zugänglich",
+            "検出されましたがアクセスできません",
+        ),
+        Message::SoakTitle => ("GPSDO soak test", "GPSDO-Dauertest", "GPSDO耐久試験"),
+        Message::Device => ("Device", "Gerät", "デバイス"),
+        Message::Started => ("Started", "Beginn", "開始"),
+        Message::Finished => ("Finished", "Ende", "終了"),
+        Message::Duration => ("Duration", "Dauer", "期間"),
+        Message::Reads => ("Reads", "Lesevorgänge", "読み取り回数"),
+        Message::Failed => ("failed", "fehlgeschlagen", "失敗"),
+        Message::DeviceAvailability => (
+            "Device availability",
+            "Geräteverfügbarkeit",
+            "デバイス可用性",
+        ),
+        Message::LockAvailability => ("Lock availability", "Lock-Verfügbarkeit", "ロック可用性"),
+        Message::LossEvents => ("Loss events", "Lock-Verluste", "ロック喪失"),
+        Message::ReadLatency => ("Read latency", "Leselatenz", "読み取り遅延"),
+        Message::Min => ("Min", "Min.", "最小"),
+        Message::Mean => ("Mean", "Mittel", "平均"),
+        Message::Max => ("Max", "Max.", "最大"),
+        Message::NoSuccessfulReads => (
+            "No successful reads.",
+            "Keine erfolgreichen Lesevorgänge.",
+            "成功した読み取りはありません。",
+        ),
+        Message::Outages => ("Outages", "Ausfälle", "障害"),
+        Message::Kind => ("Kind", "Art", "種類"),
+        Message::Start => ("Start", "Beginn", "開始"),
+        Message::End => ("End", "Ende", "終了"),
+        Message::Ongoing => ("ongoing", "andauernd", "継続中"),
+        Message::OutageDevice => ("device", "Gerät", "デバイス"),
+        Message::OutageLock => ("lock", "Lock", "ロック"),
+        Message::NoOutages => ("No outages.", "Keine Ausfälle.", "障害はありません。"),
+        Message::Time => ("Time", "Zeit", "時刻"),
+        Message::LossCount => ("Loss count", "Verlustzähler", "喪失回数"),
+        Message::NoLossEvents => (
+            "No loss events.",
+            "Keine Lock-Verluste.",
+            "ロック喪失はありません。",
+        ),
+        Message::FleetTarget => ("Target", "Ziel", "ターゲット"),
+        Message::FleetLocked => ("Locked", "Lock", "ロック"),
+        Message::FleetSatLock => ("Sat lock", "Sat-Lock", "衛星ロック"),
+        Message::FleetPllLock => ("PLL lock", "PLL-Lock", "PLLロック"),
+        Message::FleetStale => ("Stale", "Veraltet", "古いデータ"),
+        Message::FleetYes => ("yes", "ja", "はい"),
+        Message::FleetNo => ("no", "nein", "いいえ"),
+        Message::FleetUnlocked => ("not locked", "kein Lock", "ロックされていません"),
+        Message::FleetStaleData => (
+            "data is stale",
+            "Daten sind veraltet",
+            "データが古くなっています",
+        ),
+        Message::FleetAllLocked => (
+            "targets reachable and locked",
+            "Ziele erreichbar und mit Lock",
+            "件のターゲットすべてに到達でき、ロックされています",
+        ),
+        Message::VerifyCaptured => ("captured", "erfasst", "キャプチャしました:"),
+        Message::VerifyMatches => ("matches", "stimmt überein mit", "一致しました:"),
+        Message::VerifyExpected => ("expected", "erwartet", "期待値"),
+        Message::VerifyFound => ("found", "gefunden", "実際の値"),
+    };
+
+    match LANG.get().copied().unwrap_or_default() {
+        Lang::En => en,
+        Lang::De => de,
+        Lang::Ja => ja,
+    }
+}
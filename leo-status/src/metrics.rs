@@ -0,0 +1,477 @@
+use leo_status_driver::{
+    quality::{self, StatusSample},
+    state::DeviceState,
+    tracker::{HoldoverTracker, LossCounter},
+    FirmwareVersion, GpsdoConfig, GpsdoDeviceInfo, GpsdoStatus,
+};
+use prometheus::{
+    Encoder, Gauge, GaugeVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+
+use clap::ValueEnum;
+
+use std::{collections::VecDeque, io, path::Path, time::Duration};
+
+use crate::{dto::LockStatusResponse, persist, snapshot::Snapshot, time::Timestamp};
+
+/// The file written into the node_exporter textfile collector directory
+const TEXTFILE_NAME: &str = "leo_status.prom";
+
+/// How far back the status history used for the quality score goes
+const QUALITY_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// The lock gauges, which `StaleMetricsPolicy::Drop` stops exporting while the GPSDO is not responding
+const LOCK_METRICS: [&str; 3] = ["lock_status", "sat_lock_status", "pll_lock_status"];
+
+/// What happens to the lock gauges while the GPSDO is not responding
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum StaleMetricsPolicy {
+    /// Keep exporting the last values read, alongside `leo_gpsdo_data_stale`
+    #[default]
+    Freeze,
+
+    /// Stop exporting them, so Prometheus marks them stale rather than showing a lock the GPSDO may have lost
+    Drop,
+}
+
+/// The Prometheus registry and the metrics exposed by the daemon
+#[derive(Clone)]
+pub(crate) struct Metrics {
+    pub(crate) registry: Registry,
+    stale_policy: StaleMetricsPolicy,
+    pub(crate) lock_status: IntGauge,
+    pub(crate) sat_lock_status: IntGauge,
+    pub(crate) pll_lock_status: IntGauge,
+    pub(crate) reconnects: IntCounter,
+    losses: IntCounter,
+    pub(crate) data_stale: IntGauge,
+    state: IntGaugeVec,
+    pub(crate) quality_score: IntGauge,
+    holdover: Gauge,
+    longest_holdover: Gauge,
+    pub(crate) device_uptime: Gauge,
+    pub(crate) frequency_residual: GaugeVec,
+    pub(crate) clock_offset: GaugeVec,
+    pub(crate) clock_offset_unlocked_max: GaugeVec,
+    pub(crate) device_info: IntGaugeVec,
+    pub(crate) sink_sent: IntCounterVec,
+    pub(crate) sink_failures: IntCounterVec,
+    pub(crate) sink_dropped: IntCounterVec,
+    pub(crate) sink_queue_length: IntGaugeVec,
+    pub(crate) remote_lock_status: IntGaugeVec,
+    pub(crate) remote_loss_count: IntGaugeVec,
+    pub(crate) remote_last_push: GaugeVec,
+    pub(crate) update_available: IntGauge,
+    pub(crate) maintenance: IntGauge,
+    pub(crate) errors_suppressed: IntCounterVec,
+}
+
+impl Metrics {
+    pub(crate) fn new(stale_policy: StaleMetricsPolicy) -> Self {
+        let registry = Registry::new();
+        let lock_status = IntGauge::new("lock_status", "the status of the overall lock").unwrap();
+        let sat_lock_status =
+            IntGauge::new("sat_lock_status", "the status of the gps satellite lock").unwrap();
+        let pll_lock_status =
+            IntGauge::new("pll_lock_status", "the status of the pll lock").unwrap();
+        let reconnects = IntCounter::new(
+            "leo_gpsdo_reconnects_total",
+            "the number of times the gpsdo has been re-opened after an error",
+        )
+        .unwrap();
+        let losses = IntCounter::new(
+            "leo_gpsdo_losses_total",
+            "the number of times the gpsdo has lost lock, counted on across wraps and resets of its loss count",
+        )
+        .unwrap();
+        let data_stale = IntGauge::new(
+            "leo_gpsdo_data_stale",
+            "whether the gpsdo has stopped responding, so the other metrics are out of date",
+        )
+        .unwrap();
+        let state = IntGaugeVec::new(
+            Opts::new(
+                "leo_gpsdo_state",
+                "the state of the gpsdo in its lifecycle, 1 for the state it is in and 0 for the others",
+            ),
+            &["state"],
+        )
+        .unwrap();
+        let quality_score = IntGauge::new(
+            "leo_gpsdo_quality_score",
+            "how far the gpsdo can be trusted as a reference, from 0 to 100, over the last hour",
+        )
+        .unwrap();
+        let holdover = Gauge::new(
+            "leo_gpsdo_holdover_seconds",
+            "how long the gpsdo has been in holdover, with the pll locked but no gps lock, 0 when it isn't",
+        )
+        .unwrap();
+        let longest_holdover = Gauge::new(
+            "leo_gpsdo_longest_holdover_seconds",
+            "the longest the gpsdo has been in holdover since leo-status started",
+        )
+        .unwrap();
+        let device_uptime = Gauge::new(
+            "leo_gpsdo_device_uptime_seconds_estimate",
+            "an estimate of the seconds since the gpsdo booted, a lower bound until a reboot has been seen",
+        )
+        .unwrap();
+        let frequency_residual = GaugeVec::new(
+            Opts::new(
+                "leo_gpsdo_frequency_residual_hz",
+                "the fraction of a hz by which each output frequency is not a whole number of hz, 0 when it is exact",
+            ),
+            &["output"],
+        )
+        .unwrap();
+        let clock_offset = GaugeVec::new(
+            Opts::new(
+                "leo_clock_offset_seconds",
+                "the offset of the host clock from its reference, measured by each clock source, nan when it can't be measured",
+            ),
+            &["source"],
+        )
+        .unwrap();
+        let clock_offset_unlocked_max = GaugeVec::new(
+            Opts::new(
+                "leo_clock_offset_unlocked_max_seconds",
+                "the largest absolute offset of the host clock measured while the gpsdo was last unlocked",
+            ),
+            &["source"],
+        )
+        .unwrap();
+        let device_info = IntGaugeVec::new(
+            Opts::new(
+                "leo_gpsdo_device_info",
+                "information about the gpsdo in use and where it is attached",
+            ),
+            &[
+                "vendor_id",
+                "product_id",
+                "manufacturer",
+                "product",
+                "serial_number",
+                "path",
+                "port_path",
+                "interface_number",
+                "firmware_version",
+            ],
+        )
+        .unwrap();
+
+        registry.register(Box::new(lock_status.clone())).unwrap();
+        registry
+            .register(Box::new(sat_lock_status.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(pll_lock_status.clone()))
+            .unwrap();
+        registry.register(Box::new(reconnects.clone())).unwrap();
+        registry.register(Box::new(losses.clone())).unwrap();
+        registry.register(Box::new(data_stale.clone())).unwrap();
+        registry.register(Box::new(state.clone())).unwrap();
+        registry.register(Box::new(quality_score.clone())).unwrap();
+        registry.register(Box::new(holdover.clone())).unwrap();
+        registry
+            .register(Box::new(longest_holdover.clone()))
+            .unwrap();
+        registry.register(Box::new(device_uptime.clone())).unwrap();
+        registry
+            .register(Box::new(frequency_residual.clone()))
+            .unwrap();
+        registry.register(Box::new(clock_offset.clone())).unwrap();
+        registry
+            .register(Box::new(clock_offset_unlocked_max.clone()))
+            .unwrap();
+        let sink_sent = IntCounterVec::new(
+            Opts::new(
+                "leo_gpsdo_sink_sent_total",
+                "the number of snapshots delivered by each sink",
+            ),
+            &["sink"],
+        )
+        .unwrap();
+        let sink_failures = IntCounterVec::new(
+            Opts::new(
+                "leo_gpsdo_sink_failures_total",
+                "the number of snapshots each sink gave up delivering after retrying",
+            ),
+            &["sink"],
+        )
+        .unwrap();
+        let sink_dropped = IntCounterVec::new(
+            Opts::new(
+                "leo_gpsdo_sink_dropped_total",
+                "the number of snapshots dropped because the queue of the sink was full",
+            ),
+            &["sink"],
+        )
+        .unwrap();
+        let sink_queue_length = IntGaugeVec::new(
+            Opts::new(
+                "leo_gpsdo_sink_queue_length",
+                "the number of snapshots waiting to be delivered by each sink",
+            ),
+            &["sink"],
+        )
+        .unwrap();
+
+        let remote_lock_status = IntGaugeVec::new(
+            Opts::new(
+                "leo_remote_lock_status",
+                "the overall, satellite and pll lock status of the gpsdo of each remote instance pushing to the ingest endpoint",
+            ),
+            &["instance", "lock"],
+        )
+        .unwrap();
+        let remote_loss_count = IntGaugeVec::new(
+            Opts::new(
+                "leo_remote_loss_count",
+                "the number of lock losses of the gpsdo of each remote instance since it booted",
+            ),
+            &["instance"],
+        )
+        .unwrap();
+        let remote_last_push = GaugeVec::new(
+            Opts::new(
+                "leo_remote_last_push_timestamp_seconds",
+                "when each remote instance last pushed a reading, as a unix timestamp",
+            ),
+            &["instance"],
+        )
+        .unwrap();
+
+        registry.register(Box::new(device_info.clone())).unwrap();
+        registry.register(Box::new(sink_sent.clone())).unwrap();
+        registry.register(Box::new(sink_failures.clone())).unwrap();
+        registry.register(Box::new(sink_dropped.clone())).unwrap();
+        registry
+            .register(Box::new(sink_queue_length.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(remote_lock_status.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(remote_loss_count.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(remote_last_push.clone()))
+            .unwrap();
+
+        let update_available = IntGauge::new(
+            "leo_status_update_available",
+            "whether the release feed has a newer version of leo-status than the one running, 0 until the feed has been read",
+        )
+        .unwrap();
+        registry
+            .register(Box::new(update_available.clone()))
+            .unwrap();
+        let maintenance = IntGauge::new(
+            "leo_gpsdo_maintenance",
+            "whether maintenance is in progress, so lock losses shouldn't be alerted on or counted against availability",
+        )
+        .unwrap();
+        registry.register(Box::new(maintenance.clone())).unwrap();
+        let errors_suppressed = IntCounterVec::new(
+            Opts::new(
+                "leo_status_errors_suppressed_total",
+                "the number of repeats of an error left out of the log, labelled by where the error came from",
+            ),
+            &["source"],
+        )
+        .unwrap();
+        registry
+            .register(Box::new(errors_suppressed.clone()))
+            .unwrap();
+
+        let metrics = Metrics {
+            registry,
+            stale_policy,
+            lock_status,
+            sat_lock_status,
+            pll_lock_status,
+            reconnects,
+            losses,
+            data_stale,
+            state,
+            quality_score,
+            holdover,
+            longest_holdover,
+            device_uptime,
+            frequency_residual,
+            clock_offset,
+            clock_offset_unlocked_max,
+            device_info,
+            sink_sent,
+            sink_failures,
+            sink_dropped,
+            sink_queue_length,
+            remote_lock_status,
+            remote_loss_count,
+            remote_last_push,
+            update_available,
+            maintenance,
+            errors_suppressed,
+        };
+        metrics.set_state(DeviceState::default());
+
+        metrics
+    }
+
+    /// Update the lock metrics with a new status reading, which also means the data is no longer stale
+    pub(crate) fn set_status(&self, status: &GpsdoStatus) {
+        self.lock_status.set(status.locked().into());
+        self.sat_lock_status.set(status.sat_locked().into());
+        self.pll_lock_status.set(status.pll_locked().into());
+        self.data_stale.set(0);
+    }
+
+    /// Set the state the gpsdo is in
+    pub(crate) fn set_state(&self, current: DeviceState) {
+        for state in DeviceState::ALL {
+            self.state
+                .with_label_values(&[state.as_str()])
+                .set((state == current).into());
+        }
+    }
+
+    /// Update the lock loss counter to the total counted by a `LossCounter`
+    pub(crate) fn set_losses(&self, counter: &LossCounter) {
+        self.losses
+            .inc_by(counter.total().saturating_sub(self.losses.get()));
+    }
+
+    /// Update the holdover metrics from the holdover tracked so far
+    pub(crate) fn set_holdover(&self, tracker: &HoldoverTracker) {
+        self.holdover.set(
+            tracker
+                .holdover_duration()
+                .unwrap_or_default()
+                .as_secs_f64(),
+        );
+        self.longest_holdover
+            .set(tracker.longest_holdover().as_secs_f64());
+    }
+
+    /// Update the frequency residual metrics with a new config reading, leaving them out while the config is invalid
+    pub(crate) fn set_config(&self, config: &GpsdoConfig) {
+        self.frequency_residual.reset();
+        if config.checked_fout1().is_err() {
+            return;
+        }
+
+        let residuals = [
+            ("1", Some(config.fout1_residual())),
+            ("2", config.fout2_residual()),
+        ];
+        for (output, residual) in residuals {
+            if let Some(residual) = residual {
+                self.frequency_residual
+                    .with_label_values(&[output])
+                    .set(residual.hz());
+            }
+        }
+    }
+
+    /// Replace the device information metric with the device now in use
+    pub(crate) fn set_device_info(
+        &self,
+        info: &GpsdoDeviceInfo,
+        firmware_version: Option<FirmwareVersion>,
+    ) {
+        self.device_info.reset();
+        self.device_info
+            .with_label_values(&[
+                &format!("{:04x}", info.vendor_id()),
+                &format!("{:04x}", info.product_id()),
+                info.manufacturer().unwrap_or(""),
+                info.product().unwrap_or(""),
+                info.serial_number().unwrap_or(""),
+                info.path(),
+                info.port_path().unwrap_or(""),
+                &info.interface_number().to_string(),
+                &firmware_version
+                    .map(|version| version.to_string())
+                    .unwrap_or_default(),
+            ])
+            .set(1);
+    }
+
+    /// Update the metrics of a remote instance with a reading it pushed
+    pub(crate) fn set_remote_status(
+        &self,
+        instance: &str,
+        status: &LockStatusResponse,
+        received: Timestamp,
+    ) {
+        let locks = [
+            ("overall", status.locked),
+            ("sat", status.sat_lock),
+            ("pll", status.pll_lock),
+        ];
+        for (lock, locked) in locks {
+            self.remote_lock_status
+                .with_label_values(&[instance, lock])
+                .set(locked.into());
+        }
+
+        self.remote_loss_count
+            .with_label_values(&[instance])
+            .set(status.loss_count.into());
+        self.remote_last_push
+            .with_label_values(&[instance])
+            .set(received.unix_nanos() as f64 / 1e9);
+    }
+
+    /// Encode the current metrics in the Prometheus text exposition format
+    pub(crate) fn encode(&self) -> Result<Vec<u8>, prometheus::Error> {
+        let mut families = self.registry.gather();
+        if self.stale_policy == StaleMetricsPolicy::Drop && self.data_stale.get() != 0 {
+            families.retain(|family| !LOCK_METRICS.contains(&family.get_name()));
+        }
+
+        let mut buffer = vec![];
+        TextEncoder::new().encode(&families, &mut buffer)?;
+
+        Ok(buffer)
+    }
+
+    /// Write the current metrics into a node_exporter textfile collector directory. The file is replaced atomically,
+    /// so node_exporter never reads a partially written file.
+    pub(crate) fn write_textfile(&self, dir: &Path) -> io::Result<()> {
+        let buffer = self.encode().map_err(io::Error::other)?;
+
+        persist::write_atomic(&dir.join(TEXTFILE_NAME), buffer)
+    }
+}
+
+/// Keeps the last hour of status readings, to score the quality of the reference with
+#[derive(Default)]
+pub(crate) struct QualityTracker {
+    start: Option<Timestamp>,
+    history: VecDeque<StatusSample>,
+}
+
+impl QualityTracker {
+    /// Record a new reading, returning the quality score of the history up to it
+    pub(crate) fn update(&mut self, snapshot: &Snapshot) -> u8 {
+        let start = *self.start.get_or_insert(snapshot.timestamp);
+        let elapsed = snapshot.timestamp.duration_since(&start);
+
+        self.history.push_back(StatusSample {
+            elapsed,
+            status: snapshot.status,
+        });
+        while self
+            .history
+            .front()
+            .is_some_and(|sample| elapsed - sample.elapsed > QUALITY_WINDOW)
+        {
+            self.history.pop_front();
+        }
+
+        quality::quality_score(self.history.make_contiguous())
+    }
+}
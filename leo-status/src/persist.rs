@@ -0,0 +1,114 @@
+use serde::de::DeserializeOwned;
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+/// Replace a file with the given contents atomically. The contents are written to a temporary file beside it, synced
+/// to disk and renamed into place, so after a power cut the file holds either the old or the new contents, never a
+/// partial write. The temporary name ends in `.tmp`, so directory readers such as node_exporter ignore it.
+pub(crate) fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(format!(".{}.tmp", std::process::id()));
+
+    let result = (|| {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(contents.as_ref())?;
+        file.sync_all()?;
+
+        fs::rename(&temp_path, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result?;
+
+    sync_parent(path)
+}
+
+/// Sync the directory holding a file, so a rename into it survives a power cut. Directories can't be opened for
+/// syncing on every platform, so this is best effort outside Unix.
+fn sync_parent(path: &Path) -> io::Result<()> {
+    if !cfg!(unix) {
+        return Ok(());
+    }
+
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    File::open(parent)?.sync_all()
+}
+
+/// Open a JSON lines file for appending, creating it and its directory if needed. If a power cut tore the last line, it
+/// is terminated, so the next line appended stays readable.
+pub(crate) fn open_append(path: &Path) -> io::Result<File> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(path)?;
+
+    if file.metadata()?.len() > 0 {
+        let mut last = [0u8];
+        file.seek(SeekFrom::End(-1))?;
+        file.read_exact(&mut last)?;
+
+        if last[0] != b'\n' {
+            file.write_all(b"\n")?;
+        }
+    }
+
+    Ok(file)
+}
+
+/// Read every entry of a JSON lines file, oldest first. A missing file has no entries, and lines which can't be read,
+/// such as one torn by a power cut, are skipped with a warning rather than hiding every other entry.
+pub(crate) fn read_json_lines<T: DeserializeOwned>(path: &Path) -> io::Result<Vec<T>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(error) => return Err(error),
+    };
+
+    let mut entries = vec![];
+    for (number, line) in BufReader::new(file).split(b'\n').enumerate() {
+        let line = line?;
+        if line.trim_ascii().is_empty() {
+            continue;
+        }
+
+        match serde_json::from_slice(&line) {
+            Ok(entry) => entries.push(entry),
+            Err(error) => tracing::warn!(
+                "skipping corrupt line {} of {}: {}",
+                number + 1,
+                path.display(),
+                error
+            ),
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Parse a path which must be absolute, for the state of a service, which shouldn't depend on the directory it was
+/// started in
+pub(crate) fn parse_absolute(path: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(path);
+    if path.is_absolute() {
+        Ok(path)
+    } else {
+        Err(format!("{} is not an absolute path", path.display()))
+    }
+}
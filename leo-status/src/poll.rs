@@ -0,0 +1,664 @@
+#[cfg(feature = "hidapi")]
+use hidapi::{HidApi, HidDevice, HidError};
+#[cfg(feature = "hidapi")]
+use leo_status_driver::interface::GpsdoHidApiInterface;
+#[cfg(all(feature = "nusb", not(feature = "hidapi")))]
+use leo_status_driver::interface::GpsdoNusbInterface;
+use leo_status_driver::{
+    consts::{PID_LEO_BODNAR_GPSDO, VID_LEO_BONDAR},
+    sim::{GpsdoSimulator, Scenario, SimulatorError},
+    state::{DeviceState, DeviceStateMachine, StateTransition},
+    GpsdoDevice, GpsdoDeviceInfo, GpsdoError, GpsdoModel, UsbInterface,
+};
+use thiserror::Error;
+
+#[cfg(all(feature = "nusb", not(feature = "hidapi")))]
+use std::io;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, OnceLock,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{
+    dto::{DeviceResponse, LockStatusResponse, ServedDevice},
+    exit::ExitCode,
+    http::{DeviceJobs, HttpContext},
+    instance::{self, InstanceLockError},
+    journal::JournalEventKind,
+    metrics::Metrics,
+    repeats::RepeatedErrors,
+    schedule::{PollTrigger, Scheduler, Wake},
+    snapshot::{Snapshot, Snapshots},
+    startup::StartupReport,
+    supervisor::Heartbeat,
+    time::Timestamp,
+    Args, OutputFormat,
+};
+
+/// The USB backend the daemon opens the GPSDO through, hidapi unless the daemon was built with only nusb
+#[cfg(feature = "hidapi")]
+pub(crate) type DeviceInterface = HidApiDevice;
+#[cfg(all(feature = "nusb", not(feature = "hidapi")))]
+pub(crate) type DeviceInterface = GpsdoNusbInterface;
+
+/// The error of the USB backend, see `DeviceInterface`
+#[cfg(feature = "hidapi")]
+type DeviceError = HidError;
+#[cfg(all(feature = "nusb", not(feature = "hidapi")))]
+type DeviceError = io::Error;
+
+/// The number of the last poll cycle, see `Snapshot::poll_seq`
+static POLL_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// The simulated GPSDO polled with `--simulate`, shared by every poll cycle so its scenario carries on across reconnects
+static SIMULATOR: OnceLock<GpsdoSimulator> = OnceLock::new();
+
+/// The GPSDO opened by `open_ahead`, polled first instead of opening it again
+static OPENED_AHEAD: Mutex<Option<(DeviceInterface, GpsdoDeviceInfo)>> = Mutex::new(None);
+
+/// The serial number the simulated GPSDO reports
+const SIMULATED_SERIAL_NUMBER: &str = "SIMULATED";
+
+/// The delay before re-opening the device after the first error
+const INITIAL_RECONNECT_BACKOFF: Duration = Duration::from_secs(1);
+
+/// The longest delay between attempts to re-open the device
+const MAX_RECONNECT_BACKOFF: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Error)]
+/// An error which stopped the poll loop from talking to the GPSDO
+pub(crate) enum PollError {
+    #[cfg(feature = "hidapi")]
+    #[error("failed to create hidapi context: {0}")]
+    HidApi(HidError),
+
+    #[cfg(all(feature = "nusb", not(feature = "hidapi")))]
+    #[error("failed to list usb devices: {0}")]
+    Enumerate(io::Error),
+
+    #[error("could not find leo bodnar gpsdo")]
+    NotFound,
+
+    #[error("found leo bodnar gpsdo but could not open it: {0}")]
+    Open(String),
+
+    #[error("failed to communicate with gpsdo: {0}")]
+    Device(#[from] GpsdoError<InterfaceError>),
+
+    #[error(transparent)]
+    Locked(#[from] InstanceLockError),
+}
+
+impl PollError {
+    /// The code to exit with when this error stops the daemon or a command
+    pub(crate) fn exit_code(&self) -> ExitCode {
+        match self {
+            #[cfg(feature = "hidapi")]
+            PollError::HidApi(_) => ExitCode::Failure,
+            #[cfg(all(feature = "nusb", not(feature = "hidapi")))]
+            PollError::Enumerate(_) => ExitCode::Failure,
+            PollError::NotFound => ExitCode::DeviceNotFound,
+            PollError::Open(_) => ExitCode::Permission,
+            PollError::Device(_) => ExitCode::PollFailure,
+            PollError::Locked(_) => ExitCode::Locked,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+/// An error from the interface the poll loop reads the GPSDO through
+pub(crate) enum InterfaceError {
+    #[error(transparent)]
+    Device(#[from] DeviceError),
+
+    #[error(transparent)]
+    Simulator(#[from] SimulatorError),
+}
+
+/// The interface the poll loop reads the GPSDO through, a real device or the simulator
+pub(crate) enum PolledInterface {
+    Device(Box<DeviceInterface>),
+    Simulator(&'static GpsdoSimulator),
+}
+
+/// The GPSDO the poll loop has open, which requests which touch the GPSDO run on, see `DeviceQueue`
+pub(crate) type PolledDevice = GpsdoDevice<PolledInterface>;
+
+impl UsbInterface for PolledInterface {
+    type InterfaceError = InterfaceError;
+
+    fn hid_read(&self, buf: &mut [u8]) -> Result<usize, Self::InterfaceError> {
+        match self {
+            PolledInterface::Device(interface) => Ok(interface.hid_read(buf)?),
+            PolledInterface::Simulator(simulator) => Ok(simulator.hid_read(buf)?),
+        }
+    }
+
+    fn hid_get_feature_report(
+        &self,
+        report_id: u8,
+        buf: &mut [u8],
+    ) -> Result<usize, Self::InterfaceError> {
+        match self {
+            PolledInterface::Device(interface) => {
+                Ok(interface.hid_get_feature_report(report_id, buf)?)
+            }
+            PolledInterface::Simulator(simulator) => {
+                Ok(simulator.hid_get_feature_report(report_id, buf)?)
+            }
+        }
+    }
+
+    #[cfg(feature = "write")]
+    fn hid_send_feature_report(
+        &self,
+        report_id: u8,
+        data: &[u8],
+    ) -> Result<(), Self::InterfaceError> {
+        match self {
+            PolledInterface::Device(interface) => {
+                Ok(interface.hid_send_feature_report(report_id, data)?)
+            }
+            PolledInterface::Simulator(simulator) => {
+                Ok(simulator.hid_send_feature_report(report_id, data)?)
+            }
+        }
+    }
+
+    fn serial_number(&self) -> Result<Option<String>, Self::InterfaceError> {
+        match self {
+            PolledInterface::Device(interface) => Ok(interface.serial_number()?),
+            PolledInterface::Simulator(simulator) => Ok(simulator.serial_number()?),
+        }
+    }
+
+    fn firmware_version(&self) -> Result<Option<u16>, Self::InterfaceError> {
+        match self {
+            PolledInterface::Device(interface) => Ok(interface.firmware_version()?),
+            PolledInterface::Simulator(simulator) => Ok(simulator.firmware_version()?),
+        }
+    }
+
+    fn device_info(&self) -> Result<Option<GpsdoDeviceInfo>, Self::InterfaceError> {
+        match self {
+            PolledInterface::Device(interface) => Ok(interface.device_info()?),
+            PolledInterface::Simulator(simulator) => Ok(simulator.device_info()?),
+        }
+    }
+}
+
+/// A GPSDO opened through hidapi. Unlike a `GpsdoHidApiInterface`, which may borrow its device, it can be handed to
+/// another thread, such as by `open_ahead`.
+#[cfg(feature = "hidapi")]
+pub(crate) struct HidApiDevice(HidDevice);
+
+#[cfg(feature = "hidapi")]
+impl UsbInterface for HidApiDevice {
+    type InterfaceError = HidError;
+
+    fn hid_read(&self, buf: &mut [u8]) -> Result<usize, Self::InterfaceError> {
+        GpsdoHidApiInterface::new(&self.0).hid_read(buf)
+    }
+
+    fn hid_get_feature_report(
+        &self,
+        report_id: u8,
+        buf: &mut [u8],
+    ) -> Result<usize, Self::InterfaceError> {
+        GpsdoHidApiInterface::new(&self.0).hid_get_feature_report(report_id, buf)
+    }
+
+    #[cfg(feature = "write")]
+    fn hid_send_feature_report(
+        &self,
+        report_id: u8,
+        data: &[u8],
+    ) -> Result<(), Self::InterfaceError> {
+        GpsdoHidApiInterface::new(&self.0).hid_send_feature_report(report_id, data)
+    }
+
+    fn serial_number(&self) -> Result<Option<String>, Self::InterfaceError> {
+        GpsdoHidApiInterface::new(&self.0).serial_number()
+    }
+
+    fn firmware_version(&self) -> Result<Option<u16>, Self::InterfaceError> {
+        GpsdoHidApiInterface::new(&self.0).firmware_version()
+    }
+
+    fn device_info(&self) -> Result<Option<GpsdoDeviceInfo>, Self::InterfaceError> {
+        GpsdoHidApiInterface::new(&self.0).device_info()
+    }
+}
+
+/// Poll the GPSDO forever, broadcasting each reading to the consumers of snapshots. Whenever the device errors it is
+/// re-opened with exponential backoff, while the last data received stays available marked as stale.
+pub(crate) fn poll_loop(
+    args: &Args,
+    context: &HttpContext,
+    jobs: &DeviceJobs,
+    metrics: &Metrics,
+    snapshots: &Snapshots,
+    heartbeat: &Heartbeat,
+) {
+    let mut backoff = INITIAL_RECONNECT_BACKOFF;
+    let mut connected_before = false;
+    let scheduler = args.schedule.scheduler(args);
+    let between_polls = BetweenPolls {
+        scheduler: &*scheduler,
+        trigger: &context.poll_trigger,
+        jobs,
+    };
+    let mut errors = RepeatedErrors::new("poll", args.error_repeat_interval, metrics);
+
+    // Every opening of the device after the first is a reconnect, counted once it has opened rather than on each attempt
+    let mut opened_before = false;
+    let mut state = DeviceStateMachine::with_callback(|transition| {
+        if transition.to == DeviceState::Connected {
+            if opened_before {
+                metrics.reconnects.inc();
+            }
+            opened_before = true;
+        }
+        on_transition(metrics, transition)
+    });
+
+    loop {
+        let error = poll_device(
+            args,
+            context,
+            metrics,
+            snapshots,
+            heartbeat,
+            &between_polls,
+            &mut state,
+        )
+        .expect_err("polling only stops on error");
+
+        // Each attempt starts with the device faulted or disconnected, so it was read if it is now in a state only a
+        // reading puts it in
+        let polled = state.state().has_status();
+
+        // Another daemon already using the GPSDO at startup is a mistake to report, rather than wait out
+        if !connected_before && matches!(error, PollError::Locked(_)) {
+            tracing::error!("{}", error);
+            error.exit_code().exit();
+        }
+
+        // Only back off further when the device keeps failing without ever producing data
+        if polled {
+            backoff = INITIAL_RECONNECT_BACKOFF;
+            errors.clear();
+        }
+        connected_before = true;
+
+        // An error from the open device is a fault, any other error means there is no device open
+        match error {
+            PollError::Device(_) => state.fault(),
+            _ => state.disconnected(),
+        };
+
+        let lost_at = Timestamp::now();
+        context.mark_stale(lost_at, state.state());
+        metrics.data_stale.set(1);
+        if let Some(textfile_dir) = &args.textfile_dir {
+            if let Err(error) = metrics.write_textfile(textfile_dir) {
+                tracing::error!(
+                    "failed to write metrics to {}: {}",
+                    textfile_dir.display(),
+                    error
+                );
+            }
+        }
+        if polled {
+            context.journal.record(
+                lost_at,
+                JournalEventKind::DeviceLost,
+                Some(error.to_string()),
+            );
+        }
+
+        // The sandbox allows no device to be opened, so the GPSDO is re-opened by the service manager restarting the
+        // daemon
+        #[cfg(target_os = "linux")]
+        if args.sandbox {
+            tracing::error!("{}, exiting as the sandbox can't re-open the GPSDO", error);
+            error.exit_code().exit();
+        }
+
+        errors.log(&error.to_string(), || {
+            format!("{}, retrying in {:?}", error, backoff)
+        });
+        between_polls.wait_to_reconnect(backoff, heartbeat);
+        backoff = (backoff * 2).min(MAX_RECONNECT_BACKOFF);
+    }
+}
+
+/// Open the GPSDO and poll it until an error occurs, following it in its state machine
+fn poll_device(
+    args: &Args,
+    context: &HttpContext,
+    metrics: &Metrics,
+    snapshots: &Snapshots,
+    heartbeat: &Heartbeat,
+    between_polls: &BetweenPolls,
+    state: &mut DeviceStateMachine<impl FnMut(StateTransition)>,
+) -> Result<(), PollError> {
+    let (interface, info) = open_polled(args)?;
+    state.connected();
+
+    let gpsdo = GpsdoDevice::with_model(interface, model(&info));
+
+    // The open device describes itself more fully than it was described when found
+    let info = gpsdo.device_info()?.unwrap_or(info);
+    let firmware_version = gpsdo.firmware_version()?;
+    metrics.set_device_info(&info, firmware_version);
+    *context.device.write().unwrap() = Some(ServedDevice::new(
+        DeviceResponse::from(&info).with_firmware_version(firmware_version),
+    ));
+
+    let config = gpsdo.config()?;
+    *context.startup.write().unwrap() =
+        Some(StartupReport::record(&info, firmware_version, &config));
+
+    let mut first_read = true;
+    loop {
+        let snapshot = read(&gpsdo, state, first_read)?;
+
+        // The device only counts as opened once it has been read, so every opening in the journal has a loss after it
+        if first_read {
+            context.journal.record_snapshot(
+                &snapshot,
+                JournalEventKind::DeviceOpened,
+                Some(format!(
+                    "serial number {}, path {}",
+                    info.serial_number().unwrap_or("unknown"),
+                    info.path()
+                )),
+            );
+        }
+        snapshots.publish(&snapshot);
+        first_read = false;
+
+        heartbeat.beat();
+
+        between_polls.wait_for_next_poll(&snapshot, &gpsdo, heartbeat);
+    }
+}
+
+/// Open the GPSDO, read it once and broadcast the reading to the consumers of snapshots
+pub(crate) fn poll_once(
+    args: &Args,
+    context: &HttpContext,
+    metrics: &Metrics,
+    snapshots: &Snapshots,
+) -> Result<Snapshot, PollError> {
+    let (interface, info) = open_polled(args)?;
+    let mut state =
+        DeviceStateMachine::with_callback(|transition| on_transition(metrics, transition));
+    state.connected();
+
+    let gpsdo = GpsdoDevice::with_model(interface, model(&info));
+
+    // The open device describes itself more fully than it was described when found
+    let info = gpsdo.device_info()?.unwrap_or(info);
+    let firmware_version = gpsdo.firmware_version()?;
+    metrics.set_device_info(&info, firmware_version);
+    *context.device.write().unwrap() = Some(ServedDevice::new(
+        DeviceResponse::from(&info).with_firmware_version(firmware_version),
+    ));
+
+    let snapshot = read(&gpsdo, &mut state, true)?;
+    snapshots.publish(&snapshot);
+
+    Ok(snapshot)
+}
+
+/// Log a change of state of the GPSDO, and export it in the metrics
+fn on_transition(metrics: &Metrics, transition: StateTransition) {
+    tracing::info!(
+        "gpsdo state changed from {} to {}",
+        transition.from,
+        transition.to
+    );
+    metrics.set_state(transition.to);
+}
+
+/// Read the config and status of the GPSDO, following the status in its state machine
+fn read(
+    gpsdo: &GpsdoDevice<PolledInterface>,
+    state: &mut DeviceStateMachine<impl FnMut(StateTransition)>,
+    first_read: bool,
+) -> Result<Snapshot, PollError> {
+    let timestamp = Timestamp::now();
+    let config = gpsdo.config()?;
+    let status = gpsdo.status()?;
+    state.update(&status);
+
+    Ok(Snapshot {
+        timestamp,
+        config,
+        status,
+        state: state.state(),
+        first_read,
+        poll_seq: POLL_SEQ.fetch_add(1, Ordering::Relaxed) + 1,
+    })
+}
+
+/// Open the GPSDO to poll, the simulator with `--simulate` and otherwise the device selected by the command line
+/// arguments, see `open`
+pub(crate) fn open_polled(args: &Args) -> Result<(PolledInterface, GpsdoDeviceInfo), PollError> {
+    if args.simulate.is_none() {
+        if let Some((interface, info)) = OPENED_AHEAD.lock().unwrap().take() {
+            return Ok((PolledInterface::Device(Box::new(interface)), info));
+        }
+    }
+
+    DeviceOpener::new(args).open()
+}
+
+/// Opens the GPSDO selected by the command line arguments, or the simulator with `--simulate`, for requests which
+/// need the device alongside the poll loop, such as writes
+#[derive(Debug, Clone)]
+pub(crate) struct DeviceOpener {
+    serial_number: Option<String>,
+    lock_dir: PathBuf,
+    simulate: Option<Scenario>,
+}
+
+impl DeviceOpener {
+    pub(crate) fn new(args: &Args) -> Self {
+        DeviceOpener {
+            serial_number: args.serial_number.clone(),
+            lock_dir: args.lock_dir(),
+            simulate: args.simulate.clone(),
+        }
+    }
+
+    /// Open the GPSDO, locking it for this process, see `open`
+    pub(crate) fn open(&self) -> Result<(PolledInterface, GpsdoDeviceInfo), PollError> {
+        let Some(scenario) = &self.simulate else {
+            let (interface, info) =
+                open_serial_number(self.serial_number.as_deref(), Some(&self.lock_dir))?;
+            return Ok((PolledInterface::Device(Box::new(interface)), info));
+        };
+
+        let simulator = SIMULATOR.get_or_init(|| {
+            GpsdoSimulator::new(scenario.clone()).with_serial_number(SIMULATED_SERIAL_NUMBER)
+        });
+        let info = GpsdoDeviceInfo::new(
+            VID_LEO_BONDAR,
+            PID_LEO_BODNAR_GPSDO,
+            Some(SIMULATED_SERIAL_NUMBER.to_owned()),
+            "simulator".to_owned(),
+            None,
+            -1,
+        );
+
+        Ok((PolledInterface::Simulator(simulator), info))
+    }
+}
+
+/// Open the GPSDO to poll now, for the poll loop to use when it starts, so it can be opened before the daemon drops its
+/// privileges
+#[cfg(target_os = "linux")]
+pub(crate) fn open_ahead(args: &Args) -> Result<(), PollError> {
+    // The simulator needs no privileges to open
+    if args.simulate.is_none() {
+        *OPENED_AHEAD.lock().unwrap() = Some(open(args)?);
+    }
+
+    Ok(())
+}
+
+/// Read and parse the scenario script for `--simulate`. Used as a clap value parser, so a broken script stops the
+/// daemon from starting.
+pub(crate) fn load_scenario(path: &str) -> Result<Scenario, String> {
+    let script = fs::read_to_string(path)
+        .map_err(|error| format!("failed to read scenario {}: {}", path, error))?;
+
+    Scenario::parse(&script).map_err(|error| format!("invalid scenario {}: {}", path, error))
+}
+
+/// Find and open the GPSDO selected by the command line arguments, and lock it for this process, see
+/// `instance::lock`. If several devices match, the first which can be opened is used, so a device which can't be
+/// opened doesn't hide one which can.
+pub(crate) fn open(args: &Args) -> Result<(DeviceInterface, GpsdoDeviceInfo), PollError> {
+    open_serial_number(args.serial_number.as_deref(), Some(&args.lock_dir()))
+}
+
+/// Lock a GPSDO found while enumerating for this process before it is opened, so a GPSDO another process is using is
+/// never opened alongside it. Returns whether the lock was taken, to be released if opening fails.
+fn lock_before_open(lock_dir: Option<&Path>, info: &GpsdoDeviceInfo) -> Result<bool, PollError> {
+    match lock_dir {
+        Some(lock_dir) => Ok(instance::lock(lock_dir, info)?),
+        None => Ok(false),
+    }
+}
+
+/// Open the GPSDO with the given serial number, or any GPSDO without one, see `open`. Each device is locked in
+/// `lock_dir` before it is opened, unless there is none, as for a process which already holds the lock.
+#[cfg(feature = "hidapi")]
+pub(crate) fn open_serial_number(
+    serial_number: Option<&str>,
+    lock_dir: Option<&Path>,
+) -> Result<(DeviceInterface, GpsdoDeviceInfo), PollError> {
+    let hid_api = HidApi::new().map_err(PollError::HidApi)?;
+
+    let mut errors = vec![];
+    for device in GpsdoHidApiInterface::find_gpsdos(&hid_api, serial_number) {
+        let info = GpsdoHidApiInterface::device_info(device);
+        let locked = lock_before_open(lock_dir, &info)?;
+
+        match device.open_device(&hid_api) {
+            Ok(conn) => return Ok((HidApiDevice(conn), info)),
+            Err(error) => {
+                if locked {
+                    instance::unlock(&info);
+                }
+                errors.push(format!("{}: {}", device.path().to_string_lossy(), error))
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Err(PollError::NotFound)
+    } else {
+        Err(PollError::Open(errors.join(", ")))
+    }
+}
+
+/// Open the GPSDO with the given serial number, or any GPSDO without one, see the hidapi version
+#[cfg(all(feature = "nusb", not(feature = "hidapi")))]
+pub(crate) fn open_serial_number(
+    serial_number: Option<&str>,
+    lock_dir: Option<&Path>,
+) -> Result<(DeviceInterface, GpsdoDeviceInfo), PollError> {
+    let devices = GpsdoNusbInterface::find_gpsdos(serial_number).map_err(PollError::Enumerate)?;
+
+    let mut errors = vec![];
+    for device in devices {
+        let info = GpsdoNusbInterface::device_info(&device);
+        let locked = lock_before_open(lock_dir, &info)?;
+
+        match GpsdoNusbInterface::new(device) {
+            Ok(interface) => return Ok((interface, info)),
+            Err(error) => {
+                if locked {
+                    instance::unlock(&info);
+                }
+                errors.push(format!("{}: {}", info.path(), error))
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Err(PollError::NotFound)
+    } else {
+        Err(PollError::Open(errors.join(", ")))
+    }
+}
+
+/// The model of the GPSDO, from its product identifier. A device chosen by serial number may have an unknown product
+/// identifier, in which case it is treated as the standard model.
+pub(crate) fn model(info: &GpsdoDeviceInfo) -> GpsdoModel {
+    GpsdoModel::from_product_id(info.product_id()).unwrap_or(GpsdoModel::Standard)
+}
+
+/// Print a reading to the console in the given format
+pub(crate) fn print_output(format: OutputFormat, snapshot: &Snapshot, metrics: &Metrics) {
+    match format {
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string(&LockStatusResponse::from(snapshot))
+                .expect("failed to serialize status")
+        ),
+        OutputFormat::Prometheus => {
+            // The metrics consumer may not have seen this reading yet
+            metrics.set_status(&snapshot.status);
+
+            match metrics.encode() {
+                Ok(buffer) => print!("{}", String::from_utf8_lossy(&buffer)),
+                Err(error) => tracing::error!("failed to encode metrics: {}", error),
+            }
+        }
+    }
+}
+
+/// What the poll loop does between polls: wait for the next as scheduled, or as triggered, and meanwhile answer the
+/// requests which touch the GPSDO, see `DeviceQueue`
+struct BetweenPolls<'a> {
+    scheduler: &'a dyn Scheduler,
+    trigger: &'a PollTrigger,
+    jobs: &'a DeviceJobs,
+}
+
+impl BetweenPolls<'_> {
+    /// Wait for the poll after the one which read `snapshot`, running the requests waiting on the GPSDO on its device
+    fn wait_for_next_poll(&self, snapshot: &Snapshot, gpsdo: &PolledDevice, heartbeat: &Heartbeat) {
+        let next_poll = self
+            .scheduler
+            .next_poll(snapshot)
+            .map(|interval| Instant::now() + interval);
+
+        while self.trigger.wait(next_poll, heartbeat) == Wake::Jobs {
+            self.jobs.run(gpsdo);
+        }
+    }
+
+    /// Wait `backoff` before re-opening the GPSDO, refusing the requests waiting on it meanwhile as there is no device
+    /// to run them on. A trigger doesn't cut the wait short, as the GPSDO is polled as soon as it is open again anyway.
+    fn wait_to_reconnect(&self, backoff: Duration, heartbeat: &Heartbeat) {
+        let reconnect_at = Instant::now() + backoff;
+
+        while Instant::now() < reconnect_at {
+            if self.trigger.wait(Some(reconnect_at), heartbeat) == Wake::Jobs {
+                self.jobs.refuse();
+            }
+        }
+    }
+}
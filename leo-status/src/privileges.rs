@@ -0,0 +1,85 @@
+use thiserror::Error;
+
+use std::{ffi::CString, io};
+
+#[derive(Debug, Error)]
+/// An error which stopped the daemon dropping its privileges
+pub(crate) enum PrivilegeError {
+    #[error("unknown user {0:?}")]
+    UnknownUser(String),
+
+    #[error("unknown group {0:?}")]
+    UnknownGroup(String),
+
+    #[error("failed to drop privileges to user {user:?}: {error}")]
+    Drop { user: String, error: io::Error },
+}
+
+/// Switch the daemon to an unprivileged user, and group if given, otherwise the user's primary group. The user and
+/// group may be names or numeric ids, but the user must be in the user database. Called while the daemon is still
+/// single threaded, as user lookups aren't thread safe, and after the device has been opened, which is what needed the
+/// privileges.
+pub(crate) fn drop_privileges(user: &str, group: Option<&str>) -> Result<(), PrivilegeError> {
+    let (uid, primary_gid) =
+        lookup_user(user).ok_or_else(|| PrivilegeError::UnknownUser(user.to_owned()))?;
+    let gid = match group {
+        Some(group) => {
+            lookup_group(group).ok_or_else(|| PrivilegeError::UnknownGroup(group.to_owned()))?
+        }
+        None => primary_gid,
+    };
+
+    let drop_error = |error| PrivilegeError::Drop {
+        user: user.to_owned(),
+        error,
+    };
+
+    // The supplementary groups are cleared first, and the group set before the user, as neither can be changed once
+    // the daemon is no longer root
+    check(unsafe { libc::setgroups(1, &gid) }).map_err(drop_error)?;
+    check(unsafe { libc::setgid(gid) }).map_err(drop_error)?;
+    check(unsafe { libc::setuid(uid) }).map_err(drop_error)?;
+
+    // Make sure root can't be regained, which setuid allows on some systems when it only changes the effective user
+    if uid != 0 && unsafe { libc::setuid(0) } == 0 {
+        return Err(drop_error(io::Error::other("root could be regained")));
+    }
+
+    Ok(())
+}
+
+/// The user id and primary group id of a user
+fn lookup_user(user: &str) -> Option<(libc::uid_t, libc::gid_t)> {
+    let name = CString::new(user).ok()?;
+    let passwd = unsafe { libc::getpwnam(name.as_ptr()) };
+    if !passwd.is_null() {
+        return Some(unsafe { ((*passwd).pw_uid, (*passwd).pw_gid) });
+    }
+
+    let passwd = unsafe { libc::getpwuid(user.parse().ok()?) };
+    if passwd.is_null() {
+        return None;
+    }
+
+    Some(unsafe { ((*passwd).pw_uid, (*passwd).pw_gid) })
+}
+
+/// The id of a group
+fn lookup_group(group: &str) -> Option<libc::gid_t> {
+    let name = CString::new(group).ok()?;
+    let entry = unsafe { libc::getgrnam(name.as_ptr()) };
+    if !entry.is_null() {
+        return Some(unsafe { (*entry).gr_gid });
+    }
+
+    group.parse().ok()
+}
+
+/// Turn the return value of a libc call into the error it set
+fn check(result: libc::c_int) -> io::Result<()> {
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
@@ -0,0 +1,526 @@
+use clap::{Args as ClapArgs, Subcommand};
+use leo_status_driver::DriveLevel;
+#[cfg(feature = "write")]
+use leo_status_driver::{
+    Bandwidth, GpsdoConfig, GpsdoConfigError, GpsdoDevice, GpsdoModel, UsbInterface,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use std::{fs, io, path::PathBuf};
+
+#[cfg(feature = "write")]
+use crate::{
+    audit::{self, AuditLog},
+    poll::{self, DeviceOpener, PollError},
+    schedule::PollTrigger,
+    writes::{DeviceWrites, WriteError},
+};
+use crate::{exit::ExitCode, Args};
+
+#[derive(Debug, Error)]
+/// An error which stopped a profile from being found, read or applied
+pub(crate) enum ProfileError {
+    #[error("invalid profile name {0:?}, names may only contain letters, digits, '-' and '_'")]
+    InvalidName(String),
+
+    #[error("no profile named {0:?}")]
+    NotFound(String),
+
+    #[error("failed to read profile {name:?}: {source}")]
+    Read { name: String, source: io::Error },
+
+    #[error("failed to parse profile {name:?}: {source}")]
+    Parse {
+        name: String,
+        source: toml::de::Error,
+    },
+
+    #[error("invalid profile {name:?}: level must be one of 8, 16, 24 or 32 mA, not {level}")]
+    InvalidLevel { name: String, level: u8 },
+
+    #[cfg(feature = "write")]
+    #[error("invalid profile {name:?}: {source}")]
+    InvalidConfig {
+        name: String,
+        source: GpsdoConfigError,
+    },
+
+    #[cfg(feature = "write")]
+    #[error(transparent)]
+    Open(#[from] PollError),
+
+    #[cfg(feature = "write")]
+    #[error("failed to apply profile to the gpsdo: {0}")]
+    Device(String),
+
+    #[cfg(feature = "write")]
+    #[error("the gpsdo did not take the profile, the config read back after writing it differs")]
+    NotApplied,
+
+    #[cfg(feature = "write")]
+    #[error("failed to record write in the audit log, so it was not made: {0}")]
+    Audit(io::Error),
+
+    #[cfg(feature = "write")]
+    #[error("{0}")]
+    Write(#[from] WriteError),
+}
+
+impl ProfileError {
+    /// The code the profiles command exits with after this error
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            ProfileError::InvalidName(_)
+            | ProfileError::NotFound(_)
+            | ProfileError::Parse { .. }
+            | ProfileError::InvalidLevel { .. } => ExitCode::ConfigInvalid,
+            #[cfg(feature = "write")]
+            ProfileError::InvalidConfig { .. } => ExitCode::ConfigInvalid,
+            #[cfg(feature = "write")]
+            ProfileError::Open(error) => error.exit_code(),
+            _ => ExitCode::Failure,
+        }
+    }
+}
+
+/// A named configuration of the GPSDO, stored as `<name>.toml` in the profiles directory. The fields mirror the config
+/// endpoint, without the derived frequencies.
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Profile {
+    /// What the profile is for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+
+    /// Whether the output1 port of the GPSDO is active
+    output1: bool,
+
+    /// Whether the output2 port of the GPSDO is active, omitted for models with a single output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    output2: Option<bool>,
+
+    /// The drive level of the signal in milliamps
+    level: u8,
+
+    /// The parameters of the PLL which synthesizes the outputs
+    pll_params: ProfilePllParams,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ProfilePllParams {
+    /// The frequency produced by the GPSDO TCXO
+    fin: u32,
+
+    /// The divisor of fin before it enters the PLL
+    n3: u32,
+
+    /// The first divisor on the feedback loop
+    n2_hs: u8,
+
+    /// The second divisor on the feedback loop
+    n2_ls: u32,
+
+    /// The shared divisor on the output from the PLL
+    n1_hs: u8,
+
+    /// The divisor after n1_hs, heading to port one
+    nc1_ls: u32,
+
+    /// The divisor after n1_hs, heading to port two, omitted for models with a single output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    nc2_ls: Option<u32>,
+
+    /// The skew between port one and port two, 0 - 255
+    skew: u8,
+
+    /// The PLL bandwidth mode
+    bw: u8,
+}
+
+#[cfg(feature = "write")]
+impl Profile {
+    /// The configuration the profile sets on a GPSDO of the given model, or an error if it isn't a valid one
+    fn config(&self, model: GpsdoModel) -> Result<GpsdoConfig, GpsdoConfigError> {
+        let params = &self.pll_params;
+        let mut builder = GpsdoConfig::builder(model)
+            .output1(self.output1)
+            .output2(self.output2.unwrap_or(false))
+            .fin(params.fin)
+            .n3(params.n3)
+            .n2_hs(params.n2_hs)
+            .n2_ls(params.n2_ls)
+            .n1_hs(params.n1_hs)
+            .nc1_ls(params.nc1_ls)
+            .skew(params.skew)
+            .bandwidth(Bandwidth::from_raw(params.bw));
+
+        if let Some(level) = DriveLevel::from_milliamps(self.level) {
+            builder = builder.drive_level(level);
+        }
+        if let Some(nc2_ls) = params.nc2_ls {
+            builder = builder.nc2_ls(nc2_ls);
+        }
+
+        builder.build()
+    }
+}
+
+/// The profile which sets a configuration, as recorded in the audit log before and after a write
+#[cfg(feature = "write")]
+impl From<&GpsdoConfig> for Profile {
+    fn from(value: &GpsdoConfig) -> Self {
+        Profile {
+            description: None,
+            output1: value.output1(),
+            output2: value.output2(),
+            level: value.level_ma().milliamps().unwrap_or(0),
+            pll_params: ProfilePllParams {
+                fin: value.fin(),
+                n3: value.n3(),
+                n2_hs: value.n2_hs(),
+                n2_ls: value.n2_ls(),
+                n1_hs: value.n1_hs(),
+                nc1_ls: value.nc1_ls(),
+                nc2_ls: value.nc2_ls(),
+                skew: value.skew(),
+                bw: value.bw(),
+            },
+        }
+    }
+}
+
+/// A profile as listed by the profiles endpoint and command
+#[derive(Serialize, JsonSchema, Debug)]
+pub(crate) struct ProfileSummary {
+    /// The name of the profile, which is its file name without `.toml`
+    name: String,
+
+    /// What the profile is for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+}
+
+/// The directory the profiles are stored in
+#[derive(Debug, Clone)]
+pub(crate) struct ProfileStore {
+    dir: PathBuf,
+}
+
+impl ProfileStore {
+    pub(crate) fn new(dir: PathBuf) -> Self {
+        ProfileStore { dir }
+    }
+
+    /// List the profiles in the directory, sorted by name. A missing directory has no profiles.
+    pub(crate) fn list(&self) -> Result<Vec<ProfileSummary>, ProfileError> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(source) => {
+                return Err(ProfileError::Read {
+                    name: self.dir.display().to_string(),
+                    source,
+                })
+            }
+        };
+
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                let name = path.file_name()?.to_str()?.strip_suffix(".toml")?;
+
+                is_valid_name(name).then(|| name.to_owned())
+            })
+            .collect();
+        names.sort();
+
+        names
+            .into_iter()
+            .map(|name| {
+                let profile = self.load(&name)?;
+
+                Ok(ProfileSummary {
+                    name,
+                    description: profile.description,
+                })
+            })
+            .collect()
+    }
+
+    /// Read and validate the profile with the given name
+    pub(crate) fn load(&self, name: &str) -> Result<Profile, ProfileError> {
+        if !is_valid_name(name) {
+            return Err(ProfileError::InvalidName(name.to_owned()));
+        }
+
+        let contents = match fs::read_to_string(self.dir.join(format!("{}.toml", name))) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                return Err(ProfileError::NotFound(name.to_owned()))
+            }
+            Err(source) => {
+                return Err(ProfileError::Read {
+                    name: name.to_owned(),
+                    source,
+                })
+            }
+        };
+
+        let profile: Profile = toml::from_str(&contents).map_err(|source| ProfileError::Parse {
+            name: name.to_owned(),
+            source,
+        })?;
+
+        if DriveLevel::from_milliamps(profile.level).is_none() {
+            return Err(ProfileError::InvalidLevel {
+                name: name.to_owned(),
+                level: profile.level,
+            });
+        }
+
+        Ok(profile)
+    }
+
+    /// Open the GPSDO and apply the profile with the given name to it, see `apply_to`, for `profiles apply`. The daemon
+    /// applies profiles to the GPSDO its poll loop has open instead, see `DeviceQueue`.
+    #[cfg(feature = "write")]
+    pub(crate) fn apply(
+        &self,
+        name: &str,
+        opener: &DeviceOpener,
+        audit: &AuditLog,
+        writes: &DeviceWrites,
+        source: &str,
+    ) -> Result<Profile, ProfileError> {
+        let (interface, info) = opener.open()?;
+        let gpsdo = GpsdoDevice::with_model(interface, poll::model(&info));
+
+        self.apply_to(name, &gpsdo, audit, writes, source)
+    }
+
+    /// Apply the profile with the given name to the GPSDO behind the write lock with `GpsdoDevice::write_config`, then
+    /// read the config back to check the GPSDO took it. The config it replaced and the one written are recorded in the
+    /// audit log, and the poll loop reads the GPSDO once the write lock is released, see `DeviceWrites`.
+    #[cfg(feature = "write")]
+    pub(crate) fn apply_to<I: UsbInterface>(
+        &self,
+        name: &str,
+        gpsdo: &GpsdoDevice<I>,
+        audit: &AuditLog,
+        writes: &DeviceWrites,
+        source: &str,
+    ) -> Result<Profile, ProfileError>
+    where
+        I::InterfaceError: std::fmt::Display,
+    {
+        let profile = self.load(name)?;
+        let config =
+            profile
+                .config(gpsdo.model())
+                .map_err(|source| ProfileError::InvalidConfig {
+                    name: name.to_owned(),
+                    source,
+                })?;
+
+        let _write = writes.begin()?;
+        let record = audit.begin().map_err(ProfileError::Audit)?;
+
+        // A config which can't be read is still replaced, as the profile is how a garbled config is fixed
+        let before = gpsdo.config().ok().map(|before| Profile::from(&before));
+        let result = gpsdo
+            .write_config(&config)
+            .and_then(|_| gpsdo.config())
+            .map_err(|error| ProfileError::Device(error.to_string()))
+            .and_then(|written| {
+                if written.to_report_bytes() == config.to_report_bytes() {
+                    Ok(())
+                } else {
+                    Err(ProfileError::NotApplied)
+                }
+            });
+
+        record
+            .finish(
+                source,
+                &format!("apply profile {}", name),
+                before.map(|before| {
+                    serde_json::to_value(before).expect("failed to serialize profile")
+                }),
+                Some(
+                    serde_json::to_value(Profile::from(&config))
+                        .expect("failed to serialize profile"),
+                ),
+                result.as_ref().err().map(ToString::to_string),
+            )
+            .map_err(ProfileError::Audit)?;
+
+        result.map(|_| profile)
+    }
+}
+
+/// Profile names become file names, so they are restricted to characters which can't escape the profiles directory
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub(crate) struct ProfilesArgs {
+    #[command(subcommand)]
+    command: ProfilesCommand,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum ProfilesCommand {
+    /// List the profiles in the profiles directory
+    List,
+
+    /// Print a profile as JSON
+    Show { name: String },
+
+    /// Apply a profile to the GPSDO
+    #[cfg(feature = "write")]
+    Apply { name: String },
+}
+
+/// Run a profiles subcommand
+pub(crate) fn profiles(args: &Args, profiles_args: &ProfilesArgs) {
+    let store = ProfileStore::new(args.profiles_dir.clone());
+
+    let result = match &profiles_args.command {
+        ProfilesCommand::List => store.list().map(|profiles| {
+            for profile in profiles {
+                println!(
+                    "{}\t{}",
+                    profile.name,
+                    profile.description.unwrap_or_default()
+                );
+            }
+        }),
+        ProfilesCommand::Show { name } => store.load(name).map(|profile| {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&profile).expect("failed to serialize profile")
+            )
+        }),
+        #[cfg(feature = "write")]
+        ProfilesCommand::Apply { name } => store
+            .apply(
+                name,
+                &DeviceOpener::new(args),
+                &AuditLog::new(args.audit_log_path()),
+                &DeviceWrites::new(args.write_cooldown, PollTrigger::default()),
+                &audit::cli_source(),
+            )
+            .map(|_| eprintln!("applied profile {}", name)),
+    };
+
+    if let Err(error) = result {
+        eprintln!("{}", error);
+        error.exit_code().exit();
+    }
+}
+
+#[cfg(all(test, feature = "write"))]
+mod test {
+    use leo_status_driver::{
+        sim::{GpsdoSimulator, Scenario},
+        GpsdoDevice, GpsdoModel,
+    };
+
+    use std::{fs, path::PathBuf, time::Duration};
+
+    use super::{ProfileError, ProfileStore};
+    use crate::{
+        audit::AuditLog,
+        schedule::PollTrigger,
+        writes::{DeviceWrites, WriteError},
+    };
+
+    /// The profiles shipped in the repository
+    fn shipped_profiles() -> ProfileStore {
+        ProfileStore::new(PathBuf::from(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../profiles"
+        )))
+    }
+
+    /// An empty directory of its own for a test
+    fn test_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("leo-status-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("failed to create test directory");
+
+        dir
+    }
+
+    #[test]
+    fn shipped_profiles_are_valid_configs() {
+        let store = shipped_profiles();
+        let profiles = store.list().expect("failed to list profiles");
+        assert!(!profiles.is_empty());
+
+        for summary in profiles {
+            let profile = store.load(&summary.name).expect("failed to load profile");
+            profile
+                .config(GpsdoModel::Standard)
+                .unwrap_or_else(|error| panic!("profile {} is invalid: {}", summary.name, error));
+        }
+    }
+
+    #[test]
+    fn applying_a_profile_writes_it_and_records_the_config_it_replaced() {
+        let dir = test_dir("profile-apply");
+        let audit = AuditLog::new(dir.join("audit.jsonl"));
+        let writes = DeviceWrites::new(Duration::ZERO, PollTrigger::default());
+        let gpsdo = GpsdoDevice::new(GpsdoSimulator::new(Scenario::new(vec![])));
+        let replaced = gpsdo.config().expect("failed to read config");
+
+        shipped_profiles()
+            .apply_to("16m368", &gpsdo, &audit, &writes, "cli:test")
+            .expect("failed to apply profile");
+
+        let config = gpsdo.config().expect("failed to read config");
+        assert_eq!(config.checked_fout1(), Ok(16_368_000));
+        assert_eq!(config.checked_fout2(), Ok(Some(16_368_000)));
+
+        let entries = serde_json::to_value(audit.entries().expect("failed to read audit log"))
+            .expect("failed to serialize audit log");
+        assert_eq!(entries[0]["operation"], "apply profile 16m368");
+        assert_eq!(entries[0]["before"]["pll_params"]["n3"], replaced.n3());
+        assert_eq!(entries[0]["after"]["pll_params"]["n3"], 125);
+        assert!(entries[0].get("error").is_none());
+    }
+
+    #[test]
+    fn applying_a_profile_within_the_cooldown_is_refused_without_writing() {
+        let dir = test_dir("profile-cooldown");
+        let audit = AuditLog::new(dir.join("audit.jsonl"));
+        let writes = DeviceWrites::new(Duration::from_secs(60), PollTrigger::default());
+        let gpsdo = GpsdoDevice::new(GpsdoSimulator::new(Scenario::new(vec![])));
+        let store = shipped_profiles();
+
+        store
+            .apply_to("16m368", &gpsdo, &audit, &writes, "cli:test")
+            .expect("failed to apply profile");
+
+        assert!(matches!(
+            store.apply_to("10mhz", &gpsdo, &audit, &writes, "cli:test"),
+            Err(ProfileError::Write(WriteError::Cooldown(_)))
+        ));
+        assert_eq!(
+            gpsdo
+                .config()
+                .expect("failed to read config")
+                .checked_fout1(),
+            Ok(16_368_000)
+        );
+        assert_eq!(audit.entries().expect("failed to read audit log").len(), 1);
+    }
+}
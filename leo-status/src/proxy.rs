@@ -0,0 +1,310 @@
+use leo_status_driver::UsbInterface;
+#[cfg(feature = "write")]
+use leo_status_driver::{reports::Report, GpsdoConfig, GpsdoModel};
+use tiny_http::{Method, Request, Response};
+
+#[cfg(feature = "write")]
+use std::io::Read;
+use std::{io::Cursor, sync::Arc};
+
+#[cfg(feature = "write")]
+use crate::{audit::AuditLog, http, poll::PolledInterface, writes::DeviceWrites};
+use crate::{
+    auth::{AuthProvider, StaticTokens},
+    poll::PolledDevice,
+};
+
+/// The largest report accepted or returned by the proxy, beyond any the GPSDO uses
+const MAX_REPORT_LENGTH: usize = 256;
+
+/// The device proxy, which serves the GPSDO to `GpsdoRemoteInterface` on other hosts at `/proxy/`. Every request must
+/// carry the proxy token as a bearer token, and is run on the device the poll loop has open, between its polls.
+#[derive(Clone)]
+pub(crate) struct DeviceProxy {
+    token: Arc<StaticTokens>,
+}
+
+/// A request to the proxy, read from the HTTP request so it can be handled on a thread of its own
+pub(crate) enum ProxyRequest {
+    InputReport,
+    GetFeatureReport(u8),
+    #[cfg(feature = "write")]
+    SendFeatureReport {
+        report_id: u8,
+        data: Vec<u8>,
+        source: String,
+    },
+    SerialNumber,
+    FirmwareVersion,
+    DeviceInfo,
+}
+
+impl DeviceProxy {
+    pub(crate) fn new(token: String) -> Self {
+        DeviceProxy {
+            token: Arc::new(StaticTokens::new(vec![token])),
+        }
+    }
+
+    /// Read a request to `/proxy/<path>`, answering with an error response if it is unauthorized or unknown
+    pub(crate) fn parse(
+        &self,
+        request: &mut Request,
+    ) -> Result<ProxyRequest, Response<Cursor<Vec<u8>>>> {
+        if self.token.authenticate(request).is_err() {
+            return Err(Response::from_string("Unauthorized").with_status_code(401));
+        }
+
+        let path = request
+            .url()
+            .trim_start_matches("/proxy/")
+            .trim_end_matches('/');
+        let report_id = path
+            .strip_prefix("feature-report/")
+            .map(|report_id| {
+                report_id
+                    .parse::<u8>()
+                    .map_err(|_| Response::from_string("Not Found").with_status_code(404))
+            })
+            .transpose()?;
+
+        match (request.method(), path, report_id) {
+            (Method::Get, "input-report", _) => Ok(ProxyRequest::InputReport),
+            (Method::Get, "serial-number", _) => Ok(ProxyRequest::SerialNumber),
+            (Method::Get, "firmware-version", _) => Ok(ProxyRequest::FirmwareVersion),
+            (Method::Get, "device-info", _) => Ok(ProxyRequest::DeviceInfo),
+            (Method::Get, _, Some(report_id)) => Ok(ProxyRequest::GetFeatureReport(report_id)),
+            #[cfg(feature = "write")]
+            (Method::Post, _, Some(report_id)) => {
+                let source = match request.remote_addr() {
+                    Some(addr) => format!("http:{}", addr.ip()),
+                    None => "http:unknown".to_owned(),
+                };
+
+                let mut data = vec![];
+                request
+                    .as_reader()
+                    .take(MAX_REPORT_LENGTH as u64 + 1)
+                    .read_to_end(&mut data)
+                    .map_err(|error| {
+                        Response::from_string(format!("Failed to read body: {}", error))
+                            .with_status_code(400)
+                    })?;
+                if data.len() > MAX_REPORT_LENGTH {
+                    return Err(Response::from_string("Report is too large").with_status_code(413));
+                }
+
+                Ok(ProxyRequest::SendFeatureReport {
+                    report_id,
+                    data,
+                    source,
+                })
+            }
+            (_, "input-report" | "serial-number" | "firmware-version" | "device-info", _)
+            | (_, _, Some(_)) => {
+                Err(Response::from_string("Method Not Allowed").with_status_code(405))
+            }
+            _ => Err(Response::from_string("Not Found").with_status_code(404)),
+        }
+    }
+
+    /// Make the request of the GPSDO the poll loop has open, answering 204 No Content where it has nothing to give
+    pub(crate) fn handle(
+        &self,
+        request: ProxyRequest,
+        gpsdo: &PolledDevice,
+        #[cfg(feature = "write")] audit: &AuditLog,
+        #[cfg(feature = "write")] writes: &DeviceWrites,
+    ) -> Response<Cursor<Vec<u8>>> {
+        let interface = gpsdo.interface();
+
+        let mut buf = [0u8; MAX_REPORT_LENGTH];
+        let result = match request {
+            ProxyRequest::InputReport => interface
+                .hid_read(&mut buf)
+                .map(|size| Some(buf[..size].to_vec())),
+            ProxyRequest::GetFeatureReport(report_id) => interface
+                .hid_get_feature_report(report_id, &mut buf)
+                .map(|size| Some(buf[..size].to_vec())),
+            #[cfg(feature = "write")]
+            ProxyRequest::SendFeatureReport {
+                report_id,
+                data,
+                source,
+            } => {
+                return send_feature_report(
+                    interface,
+                    gpsdo.model(),
+                    audit,
+                    writes,
+                    &source,
+                    report_id,
+                    &data,
+                );
+            }
+            ProxyRequest::SerialNumber => interface
+                .serial_number()
+                .map(|serial_number| serial_number.map(String::into_bytes)),
+            ProxyRequest::FirmwareVersion => interface
+                .firmware_version()
+                .map(|version| version.map(|version| version.to_string().into_bytes())),
+            ProxyRequest::DeviceInfo => interface.device_info().map(|info| {
+                info.map(|info| serde_json::to_vec(&info).expect("failed to serialize device info"))
+            }),
+        };
+
+        match result {
+            Ok(Some(body)) => Response::from_data(body),
+            Ok(None) => Response::from_data(vec![]).with_status_code(204),
+            Err(error) => {
+                Response::from_string(format!("Failed to communicate with GPSDO: {}", error))
+                    .with_status_code(502)
+            }
+        }
+    }
+}
+
+/// Send a feature report to the GPSDO behind the write lock, recording the report it replaced and the one sent in the
+/// audit log. Any report other than a valid config report is refused with 422, see `check_report`.
+#[cfg(feature = "write")]
+fn send_feature_report(
+    interface: &PolledInterface,
+    model: GpsdoModel,
+    audit: &AuditLog,
+    writes: &DeviceWrites,
+    source: &str,
+    report_id: u8,
+    data: &[u8],
+) -> Response<Cursor<Vec<u8>>> {
+    if let Err(error) = check_report(model, report_id, data) {
+        return Response::from_string(error).with_status_code(422);
+    }
+
+    let _write = match writes.begin() {
+        Ok(write) => write,
+        Err(error) => return http::write_error_response(&error),
+    };
+
+    let record = match audit.begin() {
+        Ok(record) => record,
+        Err(error) => {
+            return Response::from_string(format!(
+                "Failed to record write in the audit log, so it was not made: {}",
+                error
+            ))
+            .with_status_code(500)
+        }
+    };
+
+    let mut buf = [0u8; MAX_REPORT_LENGTH];
+    let before = interface
+        .hid_get_feature_report(report_id, &mut buf)
+        .ok()
+        .map(|size| hex(&buf[..size]).into());
+
+    let result = interface.hid_send_feature_report(report_id, data);
+
+    let recorded = record.finish(
+        source,
+        &format!("send feature report {}", report_id),
+        before,
+        Some(hex(data).into()),
+        result.as_ref().err().map(ToString::to_string),
+    );
+
+    match (result, recorded) {
+        (Err(error), _) => {
+            Response::from_string(format!("Failed to communicate with GPSDO: {}", error))
+                .with_status_code(502)
+        }
+        (Ok(()), Err(error)) => Response::from_string(format!(
+            "Report was sent but not recorded in the audit log: {}",
+            error
+        ))
+        .with_status_code(500),
+        (Ok(()), Ok(())) => Response::from_data(vec![]).with_status_code(204),
+    }
+}
+
+/// Check a report sent through the proxy the way `GpsdoDevice::write_config` checks a config before writing it: only
+/// the config report may be written, and it must hold a config which passes `GpsdoConfig::validate` for the model of
+/// the GPSDO. Any other report is refused, as nothing would check what it does to the GPSDO.
+#[cfg(feature = "write")]
+fn check_report(model: GpsdoModel, report_id: u8, data: &[u8]) -> Result<(), String> {
+    match Report::from_feature_report_id(report_id) {
+        Some(Report::Config) => {}
+        Some(Report::Status) | None => {
+            return Err(format!(
+                "refusing to write feature report {}, only the config report may be written",
+                report_id
+            ))
+        }
+    }
+
+    let config = GpsdoConfig::from_report_bytes(model, data)
+        .map_err(|error| format!("refusing to write config report: {}", error))?;
+
+    match config.violations().next() {
+        Some(error) => Err(format!(
+            "refusing to write invalid configuration: {}",
+            error
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Format a report as hex, for the audit log
+#[cfg(feature = "write")]
+fn hex(data: &[u8]) -> String {
+    data.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(all(test, feature = "write"))]
+mod test {
+    use leo_status_driver::{
+        reports::CONFIG_REPORT_ID, Bandwidth, DriveLevel, GpsdoConfig, GpsdoModel,
+    };
+
+    use super::check_report;
+
+    fn config_report() -> [u8; 60] {
+        GpsdoConfig::builder(GpsdoModel::Standard)
+            .drive_level(DriveLevel::Ma8)
+            .fin(4_296_875)
+            .n3(30)
+            .n2_hs(10)
+            .n2_ls(3840)
+            .n1_hs(11)
+            .nc1_ls(50)
+            .nc2_ls(50)
+            .bandwidth(Bandwidth::from_raw(15))
+            .build()
+            .expect("valid config")
+            .to_report_bytes()
+    }
+
+    #[test]
+    fn valid_config_reports_are_passed_on() {
+        assert_eq!(
+            check_report(GpsdoModel::Standard, CONFIG_REPORT_ID, &config_report()),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn invalid_config_reports_are_refused() {
+        let mut report = config_report();
+        // An n1_hs of 12, beyond the highest high speed divider
+        report[12] = 8;
+
+        assert!(check_report(GpsdoModel::Standard, CONFIG_REPORT_ID, &report).is_err());
+        assert!(check_report(GpsdoModel::Standard, CONFIG_REPORT_ID, &[0u8; 60]).is_err());
+        assert!(check_report(GpsdoModel::Standard, CONFIG_REPORT_ID, &report[..10]).is_err());
+    }
+
+    #[test]
+    fn other_reports_are_refused() {
+        assert!(check_report(GpsdoModel::Standard, 1, &[0u8; 4]).is_err());
+        assert!(check_report(GpsdoModel::Standard, 0, &config_report()).is_err());
+    }
+}
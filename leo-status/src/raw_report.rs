@@ -0,0 +1,126 @@
+use clap::Args as ClapArgs;
+use leo_status_driver::{
+    interface::GpsdoRemoteInterface,
+    reports::{Report, CONFIG_REPORT_BUFFER_LEN},
+    UsbInterface,
+};
+
+use std::fmt::Display;
+
+use crate::{exit::ExitCode, poll, Args};
+
+/// The number of bytes shown on each line of the hex dump
+const BYTES_PER_LINE: usize = 16;
+
+#[derive(ClapArgs, Debug, Clone)]
+pub(crate) struct RawReportArgs {
+    #[arg(help = "Identifier of the feature report to read")]
+    report_id: u8,
+
+    #[arg(
+        long,
+        default_value_t = CONFIG_REPORT_BUFFER_LEN,
+        help = "Size of the buffer to read the report into, in bytes"
+    )]
+    len: usize,
+
+    #[arg(
+        long,
+        help = "URL of a leo-status instance serving its GPSDO with --proxy-token, to read the report from instead of a local GPSDO"
+    )]
+    remote_url: Option<String>,
+
+    #[arg(
+        long,
+        requires = "remote_url",
+        env = "LEO_STATUS_REMOTE_TOKEN",
+        hide_env_values = true,
+        default_value = "",
+        help = "Proxy token of the remote instance"
+    )]
+    remote_token: String,
+}
+
+/// Read a feature report from the selected GPSDO, or the GPSDO of a remote instance, and print it as a hex dump
+pub(crate) fn raw_report(args: &Args, raw_report_args: &RawReportArgs) {
+    if let Some(remote_url) = &raw_report_args.remote_url {
+        let interface = GpsdoRemoteInterface::new(remote_url, &raw_report_args.remote_token);
+
+        return print_report(&interface, raw_report_args);
+    }
+
+    let (interface, _) = poll::open(args).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        error.exit_code().exit();
+    });
+
+    print_report(&interface, raw_report_args);
+}
+
+fn print_report<I>(interface: &I, raw_report_args: &RawReportArgs)
+where
+    I: UsbInterface,
+    I::InterfaceError: Display,
+{
+    let mut buf = vec![0u8; raw_report_args.len.max(1)];
+    let size = interface
+        .hid_get_feature_report(raw_report_args.report_id, &mut buf)
+        .unwrap_or_else(|error| {
+            eprintln!(
+                "failed to read feature report {}: {}",
+                raw_report_args.report_id, error
+            );
+            ExitCode::PollFailure.exit();
+        });
+
+    let data = &buf[..size.min(buf.len())];
+
+    match Report::from_feature_report_id(raw_report_args.report_id) {
+        Some(report) => println!(
+            "feature report {} ({}), {} bytes received",
+            raw_report_args.report_id,
+            report.name(),
+            size
+        ),
+        None => println!(
+            "feature report {}, {} bytes received",
+            raw_report_args.report_id, size
+        ),
+    }
+    print!("{}", hex_dump(data));
+}
+
+/// Format bytes in the canonical hex + ASCII layout of `hexdump -C`
+fn hex_dump(data: &[u8]) -> String {
+    let mut output = String::new();
+
+    for (line, chunk) in data.chunks(BYTES_PER_LINE).enumerate() {
+        let mut hex = String::new();
+        for (i, byte) in chunk.iter().enumerate() {
+            if i == BYTES_PER_LINE / 2 {
+                hex.push(' ');
+            }
+            hex.push_str(&format!("{:02x} ", byte));
+        }
+
+        let ascii: String = chunk
+            .iter()
+            .map(|&byte| {
+                if byte.is_ascii_graphic() || byte == b' ' {
+                    byte as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+
+        output.push_str(&format!(
+            "{:08x}  {:<50}|{}|\n",
+            line * BYTES_PER_LINE,
+            hex,
+            ascii
+        ));
+    }
+
+    output
+}
@@ -0,0 +1,86 @@
+use prometheus::IntCounter;
+
+use std::time::{Duration, Instant};
+
+use crate::metrics::Metrics;
+
+/// Collapses repeats of the same error into a summary every `--error-repeat-interval`, so an error which persists, such
+/// as an unplugged GPSDO being re-opened, or a webhook endpoint which is down, doesn't flood the log
+pub(crate) struct RepeatedErrors {
+    /// Where the errors come from, such as `poll` or the name of a sink
+    source: String,
+    interval: Duration,
+
+    /// Counts the repeats left out of the log
+    suppressed: IntCounter,
+
+    last: Option<LastError>,
+}
+
+/// The error last logged, and the repeats of it since
+struct LastError {
+    error: String,
+    logged_at: Instant,
+    repeats: u64,
+}
+
+impl RepeatedErrors {
+    pub(crate) fn new(source: &str, interval: Duration, metrics: &Metrics) -> Self {
+        RepeatedErrors {
+            source: source.to_owned(),
+            interval,
+            suppressed: metrics.errors_suppressed.with_label_values(&[source]),
+            last: None,
+        }
+    }
+
+    /// Log `line` for an error, unless it repeats the last error logged within the interval, in which case it is only
+    /// counted. Once the interval has passed, the next repeat is logged with the number left out.
+    pub(crate) fn log(&mut self, error: &str, line: impl FnOnce() -> String) {
+        match &mut self.last {
+            Some(last) if last.error == error => {
+                if last.logged_at.elapsed() < self.interval {
+                    last.repeats += 1;
+                    self.suppressed.inc();
+                    return;
+                }
+
+                if last.repeats == 0 {
+                    tracing::warn!("{}", line());
+                } else {
+                    tracing::warn!(
+                        "{} (repeated {} times in the last {})",
+                        line(),
+                        last.repeats,
+                        humantime::format_duration(Duration::from_secs(
+                            last.logged_at.elapsed().as_secs()
+                        ))
+                    );
+                }
+                last.logged_at = Instant::now();
+                last.repeats = 0;
+            }
+            _ => {
+                self.clear();
+                tracing::warn!("{}", line());
+                self.last = Some(LastError {
+                    error: error.to_owned(),
+                    logged_at: Instant::now(),
+                    repeats: 0,
+                });
+            }
+        }
+    }
+
+    /// Forget the last error, such as once whatever failed has recovered, logging how many repeats of it were left out
+    pub(crate) fn clear(&mut self) {
+        if let Some(last) = self.last.take().filter(|last| last.repeats > 0) {
+            tracing::info!(
+                "{} error repeated {} more times before it stopped: {}",
+                self.source,
+                last.repeats,
+                last.error
+            );
+        }
+    }
+}
@@ -0,0 +1,300 @@
+use landlock::{
+    make_bitflags, path_beneath_rules, Access, AccessFs, Ruleset, RulesetAttr, RulesetCreatedAttr,
+    RulesetError, RulesetStatus, ABI,
+};
+use seccompiler::{BpfProgram, SeccompAction, SeccompFilter, TargetArch};
+use thiserror::Error;
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{clock::ClockSourceSpec, Args};
+
+/// The newest Landlock ABI the rules are written for. Older kernels enforce as much of it as they support.
+const LANDLOCK_ABI: ABI = ABI::V3;
+
+/// What the sinks read to resolve host names, and the time zone of local timestamps. TLS roots are built in.
+const SYSTEM_PATHS: &[&str] = &[
+    "/etc/resolv.conf",
+    "/etc/hosts",
+    "/etc/nsswitch.conf",
+    "/etc/gai.conf",
+    "/etc/host.conf",
+    "/etc/localtime",
+    "/usr/share/zoneinfo",
+];
+
+/// Where chronyc and the libraries it loads are found, for `--clock-source chrony`
+const PROGRAM_PATHS: &[&str] = &[
+    "/usr",
+    "/lib",
+    "/lib64",
+    "/bin",
+    "/sbin",
+    "/etc/ld.so.cache",
+];
+
+/// Where chronyc binds the socket it talks to chronyd over
+const CHRONY_SOCKET_DIR: &str = "/run/chrony";
+
+#[derive(Debug, Error)]
+/// An error which stopped the daemon sandboxing itself
+pub(crate) enum SandboxError {
+    #[error("landlock is not supported by this kernel")]
+    Unsupported,
+
+    #[error("{} must be an absolute path with --sandbox", .0.display())]
+    RelativePath(PathBuf),
+
+    #[error("failed to create {}: {error}", path.display())]
+    CreateDir { path: PathBuf, error: io::Error },
+
+    #[error("failed to restrict the daemon with landlock: {0}")]
+    Landlock(#[from] RulesetError),
+
+    #[error("failed to restrict the daemon with seccomp: {0}")]
+    Seccomp(#[from] seccompiler::Error),
+}
+
+/// Restrict the daemon once the GPSDO is open. Landlock limits the filesystem to writing the state the command line
+/// arguments point at, such as the journal, and reading what the sinks need to resolve host names, and seccomp limits
+/// the system calls to those the daemon makes, failing any other with `EPERM`. Descriptors which are already open, the
+/// GPSDO's and its lock file, are unaffected, while no device can be opened anew.
+///
+/// Both only cover the calling thread and the threads it starts afterwards, so they are applied before any are started.
+pub(crate) fn restrict(args: &Args) -> Result<(), SandboxError> {
+    restrict_filesystem(args)?;
+    restrict_syscalls(args)
+}
+
+fn restrict_filesystem(args: &Args) -> Result<(), SandboxError> {
+    let read = make_bitflags!(AccessFs::{ReadFile | ReadDir});
+    let write = read | AccessFs::from_write(LANDLOCK_ABI);
+    let execute = read | AccessFs::Execute;
+
+    let state = writable_dirs(args)?;
+    for dir in &state {
+        fs::create_dir_all(dir).map_err(|error| SandboxError::CreateDir {
+            path: dir.clone(),
+            error,
+        })?;
+    }
+
+    let mut readable = vec![args.profiles_dir.clone()];
+    if let Some(ClockSourceSpec::File(path)) = &args.clock_source {
+        readable.push(path.clone());
+    }
+
+    let chrony = matches!(args.clock_source, Some(ClockSourceSpec::Chrony));
+
+    let status = Ruleset::default()
+        .handle_access(AccessFs::from_all(LANDLOCK_ABI))?
+        .create()?
+        .add_rules(path_beneath_rules(SYSTEM_PATHS, read))?
+        .add_rules(path_beneath_rules(readable, read))?
+        .add_rules(path_beneath_rules(&state, write))?
+        .add_rules(path_beneath_rules(
+            if chrony { PROGRAM_PATHS } else { &[] },
+            execute,
+        ))?
+        .add_rules(path_beneath_rules(
+            if chrony {
+                &[CHRONY_SOCKET_DIR][..]
+            } else {
+                &[]
+            },
+            write,
+        ))?
+        .restrict_self()?;
+
+    match status.ruleset {
+        RulesetStatus::NotEnforced => Err(SandboxError::Unsupported),
+        RulesetStatus::FullyEnforced | RulesetStatus::PartiallyEnforced => Ok(()),
+    }
+}
+
+/// The directories the daemon writes in. Files are replaced by renaming a temporary file beside them, so it is their
+/// directories which need to be writable. They must be absolute, so a bare file name doesn't open up the working
+/// directory, which is `/` for many services.
+fn writable_dirs(args: &Args) -> Result<Vec<PathBuf>, SandboxError> {
+    let mut files = vec![];
+    if args.journal_store.uses_journal_path() && !args.low_resource {
+        files.push(args.journal_path());
+    }
+    #[cfg(feature = "write")]
+    files.push(args.audit_log_path());
+    if let Some(port_file) = &args.port_file {
+        files.push(port_file.clone());
+    }
+
+    let mut dirs = vec![args.state_dir.clone()];
+    for file in files {
+        dirs.push(
+            absolute(&file)?
+                .parent()
+                .unwrap_or(Path::new("/"))
+                .to_owned(),
+        );
+    }
+    if let Some(textfile_dir) = &args.textfile_dir {
+        dirs.push(absolute(textfile_dir)?.to_owned());
+    }
+
+    Ok(dirs)
+}
+
+fn absolute(path: &Path) -> Result<&Path, SandboxError> {
+    if path.is_absolute() {
+        Ok(path)
+    } else {
+        Err(SandboxError::RelativePath(path.to_owned()))
+    }
+}
+
+fn restrict_syscalls(args: &Args) -> Result<(), SandboxError> {
+    let mut syscalls = SYSCALLS.to_vec();
+    #[cfg(target_arch = "x86_64")]
+    syscalls.extend_from_slice(X86_64_SYSCALLS);
+    if matches!(args.clock_source, Some(ClockSourceSpec::Chrony)) {
+        syscalls.extend_from_slice(CHRONY_SYSCALLS);
+    }
+
+    let arch = TargetArch::try_from(std::env::consts::ARCH).map_err(seccompiler::Error::from)?;
+    let filter = SeccompFilter::new(
+        syscalls
+            .into_iter()
+            .map(|syscall| (syscall, vec![]))
+            .collect(),
+        SeccompAction::Errno(libc::EPERM as u32),
+        SeccompAction::Allow,
+        arch,
+    )
+    .map_err(seccompiler::Error::from)?;
+    let program = BpfProgram::try_from(filter).map_err(seccompiler::Error::from)?;
+
+    Ok(seccompiler::apply_filter_all_threads(&program)?)
+}
+
+/// The system calls the daemon makes once running: the memory and threads of the runtime, reading the GPSDO through
+/// its open descriptor, serving HTTP, connecting to the sinks, and writing its state
+const SYSCALLS: &[libc::c_long] = &[
+    // Memory, threads and signals
+    libc::SYS_brk,
+    libc::SYS_mmap,
+    libc::SYS_munmap,
+    libc::SYS_mremap,
+    libc::SYS_mprotect,
+    libc::SYS_madvise,
+    libc::SYS_clone,
+    libc::SYS_clone3,
+    libc::SYS_futex,
+    libc::SYS_set_robust_list,
+    libc::SYS_rseq,
+    libc::SYS_sched_yield,
+    libc::SYS_sched_getaffinity,
+    libc::SYS_rt_sigaction,
+    libc::SYS_rt_sigprocmask,
+    libc::SYS_rt_sigreturn,
+    libc::SYS_sigaltstack,
+    libc::SYS_gettid,
+    libc::SYS_getpid,
+    libc::SYS_tgkill,
+    libc::SYS_exit,
+    libc::SYS_exit_group,
+    libc::SYS_restart_syscall,
+    libc::SYS_prlimit64,
+    libc::SYS_getrandom,
+    libc::SYS_uname,
+    libc::SYS_getuid,
+    libc::SYS_geteuid,
+    libc::SYS_getgid,
+    libc::SYS_getegid,
+    // Time
+    libc::SYS_clock_gettime,
+    libc::SYS_clock_nanosleep,
+    libc::SYS_nanosleep,
+    libc::SYS_gettimeofday,
+    // Files and the GPSDO's descriptor, whose feature reports are ioctls
+    libc::SYS_read,
+    libc::SYS_write,
+    libc::SYS_readv,
+    libc::SYS_writev,
+    libc::SYS_pread64,
+    libc::SYS_pwrite64,
+    libc::SYS_ioctl,
+    libc::SYS_openat,
+    libc::SYS_close,
+    libc::SYS_lseek,
+    libc::SYS_fstat,
+    libc::SYS_newfstatat,
+    libc::SYS_statx,
+    libc::SYS_fcntl,
+    libc::SYS_flock,
+    libc::SYS_fsync,
+    libc::SYS_fdatasync,
+    libc::SYS_ftruncate,
+    libc::SYS_getdents64,
+    libc::SYS_readlinkat,
+    libc::SYS_faccessat,
+    libc::SYS_faccessat2,
+    libc::SYS_mkdirat,
+    libc::SYS_unlinkat,
+    libc::SYS_renameat,
+    libc::SYS_renameat2,
+    libc::SYS_getcwd,
+    libc::SYS_dup,
+    libc::SYS_dup3,
+    libc::SYS_pipe2,
+    libc::SYS_ppoll,
+    libc::SYS_epoll_create1,
+    libc::SYS_epoll_ctl,
+    libc::SYS_epoll_pwait,
+    libc::SYS_eventfd2,
+    // Serving HTTP and connecting to the sinks
+    libc::SYS_socket,
+    libc::SYS_socketpair,
+    libc::SYS_bind,
+    libc::SYS_listen,
+    libc::SYS_accept,
+    libc::SYS_accept4,
+    libc::SYS_connect,
+    libc::SYS_shutdown,
+    libc::SYS_getsockname,
+    libc::SYS_getpeername,
+    libc::SYS_setsockopt,
+    libc::SYS_getsockopt,
+    libc::SYS_sendto,
+    libc::SYS_recvfrom,
+    libc::SYS_sendmsg,
+    libc::SYS_recvmsg,
+    libc::SYS_sendmmsg,
+];
+
+/// The older system calls x86_64 has beside the generic ones above, which its libc may still make
+#[cfg(target_arch = "x86_64")]
+const X86_64_SYSCALLS: &[libc::c_long] = &[
+    libc::SYS_arch_prctl,
+    libc::SYS_open,
+    libc::SYS_stat,
+    libc::SYS_lstat,
+    libc::SYS_access,
+    libc::SYS_readlink,
+    libc::SYS_mkdir,
+    libc::SYS_unlink,
+    libc::SYS_rename,
+    libc::SYS_poll,
+    libc::SYS_epoll_wait,
+    libc::SYS_dup2,
+    libc::SYS_pipe,
+    libc::SYS_time,
+];
+
+/// Running chronyc for `--clock-source chrony`
+const CHRONY_SYSCALLS: &[libc::c_long] = &[
+    libc::SYS_execve,
+    libc::SYS_wait4,
+    libc::SYS_prctl,
+    libc::SYS_set_tid_address,
+];
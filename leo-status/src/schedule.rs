@@ -0,0 +1,356 @@
+use chrono::{NaiveTime, Utc};
+use clap::ValueEnum;
+use leo_status_driver::state::DeviceState;
+
+use std::{
+    fmt,
+    str::FromStr,
+    sync::{Arc, Condvar, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{snapshot::Snapshot, supervisor::Heartbeat, Args};
+
+/// How often a wait for the next poll updates the heartbeat, so the supervisor knows the loop is alive however long
+/// the schedule waits
+const HEARTBEAT_STEP: Duration = Duration::from_secs(1);
+
+/// How the poll loop decides when to read the GPSDO, chosen with `--schedule`
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum ScheduleKind {
+    /// Every `--interval`
+    #[default]
+    Fixed,
+
+    /// Every `--interval` while the GPSDO is locked, and every `--unlocked-interval` while it isn't
+    Adaptive,
+
+    /// Every `--interval` within the `--poll-window` windows, and not at all between them
+    Windows,
+
+    /// Only when `POST /poll` is received or a write to the GPSDO finishes
+    Trigger,
+}
+
+impl ScheduleKind {
+    /// Create the scheduler of this kind configured by the command line arguments
+    pub(crate) fn scheduler(self, args: &Args) -> Box<dyn Scheduler> {
+        let interval = args.interval.expect("interval is required when polling");
+
+        match self {
+            ScheduleKind::Fixed => Box::new(FixedInterval(interval)),
+            ScheduleKind::Adaptive => Box::new(Adaptive {
+                locked: interval,
+                unlocked: args.unlocked_interval,
+            }),
+            ScheduleKind::Windows => Box::new(Windows {
+                interval,
+                windows: args.poll_window.clone(),
+            }),
+            ScheduleKind::Trigger => Box::new(Trigger),
+        }
+    }
+}
+
+/// Decides when the poll loop reads the GPSDO next. Whatever the schedule, a `PollTrigger` can wake the loop sooner.
+pub(crate) trait Scheduler: Send {
+    /// How long to wait after the poll which read `snapshot` before the next, `None` to wait for a trigger
+    fn next_poll(&self, snapshot: &Snapshot) -> Option<Duration>;
+}
+
+/// Polls at a fixed interval
+struct FixedInterval(Duration);
+
+impl Scheduler for FixedInterval {
+    fn next_poll(&self, _snapshot: &Snapshot) -> Option<Duration> {
+        Some(self.0)
+    }
+}
+
+/// Polls more often while the GPSDO is unlocked, to follow it closely as it regains lock
+struct Adaptive {
+    locked: Duration,
+    unlocked: Duration,
+}
+
+impl Scheduler for Adaptive {
+    fn next_poll(&self, snapshot: &Snapshot) -> Option<Duration> {
+        if snapshot.state == DeviceState::Locked {
+            Some(self.locked)
+        } else {
+            Some(self.unlocked)
+        }
+    }
+}
+
+/// Polls at an interval within daily windows, such as observation windows, sleeping until the next one starts
+struct Windows {
+    interval: Duration,
+    windows: Vec<PollWindow>,
+}
+
+impl Scheduler for Windows {
+    fn next_poll(&self, _snapshot: &Snapshot) -> Option<Duration> {
+        let now = Utc::now().time();
+
+        if self.windows.iter().any(|window| window.contains(now)) {
+            return Some(self.interval);
+        }
+
+        self.windows
+            .iter()
+            .map(|window| window.until_start(now))
+            .min()
+    }
+}
+
+/// Only polls when triggered
+struct Trigger;
+
+impl Scheduler for Trigger {
+    fn next_poll(&self, _snapshot: &Snapshot) -> Option<Duration> {
+        None
+    }
+}
+
+/// A daily window of time in UTC, such as `22:00-04:30`, which may span midnight, to poll within or for maintenance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PollWindow {
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl FromStr for PollWindow {
+    type Err = String;
+
+    fn from_str(window: &str) -> Result<Self, Self::Err> {
+        let parse = |time: &str| NaiveTime::parse_from_str(time.trim(), "%H:%M").ok();
+
+        match window
+            .split_once('-')
+            .map(|(start, end)| (parse(start), parse(end)))
+        {
+            Some((Some(start), Some(end))) if start != end => Ok(PollWindow { start, end }),
+            _ => Err(format!(
+                "invalid poll window {:?}, expected HH:MM-HH:MM in UTC",
+                window
+            )),
+        }
+    }
+}
+
+impl fmt::Display for PollWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M")
+        )
+    }
+}
+
+impl PollWindow {
+    pub(crate) fn contains(&self, time: NaiveTime) -> bool {
+        if self.start < self.end {
+            self.start <= time && time < self.end
+        } else {
+            self.start <= time || time < self.end
+        }
+    }
+
+    /// How long from `time` until the window next starts
+    fn until_start(&self, time: NaiveTime) -> Duration {
+        let millis = self.start.signed_duration_since(time).num_milliseconds();
+
+        Duration::from_millis(millis.rem_euclid(24 * 60 * 60 * 1000) as u64)
+    }
+}
+
+/// Wakes the poll loop to read the GPSDO before its schedule would, such as on `POST /poll` or after a write, or to run
+/// the requests waiting on its device, see `DeviceQueue`
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PollTrigger {
+    wakes: Arc<(Mutex<Wakes>, Condvar)>,
+}
+
+/// What the poll loop has been woken for, see `PollTrigger`
+#[derive(Debug, Default)]
+struct Wakes {
+    poll: bool,
+    jobs: bool,
+}
+
+/// Why `PollTrigger::wait` returned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Wake {
+    /// The GPSDO is due to be read, as scheduled or triggered
+    Poll,
+
+    /// Requests are waiting to run on the poll loop's device
+    Jobs,
+}
+
+impl PollTrigger {
+    /// Poll as soon as the loop is waiting, or straight after the poll in progress
+    pub(crate) fn trigger(&self) {
+        let (wakes, condvar) = &*self.wakes;
+
+        wakes.lock().unwrap().poll = true;
+        condvar.notify_all();
+    }
+
+    /// Run the requests waiting on the device as soon as the loop is waiting, without polling
+    pub(crate) fn wake_for_jobs(&self) {
+        let (wakes, condvar) = &*self.wakes;
+
+        wakes.lock().unwrap().jobs = true;
+        condvar.notify_all();
+    }
+
+    /// Wait until `deadline`, or until triggered if there is none, returning early when triggered or woken for jobs.
+    /// Jobs are reported before a trigger, so the poll they're waiting alongside doesn't hold them up. The heartbeat
+    /// is updated every second throughout.
+    pub(crate) fn wait(&self, deadline: Option<Instant>, heartbeat: &Heartbeat) -> Wake {
+        let (wakes, condvar) = &*self.wakes;
+
+        let mut wakes = wakes.lock().unwrap();
+        loop {
+            if wakes.jobs {
+                wakes.jobs = false;
+                return Wake::Jobs;
+            }
+            if wakes.poll {
+                wakes.poll = false;
+                return Wake::Poll;
+            }
+
+            let step = match deadline {
+                Some(deadline) => deadline.saturating_duration_since(Instant::now()),
+                None => HEARTBEAT_STEP,
+            };
+            if step.is_zero() {
+                return Wake::Poll;
+            }
+
+            wakes = condvar
+                .wait_timeout(wakes, step.min(HEARTBEAT_STEP))
+                .unwrap()
+                .0;
+            heartbeat.beat();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PollTrigger, PollWindow, Wake};
+    use crate::supervisor::Heartbeat;
+
+    use chrono::NaiveTime;
+
+    use std::time::{Duration, Instant};
+
+    fn time(hour: u32, min: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, min, 0).unwrap()
+    }
+
+    fn window(window: &str) -> PollWindow {
+        window.parse().expect("valid window")
+    }
+
+    #[test]
+    fn windows_are_parsed_as_hh_mm_ranges() {
+        assert_eq!(window("08:00-17:30").to_string(), "08:00-17:30");
+        assert_eq!(window(" 22:00 - 04:30 ").to_string(), "22:00-04:30");
+
+        assert!("08:00".parse::<PollWindow>().is_err());
+        assert!("08:00-".parse::<PollWindow>().is_err());
+        assert!("8am-5pm".parse::<PollWindow>().is_err());
+        assert!("08:00-24:00".parse::<PollWindow>().is_err());
+        assert!("08:00-08:00".parse::<PollWindow>().is_err());
+    }
+
+    #[test]
+    fn windows_contain_their_start_but_not_their_end() {
+        let day = window("08:00-17:30");
+
+        assert!(!day.contains(time(7, 59)));
+        assert!(day.contains(time(8, 0)));
+        assert!(day.contains(time(12, 0)));
+        assert!(day.contains(time(17, 29)));
+        assert!(!day.contains(time(17, 30)));
+        assert!(!day.contains(time(0, 0)));
+    }
+
+    #[test]
+    fn windows_spanning_midnight_contain_both_sides_of_it() {
+        let night = window("22:00-04:30");
+
+        assert!(!night.contains(time(21, 59)));
+        assert!(night.contains(time(22, 0)));
+        assert!(night.contains(time(23, 59)));
+        assert!(night.contains(time(0, 0)));
+        assert!(night.contains(time(4, 29)));
+        assert!(!night.contains(time(4, 30)));
+        assert!(!night.contains(time(12, 0)));
+    }
+
+    #[test]
+    fn windows_start_again_the_next_day() {
+        let night = window("22:00-04:30");
+        let hours = |hours: u64| Duration::from_secs(hours * 60 * 60);
+
+        assert_eq!(night.until_start(time(21, 0)), hours(1));
+        assert_eq!(night.until_start(time(22, 0)), Duration::ZERO);
+
+        // Once it has started, the next start is tomorrow, across midnight
+        assert_eq!(night.until_start(time(23, 0)), hours(23));
+        assert_eq!(night.until_start(time(4, 30)), hours(17) + hours(1) / 2);
+        assert_eq!(
+            window("00:15-01:00").until_start(time(23, 45)),
+            hours(1) / 2
+        );
+    }
+
+    #[test]
+    fn triggers_end_the_wait_for_the_next_poll() {
+        let trigger = PollTrigger::default();
+        let heartbeat = Heartbeat::new();
+
+        // A trigger before the wait isn't lost
+        trigger.trigger();
+        let started = Instant::now();
+        let wake = trigger.wait(Some(started + Duration::from_secs(60)), &heartbeat);
+        assert_eq!(wake, Wake::Poll);
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        let waiting = {
+            let trigger = trigger.clone();
+            std::thread::spawn(move || trigger.wait(None, &Heartbeat::new()))
+        };
+        std::thread::sleep(Duration::from_millis(50));
+        trigger.trigger();
+        waiting.join().unwrap();
+
+        // Without a trigger the wait lasts until its timeout
+        let started = Instant::now();
+        let wake = trigger.wait(Some(started + Duration::from_millis(50)), &heartbeat);
+        assert_eq!(wake, Wake::Poll);
+        assert!(started.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn jobs_wake_the_loop_before_a_poll() {
+        let trigger = PollTrigger::default();
+        let heartbeat = Heartbeat::new();
+        let deadline = Instant::now() + Duration::from_secs(60);
+
+        trigger.trigger();
+        trigger.wake_for_jobs();
+        assert_eq!(trigger.wait(Some(deadline), &heartbeat), Wake::Jobs);
+
+        // The trigger is still waiting once the jobs have run
+        assert_eq!(trigger.wait(Some(deadline), &heartbeat), Wake::Poll);
+    }
+}
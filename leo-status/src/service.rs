@@ -0,0 +1,218 @@
+use clap::{parser::ValueSource, ArgMatches, Args as ClapArgs, CommandFactory, ValueEnum};
+
+use std::path::{Path, PathBuf};
+
+use crate::Args;
+
+/// The launchd label and OpenRC service name of the daemon
+const SERVICE_NAME: &str = "leo-status";
+
+/// The kind of service manager to generate a service definition for
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub(crate) enum ServiceKind {
+    /// A systemd unit file
+    Systemd,
+
+    /// A launchd property list
+    Launchd,
+
+    /// An OpenRC init script
+    Openrc,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub(crate) struct GenerateServiceArgs {
+    #[arg(
+        long,
+        value_enum,
+        help = "Service manager to generate a service definition for"
+    )]
+    kind: ServiceKind,
+
+    #[arg(
+        long,
+        help = "Path of the leo-status binary the service runs, defaults to the path of this binary"
+    )]
+    binary: Option<PathBuf>,
+}
+
+/// Print a service definition which runs the daemon with the options given on the command line, in its state directory
+pub(crate) fn generate(args: &GenerateServiceArgs, state_dir: &Path, matches: &ArgMatches) {
+    let binary = match &args.binary {
+        Some(binary) => binary.clone(),
+        None => std::env::current_exe().expect("failed to get path of leo-status binary"),
+    };
+
+    let mut command = vec![binary.to_string_lossy().into_owned()];
+    command.extend(daemon_arguments(matches));
+
+    let definition = match args.kind {
+        ServiceKind::Systemd => systemd_unit(&command, state_dir),
+        ServiceKind::Launchd => launchd_plist(&command, state_dir),
+        ServiceKind::Openrc => openrc_script(&command, state_dir),
+    };
+
+    print!("{}", definition);
+}
+
+/// Rebuild the daemon arguments which were given on the command line, so the service runs with the same options. The
+/// arguments are taken from the command definition, so new options are picked up without changes here.
+fn daemon_arguments(matches: &ArgMatches) -> Vec<String> {
+    let mut arguments = vec![];
+
+    for arg in Args::command().get_arguments() {
+        let (Some(long), id) = (arg.get_long(), arg.get_id()) else {
+            continue;
+        };
+
+        if matches.value_source(id.as_str()) != Some(ValueSource::CommandLine) {
+            continue;
+        }
+
+        if arg.get_action().takes_values() {
+            for value in matches.get_raw(id.as_str()).into_iter().flatten() {
+                arguments.push(format!("--{}", long));
+                arguments.push(value.to_string_lossy().into_owned());
+            }
+        } else {
+            arguments.push(format!("--{}", long));
+        }
+    }
+
+    arguments
+}
+
+/// Whether an argument can be used as-is on a command line without quoting
+fn is_plain(argument: &str) -> bool {
+    !argument.is_empty()
+        && argument
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@,".contains(c))
+}
+
+/// Quote an argument for a systemd command line, which expands `$` even outside of quotes
+fn quote_systemd(argument: &str) -> String {
+    if is_plain(argument) {
+        argument.to_owned()
+    } else {
+        format!(
+            "\"{}\"",
+            argument
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('$', "$$")
+        )
+    }
+}
+
+/// Quote an argument for a POSIX shell
+fn quote_shell(argument: &str) -> String {
+    if is_plain(argument) {
+        argument.to_owned()
+    } else {
+        format!("'{}'", argument.replace('\'', "'\\''"))
+    }
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn systemd_unit(command: &[String], state_dir: &Path) -> String {
+    let exec_start = command
+        .iter()
+        .map(|argument| quote_systemd(argument))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    // systemd only creates state directories under /var/lib, and sets STATE_DIRECTORY to them
+    let state_directory = match state_dir.strip_prefix("/var/lib") {
+        Ok(relative) => format!("StateDirectory={}\n", relative.display()),
+        Err(_) => String::new(),
+    };
+    let working_directory = state_dir.display();
+
+    format!(
+        "[Unit]
+Description=Leo Bodnar GPSDO status exporter
+After=network.target
+
+[Service]
+ExecStart={exec_start}
+{state_directory}WorkingDirectory={working_directory}
+Restart=on-failure
+RestartSec=5
+
+[Install]
+WantedBy=multi-user.target
+"
+    )
+}
+
+fn launchd_plist(command: &[String], state_dir: &Path) -> String {
+    let program_arguments: String = command
+        .iter()
+        .map(|argument| format!("        <string>{}</string>\n", xml_escape(argument)))
+        .collect();
+    let working_directory = xml_escape(&state_dir.to_string_lossy());
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>com.github.hal18000.{SERVICE_NAME}</string>
+    <key>ProgramArguments</key>
+    <array>
+{program_arguments}    </array>
+    <key>WorkingDirectory</key>
+    <string>{working_directory}</string>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <true/>
+</dict>
+</plist>
+"#
+    )
+}
+
+fn openrc_script(command: &[String], state_dir: &Path) -> String {
+    // OpenRC evaluates command_args with the shell, so each argument is quoted and the whole string quoted again
+    let binary = quote_shell(&command[0]);
+    let command_args = quote_shell(
+        &command[1..]
+            .iter()
+            .map(|argument| quote_shell(argument))
+            .collect::<Vec<_>>()
+            .join(" "),
+    );
+    let directory = quote_shell(&state_dir.to_string_lossy());
+
+    format!(
+        r#"#!/sbin/openrc-run
+
+name="{SERVICE_NAME}"
+description="Leo Bodnar GPSDO status exporter"
+command={binary}
+command_args={command_args}
+command_background=true
+directory={directory}
+pidfile="/run/{SERVICE_NAME}.pid"
+supervisor=supervise-daemon
+
+depend() {{
+    need net
+}}
+
+start_pre() {{
+    checkpath --directory {directory}
+}}
+"#
+    )
+}
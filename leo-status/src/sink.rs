@@ -0,0 +1,883 @@
+#[cfg(feature = "kafka")]
+use rdkafka::{
+    config::ClientConfig,
+    error::KafkaError,
+    producer::{BaseProducer, BaseRecord, DeliveryResult, Producer, ProducerContext},
+    ClientContext,
+};
+#[cfg(feature = "mqtt")]
+use rumqttc::{Client, Event, MqttOptions, Outgoing, Packet, QoS};
+use serde::Serialize;
+use serde_json::Value;
+use thiserror::Error;
+use ureq::Agent;
+
+#[cfg(feature = "kafka")]
+use std::sync::Mutex;
+#[cfg(feature = "mqtt")]
+use std::{
+    collections::HashSet,
+    sync::mpsc::{Receiver, RecvTimeoutError},
+    time::Instant,
+};
+use std::{
+    sync::mpsc::{self, TrySendError},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+#[cfg(feature = "kafka")]
+use crate::exit::ExitCode;
+use crate::{
+    diagnostics::{Diagnosis, SharedDiagnosis},
+    dto::SnapshotResponse,
+    journal::{self, ConfigChange},
+    maintenance::Maintenance,
+    metrics::Metrics,
+    repeats::RepeatedErrors,
+    snapshot::{self, Snapshot, Snapshots},
+    template::BodyTemplate,
+    Args,
+};
+
+/// How long a sink waits for a remote endpoint to respond before the attempt fails
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Error)]
+/// An error which stopped a sink from delivering a snapshot
+pub(crate) enum SinkError {
+    #[error("request failed: {0}")]
+    Http(#[from] ureq::Error),
+
+    #[error("failed to render template: {0}")]
+    Template(#[from] minijinja::Error),
+
+    #[cfg(feature = "mqtt")]
+    #[error("mqtt publish failed: {0}")]
+    Mqtt(String),
+
+    #[cfg(feature = "kafka")]
+    #[error("kafka produce failed: {0}")]
+    Kafka(#[from] KafkaError),
+}
+
+/// An output integration which delivers snapshots to a remote endpoint. Each sink runs on a thread of its own behind
+/// a bounded queue, so a slow endpoint can't stall polling or the other sinks.
+pub(crate) trait Sink: Send {
+    /// Deliver one snapshot
+    fn send(&mut self, snapshot: &Snapshot) -> Result<(), SinkError>;
+}
+
+/// How a sink retries a snapshot it failed to deliver
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RetryPolicy {
+    /// The number of attempts made before the snapshot is given up on
+    pub(crate) attempts: u32,
+
+    /// The delay before the second attempt, doubling for each attempt after
+    pub(crate) initial_backoff: Duration,
+}
+
+/// Start a sink, returning the threads which queue and deliver its snapshots. Both stop once every sender of the
+/// broadcast is dropped and the queue has drained.
+fn spawn_sink(
+    name: &str,
+    mut sink: impl Sink + 'static,
+    queue_length: usize,
+    retry_policy: RetryPolicy,
+    error_repeat_interval: Duration,
+    snapshots: &Snapshots,
+    metrics: &Metrics,
+) -> Vec<JoinHandle<()>> {
+    // The queue holds the snapshots themselves, in a buffer allocated up front, so queueing one allocates nothing
+    let (sender, receiver) = mpsc::sync_channel::<Snapshot>(queue_length);
+
+    let sent = metrics.sink_sent.with_label_values(&[name]);
+    let failures = metrics.sink_failures.with_label_values(&[name]);
+    let dropped = metrics.sink_dropped.with_label_values(&[name]);
+    let queued = metrics.sink_queue_length.with_label_values(&[name]);
+    let mut errors = RepeatedErrors::new(name, error_repeat_interval, metrics);
+
+    let enqueued = queued.clone();
+    let enqueue = snapshot::spawn_consumer(
+        &format!("{} queue", name),
+        snapshots,
+        move |snapshot| match sender.try_send(*snapshot) {
+            Ok(()) => enqueued.inc(),
+            Err(TrySendError::Full(_)) => dropped.inc(),
+            Err(TrySendError::Disconnected(_)) => {}
+        },
+    );
+
+    let sink_name = name.to_owned();
+    let deliver = thread::Builder::new()
+        .name(format!("{} sink", name))
+        .spawn(move || {
+            for snapshot in receiver {
+                queued.dec();
+
+                match send_with_retry(&mut sink, &snapshot, retry_policy) {
+                    Ok(()) => {
+                        sent.inc();
+                        errors.clear();
+                    }
+                    Err(error) => {
+                        failures.inc();
+                        errors.log(&error.to_string(), || {
+                            format!("{} sink failed to deliver snapshot: {}", sink_name, error)
+                        });
+                    }
+                }
+            }
+        })
+        .unwrap_or_else(|_| panic!("failed to spawn {} sink thread", name));
+
+    enqueue.into_iter().chain([deliver]).collect()
+}
+
+/// Deliver a snapshot, retrying with exponential backoff until the attempts of the policy run out
+fn send_with_retry(
+    sink: &mut impl Sink,
+    snapshot: &Snapshot,
+    retry_policy: RetryPolicy,
+) -> Result<(), SinkError> {
+    let mut backoff = retry_policy.initial_backoff;
+    let mut attempt = 1;
+
+    loop {
+        match sink.send(snapshot) {
+            Ok(()) => return Ok(()),
+            Err(error) if attempt >= retry_policy.attempts => return Err(error),
+            Err(_) => {
+                thread::sleep(backoff);
+                backoff *= 2;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// Start the sinks selected by the command line arguments
+pub(crate) fn spawn_sinks(
+    args: &Args,
+    diagnosis: Option<&SharedDiagnosis>,
+    maintenance: &Maintenance,
+    snapshots: &Snapshots,
+    metrics: &Metrics,
+) -> Vec<JoinHandle<()>> {
+    let retry_policy = RetryPolicy {
+        attempts: args.sink_attempts,
+        initial_backoff: args.sink_backoff,
+    };
+
+    let mut threads = vec![];
+
+    if let Some(url) = &args.webhook_url {
+        threads.extend(spawn_sink(
+            "webhook",
+            WebhookSink::new(
+                url.clone(),
+                args.webhook_template.clone(),
+                args.webhook_content_type.clone(),
+                args.webhook_token.clone(),
+                diagnosis.cloned(),
+                maintenance.clone(),
+            ),
+            args.sink_queue_length,
+            retry_policy,
+            args.error_repeat_interval,
+            snapshots,
+            metrics,
+        ));
+    }
+
+    if let Some(url) = &args.influx_url {
+        threads.extend(spawn_sink(
+            "influx",
+            InfluxSink::new(url.clone(), args.influx_token.clone()),
+            args.sink_queue_length,
+            retry_policy,
+            args.error_repeat_interval,
+            snapshots,
+            metrics,
+        ));
+    }
+
+    #[cfg(feature = "mqtt")]
+    if let Some(host) = &args.mqtt_host {
+        let mut options = MqttOptions::new(&args.mqtt_client_id, host, args.mqtt_port);
+        if let (Some(username), Some(password)) = (&args.mqtt_username, &args.mqtt_password) {
+            options.set_credentials(username, password);
+        }
+
+        threads.extend(spawn_sink(
+            "mqtt",
+            MqttSink::new(options, args.mqtt_topic.clone()),
+            args.sink_queue_length,
+            retry_policy,
+            args.error_repeat_interval,
+            snapshots,
+            metrics,
+        ));
+    }
+
+    #[cfg(feature = "kafka")]
+    if let Some(brokers) = &args.kafka_brokers {
+        let sink = KafkaSink::new(brokers, args.kafka_topic.clone()).unwrap_or_else(|error| {
+            tracing::error!("failed to create kafka producer: {}", error);
+            ExitCode::ConfigInvalid.exit();
+        });
+
+        threads.extend(spawn_sink(
+            "kafka",
+            sink,
+            args.sink_queue_length,
+            retry_policy,
+            args.error_repeat_interval,
+            snapshots,
+            metrics,
+        ));
+    }
+
+    threads
+}
+
+fn agent() -> Agent {
+    Agent::config_builder()
+        .timeout_global(Some(REQUEST_TIMEOUT))
+        .build()
+        .into()
+}
+
+/// The body of a webhook, a reading with what changed in the config since the last reading delivered
+#[derive(Serialize)]
+struct WebhookReading {
+    #[serde(flatten)]
+    reading: SnapshotResponse,
+
+    /// Always present, even if empty, so templates can use it without checking it is defined
+    config_changes: Vec<ConfigChange>,
+
+    /// The latest diagnosis of the lock losses with `--diagnostics`, otherwise `null`
+    diagnosis: Option<Diagnosis>,
+
+    /// Whether maintenance is in progress, so a notification can be held back
+    maintenance: bool,
+}
+
+/// POSTs each snapshot to a URL, as JSON or rendered with a template
+pub(crate) struct WebhookSink {
+    agent: Agent,
+    url: String,
+    template: Option<BodyTemplate>,
+    content_type: String,
+    token: Option<String>,
+
+    /// The config of the last reading delivered, which changes are reported against
+    delivered_config: Option<Value>,
+    diagnosis: Option<SharedDiagnosis>,
+    maintenance: Maintenance,
+}
+
+impl WebhookSink {
+    pub(crate) fn new(
+        url: String,
+        template: Option<BodyTemplate>,
+        content_type: String,
+        token: Option<String>,
+        diagnosis: Option<SharedDiagnosis>,
+        maintenance: Maintenance,
+    ) -> Self {
+        WebhookSink {
+            agent: agent(),
+            url,
+            template,
+            content_type,
+            token,
+            delivered_config: None,
+            diagnosis,
+            maintenance,
+        }
+    }
+}
+
+impl Sink for WebhookSink {
+    fn send(&mut self, snapshot: &Snapshot) -> Result<(), SinkError> {
+        // Only a delivered reading moves the config changes are reported against on, so a retry reports the same
+        // changes and a change in a reading given up on is reported with the next
+        let config = journal::config_value(snapshot);
+        let config_changes = self
+            .delivered_config
+            .as_ref()
+            .map(|delivered| journal::config_changes(delivered, &config))
+            .unwrap_or_default();
+
+        let reading = WebhookReading {
+            reading: SnapshotResponse::from(snapshot),
+            config_changes,
+            diagnosis: self
+                .diagnosis
+                .as_ref()
+                .and_then(|diagnosis| diagnosis.read().unwrap().clone()),
+            maintenance: self.maintenance.active(),
+        };
+        let body = match &self.template {
+            Some(template) => template.render(&reading)?,
+            None => serde_json::to_string(&reading).expect("failed to serialize snapshot"),
+        };
+
+        let mut request = self
+            .agent
+            .post(&self.url)
+            .header("Content-Type", &self.content_type);
+
+        if let Some(token) = &self.token {
+            request = request.header("Authorization", &format!("Bearer {}", token));
+        }
+
+        request.send(body)?;
+
+        self.delivered_config = Some(config);
+        Ok(())
+    }
+}
+
+/// Writes each snapshot to InfluxDB in the line protocol, timestamped with when the GPSDO was read
+pub(crate) struct InfluxSink {
+    agent: Agent,
+    url: String,
+    token: Option<String>,
+}
+
+impl InfluxSink {
+    pub(crate) fn new(url: String, token: Option<String>) -> Self {
+        InfluxSink {
+            agent: agent(),
+            url,
+            token,
+        }
+    }
+}
+
+/// Format a snapshot as an InfluxDB line protocol point, with a nanosecond timestamp
+fn influx_line(snapshot: &Snapshot) -> String {
+    let status = &snapshot.status;
+    let mut fields = vec![
+        format!("locked={}i", u8::from(status.locked())),
+        format!("sat_lock={}i", u8::from(status.sat_locked())),
+        format!("pll_lock={}i", u8::from(status.pll_locked())),
+        format!("loss_count={}i", status.loss_count()),
+        format!("state=\"{}\"", snapshot.state),
+    ];
+
+    if let Ok(fout1) = snapshot.config.checked_fout1() {
+        fields.push(format!("fout1={}i", fout1));
+    }
+    if let Ok(Some(fout2)) = snapshot.config.checked_fout2() {
+        fields.push(format!("fout2={}i", fout2));
+    }
+
+    format!(
+        "leo_gpsdo {} {}\n",
+        fields.join(","),
+        snapshot.timestamp.unix_nanos()
+    )
+}
+
+impl Sink for InfluxSink {
+    fn send(&mut self, snapshot: &Snapshot) -> Result<(), SinkError> {
+        let mut request = self
+            .agent
+            .post(&self.url)
+            .header("Content-Type", "text/plain; charset=utf-8");
+
+        if let Some(token) = &self.token {
+            request = request.header("Authorization", &format!("Token {}", token));
+        }
+
+        request.send(influx_line(snapshot))?;
+
+        Ok(())
+    }
+}
+
+/// How long the MQTT connection waits before reconnecting to the broker after it fails
+#[cfg(feature = "mqtt")]
+const MQTT_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// The JSON payload a snapshot is published as, timestamped with when the GPSDO was read rather than when it is
+/// published, so a reading held up by the sink's queue or retries keeps its place in the series
+#[cfg(feature = "mqtt")]
+fn mqtt_payload(snapshot: &Snapshot) -> Vec<u8> {
+    serde_json::to_vec(&SnapshotResponse::from(snapshot)).expect("failed to serialize snapshot")
+}
+
+/// What the connection to the broker passes back to the MQTT sink
+#[cfg(feature = "mqtt")]
+#[derive(Debug)]
+enum MqttEvent {
+    /// A publish was sent with the packet id, either for the first time or again after reconnecting
+    Published(u16),
+
+    /// The broker acknowledged the publish with the packet id
+    Acked(u16),
+
+    /// The broker started a new session, in which the publishes it hadn't acknowledged are given up
+    NewSession,
+
+    /// The connection failed
+    Failed(String),
+}
+
+/// The publishes the broker hasn't acknowledged, by packet id, so the acknowledgement of a reading isn't mistaken for a
+/// late one of an earlier reading which timed out
+#[cfg(feature = "mqtt")]
+#[derive(Debug, Default)]
+struct MqttAcks {
+    unacked: HashSet<u16>,
+
+    /// The readings published which haven't been sent yet, the last of which is the one being published
+    unsent: usize,
+
+    /// Whether a reading is being published, and its packet id once it has been sent
+    waiting: bool,
+    awaited: Option<u16>,
+}
+
+#[cfg(feature = "mqtt")]
+impl MqttAcks {
+    /// Wait for the acknowledgement of a reading just published
+    fn start(&mut self) {
+        self.unsent += 1;
+        self.waiting = true;
+        self.awaited = None;
+    }
+
+    fn finish(&mut self) {
+        self.waiting = false;
+        self.awaited = None;
+    }
+
+    /// Follow an event of the connection, returning the outcome of the reading being published once it is known
+    fn observe(&mut self, event: MqttEvent) -> Option<Result<(), String>> {
+        match event {
+            MqttEvent::Published(pkid) => {
+                // A packet id which hasn't been acknowledged yet is an earlier reading sent again after reconnecting,
+                // as a new publish never reuses one
+                if self.unacked.insert(pkid) {
+                    self.unsent = self.unsent.saturating_sub(1);
+                    if self.waiting && self.unsent == 0 {
+                        self.awaited = Some(pkid);
+                    }
+                }
+
+                None
+            }
+            MqttEvent::Acked(pkid) => {
+                self.unacked.remove(&pkid);
+                (self.waiting && self.awaited == Some(pkid)).then_some(Ok(()))
+            }
+            MqttEvent::NewSession => {
+                // The readings which weren't sent before the connection failed are given up too
+                self.unacked.clear();
+                self.unsent = 0;
+                None
+            }
+
+            // Failures of the connection while it was idle don't concern the next reading
+            MqttEvent::Failed(error) => self.waiting.then_some(Err(error)),
+        }
+    }
+}
+
+/// Publishes each snapshot to an MQTT topic as JSON, at least once. The connection to the broker is kept on a thread of
+/// its own, which reconnects after a failure and passes the publishes sent and the broker's acknowledgements back to
+/// the sink, which takes only the acknowledgement with the packet id of the reading as its delivery.
+#[cfg(feature = "mqtt")]
+pub(crate) struct MqttSink {
+    client: Client,
+    topic: String,
+    events: Receiver<MqttEvent>,
+    acks: MqttAcks,
+}
+
+#[cfg(feature = "mqtt")]
+impl MqttSink {
+    pub(crate) fn new(options: MqttOptions, topic: String) -> Self {
+        let (client, mut connection) = Client::new(options, 10);
+        let (sender, events) = mpsc::channel();
+
+        thread::Builder::new()
+            .name("mqtt connection".to_owned())
+            .spawn(move || {
+                for notification in connection.iter() {
+                    let event = match notification {
+                        Ok(Event::Outgoing(Outgoing::Publish(pkid))) => MqttEvent::Published(pkid),
+                        Ok(Event::Incoming(Packet::PubAck(ack))) => MqttEvent::Acked(ack.pkid),
+                        Ok(Event::Incoming(Packet::ConnAck(ack))) if !ack.session_present => {
+                            MqttEvent::NewSession
+                        }
+                        Ok(_) => continue,
+                        Err(error) => {
+                            thread::sleep(MQTT_RECONNECT_DELAY);
+                            MqttEvent::Failed(error.to_string())
+                        }
+                    };
+
+                    // The sink has gone away
+                    if sender.send(event).is_err() {
+                        return;
+                    }
+                }
+            })
+            .expect("failed to spawn mqtt connection thread");
+
+        MqttSink {
+            client,
+            topic,
+            events,
+            acks: MqttAcks::default(),
+        }
+    }
+
+    /// Wait for the outcome of the reading just published
+    fn wait_for_ack(&mut self) -> Result<(), SinkError> {
+        let deadline = Instant::now() + REQUEST_TIMEOUT;
+
+        loop {
+            match self
+                .events
+                .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            {
+                Ok(event) => {
+                    if let Some(result) = self.acks.observe(event) {
+                        return result.map_err(SinkError::Mqtt);
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(SinkError::Mqtt(
+                        "the broker did not acknowledge the reading".to_owned(),
+                    ))
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(SinkError::Mqtt("the connection has stopped".to_owned()))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "mqtt")]
+impl Sink for MqttSink {
+    fn send(&mut self, snapshot: &Snapshot) -> Result<(), SinkError> {
+        // Catch up with the connection while it was idle, such as late acknowledgements of earlier readings
+        while let Ok(event) = self.events.try_recv() {
+            self.acks.observe(event);
+        }
+
+        self.client
+            .publish(&self.topic, QoS::AtLeastOnce, false, mqtt_payload(snapshot))
+            .map_err(|error| SinkError::Mqtt(error.to_string()))?;
+
+        self.acks.start();
+        let result = self.wait_for_ack();
+        self.acks.finish();
+
+        result
+    }
+}
+
+/// Keeps the outcome of the latest delivery to Kafka, for the sink to collect once it has flushed the producer
+#[cfg(feature = "kafka")]
+#[derive(Default)]
+struct DeliveryContext {
+    delivered: Mutex<Option<Result<(), KafkaError>>>,
+}
+
+#[cfg(feature = "kafka")]
+impl ClientContext for DeliveryContext {}
+
+#[cfg(feature = "kafka")]
+impl ProducerContext for DeliveryContext {
+    type DeliveryOpaque = ();
+
+    fn delivery(&self, delivery_result: &DeliveryResult<'_>, _delivery_opaque: ()) {
+        *self.delivered.lock().unwrap() = Some(
+            delivery_result
+                .as_ref()
+                .map(|_| ())
+                .map_err(|(error, _)| error.clone()),
+        );
+    }
+}
+
+/// Produces each snapshot to a Kafka topic as JSON, timestamped with when the GPSDO was read. Each reading is flushed
+/// before the next, so the sink's retries and queue work as for the other sinks.
+#[cfg(feature = "kafka")]
+pub(crate) struct KafkaSink {
+    producer: BaseProducer<DeliveryContext>,
+    topic: String,
+}
+
+#[cfg(feature = "kafka")]
+impl KafkaSink {
+    pub(crate) fn new(brokers: &str, topic: String) -> Result<Self, KafkaError> {
+        let producer = ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .set(
+                "message.timeout.ms",
+                REQUEST_TIMEOUT.as_millis().to_string(),
+            )
+            .create_with_context(DeliveryContext::default())?;
+
+        Ok(KafkaSink { producer, topic })
+    }
+}
+
+#[cfg(feature = "kafka")]
+impl Sink for KafkaSink {
+    fn send(&mut self, snapshot: &Snapshot) -> Result<(), SinkError> {
+        let payload = serde_json::to_vec(&SnapshotResponse::from(snapshot))
+            .expect("failed to serialize snapshot");
+        let record = BaseRecord::<(), _>::to(&self.topic)
+            .payload(&payload)
+            .timestamp((snapshot.timestamp.unix_nanos() / 1_000_000) as i64);
+
+        self.producer.send(record).map_err(|(error, _)| error)?;
+        self.producer.flush(REQUEST_TIMEOUT)?;
+
+        let delivered = self.producer.context().delivered.lock().unwrap().take();
+        Ok(delivered.unwrap_or(Err(KafkaError::Canceled))?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use leo_status_driver::{
+        state::DeviceState, Bandwidth, DriveLevel, GpsdoConfig, GpsdoModel, GpsdoStatus,
+    };
+
+    use std::{
+        sync::mpsc::{self, Receiver, Sender},
+        time::{Duration, Instant},
+    };
+
+    use super::{influx_line, send_with_retry, spawn_sink, RetryPolicy, Sink, SinkError};
+    #[cfg(feature = "mqtt")]
+    use super::{mqtt_payload, MqttAcks, MqttEvent};
+    use crate::{
+        metrics::{Metrics, StaleMetricsPolicy},
+        snapshot::{Snapshot, Snapshots},
+        time::Timestamp,
+    };
+
+    fn snapshot() -> Snapshot {
+        let config = GpsdoConfig::builder(GpsdoModel::Standard)
+            .drive_level(DriveLevel::Ma8)
+            .fin(4_296_875)
+            .n3(30)
+            .n2_hs(10)
+            .n2_ls(3840)
+            .n1_hs(11)
+            .nc1_ls(50)
+            .nc2_ls(100)
+            .bandwidth(Bandwidth::from_raw(15))
+            .build()
+            .expect("valid config");
+
+        Snapshot {
+            timestamp: Timestamp::now(),
+            config,
+            // Two losses, with both the satellites and the PLL locked
+            status: GpsdoStatus::from_report_bytes(&[2, 0x00]).unwrap(),
+            state: DeviceState::Locked,
+            first_read: false,
+            poll_seq: 7,
+        }
+    }
+
+    fn failure() -> SinkError {
+        SinkError::Http(ureq::Error::ConnectionFailed)
+    }
+
+    /// Fails the first `failures` snapshots it is sent, recording when each attempt was made
+    struct FailingSink {
+        failures: u32,
+        attempts: Vec<Instant>,
+    }
+
+    impl Sink for FailingSink {
+        fn send(&mut self, _snapshot: &Snapshot) -> Result<(), SinkError> {
+            self.attempts.push(Instant::now());
+
+            if self.attempts.len() as u32 <= self.failures {
+                return Err(failure());
+            }
+            Ok(())
+        }
+    }
+
+    /// Tells the test when it starts on each snapshot, then waits for the test to let it deliver it
+    struct BlockedSink {
+        started: Sender<()>,
+        release: Receiver<()>,
+    }
+
+    impl Sink for BlockedSink {
+        fn send(&mut self, _snapshot: &Snapshot) -> Result<(), SinkError> {
+            self.started.send(()).unwrap();
+            self.release.recv().unwrap();
+            Ok(())
+        }
+    }
+
+    const RETRY_POLICY: RetryPolicy = RetryPolicy {
+        attempts: 3,
+        initial_backoff: Duration::from_millis(20),
+    };
+
+    #[test]
+    fn failed_sends_are_retried_with_doubling_backoff() {
+        let mut sink = FailingSink {
+            failures: 2,
+            attempts: vec![],
+        };
+
+        assert!(send_with_retry(&mut sink, &snapshot(), RETRY_POLICY).is_ok());
+
+        assert_eq!(sink.attempts.len(), 3);
+        assert!(sink.attempts[1] - sink.attempts[0] >= Duration::from_millis(20));
+        assert!(sink.attempts[2] - sink.attempts[1] >= Duration::from_millis(40));
+    }
+
+    #[test]
+    fn sends_are_given_up_on_once_the_attempts_run_out() {
+        let mut sink = FailingSink {
+            failures: u32::MAX,
+            attempts: vec![],
+        };
+
+        assert!(send_with_retry(&mut sink, &snapshot(), RETRY_POLICY).is_err());
+        assert_eq!(sink.attempts.len(), 3);
+    }
+
+    #[test]
+    fn snapshots_are_dropped_once_the_queue_is_full() {
+        let metrics = Metrics::new(StaleMetricsPolicy::default());
+        // Snapshots are queued as they are published
+        let snapshots = Snapshots::new(true);
+        let (started, started_receiver) = mpsc::channel();
+        let (release_sender, release) = mpsc::channel();
+
+        let threads = spawn_sink(
+            "test",
+            BlockedSink { started, release },
+            1,
+            RETRY_POLICY,
+            Duration::from_secs(60),
+            &snapshots,
+            &metrics,
+        );
+
+        // The first is being delivered, the second waits in the queue, and there is no room for the rest
+        snapshots.publish(&snapshot());
+        started_receiver.recv().unwrap();
+        for _ in 0..3 {
+            snapshots.publish(&snapshot());
+        }
+
+        let dropped = metrics.sink_dropped.with_label_values(&["test"]);
+        assert_eq!(dropped.get(), 2);
+
+        release_sender.send(()).unwrap();
+        release_sender.send(()).unwrap();
+        drop(snapshots);
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(metrics.sink_sent.with_label_values(&["test"]).get(), 2);
+        assert_eq!(dropped.get(), 2);
+    }
+
+    #[test]
+    fn influx_lines_hold_the_status_and_output_frequencies() {
+        let snapshot = snapshot();
+
+        assert_eq!(
+            influx_line(&snapshot),
+            format!(
+                "leo_gpsdo locked=1i,sat_lock=1i,pll_lock=1i,loss_count=2i,state=\"locked\",fout1=10000000i,fout2=5000000i {}\n",
+                snapshot.timestamp.unix_nanos()
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "mqtt")]
+    fn mqtt_payloads_are_timestamped_when_the_gpsdo_was_read() {
+        // Read an hour away from now, so the time it is published can't pass for it
+        let snapshot = Snapshot {
+            timestamp: Timestamp::now().after(Duration::from_secs(60 * 60)),
+            ..snapshot()
+        };
+
+        let payload: serde_json::Value =
+            serde_json::from_slice(&mqtt_payload(&snapshot)).expect("valid json payload");
+        assert_eq!(payload["timestamp"], snapshot.timestamp.format());
+    }
+
+    #[test]
+    #[cfg(feature = "mqtt")]
+    fn mqtt_acks_only_take_the_ack_of_the_reading() {
+        let mut acks = MqttAcks::default();
+
+        // An earlier reading timed out without being acknowledged
+        acks.start();
+        assert_eq!(acks.observe(MqttEvent::Published(1)), None);
+        acks.finish();
+
+        // Its acknowledgement, or it being sent again after reconnecting, doesn't deliver the next reading
+        acks.start();
+        assert_eq!(acks.observe(MqttEvent::Published(1)), None);
+        assert_eq!(acks.observe(MqttEvent::Published(2)), None);
+        assert_eq!(acks.observe(MqttEvent::Acked(1)), None);
+        assert_eq!(acks.observe(MqttEvent::Acked(2)), Some(Ok(())));
+        acks.finish();
+
+        // Nor does that of an earlier reading which timed out before it was even sent
+        acks.start();
+        acks.finish();
+        acks.start();
+        assert_eq!(acks.observe(MqttEvent::Published(3)), None);
+        assert_eq!(acks.observe(MqttEvent::Published(4)), None);
+        assert_eq!(acks.observe(MqttEvent::Acked(3)), None);
+        assert_eq!(acks.observe(MqttEvent::Acked(4)), Some(Ok(())));
+        acks.finish();
+
+        // Nor do failures while idle
+        assert_eq!(acks.observe(MqttEvent::Failed("idle".to_owned())), None);
+        acks.start();
+        assert_eq!(
+            acks.observe(MqttEvent::Failed("lost".to_owned())),
+            Some(Err("lost".to_owned()))
+        );
+        acks.finish();
+    }
+
+    #[test]
+    #[cfg(feature = "mqtt")]
+    fn mqtt_acks_forget_unacked_publishes_in_a_new_session() {
+        let mut acks = MqttAcks::default();
+
+        acks.start();
+        acks.observe(MqttEvent::Published(1));
+        acks.finish();
+
+        // The broker gave up the publish, so its packet id may be used for the next reading
+        acks.observe(MqttEvent::NewSession);
+        acks.start();
+        assert_eq!(acks.observe(MqttEvent::Published(1)), None);
+        assert_eq!(acks.observe(MqttEvent::Acked(1)), Some(Ok(())));
+    }
+}
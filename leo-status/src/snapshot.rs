@@ -0,0 +1,245 @@
+use leo_status_driver::{
+    state::DeviceState,
+    tracker::{HoldoverTracker, LossCounter},
+    GpsdoConfig, GpsdoStatus,
+};
+
+use std::{
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+};
+
+use crate::{
+    broadcast::Broadcast,
+    clock::ClockTracker,
+    diagnostics::{self, LossAnalyzer},
+    dto::Served,
+    events::LockTracker,
+    http::HttpContext,
+    journal::JournalTracker,
+    maintenance::{AccountedTime, MaintenanceTracker},
+    metrics::{Metrics, QualityTracker},
+    poll, sink,
+    time::Timestamp,
+    uptime::UptimeEstimator,
+    Args,
+};
+
+/// A reading of the GPSDO, broadcast to every consumer after each poll
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Snapshot {
+    pub(crate) timestamp: Timestamp,
+    pub(crate) config: GpsdoConfig,
+    pub(crate) status: GpsdoStatus,
+
+    /// The state of the device once this reading was taken
+    pub(crate) state: DeviceState,
+
+    /// Whether this is the first reading since the GPSDO was opened
+    pub(crate) first_read: bool,
+
+    /// The number of the poll cycle, counting from 1 when the daemon starts
+    pub(crate) poll_seq: u64,
+}
+
+/// A consumer of snapshots called on the poll loop's thread
+type InlineConsumer = Box<dyn FnMut(&Snapshot) + Send>;
+
+/// The broadcast of snapshots from the poll loop to the consumers. Each consumer runs on a thread of its own, or with
+/// `--low-resource` is called inline by the poll loop, so publishing a snapshot allocates nothing.
+#[derive(Clone)]
+pub(crate) enum Snapshots {
+    Threads(Broadcast<Arc<Snapshot>>),
+    Inline(Arc<Mutex<Vec<InlineConsumer>>>),
+}
+
+impl Snapshots {
+    pub(crate) fn new(low_resource: bool) -> Self {
+        if low_resource {
+            Snapshots::Inline(Arc::new(Mutex::new(vec![])))
+        } else {
+            Snapshots::Threads(Broadcast::new())
+        }
+    }
+
+    /// Hand a snapshot to every consumer, returning once the inline consumers have finished with it
+    pub(crate) fn publish(&self, snapshot: &Snapshot) {
+        match self {
+            Snapshots::Threads(broadcast) => broadcast.publish(&Arc::new(*snapshot)),
+            Snapshots::Inline(consumers) => {
+                for consume in consumers.lock().unwrap().iter_mut() {
+                    consume(snapshot);
+                }
+            }
+        }
+    }
+}
+
+/// Start a thread calling `consume` with every snapshot published, until every sender of the broadcast is dropped.
+/// With `--low-resource` the consumer is called inline instead, and there is no thread to join.
+pub(crate) fn spawn_consumer(
+    name: &str,
+    snapshots: &Snapshots,
+    mut consume: impl FnMut(&Snapshot) + Send + 'static,
+) -> Option<JoinHandle<()>> {
+    let broadcast = match snapshots {
+        Snapshots::Threads(broadcast) => broadcast,
+        Snapshots::Inline(consumers) => {
+            consumers.lock().unwrap().push(Box::new(consume));
+            return None;
+        }
+    };
+    let receiver = broadcast.subscribe();
+
+    let consumer = thread::Builder::new()
+        .name(name.to_owned())
+        .spawn(move || {
+            for snapshot in receiver {
+                consume(&snapshot);
+            }
+        })
+        .unwrap_or_else(|_| panic!("failed to spawn {} thread", name));
+
+    Some(consumer)
+}
+
+/// Start the consumers of snapshots selected by the command line arguments
+pub(crate) fn spawn_consumers(
+    args: &Args,
+    context: &HttpContext,
+    metrics: &Metrics,
+    snapshots: &Snapshots,
+) -> Vec<JoinHandle<()>> {
+    let mut consumers = vec![];
+
+    // The responses are built from the latest snapshot as they are requested, rather than after every poll
+    let latest = context.latest.clone();
+    consumers.extend(spawn_consumer("http state", snapshots, move |snapshot| {
+        *latest.write().unwrap() = Some(Served::new(*snapshot));
+    }));
+
+    let maintenance = context.maintenance.clone();
+    let maintenance_journal = context.journal.clone();
+    let maintenance_metrics = metrics.clone();
+    let mut maintenance_tracker = MaintenanceTracker::default();
+    consumers.extend(spawn_consumer("maintenance", snapshots, move |snapshot| {
+        maintenance_tracker.update(
+            &maintenance,
+            &maintenance_journal,
+            &maintenance_metrics,
+            snapshot,
+        );
+    }));
+
+    let metrics_args = args.clone();
+    let metrics_maintenance = context.maintenance.clone();
+    let updated_metrics = metrics.clone();
+    let mut quality_tracker = QualityTracker::default();
+    let mut loss_counter = LossCounter::new();
+    let mut holdover_tracker = HoldoverTracker::new();
+    let mut holdover_time = AccountedTime::default();
+    consumers.extend(spawn_consumer("metrics", snapshots, move |snapshot| {
+        updated_metrics.set_status(&snapshot.status);
+        updated_metrics.set_config(&snapshot.config);
+
+        loss_counter.update(&snapshot.status);
+        updated_metrics.set_losses(&loss_counter);
+
+        // Lock losses during maintenance aren't counted against the quality score or the holdover, and nor is the
+        // time maintenance lasted
+        if let Some(elapsed) =
+            holdover_time.update(snapshot.timestamp, metrics_maintenance.active())
+        {
+            updated_metrics
+                .quality_score
+                .set(quality_tracker.update(snapshot).into());
+
+            holdover_tracker.update(&snapshot.status, elapsed);
+            updated_metrics.set_holdover(&holdover_tracker);
+        }
+
+        if let Some(textfile_dir) = &metrics_args.textfile_dir {
+            if let Err(error) = updated_metrics.write_textfile(textfile_dir) {
+                tracing::error!(
+                    "failed to write metrics to {}: {}",
+                    textfile_dir.display(),
+                    error
+                );
+            }
+        }
+    }));
+
+    let uptime_context = context.clone();
+    let uptime_metrics = metrics.clone();
+    let mut uptime_estimator = UptimeEstimator::default();
+    consumers.extend(spawn_consumer("uptime", snapshots, move |snapshot| {
+        let booted = uptime_estimator.update(snapshot);
+
+        uptime_metrics.device_uptime.set(booted.age_secs());
+        if let Some(device) = uptime_context.device.write().unwrap().as_mut() {
+            device.set_booted(booted);
+        }
+    }));
+
+    if context.journal.is_enabled() {
+        let journal = context.journal.clone();
+        let mut journal_tracker = JournalTracker::default();
+        consumers.extend(spawn_consumer("journal", snapshots, move |snapshot| {
+            journal_tracker.update(&journal, snapshot);
+        }));
+    }
+
+    if let Some(clock_source) = &args.clock_source {
+        let clock_journal = context.journal.clone();
+        let clock_metrics = metrics.clone();
+        let mut clock_tracker = ClockTracker::new(clock_source.source());
+        consumers.extend(spawn_consumer("clock", snapshots, move |snapshot| {
+            clock_tracker.update(&clock_journal, &clock_metrics, snapshot);
+        }));
+    }
+
+    if let Some(diagnosis) = &context.diagnosis {
+        let diagnosis = diagnosis.clone();
+        let diagnostics_journal = context.journal.clone();
+        let diagnostics_maintenance = context.maintenance.clone();
+        let mut analyzer = LossAnalyzer::default();
+        consumers.extend(spawn_consumer("diagnostics", snapshots, move |snapshot| {
+            // Lock losses during maintenance are expected, so aren't diagnosed
+            if !diagnostics_maintenance.active() {
+                diagnostics::update(&mut analyzer, &diagnosis, &diagnostics_journal, snapshot);
+            }
+        }));
+    }
+
+    let events = context.events.clone();
+    let mut tracker = LockTracker::default();
+    consumers.extend(spawn_consumer("events", snapshots, move |snapshot| {
+        for event in tracker.update(
+            &snapshot.status,
+            snapshot.timestamp,
+            Some(snapshot.poll_seq),
+        ) {
+            events.publish(&event);
+        }
+    }));
+
+    // In single-shot mode the caller prints the reading once it has been read
+    if args.stdout && !args.once {
+        let format = args.output;
+        let printed_metrics = metrics.clone();
+
+        consumers.extend(spawn_consumer("stdout", snapshots, move |snapshot| {
+            poll::print_output(format, snapshot, &printed_metrics)
+        }));
+    }
+
+    consumers.extend(sink::spawn_sinks(
+        args,
+        context.diagnosis.as_ref(),
+        &context.maintenance,
+        snapshots,
+        metrics,
+    ));
+
+    consumers
+}
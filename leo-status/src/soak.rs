@@ -0,0 +1,455 @@
+use clap::Args as ClapArgs;
+use leo_status_driver::{
+    state::{DeviceState, DeviceStateMachine},
+    tracker::{StatusEvent, StatusTracker},
+    GpsdoDevice, GpsdoStatus,
+};
+use minijinja::{Environment, Value};
+use serde::Serialize;
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    exit::ExitCode,
+    messages::{self, Message},
+    persist, poll,
+    time::Timestamp,
+    Args,
+};
+
+/// The template of the HTML report, with its text looked up in `t`. The JSON report is the serialized `SoakReport`.
+const HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{{ t.soak_title }} {{ started }}</title>
+<style>
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; margin-bottom: 2em; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.8em; text-align: left; }
+</style>
+</head>
+<body>
+<h1>{{ t.soak_title }}</h1>
+<table>
+<tr><th>{{ t.device }}</th><td>{{ serial_number or t.unknown }}</td></tr>
+<tr><th>{{ t.started }}</th><td>{{ started }}</td></tr>
+<tr><th>{{ t.finished }}</th><td>{{ finished }}</td></tr>
+<tr><th>{{ t.duration }}</th><td>{{ duration_secs | round(1) }} s</td></tr>
+<tr><th>{{ t.reads }}</th><td>{{ reads }} ({{ failed_reads }} {{ t.failed }})</td></tr>
+<tr><th>{{ t.device_availability }}</th><td>{{ device_availability | round(3) }} %</td></tr>
+<tr><th>{{ t.lock_availability }}</th><td>{{ lock_availability | round(3) }} %</td></tr>
+<tr><th>{{ t.loss_events }}</th><td>{{ loss_events | length }}</td></tr>
+</table>
+<h2>{{ t.read_latency }}</h2>
+{% if latency %}
+<table>
+<tr><th>{{ t.min }}</th><th>{{ t.mean }}</th><th>p50</th><th>p95</th><th>p99</th><th>{{ t.max }}</th></tr>
+<tr>
+<td>{{ latency.min_ms | round(2) }} ms</td>
+<td>{{ latency.mean_ms | round(2) }} ms</td>
+<td>{{ latency.p50_ms | round(2) }} ms</td>
+<td>{{ latency.p95_ms | round(2) }} ms</td>
+<td>{{ latency.p99_ms | round(2) }} ms</td>
+<td>{{ latency.max_ms | round(2) }} ms</td>
+</tr>
+</table>
+{% else %}
+<p>{{ t.no_successful_reads }}</p>
+{% endif %}
+<h2>{{ t.outages }}</h2>
+{% if outages %}
+<table>
+<tr><th>{{ t.kind }}</th><th>{{ t.start }}</th><th>{{ t.end }}</th><th>{{ t.duration }}</th></tr>
+{% for outage in outages %}
+<tr>
+<td>{{ t["outage_" ~ outage.kind] }}</td>
+<td>{{ outage.start }}</td>
+<td>{{ outage.end }}{% if outage.ongoing %} ({{ t.ongoing }}){% endif %}</td>
+<td>{{ outage.duration_secs | round(1) }} s</td>
+</tr>
+{% endfor %}
+</table>
+{% else %}
+<p>{{ t.no_outages }}</p>
+{% endif %}
+<h2>{{ t.loss_events }}</h2>
+{% if loss_events %}
+<table>
+<tr><th>{{ t.time }}</th><th>{{ t.loss_count }}</th></tr>
+{% for event in loss_events %}
+<tr><td>{{ event.timestamp }}</td><td>{{ event.loss_count }}</td></tr>
+{% endfor %}
+</table>
+{% else %}
+<p>{{ t.no_loss_events }}</p>
+{% endif %}
+</body>
+</html>
+"#;
+
+#[derive(ClapArgs, Debug, Clone)]
+pub(crate) struct SoakArgs {
+    #[arg(
+        long,
+        value_parser = humantime::parse_duration,
+        help = "How long to capture for, such as 72h"
+    )]
+    duration: Duration,
+
+    #[arg(
+        long,
+        help = "File to write the report to when the capture finishes, as HTML if it ends in .html or .htm, otherwise as JSON"
+    )]
+    report: PathBuf,
+}
+
+/// Why the GPSDO was unavailable during a soak test
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum OutageKind {
+    /// The device could not be opened or read
+    Device,
+
+    /// The device was read but was not in the locked state
+    Lock,
+}
+
+#[derive(Serialize, Debug)]
+struct Outage {
+    kind: OutageKind,
+    start: Timestamp,
+    end: Timestamp,
+    duration_secs: f64,
+
+    /// Whether the outage was still going on when the capture finished
+    ongoing: bool,
+}
+
+/// The loss count of the GPSDO going up
+#[derive(Serialize, Debug)]
+struct LossEvent {
+    timestamp: Timestamp,
+    loss_count: u8,
+}
+
+/// Statistics of how long each status read took, in milliseconds
+#[derive(Serialize, Debug)]
+struct LatencyStats {
+    min_ms: f64,
+    mean_ms: f64,
+    p50_ms: f64,
+    p95_ms: f64,
+    p99_ms: f64,
+    max_ms: f64,
+}
+
+/// The summary of a soak test, written as the report
+#[derive(Serialize, Debug)]
+struct SoakReport {
+    serial_number: Option<String>,
+    started: Timestamp,
+    finished: Timestamp,
+    duration_secs: f64,
+
+    /// The number of attempts to read the status, including failed attempts
+    reads: u64,
+    failed_reads: u64,
+
+    /// The percentage of reads which succeeded
+    device_availability: f64,
+
+    /// The percentage of reads which found the GPSDO in the locked state
+    lock_availability: f64,
+
+    outages: Vec<Outage>,
+    loss_events: Vec<LossEvent>,
+
+    /// `None` if no read succeeded
+    latency: Option<LatencyStats>,
+}
+
+/// Collects the readings of a soak test into a report
+struct SoakRecorder {
+    started: Timestamp,
+    serial_number: Option<String>,
+    reads: u64,
+    failed_reads: u64,
+    locked_reads: u64,
+    latencies: Vec<Duration>,
+    outages: Vec<Outage>,
+    device_outage: Option<Timestamp>,
+    lock_outage: Option<Timestamp>,
+    loss_events: Vec<LossEvent>,
+    tracker: StatusTracker,
+    state: DeviceStateMachine,
+}
+
+impl SoakRecorder {
+    fn new(started: Timestamp) -> Self {
+        SoakRecorder {
+            started,
+            serial_number: None,
+            reads: 0,
+            failed_reads: 0,
+            locked_reads: 0,
+            latencies: vec![],
+            outages: vec![],
+            device_outage: None,
+            lock_outage: None,
+            loss_events: vec![],
+            tracker: StatusTracker::new(),
+            state: DeviceStateMachine::new(),
+        }
+    }
+
+    /// Record a status read which succeeded and how long it took
+    fn record_status(&mut self, timestamp: Timestamp, latency: Duration, status: &GpsdoStatus) {
+        self.reads += 1;
+        self.latencies.push(latency);
+
+        if let Some(start) = self.device_outage.take() {
+            self.end_outage(OutageKind::Device, start, timestamp, false);
+        }
+
+        self.state.update(status);
+        if self.state.state() == DeviceState::Locked {
+            self.locked_reads += 1;
+
+            if let Some(start) = self.lock_outage.take() {
+                self.end_outage(OutageKind::Lock, start, timestamp, false);
+            }
+        } else if self.lock_outage.is_none() {
+            tracing::warn!("gpsdo lost lock");
+            self.lock_outage = Some(timestamp);
+        }
+
+        // A loss count which goes down, as the GPSDO rebooted, isn't a loss of lock, so has no event
+        if self
+            .tracker
+            .update(status)
+            .any(|event| matches!(event, StatusEvent::LossCountIncreased(_)))
+        {
+            self.loss_events.push(LossEvent {
+                timestamp,
+                loss_count: status.loss_count(),
+            });
+        }
+    }
+
+    /// Record the GPSDO being opened
+    fn record_open(&mut self, serial_number: Option<String>) {
+        self.serial_number = serial_number;
+        self.state.connected();
+    }
+
+    /// Record a failure to open the GPSDO
+    fn record_open_error(&mut self, timestamp: Timestamp, error: &str) {
+        self.state.disconnected();
+        self.record_error(timestamp, error);
+    }
+
+    /// Record a failure to read the GPSDO once open
+    fn record_read_error(&mut self, timestamp: Timestamp, error: &str) {
+        self.state.fault();
+        self.record_error(timestamp, error);
+    }
+
+    fn record_error(&mut self, timestamp: Timestamp, error: &str) {
+        self.reads += 1;
+        self.failed_reads += 1;
+
+        if self.device_outage.is_none() {
+            tracing::warn!("gpsdo unavailable: {}", error);
+            self.device_outage = Some(timestamp);
+        }
+    }
+
+    fn end_outage(&mut self, kind: OutageKind, start: Timestamp, end: Timestamp, ongoing: bool) {
+        if !ongoing {
+            tracing::info!(?kind, "gpsdo outage ended");
+        }
+
+        self.outages.push(Outage {
+            kind,
+            start,
+            end,
+            duration_secs: end.duration_since(&start).as_secs_f64(),
+            ongoing,
+        });
+    }
+
+    /// Close any outages still going on and summarize the capture
+    fn finish(mut self, finished: Timestamp) -> SoakReport {
+        if let Some(start) = self.device_outage.take() {
+            self.end_outage(OutageKind::Device, start, finished, true);
+        }
+        if let Some(start) = self.lock_outage.take() {
+            self.end_outage(OutageKind::Lock, start, finished, true);
+        }
+        self.outages.sort_by_key(|outage| outage.start.unix_nanos());
+
+        let percentage = |count: u64| match self.reads {
+            0 => 0.0,
+            reads => count as f64 * 100.0 / reads as f64,
+        };
+
+        SoakReport {
+            serial_number: self.serial_number,
+            started: self.started,
+            finished,
+            duration_secs: finished.duration_since(&self.started).as_secs_f64(),
+            reads: self.reads,
+            failed_reads: self.failed_reads,
+            device_availability: percentage(self.reads - self.failed_reads),
+            lock_availability: percentage(self.locked_reads),
+            outages: self.outages,
+            loss_events: self.loss_events,
+            latency: latency_stats(self.latencies),
+        }
+    }
+}
+
+fn latency_stats(mut latencies: Vec<Duration>) -> Option<LatencyStats> {
+    latencies.sort();
+
+    let millis = |latency: &Duration| latency.as_secs_f64() * 1000.0;
+    let percentile =
+        |percent: usize| millis(&latencies[((latencies.len() - 1) * percent).div_ceil(100)]);
+
+    Some(LatencyStats {
+        min_ms: millis(latencies.first()?),
+        mean_ms: latencies.iter().map(millis).sum::<f64>() / latencies.len() as f64,
+        p50_ms: percentile(50),
+        p95_ms: percentile(95),
+        p99_ms: percentile(99),
+        max_ms: millis(latencies.last()?),
+    })
+}
+
+/// Capture the status of the GPSDO for the duration given, re-opening it whenever it fails, then write the report
+pub(crate) fn soak(args: &Args, soak_args: &SoakArgs) {
+    let interval = args.interval.unwrap_or(Duration::from_secs(1));
+    let deadline = Instant::now() + soak_args.duration;
+
+    let mut recorder = SoakRecorder::new(Timestamp::now());
+    let mut device = None;
+
+    tracing::info!(
+        duration = %humantime::format_duration(soak_args.duration),
+        report = %soak_args.report.display(),
+        "starting soak test"
+    );
+
+    while Instant::now() < deadline {
+        if device.is_none() {
+            match poll::open(args) {
+                Ok((interface, info)) => {
+                    recorder.record_open(info.serial_number().map(str::to_owned));
+                    device = Some((interface, info));
+                }
+                Err(error) => recorder.record_open_error(Timestamp::now(), &error.to_string()),
+            }
+        }
+
+        if let Some((interface, info)) = &device {
+            let gpsdo = GpsdoDevice::with_model(interface, poll::model(info));
+
+            let read_start = Instant::now();
+            match gpsdo.status() {
+                Ok(status) => {
+                    recorder.record_status(Timestamp::now(), read_start.elapsed(), &status)
+                }
+                Err(error) => {
+                    recorder.record_read_error(Timestamp::now(), &error.to_string());
+                    device = None;
+                }
+            }
+        }
+
+        std::thread::sleep(interval.min(deadline.saturating_duration_since(Instant::now())));
+    }
+
+    let report = recorder.finish(Timestamp::now());
+
+    if let Err(error) = write_report(&report, &soak_args.report) {
+        eprintln!(
+            "failed to write report {}: {}",
+            soak_args.report.display(),
+            error
+        );
+        ExitCode::Failure.exit();
+    }
+
+    tracing::info!(
+        device_availability = report.device_availability,
+        lock_availability = report.lock_availability,
+        outages = report.outages.len(),
+        loss_events = report.loss_events.len(),
+        "soak test finished"
+    );
+}
+
+/// Build the text of the HTML report in the configured language, keyed as the template looks it up
+fn report_text() -> BTreeMap<&'static str, &'static str> {
+    [
+        ("soak_title", Message::SoakTitle),
+        ("device", Message::Device),
+        ("unknown", Message::Unknown),
+        ("started", Message::Started),
+        ("finished", Message::Finished),
+        ("duration", Message::Duration),
+        ("reads", Message::Reads),
+        ("failed", Message::Failed),
+        ("device_availability", Message::DeviceAvailability),
+        ("lock_availability", Message::LockAvailability),
+        ("loss_events", Message::LossEvents),
+        ("read_latency", Message::ReadLatency),
+        ("min", Message::Min),
+        ("mean", Message::Mean),
+        ("max", Message::Max),
+        ("no_successful_reads", Message::NoSuccessfulReads),
+        ("outages", Message::Outages),
+        ("kind", Message::Kind),
+        ("start", Message::Start),
+        ("end", Message::End),
+        ("ongoing", Message::Ongoing),
+        ("outage_device", Message::OutageDevice),
+        ("outage_lock", Message::OutageLock),
+        ("no_outages", Message::NoOutages),
+        ("time", Message::Time),
+        ("loss_count", Message::LossCount),
+        ("no_loss_events", Message::NoLossEvents),
+    ]
+    .into_iter()
+    .map(|(key, message)| (key, messages::text(message)))
+    .collect()
+}
+
+/// Write the report as HTML or JSON, chosen by the extension of the path
+fn write_report(report: &SoakReport, path: &Path) -> Result<(), String> {
+    let html = path
+        .extension()
+        .is_some_and(|extension| extension == "html" || extension == "htm");
+
+    let contents = if html {
+        let mut environment = Environment::new();
+        environment.add_global("t", Value::from_serialize(report_text()));
+        environment
+            .add_template("report.html", HTML_TEMPLATE)
+            .map_err(|error| error.to_string())?;
+
+        environment
+            .get_template("report.html")
+            .and_then(|template| template.render(report))
+            .map_err(|error| error.to_string())?
+    } else {
+        serde_json::to_string_pretty(report).map_err(|error| error.to_string())?
+    };
+
+    persist::write_atomic(path, contents).map_err(|error| error.to_string())
+}
@@ -0,0 +1,73 @@
+use leo_status_driver::{FirmwareVersion, GpsdoConfig, GpsdoDeviceInfo, GpsdoModel};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    dto::{ConfigResponse, DeviceResponse},
+    time::Timestamp,
+};
+
+/// What the daemon latched onto when it opened the GPSDO, logged at startup and after every reconnect
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct StartupReport {
+    /// When the GPSDO was opened, in RFC 3339 format
+    #[schemars(with = "String")]
+    timestamp: Timestamp,
+
+    /// The GPSDO in use and where it is attached
+    device: DeviceResponse,
+
+    /// The configuration read from the GPSDO, with the frequencies it produces
+    config: ConfigResponse,
+
+    /// Anything about the GPSDO which may need attention, such as an invalid configuration
+    warnings: Vec<String>,
+}
+
+impl StartupReport {
+    /// Build the report for a GPSDO which has just been opened, and log it with a warning for each problem found
+    pub(crate) fn record(
+        info: &GpsdoDeviceInfo,
+        firmware_version: Option<FirmwareVersion>,
+        config: &GpsdoConfig,
+    ) -> Self {
+        let mut warnings = vec![];
+
+        if GpsdoModel::from_product_id(info.product_id()).is_none() {
+            warnings.push(format!(
+                "unknown product id {:#06x}, treating the device as a standard two output GPSDO",
+                info.product_id()
+            ));
+        }
+        if info.serial_number().is_none() {
+            warnings.push("the device has no serial number".to_owned());
+        }
+        if firmware_version.is_none() {
+            warnings.push("the firmware version could not be read".to_owned());
+        }
+        for violation in config.validate() {
+            warnings.push(format!("invalid configuration: {}", violation));
+        }
+
+        tracing::info!(
+            serial_number = info.serial_number().unwrap_or("unknown"),
+            path = info.path(),
+            port_path = info.port_path().unwrap_or("unknown"),
+            firmware_version = %firmware_version.map_or("unknown".to_owned(), |version| version.to_string()),
+            fout1 = ?config.checked_fout1().ok(),
+            fout2 = ?config.checked_fout2().ok().flatten(),
+            config = ?config,
+            "opened gpsdo"
+        );
+        for warning in &warnings {
+            tracing::warn!("{}", warning);
+        }
+
+        StartupReport {
+            timestamp: Timestamp::now(),
+            device: DeviceResponse::from(info).with_firmware_version(firmware_version),
+            config: config.into(),
+            warnings,
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use std::{
+    sync::{Arc, Mutex},
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use crate::exit::ExitCode;
+
+/// How often the supervisor checks on its components
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The delay before restarting a component the first time it exits
+const INITIAL_RESTART_BACKOFF: Duration = Duration::from_secs(1);
+
+/// The longest delay between restarts of a component which keeps dying
+const MAX_RESTART_BACKOFF: Duration = Duration::from_secs(60);
+
+/// A timestamp which a supervised component updates each time it makes progress
+#[derive(Clone)]
+pub(crate) struct Heartbeat(Arc<Mutex<Instant>>);
+
+impl Heartbeat {
+    pub(crate) fn new() -> Self {
+        Heartbeat(Arc::new(Mutex::new(Instant::now())))
+    }
+
+    /// Record that the component has made progress
+    pub(crate) fn beat(&self) {
+        *self.0.lock().expect("failed to get heartbeat mutex") = Instant::now();
+    }
+
+    /// Time since the component last made progress
+    pub(crate) fn age(&self) -> Duration {
+        self.0
+            .lock()
+            .expect("failed to get heartbeat mutex")
+            .elapsed()
+    }
+}
+
+/// What the supervisor should do when a component's thread has exited
+pub(crate) enum OnExit {
+    /// Start the component again, backing off exponentially if it keeps exiting
+    Restart,
+
+    /// Exit the process so the service manager can recover
+    Exit,
+}
+
+/// A thread watched by the supervisor
+pub(crate) struct Component {
+    name: &'static str,
+    spawn: Box<dyn Fn(Heartbeat) -> JoinHandle<()>>,
+    on_exit: OnExit,
+    stall_timeout: Duration,
+    heartbeat: Heartbeat,
+    handle: JoinHandle<()>,
+    started_at: Instant,
+    backoff: Duration,
+    restart_at: Option<Instant>,
+}
+
+impl Component {
+    /// Start a component. `spawn` is called again each time the component is restarted, and is given the heartbeat
+    /// the new thread must keep updating at least every `stall_timeout`.
+    pub(crate) fn start(
+        name: &'static str,
+        on_exit: OnExit,
+        stall_timeout: Duration,
+        spawn: impl Fn(Heartbeat) -> JoinHandle<()> + 'static,
+    ) -> Self {
+        let heartbeat = Heartbeat::new();
+        let handle = spawn(heartbeat.clone());
+
+        Component {
+            name,
+            spawn: Box::new(spawn),
+            on_exit,
+            stall_timeout,
+            heartbeat,
+            handle,
+            started_at: Instant::now(),
+            backoff: INITIAL_RESTART_BACKOFF,
+            restart_at: None,
+        }
+    }
+
+    fn check(&mut self) {
+        if let Some(restart_at) = self.restart_at {
+            if Instant::now() >= restart_at {
+                tracing::info!("restarting {}", self.name);
+
+                self.heartbeat.beat();
+                self.handle = (self.spawn)(self.heartbeat.clone());
+                self.started_at = Instant::now();
+                self.restart_at = None;
+            }
+
+            return;
+        }
+
+        if self.handle.is_finished() {
+            match self.on_exit {
+                OnExit::Restart => {
+                    // A component which ran for a good while before exiting starts over with a short backoff
+                    if self.started_at.elapsed() > MAX_RESTART_BACKOFF {
+                        self.backoff = INITIAL_RESTART_BACKOFF;
+                    }
+
+                    tracing::error!("{} exited, restarting in {:?}", self.name, self.backoff);
+
+                    self.restart_at = Some(Instant::now() + self.backoff);
+                    self.backoff = (self.backoff * 2).min(MAX_RESTART_BACKOFF);
+                }
+                OnExit::Exit => {
+                    tracing::error!("{} exited, exiting", self.name);
+                    ExitCode::Unavailable.exit();
+                }
+            }
+        } else if self.heartbeat.age() > self.stall_timeout {
+            // A stalled thread cannot be stopped from the outside, so the only way to recover is a process restart
+            tracing::error!(
+                "{} has made no progress for {:?}, exiting",
+                self.name,
+                self.heartbeat.age()
+            );
+            ExitCode::Unavailable.exit();
+        }
+    }
+}
+
+/// Watch the given components forever, restarting or exiting as they die or stall
+pub(crate) fn supervise(mut components: Vec<Component>) -> ! {
+    loop {
+        std::thread::sleep(CHECK_INTERVAL);
+
+        for component in components.iter_mut() {
+            component.check();
+        }
+    }
+}
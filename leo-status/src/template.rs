@@ -0,0 +1,38 @@
+use minijinja::{Environment, UndefinedBehavior};
+use serde::Serialize;
+
+use std::{fs, path::Path};
+
+/// A minijinja template for the body of a notification, so sites can match the payload their tooling expects. Fields
+/// which don't exist are an error rather than empty, so a typo is found on the first delivery.
+#[derive(Debug, Clone)]
+pub(crate) struct BodyTemplate {
+    source: String,
+}
+
+impl BodyTemplate {
+    /// Read a template from a file, checking its syntax. Used as a clap value parser, so a broken template stops the
+    /// daemon from starting.
+    pub(crate) fn load(path: &str) -> Result<Self, String> {
+        let source = fs::read_to_string(Path::new(path))
+            .map_err(|error| format!("failed to read template {}: {}", path, error))?;
+
+        environment()
+            .template_from_str(&source)
+            .map_err(|error| format!("invalid template {}: {}", path, error))?;
+
+        Ok(BodyTemplate { source })
+    }
+
+    /// Render the template with the fields of a value
+    pub(crate) fn render<T: Serialize>(&self, value: &T) -> Result<String, minijinja::Error> {
+        environment().render_str(&self.source, value)
+    }
+}
+
+fn environment() -> Environment<'static> {
+    let mut environment = Environment::new();
+    environment.set_undefined_behavior(UndefinedBehavior::Strict);
+
+    environment
+}
@@ -0,0 +1,95 @@
+use chrono::{DateTime, Local, SecondsFormat, Utc};
+use clap::ValueEnum;
+use serde::{Serialize, Serializer};
+
+use std::{
+    sync::OnceLock,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// The time zone used for every timestamp the daemon outputs
+#[derive(ValueEnum, Debug, Clone, Copy, Default)]
+pub(crate) enum TimestampZone {
+    /// Coordinated Universal Time, with a `Z` suffix
+    #[default]
+    Utc,
+
+    /// The local time zone of the host, with its offset from UTC
+    Local,
+}
+
+static TIMESTAMP_ZONE: OnceLock<TimestampZone> = OnceLock::new();
+
+/// Set the time zone used for timestamps. This should be called once at startup, before any output is produced.
+pub(crate) fn init(zone: TimestampZone) {
+    TIMESTAMP_ZONE
+        .set(zone)
+        .expect("timestamp zone has already been set");
+}
+
+/// A point in time, recorded on both the wall clock and the monotonic clock. The wall clock time is used for
+/// timestamps, while ages are measured with the monotonic clock so they are unaffected by clock adjustments.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Timestamp {
+    wall: SystemTime,
+    monotonic: Instant,
+}
+
+impl Timestamp {
+    pub(crate) fn now() -> Self {
+        Timestamp {
+            wall: SystemTime::now(),
+            monotonic: Instant::now(),
+        }
+    }
+
+    /// Format the time in RFC 3339 format, in the configured time zone
+    pub(crate) fn format(&self) -> String {
+        format_system_time(self.wall)
+    }
+
+    /// The number of nanoseconds between the Unix epoch and this time, on the wall clock
+    pub(crate) fn unix_nanos(&self) -> u128 {
+        self.wall
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos()
+    }
+
+    /// The time between an earlier time and this time, on the monotonic clock
+    pub(crate) fn duration_since(&self, earlier: &Timestamp) -> Duration {
+        self.monotonic.saturating_duration_since(earlier.monotonic)
+    }
+
+    /// The time `duration` after this time
+    pub(crate) fn after(&self, duration: Duration) -> Self {
+        Timestamp {
+            wall: self.wall + duration,
+            monotonic: self.monotonic + duration,
+        }
+    }
+
+    /// The number of seconds since this time
+    pub(crate) fn age_secs(&self) -> f64 {
+        self.monotonic.elapsed().as_secs_f64()
+    }
+}
+
+/// Timestamps are serialized in RFC 3339 format, in the configured time zone
+impl Serialize for Timestamp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.format())
+    }
+}
+
+/// Format a wall clock time in RFC 3339 format, in the configured time zone
+pub(crate) fn format_system_time(time: SystemTime) -> String {
+    match TIMESTAMP_ZONE.get().copied().unwrap_or_default() {
+        TimestampZone::Utc => {
+            DateTime::<Utc>::from(time).to_rfc3339_opts(SecondsFormat::Secs, true)
+        }
+        TimestampZone::Local => {
+            DateTime::<Local>::from(time).to_rfc3339_opts(SecondsFormat::Secs, false)
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use thiserror::Error;
+use ureq::Agent;
+
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use crate::{dto::VersionResponse, metrics::Metrics, time::Timestamp};
+
+/// How long reading the release feed may take before it fails
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Error)]
+/// An error which stopped the release feed being read
+pub(crate) enum UpdateCheckError {
+    #[error("failed to read release feed: {0}")]
+    Http(#[from] ureq::Error),
+
+    #[error("release feed has no version {0:?}")]
+    InvalidVersion(String),
+}
+
+/// The version served at `/version`, with the latest release once the feed has been read
+pub(crate) fn running_version() -> VersionResponse {
+    VersionResponse {
+        version: env!("CARGO_PKG_VERSION").to_owned(),
+        latest_version: None,
+        update_available: None,
+        checked_at: None,
+    }
+}
+
+/// Read the release feed every `interval` on a thread of its own, recording the latest release in `version` and
+/// whether it is newer than the running one in the update metric. A failed read keeps the result of the last one.
+pub(crate) fn spawn_update_check(
+    feed: String,
+    interval: Duration,
+    version: Arc<RwLock<VersionResponse>>,
+    metrics: Metrics,
+) {
+    std::thread::Builder::new()
+        .name("update-check".to_owned())
+        .spawn(move || {
+            let agent: Agent = Agent::config_builder()
+                .timeout_global(Some(REQUEST_TIMEOUT))
+                .build()
+                .into();
+
+            loop {
+                match latest_version(&agent, &feed) {
+                    Ok(latest) => {
+                        let available = is_newer(&latest, env!("CARGO_PKG_VERSION"));
+                        metrics.update_available.set(available.into());
+
+                        let mut version = version.write().unwrap();
+                        version.latest_version = Some(latest);
+                        version.update_available = Some(available);
+                        version.checked_at = Some(Timestamp::now().format());
+                    }
+                    Err(error) => tracing::warn!("{}", error),
+                }
+
+                std::thread::sleep(interval);
+            }
+        })
+        .expect("failed to spawn update check thread");
+}
+
+/// Read the latest version from the feed, either a JSON object with a `tag_name`, as GitHub serves the latest release
+/// of a repository, or the version as plain text
+fn latest_version(agent: &Agent, feed: &str) -> Result<String, UpdateCheckError> {
+    let mut response = agent.get(feed).call()?;
+    let body = response.body_mut().read_to_string()?;
+
+    let version = match serde_json::from_str::<serde_json::Value>(&body) {
+        Ok(release @ serde_json::Value::Object(_)) => release
+            .get("tag_name")
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or_default()
+            .to_owned(),
+        _ => body.trim().to_owned(),
+    };
+
+    match parse_version(&version) {
+        Some(_) => Ok(version),
+        None => Err(UpdateCheckError::InvalidVersion(version)),
+    }
+}
+
+/// Whether `latest` is a later version than `running`, comparing their major, minor and patch numbers
+fn is_newer(latest: &str, running: &str) -> bool {
+    match (parse_version(latest), parse_version(running)) {
+        (Some(latest), Some(running)) => latest > running,
+        _ => false,
+    }
+}
+
+/// The major, minor and patch numbers of a version such as `v2.1.0`, ignoring any pre-release or build suffix. Missing
+/// numbers count as 0, so `2.1` is `2.1.0`.
+fn parse_version(version: &str) -> Option<[u64; 3]> {
+    let version = version.trim_start_matches('v');
+    let version = version.split(['-', '+']).next()?;
+
+    let mut numbers = [0; 3];
+    for (index, number) in version.split('.').enumerate() {
+        *numbers.get_mut(index)? = number.parse().ok()?;
+    }
+
+    Some(numbers)
+}
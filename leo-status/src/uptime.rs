@@ -0,0 +1,32 @@
+use leo_status_driver::GpsdoStatus;
+
+use crate::{snapshot::Snapshot, time::Timestamp};
+
+/// Estimates when the GPSDO booted, as it has no uptime report of its own. A boot is assumed when the loss count goes
+/// down, as it is only reset by a reboot, or when the device is re-opened without the GPS lock it had before, as a
+/// freshly booted device has to acquire it again. Until a boot is seen the estimate is when the device was first read,
+/// so it is a lower bound on the uptime.
+#[derive(Default)]
+pub(crate) struct UptimeEstimator {
+    booted: Option<Timestamp>,
+    previous: Option<GpsdoStatus>,
+}
+
+impl UptimeEstimator {
+    /// Record a new reading, returning when the GPSDO is estimated to have booted
+    pub(crate) fn update(&mut self, snapshot: &Snapshot) -> Timestamp {
+        let status = snapshot.status;
+
+        let rebooted = self.previous.is_some_and(|previous| {
+            status.loss_count() < previous.loss_count()
+                || (snapshot.first_read && previous.sat_locked() && !status.sat_locked())
+        });
+        self.previous = Some(status);
+
+        if rebooted {
+            self.booted = Some(snapshot.timestamp);
+        }
+
+        *self.booted.get_or_insert(snapshot.timestamp)
+    }
+}
@@ -0,0 +1,195 @@
+use clap::Args as ClapArgs;
+use leo_status_driver::GpsdoDevice;
+use serde::Serialize;
+use serde_json::Value;
+
+use std::{fs, path::PathBuf};
+
+use crate::{
+    dto::ConfigResponse,
+    exit::ExitCode,
+    messages::{self, Message},
+    persist, poll, Args,
+};
+
+#[derive(ClapArgs, Debug, Clone)]
+pub(crate) struct VerifyArgs {
+    #[arg(
+        long,
+        help = "Golden capture to compare the GPSDO against, a config as served by the config endpoint"
+    )]
+    golden: PathBuf,
+
+    #[arg(
+        long,
+        default_value = "0",
+        help = "How far each derived frequency may be from the golden capture, in Hz"
+    )]
+    tolerance_hz: u64,
+
+    #[arg(
+        long,
+        help = "Only compare the outputs, level and derived frequencies, so a unit programmed with different PLL dividers giving the same frequencies passes"
+    )]
+    ignore_pll_params: bool,
+
+    #[arg(
+        long,
+        help = "Write the config of the GPSDO to the golden capture rather than comparing against it, to capture a known good unit"
+    )]
+    capture: bool,
+}
+
+/// A field of the config which doesn't match the golden capture
+struct Mismatch {
+    field: &'static str,
+    expected: Value,
+    found: Value,
+}
+
+/// The fields of a config which differ from the golden capture
+#[derive(Default)]
+struct Comparison {
+    mismatches: Vec<Mismatch>,
+}
+
+impl Comparison {
+    /// Check a field is exactly the golden value
+    fn exact<T: Serialize + PartialEq>(&mut self, field: &'static str, expected: &T, found: &T) {
+        if expected != found {
+            self.push(field, expected, found);
+        }
+    }
+
+    /// Check a frequency is within the tolerance of the golden value. A frequency which couldn't be derived only
+    /// matches one which couldn't be derived either.
+    fn frequency<T: Into<u64> + Copy + Serialize>(
+        &mut self,
+        field: &'static str,
+        expected: Option<T>,
+        found: Option<T>,
+        tolerance_hz: u64,
+    ) {
+        let matches = match (expected.map(Into::into), found.map(Into::into)) {
+            (Some(expected), Some(found)) => expected.abs_diff(found) <= tolerance_hz,
+            (expected, found) => expected == found,
+        };
+
+        if !matches {
+            self.push(field, &expected, &found);
+        }
+    }
+
+    fn push(&mut self, field: &'static str, expected: &impl Serialize, found: &impl Serialize) {
+        self.mismatches.push(Mismatch {
+            field,
+            expected: serde_json::to_value(expected).expect("failed to serialize config field"),
+            found: serde_json::to_value(found).expect("failed to serialize config field"),
+        });
+    }
+}
+
+/// Compare a config read from the GPSDO against the golden capture, ignoring when and how it was read
+fn compare(
+    golden: &ConfigResponse,
+    config: &ConfigResponse,
+    verify_args: &VerifyArgs,
+) -> Comparison {
+    let tolerance_hz = verify_args.tolerance_hz;
+    let mut comparison = Comparison::default();
+
+    comparison.exact("output1", &golden.output1, &config.output1);
+    comparison.exact("output2", &golden.output2, &config.output2);
+    comparison.exact("level", &golden.level, &config.level);
+    comparison.frequency("fout1", golden.fout1, config.fout1, tolerance_hz);
+    comparison.frequency("fout2", golden.fout2, config.fout2, tolerance_hz);
+
+    if !verify_args.ignore_pll_params {
+        let (golden, config) = (&golden.pll_params, &config.pll_params);
+
+        comparison.exact("pll_params.fin", &golden.fin, &config.fin);
+        comparison.exact("pll_params.n3", &golden.n3, &config.n3);
+        comparison.exact("pll_params.n2_hs", &golden.n2_hs, &config.n2_hs);
+        comparison.exact("pll_params.n2_ls", &golden.n2_ls, &config.n2_ls);
+        comparison.exact("pll_params.n1_hs", &golden.n1_hs, &config.n1_hs);
+        comparison.exact("pll_params.nc1_ls", &golden.nc1_ls, &config.nc1_ls);
+        comparison.exact("pll_params.nc2_ls", &golden.nc2_ls, &config.nc2_ls);
+        comparison.exact("pll_params.skew", &golden.skew, &config.skew);
+        comparison.exact("pll_params.bw", &golden.bw, &config.bw);
+        comparison.frequency("pll_params.f3", golden.f3, config.f3, tolerance_hz);
+        comparison.frequency("pll_params.fosc", golden.fosc, config.fosc, tolerance_hz);
+    }
+
+    comparison
+}
+
+/// Read the config of the GPSDO and compare it against the golden capture, printing each field which differs and
+/// exiting with `ExitCode::Mismatch` if any do, for end of line testing. With `--capture` the golden capture is written
+/// instead.
+pub(crate) fn verify(args: &Args, verify_args: &VerifyArgs) {
+    let (interface, info) = poll::open_polled(args).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        error.exit_code().exit();
+    });
+    let config = GpsdoDevice::with_model(interface, poll::model(&info))
+        .config()
+        .unwrap_or_else(|error| {
+            eprintln!("failed to read config: {}", error);
+            ExitCode::PollFailure.exit();
+        });
+    let config = ConfigResponse::from(&config);
+
+    if verify_args.capture {
+        let contents =
+            serde_json::to_string_pretty(&config).expect("failed to serialize config") + "\n";
+        if let Err(error) = persist::write_atomic(&verify_args.golden, contents) {
+            eprintln!(
+                "failed to write golden capture {}: {}",
+                verify_args.golden.display(),
+                error
+            );
+            ExitCode::Failure.exit();
+        }
+
+        println!(
+            "{} {}",
+            messages::text(Message::VerifyCaptured),
+            verify_args.golden.display()
+        );
+        return;
+    }
+
+    let golden: ConfigResponse = fs::read_to_string(&verify_args.golden)
+        .map_err(|error| error.to_string())
+        .and_then(|golden| serde_json::from_str(&golden).map_err(|error| error.to_string()))
+        .unwrap_or_else(|error| {
+            eprintln!(
+                "failed to read golden capture {}: {}",
+                verify_args.golden.display(),
+                error
+            );
+            ExitCode::ConfigInvalid.exit();
+        });
+
+    let comparison = compare(&golden, &config, verify_args);
+    if comparison.mismatches.is_empty() {
+        println!(
+            "{} {}",
+            messages::text(Message::VerifyMatches),
+            verify_args.golden.display()
+        );
+        return;
+    }
+
+    for mismatch in &comparison.mismatches {
+        println!(
+            "{}: {} {}, {} {}",
+            mismatch.field,
+            messages::text(Message::VerifyExpected),
+            mismatch.expected,
+            messages::text(Message::VerifyFound),
+            mismatch.found
+        );
+    }
+    ExitCode::Mismatch.exit();
+}
@@ -0,0 +1,155 @@
+use thiserror::Error;
+
+use std::{
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use crate::schedule::PollTrigger;
+
+#[derive(Debug, Error)]
+/// An error which stopped a write to the GPSDO from starting
+pub(crate) enum WriteError {
+    #[error(
+        "the GPSDO was written to moments ago, try again in {}",
+        humantime::format_duration(*.0)
+    )]
+    Cooldown(Duration),
+}
+
+impl WriteError {
+    /// How long the caller should wait before trying again, in whole seconds for a `Retry-After` header
+    pub(crate) fn retry_after(&self) -> u64 {
+        match self {
+            WriteError::Cooldown(remaining) => remaining.as_secs(),
+        }
+    }
+}
+
+/// The lock every write to the GPSDO is made behind, so writes from concurrent callers can't interleave partial
+/// reconfigurations. After each write, the next write is refused until the cooldown has passed, and the poll loop is
+/// woken to read the device again so the served status reflects the write.
+#[derive(Debug, Clone)]
+pub(crate) struct DeviceWrites {
+    last_write: Arc<Mutex<Option<Instant>>>,
+    cooldown: Duration,
+    poll: PollTrigger,
+}
+
+/// A write in progress, holding the lock until it is dropped
+pub(crate) struct DeviceWrite<'a> {
+    writes: &'a DeviceWrites,
+    last_write: MutexGuard<'a, Option<Instant>>,
+}
+
+impl DeviceWrites {
+    /// Serialize writes, triggering `poll` after each
+    pub(crate) fn new(cooldown: Duration, poll: PollTrigger) -> Self {
+        DeviceWrites {
+            last_write: Arc::new(Mutex::new(None)),
+            cooldown,
+            poll,
+        }
+    }
+
+    /// Take the lock for a write, waiting for any write in progress, and failing if the last one finished within the
+    /// cooldown
+    pub(crate) fn begin(&self) -> Result<DeviceWrite<'_>, WriteError> {
+        let last_write = self
+            .last_write
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+
+        if let Some(remaining) = last_write
+            .map(|last_write| self.cooldown.saturating_sub(last_write.elapsed()))
+            .filter(|remaining| !remaining.is_zero())
+        {
+            // Rounded up to whole seconds, for messages and the `Retry-After` header
+            return Err(WriteError::Cooldown(Duration::from_secs(
+                remaining.as_secs_f64().ceil() as u64,
+            )));
+        }
+
+        Ok(DeviceWrite {
+            writes: self,
+            last_write,
+        })
+    }
+}
+
+impl Drop for DeviceWrite<'_> {
+    fn drop(&mut self) {
+        *self.last_write = Some(Instant::now());
+        self.writes.poll.trigger();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::mpsc,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use super::{DeviceWrites, WriteError};
+    use crate::{schedule::PollTrigger, supervisor::Heartbeat};
+
+    #[test]
+    fn writes_within_the_cooldown_are_refused() {
+        let writes = DeviceWrites::new(Duration::from_secs(60), PollTrigger::default());
+
+        drop(writes.begin().expect("the first write should start"));
+
+        match writes.begin() {
+            Err(error @ WriteError::Cooldown(_)) => assert_eq!(error.retry_after(), 60),
+            Ok(_) => panic!("a write within the cooldown should be refused"),
+        };
+    }
+
+    #[test]
+    fn writes_after_the_cooldown_are_allowed() {
+        let writes = DeviceWrites::new(Duration::ZERO, PollTrigger::default());
+
+        drop(writes.begin().expect("the first write should start"));
+        drop(writes.begin().expect("the second write should start"));
+    }
+
+    #[test]
+    fn writes_wait_for_the_write_in_progress() {
+        let writes = DeviceWrites::new(Duration::ZERO, PollTrigger::default());
+        let (started, receiver) = mpsc::channel();
+
+        let write = writes.begin().expect("the first write should start");
+        let waiting = {
+            let writes = writes.clone();
+            thread::spawn(move || {
+                let _write = writes.begin().expect("the second write should start");
+                started.send(Instant::now()).expect("failed to send");
+            })
+        };
+
+        assert!(receiver.recv_timeout(Duration::from_millis(100)).is_err());
+        let finished = Instant::now();
+        drop(write);
+
+        let second_started = receiver
+            .recv_timeout(Duration::from_secs(5))
+            .expect("the second write should start once the first finishes");
+        assert!(second_started >= finished);
+        waiting.join().expect("write thread panicked");
+    }
+
+    #[test]
+    fn finishing_a_write_triggers_a_poll() {
+        let poll = PollTrigger::default();
+        let writes = DeviceWrites::new(Duration::ZERO, poll.clone());
+
+        drop(writes.begin().expect("the write should start"));
+
+        // Triggered polls return straight away, instead of waiting out the timeout
+        let waited = Instant::now();
+        poll.wait(Some(waited + Duration::from_secs(30)), &Heartbeat::new());
+        assert!(waited.elapsed() < Duration::from_secs(5));
+    }
+}